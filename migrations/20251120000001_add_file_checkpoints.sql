@@ -0,0 +1,10 @@
+-- Migration to support undo / checkpoints for file modifications
+-- Snapshots of files taken before a tool modifies them are stored in the
+-- files table. checkpoint_id is the user message that started the turn;
+-- snapshot_data holds the raw file bytes so binary and non-UTF-8 files can
+-- be restored, and is NULL when the file did not exist yet.
+
+ALTER TABLE files ADD COLUMN checkpoint_id TEXT;
+ALTER TABLE files ADD COLUMN snapshot_data BLOB;
+
+CREATE INDEX IF NOT EXISTS idx_files_checkpoint ON files(session_id, checkpoint_id);
@@ -0,0 +1,6 @@
+-- Migration to link agent turns to the git commits auto-commit mode makes
+-- commit_hash is set on the assistant message that ended the turn and on
+-- the file snapshots taken during it.
+
+ALTER TABLE messages ADD COLUMN commit_hash TEXT;
+ALTER TABLE files ADD COLUMN commit_hash TEXT;
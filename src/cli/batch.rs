@@ -0,0 +1,487 @@
+//! Batch Runs
+//!
+//! `crustly batch prompts.jsonl` runs many independent prompts without a
+//! terminal, a few at a time. Each input line is a JSON object:
+//!
+//! | Field               | Meaning                                              |
+//! |---------------------|------------------------------------------------------|
+//! | `prompt`            | The prompt (required)                                |
+//! | `id`                | Name used in the results (default: the line number)  |
+//! | `working_directory` | Directory the tools work in (default: the current)   |
+//! | `model`             | Model to ask instead of the provider default         |
+//! | `auto_approve`      | Approve every tool call                              |
+//! | `read_only`         | Plan mode: only calls that just read are approved    |
+//! | `allow`             | Tools whose calls are approved                       |
+//! | `timeout_secs`      | Time limit, overriding `--timeout`                   |
+//!
+//! Each prompt gets a session of its own. A result line is appended to the
+//! results file as soon as a prompt finishes, so an interrupted batch picks
+//! up where it stopped when run again: prompts that already have a result
+//! are skipped.
+
+use super::ApprovalPolicy;
+use crate::config::Config;
+use crate::llm::agent::{AgentService, ApprovalCallback, ApprovalDecision, AutoCommit};
+use crate::llm::provider::{Provider, TokenUsage};
+use crate::lsp::LspManager;
+use crate::mcp::McpManager;
+use crate::services::{ServiceContext, SessionService};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Prompts run at the same time unless `--jobs` says otherwise
+pub const DEFAULT_JOBS: usize = 4;
+
+/// Time limit of one prompt unless `--timeout` says otherwise
+pub const DEFAULT_TIMEOUT_SECS: u64 = 600;
+
+/// One prompt of a batch
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchItem {
+    #[serde(default)]
+    pub id: String,
+    pub prompt: String,
+    #[serde(default)]
+    pub working_directory: Option<PathBuf>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub auto_approve: bool,
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+impl BatchItem {
+    /// How this prompt's tool approval requests are answered
+    pub fn policy(&self) -> ApprovalPolicy {
+        ApprovalPolicy {
+            auto_approve: self.auto_approve,
+            read_only: self.read_only,
+            allow: self.allow.clone(),
+        }
+    }
+}
+
+/// How a prompt ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Success,
+    Failed,
+    TimedOut,
+}
+
+/// One line of the results file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResult {
+    pub id: String,
+    pub status: BatchStatus,
+    pub content: Option<String>,
+    pub error: Option<String>,
+    pub model: Option<String>,
+    pub usage: Option<TokenUsage>,
+    pub cost: Option<f64>,
+    pub session_id: Option<Uuid>,
+    /// Tool calls refused by the item's policy
+    pub denied_tools: usize,
+    pub duration_ms: u64,
+}
+
+impl BatchResult {
+    fn failed(id: &str, error: String) -> Self {
+        Self {
+            id: id.to_string(),
+            status: BatchStatus::Failed,
+            content: None,
+            error: Some(error),
+            model: None,
+            usage: None,
+            cost: None,
+            session_id: None,
+            denied_tools: 0,
+            duration_ms: 0,
+        }
+    }
+}
+
+/// Parse a batch file, naming unnamed prompts after their line number
+pub fn parse_items(text: &str) -> Result<Vec<BatchItem>> {
+    let mut items = Vec::new();
+    let mut ids = HashSet::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut item: BatchItem = serde_json::from_str(line)
+            .with_context(|| format!("Invalid prompt on line {}", index + 1))?;
+        if item.id.is_empty() {
+            item.id = (index + 1).to_string();
+        }
+        if !ids.insert(item.id.clone()) {
+            bail!("Duplicate id '{}' on line {}", item.id, index + 1);
+        }
+        items.push(item);
+    }
+    Ok(items)
+}
+
+/// IDs of the prompts that already have a result and needn't run again
+///
+/// Lines that don't parse, such as one cut short by an interruption, are
+/// ignored. With `retry_failed`, only successes count.
+pub fn finished_ids(results: &str, retry_failed: bool) -> HashSet<String> {
+    let mut finished = HashSet::new();
+    for result in results
+        .lines()
+        .filter_map(|line| serde_json::from_str::<BatchResult>(line).ok())
+    {
+        if retry_failed && result.status != BatchStatus::Success {
+            finished.remove(&result.id);
+        } else {
+            finished.insert(result.id);
+        }
+    }
+    finished
+}
+
+/// `prompts.jsonl` → `prompts.results.jsonl`
+pub fn default_output_path(input: &Path) -> PathBuf {
+    input.with_extension("results.jsonl")
+}
+
+/// What the prompts of a batch share
+pub(super) struct BatchContext<'a> {
+    pub config: &'a Config,
+    pub context: ServiceContext,
+    pub provider: Arc<dyn Provider>,
+    pub mcp_manager: Arc<McpManager>,
+    pub timeout: Duration,
+    /// Shared by all prompts, so their commits to the branch are serialized
+    auto_commit: Option<AutoCommit>,
+    /// One per working directory, so prompts in the same project share servers
+    lsp_managers: Mutex<HashMap<PathBuf, Arc<LspManager>>>,
+}
+
+impl<'a> BatchContext<'a> {
+    pub fn new(
+        config: &'a Config,
+        context: ServiceContext,
+        provider: Arc<dyn Provider>,
+        mcp_manager: Arc<McpManager>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            config,
+            context,
+            provider,
+            mcp_manager,
+            timeout,
+            auto_commit: super::auto_commit(config),
+            lsp_managers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The language servers for prompts working in `working_directory`
+    fn lsp_manager(&self, working_directory: &Path) -> Arc<LspManager> {
+        self.lsp_managers
+            .lock()
+            .unwrap()
+            .entry(working_directory.to_path_buf())
+            .or_insert_with(|| super::lsp_manager(self.config, working_directory))
+            .clone()
+    }
+
+    /// Stop the language servers the prompts started
+    pub async fn shutdown(&self) {
+        let managers: Vec<_> = self.lsp_managers.lock().unwrap().drain().collect();
+        for (_, manager) in managers {
+            manager.shutdown().await;
+        }
+    }
+}
+
+/// Run one prompt in a session of its own
+pub(super) async fn run_item(batch: &BatchContext<'_>, item: &BatchItem) -> BatchResult {
+    let started = Instant::now();
+    let mut result = match run_agent(batch, item).await {
+        Ok(result) => result,
+        Err(e) => BatchResult::failed(&item.id, format!("{:#}", e)),
+    };
+    result.duration_ms = started.elapsed().as_millis() as u64;
+    result
+}
+
+async fn run_agent(batch: &BatchContext<'_>, item: &BatchItem) -> Result<BatchResult> {
+    let config = batch.config;
+    let current_directory = std::env::current_dir().context("Failed to get current directory")?;
+    let working_directory = match &item.working_directory {
+        Some(dir) => current_directory.join(dir),
+        None => current_directory,
+    };
+    if !working_directory.is_dir() {
+        bail!(
+            "Working directory {} not found",
+            working_directory.display()
+        );
+    }
+
+    let lsp_manager = batch.lsp_manager(&working_directory);
+    let mut tool_registry = super::build_tool_registry(config, &lsp_manager);
+    batch.mcp_manager.register_tools(&mut tool_registry);
+    let tool_registry = Arc::new(tool_registry);
+
+    let policy = item.policy();
+    let denied = Arc::new(AtomicUsize::new(0));
+    let approval_callback: ApprovalCallback = {
+        let policy = policy.clone();
+        let denied = denied.clone();
+        let id = item.id.clone();
+        let tool_registry = tool_registry.clone();
+        Arc::new(move |info| {
+            let approved = tool_registry
+                .get(&info.tool_name)
+                .is_some_and(|tool| policy.approves(tool.as_ref(), &info.tool_input));
+            if !approved {
+                denied.fetch_add(1, Ordering::SeqCst);
+                eprintln!("⛔ [{}] Denied '{}'", id, info.tool_name);
+            }
+            Box::pin(async move { Ok(ApprovalDecision::from(approved)) })
+        })
+    };
+
+    let agent_service = AgentService::new(batch.provider.clone(), batch.context.clone())
+        .with_tool_registry(tool_registry)
+        .with_auto_approve_tools(policy.auto_approve)
+        .with_approval_callback(Some(approval_callback))
+        .with_lsp_manager(Some(lsp_manager.clone()))
+        .with_project_context(super::project_context(config, &working_directory))
+        .with_repo_map(super::repo_map(config, &working_directory))
+        .with_auto_commit(batch.auto_commit.clone())
+        .with_working_directory(working_directory)
+        .with_system_prompt(super::SYSTEM_PROMPT.to_string())
+        .with_max_tool_iterations(20);
+
+    let session_service = SessionService::new(batch.context.clone());
+    let session = session_service
+        .create_session(Some(format!("Batch: {}", item.id)))
+        .await?;
+
+    let timeout = item
+        .timeout_secs
+        .map(Duration::from_secs)
+        .unwrap_or(batch.timeout);
+    let response = tokio::time::timeout(
+        timeout,
+        agent_service.send_message_with_tools_and_mode(
+            session.id,
+            item.prompt.clone(),
+            item.model.clone(),
+            policy.read_only,
+        ),
+    )
+    .await;
+
+    let failed = BatchResult {
+        session_id: Some(session.id),
+        denied_tools: denied.load(Ordering::SeqCst),
+        ..BatchResult::failed(&item.id, String::new())
+    };
+    let result = match response {
+        Ok(Ok(response)) => {
+            if let Err(e) = session_service
+                .update_session_model(session.id, &response.model)
+                .await
+            {
+                tracing::warn!("Failed to update session model: {}", e);
+            }
+            BatchResult {
+                status: BatchStatus::Success,
+                content: Some(response.content),
+                error: None,
+                model: Some(response.model),
+                usage: Some(response.usage),
+                cost: Some(response.cost),
+                ..failed
+            }
+        }
+        Ok(Err(e)) => BatchResult {
+            error: Some(e.to_string()),
+            ..failed
+        },
+        Err(_) => BatchResult {
+            status: BatchStatus::TimedOut,
+            error: Some(format!("Timed out after {}s", timeout.as_secs())),
+            ..failed
+        },
+    };
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::llm::provider::{ContentBlock, LLMRequest, LLMResponse, ProviderStream, StopReason};
+    use crate::llm::tools::{bash::BashTool, edit::EditTool};
+    use async_trait::async_trait;
+
+    /// Provider answering every request with the same text
+    struct MockProvider;
+
+    #[async_trait]
+    impl Provider for MockProvider {
+        async fn complete(
+            &self,
+            _request: LLMRequest,
+        ) -> crate::llm::provider::Result<LLMResponse> {
+            Ok(LLMResponse {
+                id: "test-response".to_string(),
+                model: "mock-model".to_string(),
+                content: vec![ContentBlock::Text {
+                    text: "Done".to_string(),
+                }],
+                stop_reason: Some(StopReason::EndTurn),
+                usage: TokenUsage {
+                    input_tokens: 10,
+                    output_tokens: 20,
+                },
+            })
+        }
+
+        async fn stream(
+            &self,
+            _request: LLMRequest,
+        ) -> crate::llm::provider::Result<ProviderStream> {
+            unimplemented!("Streaming not needed for batch tests")
+        }
+
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        fn default_model(&self) -> &str {
+            "mock-model"
+        }
+
+        fn supported_models(&self) -> Vec<String> {
+            vec!["mock-model".to_string()]
+        }
+
+        fn context_window(&self, _model: &str) -> Option<u32> {
+            Some(4096)
+        }
+
+        fn calculate_cost(&self, _model: &str, _input: u32, _output: u32) -> f64 {
+            0.001
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_item_keeps_session_usage() {
+        let db = Database::connect_in_memory().await.unwrap();
+        db.run_migrations().await.unwrap();
+        let context = ServiceContext::new(db.pool().clone());
+        let config = Config::default();
+        let mcp_manager = Arc::new(McpManager::start(&config.mcp).await);
+        let batch = BatchContext::new(
+            &config,
+            context.clone(),
+            Arc::new(MockProvider),
+            mcp_manager,
+            Duration::from_secs(60),
+        );
+        let dir = tempfile::TempDir::new().unwrap();
+        let item = BatchItem {
+            id: "docs".to_string(),
+            prompt: "Add docs".to_string(),
+            working_directory: Some(dir.path().to_path_buf()),
+            model: None,
+            auto_approve: false,
+            read_only: false,
+            allow: Vec::new(),
+            timeout_secs: None,
+        };
+
+        let result = run_item(&batch, &item).await;
+        assert_eq!(result.status, BatchStatus::Success, "{:?}", result.error);
+
+        let session = SessionService::new(context)
+            .get_session_required(result.session_id.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(session.model.as_deref(), Some("mock-model"));
+        assert_eq!(session.total_cost, result.cost.unwrap());
+        assert!(session.total_cost > 0.0);
+        assert_eq!(session.token_count, 30);
+        batch.shutdown().await;
+    }
+
+    #[test]
+    fn test_parse_items() {
+        let items = parse_items(
+            r#"{"prompt": "Add docs", "model": "small", "allow": ["edit_file"]}
+
+{"id": "tests", "prompt": "Add tests", "working_directory": "crates/core", "timeout_secs": 60}
+"#,
+        )
+        .unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].id, "1");
+        assert_eq!(items[0].model.as_deref(), Some("small"));
+        let edit = serde_json::json!({ "path": "a.rs", "operation": "replace" });
+        let command = serde_json::json!({ "command": "ls" });
+        assert!(items[0].policy().approves(&EditTool, &edit));
+        assert!(!items[0].policy().approves(&BashTool, &command));
+        assert_eq!(items[1].id, "tests");
+        assert_eq!(
+            items[1].working_directory,
+            Some(PathBuf::from("crates/core"))
+        );
+
+        let error = parse_items("{\"prompt\": \"a\", \"modle\": \"x\"}").unwrap_err();
+        assert_eq!(error.to_string(), "Invalid prompt on line 1");
+        let error =
+            parse_items("{\"id\": \"a\", \"prompt\": \"a\"}\n{\"id\": \"a\", \"prompt\": \"b\"}")
+                .unwrap_err();
+        assert_eq!(error.to_string(), "Duplicate id 'a' on line 2");
+    }
+
+    #[test]
+    fn test_finished_ids() {
+        let line = |id: &str, status: BatchStatus| {
+            let mut result = BatchResult::failed(id, "boom".to_string());
+            result.status = status;
+            serde_json::to_string(&result).unwrap()
+        };
+        let results = [
+            line("1", BatchStatus::Success),
+            line("2", BatchStatus::Failed),
+            line("3", BatchStatus::TimedOut),
+            line("3", BatchStatus::Success),
+            // Cut short by an interruption
+            "{\"id\": \"4\", \"sta".to_string(),
+        ]
+        .join("\n");
+
+        let all: HashSet<_> = ["1", "2", "3"].map(String::from).into();
+        assert_eq!(finished_ids(&results, false), all);
+        let successes: HashSet<_> = ["1", "3"].map(String::from).into();
+        assert_eq!(finished_ids(&results, true), successes);
+
+        assert_eq!(
+            default_output_path(Path::new("evals/prompts.jsonl")),
+            PathBuf::from("evals/prompts.results.jsonl")
+        );
+    }
+}
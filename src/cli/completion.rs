@@ -0,0 +1,198 @@
+//! Shell Completion and Man Pages
+//!
+//! `crustly completions <shell>` prints a script that hands completion back
+//! to crustly (`COMPLETE=<shell> crustly -- <words>`), answered by
+//! `CompleteEnv` in `main` before anything else runs. That way values such as
+//! session IDs and model names come from the database and configuration at
+//! the moment of completion rather than when the script was generated.
+
+use crate::config::Config;
+use crate::db::{models::Session, repository::SessionListOptions, Database};
+use crate::services::{ServiceContext, SessionService};
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::Shells;
+use clap_complete::Shell;
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
+use std::path::Path;
+
+/// Environment variable that switches crustly into completion mode
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+/// Providers whose API keys can be stored in the OS keyring
+pub const KEYRING_PROVIDERS: &[&str] = &["anthropic", "openai", "gemini", "azure"];
+
+/// Most session IDs offered
+const MAX_SESSION_CANDIDATES: usize = 50;
+
+/// Write the script that registers completions with `shell`
+pub fn write_registration(shell: Shell, buf: &mut dyn std::io::Write) -> std::io::Result<()> {
+    let name = shell.to_string();
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(&name)
+        .ok_or_else(|| std::io::Error::other(format!("Unsupported shell: {}", name)))?;
+    // Call back into the binary the script was generated with, by absolute
+    // path so the script works from any directory
+    let bin = std::env::current_exe()
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "crustly".to_string());
+    completer.write_registration(COMPLETE_VAR, "crustly", &bin, &bin, buf)
+}
+
+/// Write a man page for crustly and one for each subcommand into `dir`
+pub fn write_man_pages(command: clap::Command, dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    clap_mangen::generate_to(command, dir)
+}
+
+/// Session IDs, most recently active first, with their titles as help
+pub fn session_ids(current: &OsStr) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy().to_lowercase();
+    sessions()
+        .into_iter()
+        .filter(|session| session.id.to_string().starts_with(&current))
+        .take(MAX_SESSION_CANDIDATES)
+        .map(|session| {
+            let help = session.title.unwrap_or_else(|| "Untitled".to_string());
+            CompletionCandidate::new(session.id.to_string()).help(Some(help.into()))
+        })
+        .collect()
+}
+
+/// Model names used in past sessions or set as provider defaults
+pub fn models(current: &OsStr) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy();
+    let mut models = BTreeSet::new();
+
+    if let Ok(config) = Config::load() {
+        let providers = &config.providers;
+        let defaults = [
+            &providers.anthropic,
+            &providers.openai,
+            &providers.gemini,
+            &providers.bedrock,
+            &providers.azure,
+            &providers.vertex,
+        ];
+        models.extend(
+            defaults
+                .into_iter()
+                .flatten()
+                .filter_map(|provider| provider.default_model.clone()),
+        );
+        models.extend(
+            providers
+                .qwen
+                .as_ref()
+                .and_then(|qwen| qwen.default_model.clone()),
+        );
+    }
+    models.extend(sessions().into_iter().filter_map(|session| session.model));
+
+    models
+        .into_iter()
+        .filter(|model| model.starts_with(current.as_ref()))
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Provider names, noting the ones configured
+pub fn providers(current: &OsStr) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy();
+    let config = Config::load().ok();
+    let configured = |name: &str| {
+        let Some(config) = &config else {
+            return false;
+        };
+        let providers = &config.providers;
+        match name {
+            "anthropic" => providers.anthropic.is_some(),
+            "openai" => providers.openai.is_some(),
+            "gemini" => providers.gemini.is_some(),
+            "azure" => providers.azure.is_some(),
+            _ => false,
+        }
+    };
+
+    KEYRING_PROVIDERS
+        .iter()
+        .filter(|name| name.starts_with(current.as_ref()))
+        .map(|name| {
+            let help = configured(name).then(|| "configured".into());
+            CompletionCandidate::new(name).help(help)
+        })
+        .collect()
+}
+
+/// All sessions in the database of the default configuration, most
+/// recently active first
+///
+/// Completion runs before the async runtime exists, so this starts a small
+/// one of its own. Any failure just means no candidates.
+fn sessions() -> Vec<Session> {
+    let Ok(config) = Config::load() else {
+        return Vec::new();
+    };
+    // Don't create a database just to complete a word
+    if !config.database.path.exists() {
+        return Vec::new();
+    }
+    let Ok(runtime) = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    else {
+        return Vec::new();
+    };
+    runtime.block_on(async {
+        let Ok(db) = Database::connect(&config.database.path).await else {
+            return Vec::new();
+        };
+        SessionService::new(ServiceContext::new(db.pool().clone()))
+            .list_sessions(SessionListOptions {
+                include_archived: true,
+                limit: None,
+                offset: 0,
+            })
+            .await
+            .unwrap_or_default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registration_scripts() {
+        for shell in [
+            Shell::Bash,
+            Shell::Zsh,
+            Shell::Fish,
+            Shell::Elvish,
+            Shell::PowerShell,
+        ] {
+            let mut script = Vec::new();
+            write_registration(shell, &mut script).unwrap();
+            let script = String::from_utf8(script).unwrap();
+            assert!(script.contains(COMPLETE_VAR), "{}: {}", shell, script);
+        }
+
+        // The binary is referenced by absolute path
+        let mut script = Vec::new();
+        write_registration(Shell::Bash, &mut script).unwrap();
+        let exe = std::env::current_exe().unwrap();
+        assert!(String::from_utf8(script)
+            .unwrap()
+            .contains(exe.to_string_lossy().as_ref()));
+    }
+
+    #[test]
+    fn test_provider_candidates() {
+        let names: Vec<_> = providers(OsStr::new("a"))
+            .into_iter()
+            .map(|candidate| candidate.get_value().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["anthropic", "azure"]);
+    }
+}
@@ -0,0 +1,540 @@
+//! Doctor
+//!
+//! `crustly doctor` checks the setup that other commands rely on and
+//! reports each problem with a fix:
+//!
+//! - config files: syntax, unknown keys, the old `[llm]`/`[secrets]` layout
+//!   (migrated with `--migrate`, or after asking), and validation
+//! - providers: credentials, and a one-token request to each (skipped with
+//!   `--offline`)
+//! - the OS keyring
+//! - the database: integrity and migrations
+//!
+//! Unlike other commands, it runs when the configuration doesn't load.
+
+use crate::config::{self, Config};
+use crate::db::Database;
+use crate::llm::provider::{self, LLMRequest, Message, ProviderError};
+use anyhow::Result;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use toml_edit::DocumentMut;
+
+/// Time a provider has to answer the test request
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Providers in the config that Crustly can't create yet
+const UNSUPPORTED_PROVIDERS: &[&str] = &["gemini", "bedrock", "azure", "vertex"];
+
+/// Outcome of a check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Failed,
+}
+
+/// One line of the report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub status: CheckStatus,
+    pub title: String,
+    pub details: Vec<String>,
+    /// What to do about a warning or failure
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn new(status: CheckStatus, title: impl Into<String>) -> Self {
+        Self {
+            status,
+            title: title.into(),
+            details: Vec::new(),
+            fix: None,
+        }
+    }
+
+    pub fn ok(title: impl Into<String>) -> Self {
+        Self::new(CheckStatus::Ok, title)
+    }
+
+    pub fn warning(title: impl Into<String>) -> Self {
+        Self::new(CheckStatus::Warning, title)
+    }
+
+    pub fn failed(title: impl Into<String>) -> Self {
+        Self::new(CheckStatus::Failed, title)
+    }
+
+    pub fn details(mut self, details: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.details.extend(details.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+
+    fn print(&self) {
+        let icon = match self.status {
+            CheckStatus::Ok => "✅",
+            CheckStatus::Warning => "⚠️ ",
+            CheckStatus::Failed => "❌",
+        };
+        println!("{} {}", icon, self.title);
+        for line in self.details.iter().flat_map(|detail| detail.lines()) {
+            println!("     {}", line);
+        }
+        if let Some(fix) = &self.fix {
+            println!("     fix: {}", fix);
+        }
+    }
+}
+
+/// What `crustly doctor` was asked to do
+pub struct DoctorOptions {
+    /// `--config`, checked instead of the default config files
+    pub config_path: Option<PathBuf>,
+    /// Don't contact providers
+    pub offline: bool,
+    /// Migrate old config layouts without asking
+    pub migrate: bool,
+}
+
+/// Checks printed as they finish
+#[derive(Default)]
+struct Report {
+    checks: Vec<Check>,
+}
+
+impl Report {
+    fn section(&self, name: &str) {
+        println!("\n{}", name);
+    }
+
+    fn add(&mut self, check: Check) {
+        check.print();
+        self.checks.push(check);
+    }
+
+    fn count(&self, status: CheckStatus) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+}
+
+/// Run all checks, failing if any did
+pub(super) async fn run(options: DoctorOptions) -> Result<()> {
+    println!("🩺 Crustly Doctor");
+    let mut report = Report::default();
+
+    report.section("Configuration");
+    check_config_files(&mut report, &options);
+    let config = match &options.config_path {
+        Some(path) => Config::load_from_path(path),
+        None => Config::load(),
+    };
+    let config = match config {
+        Ok(config) => {
+            report.add(match config.validate() {
+                Ok(()) => Check::ok("Configuration is valid"),
+                Err(e) => Check::failed("Configuration is invalid")
+                    .details([e.to_string()])
+                    .fix("Correct the setting named above"),
+            });
+            Some(config)
+        }
+        Err(e) => {
+            report.add(
+                Check::failed("Configuration doesn't load")
+                    .details([e.to_string()])
+                    .fix("Fix the problems above; provider and database checks are skipped"),
+            );
+            None
+        }
+    };
+
+    if let Some(config) = &config {
+        report.section("Providers");
+        check_providers(&mut report, config, options.offline).await;
+    }
+
+    report.section("Keyring");
+    report.add(match config::SecretString::check_keyring() {
+        Ok(()) => Check::ok("OS keyring is available"),
+        Err(e) => Check::warning("OS keyring is not available")
+            .details([format!("{:#}", e)])
+            .fix(
+                "`crustly keyring` and MCP bearer tokens need it; \
+                 use environment variables or the config file instead",
+            ),
+    });
+
+    if let Some(config) = &config {
+        report.section("Database");
+        check_database(&mut report, &config.database.path).await;
+    }
+
+    let failed = report.count(CheckStatus::Failed);
+    let warnings = report.count(CheckStatus::Warning);
+    println!();
+    if failed > 0 {
+        anyhow::bail!("{} check(s) failed, {} warning(s)", failed, warnings);
+    }
+    if warnings > 0 {
+        println!("✅ No problems, {} warning(s)", warnings);
+    } else {
+        println!("✅ No problems found");
+    }
+    Ok(())
+}
+
+/// Config files that are loaded, in order
+fn config_files(config_path: Option<&Path>) -> Vec<PathBuf> {
+    match config_path {
+        Some(path) => vec![path.to_path_buf()],
+        None => Config::system_config_path()
+            .into_iter()
+            .chain([Config::local_config_path()])
+            .filter(|path| path.exists())
+            .collect(),
+    }
+}
+
+fn check_config_files(report: &mut Report, options: &DoctorOptions) {
+    let files = config_files(options.config_path.as_deref());
+    if files.is_empty() {
+        report.add(
+            Check::ok("No config file; using defaults and environment variables")
+                .details(["`crustly init` writes one to edit"]),
+        );
+    }
+
+    for path in files {
+        let name = path.display();
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) => {
+                report.add(
+                    Check::failed(format!("{} can't be read", name))
+                        .details([e.to_string()])
+                        .fix("Check the path and the file's permissions"),
+                );
+                continue;
+            }
+        };
+
+        let mut unknown = match config::unknown_keys(&text) {
+            Ok(unknown) => unknown,
+            Err(e) => {
+                report.add(
+                    Check::failed(format!("{} doesn't parse", name))
+                        .details([e.to_string()])
+                        .fix("Correct the line shown above"),
+                );
+                continue;
+            }
+        };
+        report.add(Check::ok(format!("{} parses", name)));
+
+        if let Ok(mut doc) = text.parse::<DocumentMut>() {
+            let changes = config::migrate_legacy_layout(&mut doc);
+            if !changes.is_empty() {
+                // The old tables are reported as the migration, not as unknown
+                if let Ok(migrated) = config::unknown_keys(&doc.to_string()) {
+                    unknown = migrated;
+                }
+                report.add(migrate_config_file(&path, changes, options.migrate));
+            }
+        }
+
+        if !unknown.is_empty() {
+            report.add(
+                Check::warning(format!(
+                    "{} has {} unknown key(s), which are ignored",
+                    name,
+                    unknown.len()
+                ))
+                .details(unknown)
+                .fix("Correct their spelling or remove them"),
+            );
+        }
+    }
+}
+
+/// Migrate an old config layout if asked to, or if the user agrees
+fn migrate_config_file(path: &Path, changes: Vec<String>, migrate: bool) -> Check {
+    let name = path.display();
+    let migrate = migrate || confirm(&format!("Migrate {} to the current layout?", name));
+    if !migrate {
+        return Check::warning(format!(
+            "{} uses the old [llm]/[secrets] layout, which is ignored",
+            name
+        ))
+        .details(changes)
+        .fix("Run `crustly doctor --migrate` to make these changes");
+    }
+    match config::migrate_file(path) {
+        Ok((backup, changes)) => Check::ok(format!(
+            "Migrated {} (the original is in {})",
+            name,
+            backup.display()
+        ))
+        .details(changes),
+        Err(e) => Check::failed(format!("Migrating {} failed", name))
+            .details([format!("{:#}", e)])
+            .fix("Make the changes by hand")
+            .details(changes),
+    }
+}
+
+/// Ask a yes/no question when a user is there to answer it
+fn confirm(question: &str) -> bool {
+    if !std::io::stdin().is_terminal() {
+        return false;
+    }
+    print!("❓ {} [y/N] ", question);
+    if std::io::stdout().flush().is_err() {
+        return false;
+    }
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Names of the providers in the configuration
+fn configured_providers(config: &Config) -> Vec<&'static str> {
+    let providers = &config.providers;
+    [
+        ("qwen", providers.qwen.is_some()),
+        ("openai", providers.openai.is_some()),
+        ("anthropic", providers.anthropic.is_some()),
+        ("gemini", providers.gemini.is_some()),
+        ("bedrock", providers.bedrock.is_some()),
+        ("azure", providers.azure.is_some()),
+        ("vertex", providers.vertex.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(name, configured)| configured.then_some(name))
+    .collect()
+}
+
+/// Where a provider's credentials come from
+fn credentials_fix(name: &str) -> String {
+    match name {
+        "anthropic" => "Set ANTHROPIC_API_KEY, or api_key in [providers.anthropic]".to_string(),
+        "openai" => "Set OPENAI_API_KEY, or OPENAI_BASE_URL for a local server".to_string(),
+        "qwen" => "Set DASHSCOPE_API_KEY, or QWEN_BASE_URL for a local server".to_string(),
+        other => format!("Set api_key in [providers.{}]", other),
+    }
+}
+
+async fn check_providers(report: &mut Report, config: &Config, offline: bool) {
+    let names = configured_providers(config);
+    if names.is_empty() {
+        report.add(Check::failed("No provider configured").fix(
+            "Set ANTHROPIC_API_KEY, OPENAI_API_KEY or DASHSCOPE_API_KEY, \
+                 or OPENAI_BASE_URL or QWEN_BASE_URL for a local server",
+        ));
+        return;
+    }
+
+    for name in names {
+        if UNSUPPORTED_PROVIDERS.contains(&name) {
+            report.add(
+                Check::warning(format!(
+                    "{}: configured, but Crustly can't use it yet",
+                    name
+                ))
+                .fix("Use anthropic, openai or qwen"),
+            );
+            continue;
+        }
+        let provider = match provider::create_named_provider(config, name) {
+            Ok(Some(provider)) => provider,
+            Ok(None) => {
+                report.add(
+                    Check::failed(format!("{}: no API key or base URL", name))
+                        .fix(credentials_fix(name)),
+                );
+                continue;
+            }
+            Err(e) => {
+                report.add(
+                    Check::failed(format!("{}: can't be set up", name))
+                        .details([format!("{:#}", e)]),
+                );
+                continue;
+            }
+        };
+        if offline {
+            report.add(Check::ok(format!(
+                "{}: credentials set (not contacted with --offline)",
+                name
+            )));
+            continue;
+        }
+
+        let model = provider.default_model().to_string();
+        let request =
+            LLMRequest::new(model.clone(), vec![Message::user("ping")]).with_max_tokens(1);
+        let started = Instant::now();
+        report.add(
+            match tokio::time::timeout(PROVIDER_TIMEOUT, provider.complete(request)).await {
+                Ok(Ok(_)) => Check::ok(format!(
+                    "{}: {} answered in {:.1}s",
+                    name,
+                    model,
+                    started.elapsed().as_secs_f64()
+                )),
+                Ok(Err(e)) => Check::failed(format!("{}: {} didn't answer", name, model))
+                    .details([e.to_string()])
+                    .fix(provider_error_fix(name, &e)),
+                Err(_) => Check::failed(format!(
+                    "{}: {} didn't answer within {}s",
+                    name,
+                    model,
+                    PROVIDER_TIMEOUT.as_secs()
+                ))
+                .fix("Check the base URL and that the server is running"),
+            },
+        );
+    }
+}
+
+/// What to do about a failed test request
+fn provider_error_fix(name: &str, error: &ProviderError) -> String {
+    match (error, error.status_code()) {
+        (ProviderError::InvalidApiKey, _) | (_, Some(401 | 403)) => {
+            format!("The API key was refused. {}", credentials_fix(name))
+        }
+        (ProviderError::ModelNotFound(_), _) | (_, Some(404)) => format!(
+            "Check default_model in [providers.{}], and the base URL if set",
+            name
+        ),
+        (ProviderError::RateLimitExceeded(_), _) | (_, Some(429)) => {
+            "The key works but hit a rate limit or quota; check the account".to_string()
+        }
+        (ProviderError::HttpError(_) | ProviderError::Timeout(_), _) => {
+            "Check the network, the base URL, and that the server is running".to_string()
+        }
+        _ => "See the error above".to_string(),
+    }
+}
+
+async fn check_database(report: &mut Report, path: &Path) {
+    let name = path.display();
+    if !path.exists() {
+        report.add(
+            Check::warning(format!("Database {} doesn't exist yet", name))
+                .fix("It's created on first use, or run `crustly db init`"),
+        );
+        return;
+    }
+    let db = match Database::open_read_only(path).await {
+        Ok(db) => db,
+        Err(e) => {
+            report.add(
+                Check::failed(format!("Database {} can't be opened", name))
+                    .details([format!("{:#}", e)])
+                    .fix("Check the file's permissions"),
+            );
+            return;
+        }
+    };
+
+    let start_over = "Move the file aside and run `crustly db init` for an empty database";
+    report.add(match db.integrity_problems().await {
+        Ok(problems) if problems.is_empty() => Check::ok(format!("Database {} is intact", name)),
+        Ok(problems) => Check::failed(format!("Database {} is damaged", name))
+            .details(problems.into_iter().take(5))
+            .fix(start_over),
+        Err(e) => Check::failed(format!("Database {} can't be checked", name))
+            .details([format!("{:#}", e)])
+            .fix(start_over),
+    });
+
+    match db.migration_status().await {
+        Ok(status) => {
+            let broken: Vec<String> = status
+                .failed
+                .iter()
+                .map(|m| format!("{} (failed)", m))
+                .chain(status.modified.iter().map(|m| format!("{} (changed)", m)))
+                .collect();
+            let clean = broken.is_empty();
+            if !clean {
+                report.add(
+                    Check::failed("Database migrations didn't apply cleanly")
+                        .details(broken)
+                        .fix(start_over),
+                );
+            }
+            if !status.pending.is_empty() {
+                report.add(
+                    Check::warning(format!(
+                        "{} database migration(s) pending",
+                        status.pending.len()
+                    ))
+                    .details(status.pending)
+                    .fix("They're applied when crustly next starts, or run `crustly db init`"),
+                );
+            }
+            if !status.unknown.is_empty() {
+                report.add(
+                    Check::warning("Database was migrated by a newer Crustly")
+                        .details(status.unknown)
+                        .fix("Upgrade Crustly"),
+                );
+            }
+            if status.applied > 0 && clean {
+                report.add(Check::ok(format!(
+                    "{} database migration(s) applied",
+                    status.applied
+                )));
+            }
+        }
+        Err(e) => report
+            .add(Check::failed("Database migrations can't be read").details([format!("{:#}", e)])),
+    }
+    let _ = db.close().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_error_fix() {
+        let refused = ProviderError::ApiError {
+            status: 401,
+            message: "invalid x-api-key".to_string(),
+            error_type: None,
+        };
+        assert_eq!(
+            provider_error_fix("anthropic", &refused),
+            "The API key was refused. Set ANTHROPIC_API_KEY, or api_key in [providers.anthropic]"
+        );
+        assert_eq!(
+            provider_error_fix("openai", &ProviderError::ModelNotFound("gpt-5".to_string())),
+            "Check default_model in [providers.openai], and the base URL if set"
+        );
+        assert_eq!(
+            provider_error_fix("qwen", &ProviderError::Timeout(30)),
+            "Check the network, the base URL, and that the server is running"
+        );
+    }
+
+    #[test]
+    fn test_config_files() {
+        let custom = PathBuf::from("/missing/crustly.toml");
+        assert_eq!(config_files(Some(&custom)), vec![custom]);
+        assert_eq!(
+            credentials_fix("vertex"),
+            "Set api_key in [providers.vertex]"
+        );
+    }
+}
@@ -2,8 +2,15 @@
 //!
 //! Command-line interface for Crustly using Clap v4.
 
+pub mod batch;
+pub mod completion;
+pub mod doctor;
+
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use chrono::{DateTime, TimeZone, Utc};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::engine::ArgValueCompleter;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 /// System prompt that encourages proactive tool usage for codebase exploration
@@ -19,11 +26,11 @@ When asked to analyze or explore a codebase:
 2. Use 'glob' tool with patterns like "**/*.rs", "**/*.toml", "**/*.md" to find files
 3. Use 'grep' tool to search for patterns, functions, or keywords in code
 4. Use 'read_file' tool to read specific files you've identified
-5. Use 'bash' tool for git operations like: git log, git diff, git branch
+5. Use 'git' tool for repository operations: status, diff, log, blame, show
 
 When asked to make changes:
 1. Use 'read_file' first to understand the current code
-2. Use 'edit_file' to modify existing files
+2. Use 'edit_file' to modify existing files, or 'apply_patch' when a change spans several files
 3. Use 'write_file' to create new files
 4. Use 'bash' to run tests or build commands
 
@@ -31,8 +38,13 @@ Available tools and when to use them:
 - ls: List directory contents (use recursive=true for deep exploration)
 - glob: Find files matching patterns (e.g., "**/*.rs" for all Rust files)
 - grep: Search for text/patterns in files (use for finding functions, TODOs, etc.)
+- lsp: Go to definition, find references, hover and symbol search through language servers
+- rename_symbol: Rename a function, type or variable everywhere it is used
+- git: Status, diff, log, blame and show; stage, commit, branch, stash and worktrees
+- code_outline: List the functions and types of a file or directory with line ranges, or read one symbol's source (cheaper than reading whole files)
 - read_file: Read file contents
 - edit_file: Modify existing files
+- apply_patch: Change several files at once with a unified diff or edit list (all-or-nothing)
 - write_file: Create new files
 - bash: Run shell commands (git, cargo, npm, etc.)
 - execute_code: Test code snippets
@@ -40,6 +52,7 @@ Available tools and when to use them:
 - http_request: Call external APIs
 - task_manager: Track multi-step work
 - session_context: Remember important facts
+- read_output: Page through tool output that was truncated (use the artifact_id from the notice)
 - plan: Create structured plans for complex tasks (use when user requests require multiple coordinated steps)
 
 CRITICAL: PLAN TOOL USAGE
@@ -113,20 +126,61 @@ pub struct Cli {
 pub enum Commands {
     /// Start interactive TUI mode (default)
     Chat {
-        /// Session ID to resume
-        #[arg(short, long)]
+        /// Session ID (or a unique prefix of it) to resume
+        #[arg(short, long, add = ArgValueCompleter::new(completion::session_ids))]
         session: Option<String>,
+
+        /// Resume the most recent session
+        #[arg(long = "continue", conflicts_with = "session")]
+        continue_session: bool,
     },
 
     /// Run a single command non-interactively
+    ///
+    /// Tools that need approval are denied unless allowed with --allow or
+    /// --auto-approve; --read-only also approves calls that only read.
+    ///
+    /// Exit codes: 0 success, 1 error, 3 a tool call was denied, 4 too many
+    /// tool iterations, 5 provider error, 6 some prompts of a batch failed.
     Run {
-        /// The prompt to execute
+        /// The prompt to execute, or `-` to read it from stdin
+        ///
+        /// Otherwise, anything piped to stdin is attached to the prompt
+        /// (`git diff | crustly run "Review this"`).
         prompt: String,
 
+        /// Attach a file to the prompt (repeatable)
+        #[arg(long, value_name = "PATH")]
+        file: Vec<PathBuf>,
+
+        /// Don't read stdin, even when it isn't a terminal
+        #[arg(long)]
+        no_stdin: bool,
+
+        /// Write the answer to this file instead of stdout
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+
         /// Auto-approve all tool executions (dangerous!)
         #[arg(long, alias = "yolo")]
         auto_approve: bool,
 
+        /// Run in Plan mode: only calls that just read are approved
+        #[arg(long)]
+        read_only: bool,
+
+        /// Approve calls to a tool that needs approval (repeatable)
+        #[arg(long, value_name = "TOOL")]
+        allow: Vec<String>,
+
+        /// Append to this session (ID or a unique prefix of it)
+        #[arg(short, long, add = ArgValueCompleter::new(completion::session_ids))]
+        session: Option<String>,
+
+        /// Append to the most recent session
+        #[arg(long = "continue", conflicts_with = "session")]
+        continue_session: bool,
+
         /// Output format
         #[arg(short, long, default_value = "text")]
         format: OutputFormat,
@@ -146,6 +200,12 @@ pub enum Commands {
         show_secrets: bool,
     },
 
+    /// Session management
+    Sessions {
+        #[command(subcommand)]
+        operation: SessionCommands,
+    },
+
     /// Database operations
     Db {
         #[command(subcommand)]
@@ -163,6 +223,71 @@ pub enum Commands {
         #[command(subcommand)]
         operation: KeyringCommands,
     },
+
+    /// Model Context Protocol operations
+    Mcp {
+        #[command(subcommand)]
+        operation: McpCommands,
+    },
+
+    /// Run many prompts from a JSONL file without a terminal
+    ///
+    /// Results are appended to the results file as each prompt finishes;
+    /// running the same batch again skips prompts that already have one.
+    ///
+    /// Exit codes: 0 every prompt succeeded, 1 error, 6 some prompts failed
+    /// or timed out.
+    Batch {
+        /// File with one JSON prompt per line
+        input: PathBuf,
+
+        /// Results file [default: <input>.results.jsonl]
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+
+        /// Prompts run at the same time
+        #[arg(short, long, default_value_t = batch::DEFAULT_JOBS)]
+        jobs: usize,
+
+        /// Time limit of each prompt, in seconds
+        #[arg(long, value_name = "SECS", default_value_t = batch::DEFAULT_TIMEOUT_SECS)]
+        timeout: u64,
+
+        /// Run prompts again whose last result failed or timed out
+        #[arg(long)]
+        retry_failed: bool,
+    },
+
+    /// Check the configuration, providers, keyring and database
+    ///
+    /// Each problem is reported with a fix. Exits with 1 if any check failed.
+    Doctor {
+        /// Don't send test requests to providers
+        #[arg(long)]
+        offline: bool,
+
+        /// Migrate config files in the old [llm]/[secrets] layout without
+        /// asking (the original is kept as <file>.bak)
+        #[arg(long)]
+        migrate: bool,
+    },
+
+    /// Print a shell completion script
+    ///
+    /// The script asks crustly for completions as you type, so session IDs
+    /// and model names are always current. For example, in ~/.bashrc:
+    /// `source <(crustly completions bash)`
+    Completions {
+        /// Shell to complete in
+        shell: clap_complete::Shell,
+    },
+
+    /// Print the man page, or write pages for all subcommands to a directory
+    Man {
+        /// Directory to write crustly.1 and a page per subcommand to
+        #[arg(short, long, value_name = "DIR")]
+        output: Option<PathBuf>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -185,6 +310,119 @@ pub enum LogCommands {
     Open,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum SessionCommands {
+    /// List sessions, most recently active first
+    List {
+        /// Include archived sessions
+        #[arg(short, long)]
+        archived: bool,
+        /// Only sessions active on or after this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_parser = parse_since)]
+        since: Option<DateTime<Utc>>,
+        /// Only sessions active on or before this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_parser = parse_until)]
+        until: Option<DateTime<Utc>>,
+        /// Only sessions whose model contains this text
+        #[arg(short, long, add = ArgValueCompleter::new(completion::models))]
+        model: Option<String>,
+        /// Only sessions that cost at least this much (USD)
+        #[arg(long)]
+        min_cost: Option<f64>,
+        /// Only sessions that cost at most this much (USD)
+        #[arg(long)]
+        max_cost: Option<f64>,
+        /// Maximum number of sessions to show
+        #[arg(short, long)]
+        limit: Option<usize>,
+        /// Output format
+        #[arg(short, long, default_value = "table")]
+        format: ListFormat,
+    },
+    /// Show a session and its messages
+    Show {
+        /// Session ID or a unique prefix of it
+        #[arg(add = ArgValueCompleter::new(completion::session_ids))]
+        session: String,
+        /// Output format
+        #[arg(short, long, default_value = "table")]
+        format: ListFormat,
+    },
+    /// Rename a session
+    Rename {
+        /// Session ID or a unique prefix of it
+        #[arg(add = ArgValueCompleter::new(completion::session_ids))]
+        session: String,
+        /// New title
+        title: String,
+    },
+    /// Archive a session (hidden from lists unless --archived)
+    Archive {
+        /// Session ID or a unique prefix of it
+        #[arg(add = ArgValueCompleter::new(completion::session_ids))]
+        session: String,
+    },
+    /// Unarchive a session
+    Unarchive {
+        /// Session ID or a unique prefix of it
+        #[arg(add = ArgValueCompleter::new(completion::session_ids))]
+        session: String,
+    },
+    /// Delete a session and its messages permanently
+    Delete {
+        /// Session ID or a unique prefix of it
+        #[arg(add = ArgValueCompleter::new(completion::session_ids))]
+        session: String,
+        /// Skip confirmation prompt (use with caution)
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Export a session and its messages as JSON
+    Export {
+        /// Session ID or a unique prefix of it
+        #[arg(add = ArgValueCompleter::new(completion::session_ids))]
+        session: String,
+        /// File to write (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import a session exported with `sessions export`, as a new session
+    Import {
+        /// File to read, or `-` for stdin
+        input: String,
+    },
+}
+
+/// Output format for listings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListFormat {
+    Table,
+    Json,
+}
+
+/// Parse a `--since` date; a bare date means the start of that day (UTC)
+fn parse_since(value: &str) -> std::result::Result<DateTime<Utc>, String> {
+    parse_date(value, chrono::NaiveTime::MIN)
+}
+
+/// Parse an `--until` date; a bare date means the end of that day (UTC)
+fn parse_until(value: &str) -> std::result::Result<DateTime<Utc>, String> {
+    let end_of_day = chrono::NaiveTime::from_hms_opt(23, 59, 59).unwrap_or_default();
+    parse_date(value, end_of_day)
+}
+
+fn parse_date(
+    value: &str,
+    time_of_day: chrono::NaiveTime,
+) -> std::result::Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| Utc.from_utc_datetime(&date.and_time(time_of_day)))
+        .map_err(|_| format!("invalid date '{}': use YYYY-MM-DD or RFC 3339", value))
+}
+
 #[derive(Subcommand, Debug)]
 pub enum DbCommands {
     /// Initialize database
@@ -204,6 +442,7 @@ pub enum KeyringCommands {
     /// Store an API key in OS keyring
     Set {
         /// Provider name (anthropic, openai, gemini, azure)
+        #[arg(add = ArgValueCompleter::new(completion::providers))]
         provider: String,
         /// API key to store
         api_key: String,
@@ -211,22 +450,154 @@ pub enum KeyringCommands {
     /// Retrieve an API key from OS keyring
     Get {
         /// Provider name
+        #[arg(add = ArgValueCompleter::new(completion::providers))]
         provider: String,
     },
     /// Delete an API key from OS keyring
     Delete {
         /// Provider name
+        #[arg(add = ArgValueCompleter::new(completion::providers))]
         provider: String,
     },
     /// List all stored providers
     List,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum McpCommands {
+    /// Serve Crustly's tools to MCP clients over stdio
+    Serve {
+        /// Run every tool in read-only mode
+        #[arg(long)]
+        read_only: bool,
+
+        /// Run tools that need approval without asking (dangerous!)
+        #[arg(long, alias = "yolo")]
+        auto_approve: bool,
+
+        /// Allow a tool that needs approval (repeatable)
+        #[arg(long, value_name = "TOOL")]
+        allow: Vec<String>,
+
+        /// Hide a tool from clients (repeatable)
+        #[arg(long, value_name = "TOOL")]
+        deny: Vec<String>,
+    },
+}
+
 #[derive(Debug, Clone, Copy, clap::ValueEnum)]
 pub enum OutputFormat {
     Text,
     Json,
     Markdown,
+    /// Newline-delimited JSON events, written as they happen
+    StreamJson,
+}
+
+/// Which session a command works in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionChoice {
+    /// The command's usual choice: the most recent session for `chat`, a
+    /// new one for `run`
+    Default,
+    /// The most recent session
+    Continue,
+    /// A session by ID or unique ID prefix
+    Id(String),
+}
+
+impl SessionChoice {
+    /// Choice made by the `--session` and `--continue` flags
+    pub fn from_args(session: Option<String>, continue_session: bool) -> Self {
+        match session {
+            Some(id) => Self::Id(id),
+            None if continue_session => Self::Continue,
+            None => Self::Default,
+        }
+    }
+
+    /// The chosen existing session, or `None` for the default
+    async fn resolve(
+        &self,
+        session_service: &crate::services::SessionService,
+    ) -> Result<Option<crate::db::models::Session>> {
+        match self {
+            Self::Default => Ok(None),
+            Self::Continue => session_service
+                .get_most_recent_session()
+                .await?
+                .map(Some)
+                .ok_or_else(|| anyhow::anyhow!("No session to continue")),
+            Self::Id(id) => session_service.resolve_session(id).await.map(Some),
+        }
+    }
+}
+
+/// What `crustly run` reads and where its answer goes
+struct RunInputs {
+    prompt: String,
+    files: Vec<PathBuf>,
+    /// Attach stdin when it isn't a terminal
+    read_stdin: bool,
+    output: Option<PathBuf>,
+}
+
+/// How `crustly run` answers tool approval requests, with nobody to ask
+#[derive(Debug, Clone, Default)]
+pub struct ApprovalPolicy {
+    /// Approve every call
+    pub auto_approve: bool,
+    /// Run in Plan mode, approving only calls that can't change anything
+    pub read_only: bool,
+    /// Tools whose calls are approved
+    pub allow: Vec<String>,
+}
+
+impl ApprovalPolicy {
+    /// Whether a call to `tool` with `input` that needs approval may run
+    pub fn approves(&self, tool: &dyn crate::llm::tools::Tool, input: &serde_json::Value) -> bool {
+        use crate::llm::tools::ToolCapability;
+
+        if self.auto_approve || self.allow.iter().any(|name| name == tool.name()) {
+            return true;
+        }
+
+        // Plan mode doesn't rely on each tool refusing to write by itself
+        self.read_only
+            && !tool.call_capabilities(input).iter().any(|capability| {
+                matches!(
+                    capability,
+                    ToolCapability::WriteFiles
+                        | ToolCapability::ExecuteShell
+                        | ToolCapability::Network
+                        | ToolCapability::SystemModification
+                )
+            })
+    }
+}
+
+/// Exit codes of `crustly run` and `crustly batch`
+///
+/// Listed in the help of both commands; keep them in sync.
+pub mod exit_code {
+    /// A tool call was denied by the approval policy
+    pub const TOOL_DENIED: i32 = 3;
+    /// The agent hit the tool iteration limit
+    pub const MAX_ITERATIONS: i32 = 4;
+    /// The LLM provider failed
+    pub const PROVIDER_ERROR: i32 = 5;
+    /// Some prompts of a batch failed or timed out
+    pub const BATCH_FAILED: i32 = 6;
+}
+
+/// Failure that ends the process with a specific exit code
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct ExitError {
+    /// Process exit code
+    pub code: i32,
+    /// Message printed to stderr
+    pub message: String,
 }
 
 /// Main CLI entry point
@@ -238,28 +609,81 @@ pub async fn run() -> Result<()> {
         tracing::info!("Debug mode enabled");
     }
 
+    // Generated output doesn't depend on the configuration
+    match cli.command {
+        Some(Commands::Completions { shell }) => return cmd_completions(shell),
+        Some(Commands::Man { output }) => return cmd_man(output),
+        // Runs even when the configuration doesn't load
+        Some(Commands::Doctor { offline, migrate }) => {
+            let options = doctor::DoctorOptions {
+                config_path: cli.config.map(PathBuf::from),
+                offline,
+                migrate,
+            };
+            return doctor::run(options).await;
+        }
+        _ => {}
+    }
+
     // Load configuration
     let config = load_config(cli.config.as_deref()).await?;
 
     match cli.command {
-        None | Some(Commands::Chat { session: _ }) => {
-            // Default: Interactive TUI mode
-            let session = match &cli.command {
-                Some(Commands::Chat { session }) => session.clone(),
-                _ => None,
-            };
-            cmd_chat(&config, session).await
+        None => cmd_chat(&config, SessionChoice::Default).await,
+        Some(Commands::Chat {
+            session,
+            continue_session,
+        }) => {
+            let choice = SessionChoice::from_args(session, continue_session);
+            cmd_chat(&config, choice).await
         }
         Some(Commands::Init { force }) => cmd_init(&config, force).await,
         Some(Commands::Config { show_secrets }) => cmd_config(&config, show_secrets).await,
+        Some(Commands::Sessions { operation }) => cmd_sessions(&config, operation).await,
         Some(Commands::Db { operation }) => cmd_db(&config, operation).await,
         Some(Commands::Logs { operation }) => cmd_logs(operation).await,
         Some(Commands::Keyring { operation }) => cmd_keyring(operation).await,
+        Some(Commands::Mcp { operation }) => cmd_mcp(&config, operation).await,
+        Some(Commands::Batch {
+            input,
+            output,
+            jobs,
+            timeout,
+            retry_failed,
+        }) => {
+            let output = output.unwrap_or_else(|| batch::default_output_path(&input));
+            cmd_batch(&config, &input, &output, jobs, timeout, retry_failed).await
+        }
+        // Handled above
+        Some(Commands::Completions { .. } | Commands::Man { .. } | Commands::Doctor { .. }) => {
+            Ok(())
+        }
         Some(Commands::Run {
             prompt,
+            file,
+            no_stdin,
+            output,
             auto_approve,
+            read_only,
+            allow,
+            session,
+            continue_session,
             format,
-        }) => cmd_run(&config, prompt, auto_approve, format).await,
+        }) => {
+            let inputs = RunInputs {
+                prompt,
+                files: file,
+                read_stdin: !no_stdin,
+                output,
+            };
+            let policy = ApprovalPolicy {
+                auto_approve,
+                read_only,
+                allow,
+            };
+            let choice = SessionChoice::from_args(session, continue_session);
+            cmd_run(&config, inputs, policy, choice, format).await
+        }
     }
 }
 
@@ -460,6 +884,13 @@ async fn cmd_db(config: &crate::config::Config, operation: DbCommands) -> Result
                 .execute(db.pool())
                 .await?;
 
+            // Stored tool output belongs to the deleted sessions
+            let artifacts = crate::llm::tools::output::artifacts_root();
+            if artifacts.exists() {
+                std::fs::remove_dir_all(&artifacts)
+                    .with_context(|| format!("Failed to remove {}", artifacts.display()))?;
+            }
+
             println!(
                 "✅ Successfully cleared {} sessions, {} messages, and {} files",
                 session_count, message_count, file_count
@@ -470,21 +901,355 @@ async fn cmd_db(config: &crate::config::Config, operation: DbCommands) -> Result
     }
 }
 
+/// Print the completion script for a shell
+fn cmd_completions(shell: clap_complete::Shell) -> Result<()> {
+    completion::write_registration(shell, &mut std::io::stdout())
+        .context("Failed to write completion script")
+}
+
+/// Print the man page, or write all pages to a directory
+fn cmd_man(output: Option<PathBuf>) -> Result<()> {
+    match output {
+        Some(dir) => {
+            completion::write_man_pages(Cli::command(), &dir)
+                .with_context(|| format!("Failed to write man pages to {}", dir.display()))?;
+            eprintln!("✅ Wrote man pages to {}", dir.display());
+        }
+        None => clap_mangen::Man::new(Cli::command())
+            .render(&mut std::io::stdout())
+            .context("Failed to write man page")?,
+    }
+    Ok(())
+}
+
+/// Session management commands
+async fn cmd_sessions(config: &crate::config::Config, operation: SessionCommands) -> Result<()> {
+    use crate::{
+        db::{repository::SessionListOptions, Database},
+        services::{ServiceContext, SessionExport, SessionFilter, SessionService},
+    };
+
+    let db = Database::connect(&config.database.path).await?;
+    db.run_migrations().await?;
+    let service = SessionService::new(ServiceContext::new(db.pool().clone()));
+
+    match operation {
+        SessionCommands::List {
+            archived,
+            since,
+            until,
+            model,
+            min_cost,
+            max_cost,
+            limit,
+            format,
+        } => {
+            let filter = SessionFilter {
+                since,
+                until,
+                model,
+                min_cost,
+                max_cost,
+            };
+            let sessions: Vec<_> = service
+                .list_sessions(SessionListOptions {
+                    include_archived: archived,
+                    limit: None,
+                    offset: 0,
+                })
+                .await?
+                .into_iter()
+                .filter(|session| filter.matches(session))
+                .take(limit.unwrap_or(usize::MAX))
+                .collect();
+
+            match format {
+                ListFormat::Json => println!("{}", serde_json::to_string_pretty(&sessions)?),
+                ListFormat::Table if sessions.is_empty() => println!("No sessions found"),
+                ListFormat::Table => {
+                    println!(
+                        "{:<8}  {:<16}  {:<28}  {:>9}  {:>10}  TITLE",
+                        "ID", "UPDATED", "MODEL", "TOKENS", "COST"
+                    );
+                    for session in &sessions {
+                        let mut title = session.title.clone().unwrap_or_default();
+                        if session.archived_at.is_some() {
+                            title.push_str(" (archived)");
+                        }
+                        println!(
+                            "{:<8}  {:<16}  {:<28}  {:>9}  {:>10}  {}",
+                            &session.id.to_string()[..8],
+                            session.updated_at.format("%Y-%m-%d %H:%M"),
+                            session.model.as_deref().unwrap_or("-"),
+                            session.token_count,
+                            format!("${:.4}", session.total_cost),
+                            title
+                        );
+                    }
+                }
+            }
+            Ok(())
+        }
+        SessionCommands::Show { session, format } => {
+            let session = service.resolve_session(&session).await?;
+            let export = service.export_session(session.id).await?;
+
+            match format {
+                ListFormat::Json => println!("{}", serde_json::to_string_pretty(&export)?),
+                ListFormat::Table => {
+                    let session = &export.session;
+                    println!(
+                        "📝 {}",
+                        session.title.as_deref().unwrap_or("Untitled session")
+                    );
+                    println!("ID:       {}", session.id);
+                    println!("Model:    {}", session.model.as_deref().unwrap_or("-"));
+                    println!("Created:  {}", session.created_at.to_rfc3339());
+                    println!("Updated:  {}", session.updated_at.to_rfc3339());
+                    if let Some(archived_at) = session.archived_at {
+                        println!("Archived: {}", archived_at.to_rfc3339());
+                    }
+                    println!("Tokens:   {}", session.token_count);
+                    println!("Cost:     ${:.6}", session.total_cost);
+                    for message in &export.messages {
+                        println!(
+                            "\n── {} ({}) ──",
+                            message.role,
+                            message.created_at.format("%Y-%m-%d %H:%M")
+                        );
+                        println!("{}", message.content);
+                    }
+                }
+            }
+            Ok(())
+        }
+        SessionCommands::Rename { session, title } => {
+            let session = service.resolve_session(&session).await?;
+            service
+                .update_session_title(session.id, Some(title.clone()))
+                .await?;
+            println!("✅ Renamed session {} to '{}'", session.id, title);
+            Ok(())
+        }
+        SessionCommands::Archive { session } => {
+            let session = service.resolve_session(&session).await?;
+            service.archive_session(session.id).await?;
+            println!("📦 Archived session {}", session.id);
+            Ok(())
+        }
+        SessionCommands::Unarchive { session } => {
+            let session = service.resolve_session(&session).await?;
+            service.unarchive_session(session.id).await?;
+            println!("✅ Unarchived session {}", session.id);
+            Ok(())
+        }
+        SessionCommands::Delete { session, force } => {
+            let session = service.resolve_session(&session).await?;
+
+            if !force {
+                use std::io::{self, Write};
+                print!(
+                    "Delete session {} ({}) and its messages? Type 'yes' to confirm: ",
+                    session.id,
+                    session.title.as_deref().unwrap_or("untitled")
+                );
+                io::stdout().flush()?;
+
+                let mut input = String::new();
+                io::stdin().read_line(&mut input)?;
+
+                if input.trim().to_lowercase() != "yes" {
+                    println!("❌ Cancelled - nothing was deleted");
+                    return Ok(());
+                }
+            }
+
+            service.delete_session(session.id).await?;
+            println!("🗑️  Deleted session {}", session.id);
+            Ok(())
+        }
+        SessionCommands::Export { session, output } => {
+            let session = service.resolve_session(&session).await?;
+            let export = service.export_session(session.id).await?;
+            let json = serde_json::to_string_pretty(&export)?;
+
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, json)
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    eprintln!(
+                        "✅ Exported session {} ({} messages) to {}",
+                        session.id,
+                        export.messages.len(),
+                        path.display()
+                    );
+                }
+                None => println!("{}", json),
+            }
+            Ok(())
+        }
+        SessionCommands::Import { input } => {
+            let json = if input == "-" {
+                let mut json = String::new();
+                std::io::Read::read_to_string(&mut std::io::stdin(), &mut json)
+                    .context("Failed to read session from stdin")?;
+                json
+            } else {
+                std::fs::read_to_string(&input)
+                    .with_context(|| format!("Failed to read {}", input))?
+            };
+            let export: SessionExport =
+                serde_json::from_str(&json).context("Not a session export")?;
+            let message_count = export.messages.len();
+
+            let session = service.import_session(export).await?;
+            println!(
+                "✅ Imported session {} ({} messages)",
+                session.id, message_count
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Create the language server manager; servers start when first used
+fn lsp_manager(
+    config: &crate::config::Config,
+    working_directory: &std::path::Path,
+) -> Arc<crate::lsp::LspManager> {
+    Arc::new(
+        crate::lsp::LspManager::new(&config.lsp, working_directory.to_path_buf())
+            .with_trace(config.debug.debug_lsp),
+    )
+}
+
+/// Create the repository map for the system prompt, if enabled
+fn repo_map(
+    config: &crate::config::Config,
+    working_directory: &std::path::Path,
+) -> Option<Arc<crate::llm::prompt::RepoMap>> {
+    if !config.repo_map.enabled {
+        return None;
+    }
+    let root = working_directory.to_path_buf();
+    let cache_path = crate::llm::prompt::RepoMap::default_cache_path(&root);
+    Some(Arc::new(
+        crate::llm::prompt::RepoMap::new(root, config.repo_map.max_tokens)
+            .with_cache_path(cache_path),
+    ))
+}
+
+/// Create the project instruction file loader, if enabled
+fn project_context(
+    config: &crate::config::Config,
+    working_directory: &std::path::Path,
+) -> Option<Arc<crate::llm::prompt::ProjectContext>> {
+    let settings = &config.context_files;
+    if !settings.enabled {
+        return None;
+    }
+    Some(Arc::new(crate::llm::prompt::ProjectContext::new(
+        working_directory.to_path_buf(),
+        settings.max_file_chars,
+        settings.max_total_chars,
+    )))
+}
+
+/// Create the auto-commit mode, if enabled
+fn auto_commit(config: &crate::config::Config) -> Option<crate::llm::agent::AutoCommit> {
+    let settings = &config.auto_commit;
+    settings.enabled.then(|| {
+        crate::llm::agent::AutoCommit::new(
+            settings.branch.clone(),
+            settings.author_name.clone(),
+            settings.author_email.clone(),
+        )
+    })
+}
+
+/// Create the tool registry shared by interactive and non-interactive modes
+fn build_tool_registry(
+    config: &crate::config::Config,
+    lsp: &Arc<crate::lsp::LspManager>,
+) -> crate::llm::tools::ToolRegistry {
+    use crate::llm::tools::{
+        apply_patch::ApplyPatchTool,
+        bash::BashTool,
+        code_exec::CodeExecTool,
+        context::ContextTool,
+        doc_parser::DocParserTool,
+        edit::EditTool,
+        git::GitTool,
+        glob::GlobTool,
+        grep::GrepTool,
+        http::HttpClientTool,
+        ls::LsTool,
+        lsp::{LspTool, RenameSymbolTool},
+        notebook::NotebookEditTool,
+        outline::CodeOutlineTool,
+        plan_tool::PlanTool,
+        read::ReadTool,
+        read_output::ReadOutputTool,
+        task::TaskTool,
+        web_search::WebSearchTool,
+        write::WriteTool,
+        OutputPolicy, ToolRegistry,
+    };
+
+    let mut tool_registry = ToolRegistry::new().with_output_policy(OutputPolicy::new(
+        config.tools.max_output_chars,
+        config.tools.max_output_lines,
+    ));
+    // Phase 1: Essential file operations
+    tool_registry.register(Arc::new(ReadTool));
+    tool_registry.register(Arc::new(WriteTool));
+    tool_registry.register(Arc::new(EditTool));
+    tool_registry.register(Arc::new(ApplyPatchTool));
+    tool_registry.register(Arc::new(BashTool));
+    tool_registry.register(Arc::new(LsTool));
+    tool_registry.register(Arc::new(GlobTool));
+    tool_registry.register(Arc::new(GrepTool));
+    // Phase 2: Advanced features
+    tool_registry.register(Arc::new(WebSearchTool));
+    tool_registry.register(Arc::new(CodeExecTool));
+    tool_registry.register(Arc::new(NotebookEditTool));
+    tool_registry.register(Arc::new(DocParserTool));
+    // Phase 3: Workflow & integration
+    tool_registry.register(Arc::new(TaskTool));
+    tool_registry.register(Arc::new(ContextTool));
+    tool_registry.register(Arc::new(HttpClientTool));
+    tool_registry.register(Arc::new(PlanTool));
+    tool_registry.register(Arc::new(ReadOutputTool));
+    tool_registry.register(Arc::new(LspTool::new(lsp.clone())));
+    tool_registry.register(Arc::new(RenameSymbolTool::new(lsp.clone())));
+    tool_registry.register(Arc::new(CodeOutlineTool));
+    tool_registry.register(Arc::new(GitTool));
+    tool_registry
+}
+
+/// Start the configured MCP servers and register their tools
+async fn start_mcp_servers(
+    config: &crate::config::Config,
+    tool_registry: &mut crate::llm::tools::ToolRegistry,
+) -> crate::mcp::McpManager {
+    let manager = crate::mcp::McpManager::start(&config.mcp).await;
+    let registered = manager.register_tools(tool_registry);
+    if !manager.clients().is_empty() {
+        tracing::info!(
+            "Registered {} tool(s) from {} MCP server(s)",
+            registered,
+            manager.clients().len()
+        );
+    }
+    manager
+}
+
 /// Start interactive chat session
-async fn cmd_chat(config: &crate::config::Config, _session_id: Option<String>) -> Result<()> {
+async fn cmd_chat(config: &crate::config::Config, session: SessionChoice) -> Result<()> {
     use crate::{
         db::Database,
-        llm::{
-            agent::AgentService,
-            tools::{
-                bash::BashTool, code_exec::CodeExecTool, context::ContextTool,
-                doc_parser::DocParserTool, edit::EditTool, glob::GlobTool, grep::GrepTool,
-                http::HttpClientTool, ls::LsTool, notebook::NotebookEditTool, plan_tool::PlanTool,
-                read::ReadTool, registry::ToolRegistry, task::TaskTool, web_search::WebSearchTool,
-                write::WriteTool,
-            },
-        },
-        services::ServiceContext,
+        llm::agent::AgentService,
+        services::{ServiceContext, SessionService},
         tui,
     };
 
@@ -660,34 +1425,18 @@ async fn cmd_chat(config: &crate::config::Config, _session_id: Option<String>) -
     // Helper function for fallback provider - REMOVED, now in factory module
     */
 
+    // Get working directory
+    let working_directory = std::env::current_dir().unwrap_or_default();
+
     // Create tool registry
     tracing::debug!("Setting up tool registry");
-    let mut tool_registry = ToolRegistry::new();
-    // Phase 1: Essential file operations
-    tool_registry.register(Arc::new(ReadTool));
-    tool_registry.register(Arc::new(WriteTool));
-    tool_registry.register(Arc::new(EditTool));
-    tool_registry.register(Arc::new(BashTool));
-    tool_registry.register(Arc::new(LsTool));
-    tool_registry.register(Arc::new(GlobTool));
-    tool_registry.register(Arc::new(GrepTool));
-    // Phase 2: Advanced features
-    tool_registry.register(Arc::new(WebSearchTool));
-    tool_registry.register(Arc::new(CodeExecTool));
-    tool_registry.register(Arc::new(NotebookEditTool));
-    tool_registry.register(Arc::new(DocParserTool));
-    // Phase 3: Workflow & integration
-    tool_registry.register(Arc::new(TaskTool));
-    tool_registry.register(Arc::new(ContextTool));
-    tool_registry.register(Arc::new(HttpClientTool));
-    tool_registry.register(Arc::new(PlanTool));
+    let lsp_manager = lsp_manager(config, &working_directory);
+    let mut tool_registry = build_tool_registry(config, &lsp_manager);
+    let mcp_manager = Arc::new(start_mcp_servers(config, &mut tool_registry).await);
 
     // Create service context
     let service_context = ServiceContext::new(db.pool().clone());
 
-    // Get working directory
-    let working_directory = std::env::current_dir().unwrap_or_default();
-
     // Create agent service with system prompt and working directory
     let agent_service = Arc::new(
         AgentService::new(provider.clone(), service_context.clone())
@@ -699,6 +1448,14 @@ async fn cmd_chat(config: &crate::config::Config, _session_id: Option<String>) -
     // Create TUI app first (so we can get the event sender)
     tracing::debug!("Creating TUI app");
     let mut app = tui::App::new(agent_service, service_context.clone());
+    app.set_mcp_manager(mcp_manager.clone());
+    if let Some(session) = session
+        .resolve(&SessionService::new(service_context.clone()))
+        .await?
+    {
+        tracing::info!("Resuming session {}", session.id);
+        app.set_initial_session(session.id);
+    }
 
     // Get event sender from app
     let event_sender = app.event_sender();
@@ -720,6 +1477,7 @@ async fn cmd_chat(config: &crate::config::Config, _session_id: Option<String>) -
                 tool_description: tool_info.tool_description,
                 tool_input: tool_info.tool_input,
                 capabilities: tool_info.capabilities,
+                preview: tool_info.preview,
                 response_tx,
                 requested_at: std::time::Instant::now(),
             };
@@ -741,10 +1499,29 @@ async fn cmd_chat(config: &crate::config::Config, _session_id: Option<String>) -
                 )
             })?;
 
-            Ok(response.approved)
+            Ok(match (response.approved, response.edited_input) {
+                (true, Some(input)) => {
+                    crate::llm::agent::ApprovalDecision::ApprovedWithInput(input)
+                }
+                (approved, _) => approved.into(),
+            })
         })
     });
 
+    // Forward live tool output to the TUI
+    let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+    let progress_event_sender = app.event_sender();
+    tokio::spawn(async move {
+        while let Some(event) = progress_rx.recv().await {
+            if progress_event_sender
+                .send(crate::tui::events::TuiEvent::ToolProgress(event))
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
     // Create agent service with approval callback
     tracing::debug!("Creating agent service with approval callback");
     let agent_service = Arc::new(
@@ -752,6 +1529,11 @@ async fn cmd_chat(config: &crate::config::Config, _session_id: Option<String>) -
             .with_system_prompt(SYSTEM_PROMPT.to_string())
             .with_tool_registry(Arc::new(tool_registry))
             .with_approval_callback(Some(approval_callback))
+            .with_progress_sender(Some(progress_tx))
+            .with_lsp_manager(Some(lsp_manager.clone()))
+            .with_project_context(project_context(config, &working_directory))
+            .with_repo_map(repo_map(config, &working_directory))
+            .with_auto_commit(auto_commit(config))
             .with_max_tool_iterations(20)
             .with_working_directory(working_directory),
     );
@@ -761,7 +1543,10 @@ async fn cmd_chat(config: &crate::config::Config, _session_id: Option<String>) -
 
     // Run TUI
     tracing::debug!("Launching TUI");
-    tui::run(app).await.context("TUI error")?;
+    let result = tui::run(app).await.context("TUI error");
+    mcp_manager.shutdown().await;
+    lsp_manager.shutdown().await;
+    result?;
 
     println!("\n👋 Goodbye!");
 
@@ -771,27 +1556,55 @@ async fn cmd_chat(config: &crate::config::Config, _session_id: Option<String>) -
 /// Run a single command non-interactively
 async fn cmd_run(
     config: &crate::config::Config,
-    prompt: String,
-    auto_approve: bool,
+    inputs: RunInputs,
+    policy: ApprovalPolicy,
+    session: SessionChoice,
     format: OutputFormat,
 ) -> Result<()> {
     use crate::{
         db::Database,
-        llm::{
-            agent::AgentService,
-            tools::{
-                bash::BashTool, code_exec::CodeExecTool, context::ContextTool,
-                doc_parser::DocParserTool, edit::EditTool, glob::GlobTool, grep::GrepTool,
-                http::HttpClientTool, ls::LsTool, notebook::NotebookEditTool, plan_tool::PlanTool,
-                read::ReadTool, registry::ToolRegistry, task::TaskTool, web_search::WebSearchTool,
-                write::WriteTool,
-            },
-        },
+        llm::agent::{AgentError, AgentEvent, AgentService, ApprovalCallback, ApprovalDecision},
         services::{ServiceContext, SessionService},
     };
+    use std::io::IsTerminal;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    if inputs.output.is_some() && matches!(format, OutputFormat::StreamJson) {
+        anyhow::bail!("--output can't be used with --format stream-json");
+    }
+
+    let read_stdin = || -> Result<String> {
+        let mut input = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut input)
+            .context("Failed to read stdin")?;
+        Ok(input)
+    };
+    let (prompt, stdin) = if inputs.prompt == "-" {
+        let input = read_stdin()?.trim().to_string();
+        if input.is_empty() {
+            anyhow::bail!("No prompt on stdin");
+        }
+        (input, None)
+    } else if inputs.read_stdin && !std::io::stdin().is_terminal() {
+        let input = read_stdin()?;
+        (
+            inputs.prompt,
+            Some(input).filter(|input| !input.trim().is_empty()),
+        )
+    } else {
+        (inputs.prompt, None)
+    };
 
     tracing::info!("Running non-interactive command: {}", prompt);
 
+    let mut files = Vec::new();
+    for path in &inputs.files {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        files.push((path.clone(), content));
+    }
+    let prompt = attach_context(&prompt, stdin.as_deref(), &files);
+
     // Initialize database
     let db = Database::connect(&config.database.path).await?;
     db.run_migrations().await?;
@@ -800,54 +1613,129 @@ async fn cmd_run(
     let provider = crate::llm::provider::create_provider(config)?;
 
     // Create tool registry
-    let mut tool_registry = ToolRegistry::new();
-    // Phase 1: Essential file operations
-    tool_registry.register(Arc::new(ReadTool));
-    tool_registry.register(Arc::new(WriteTool));
-    tool_registry.register(Arc::new(EditTool));
-    tool_registry.register(Arc::new(BashTool));
-    tool_registry.register(Arc::new(LsTool));
-    tool_registry.register(Arc::new(GlobTool));
-    tool_registry.register(Arc::new(GrepTool));
-    // Phase 2: Advanced features
-    tool_registry.register(Arc::new(WebSearchTool));
-    tool_registry.register(Arc::new(CodeExecTool));
-    tool_registry.register(Arc::new(NotebookEditTool));
-    tool_registry.register(Arc::new(DocParserTool));
-    // Phase 3: Workflow & integration
-    tool_registry.register(Arc::new(TaskTool));
-    tool_registry.register(Arc::new(ContextTool));
-    tool_registry.register(Arc::new(HttpClientTool));
-    tool_registry.register(Arc::new(PlanTool));
+    let working_directory = std::env::current_dir().context("Failed to get current directory")?;
+    let lsp_manager = lsp_manager(config, &working_directory);
+    let mut tool_registry = build_tool_registry(config, &lsp_manager);
+    let mcp_manager = Arc::new(start_mcp_servers(config, &mut tool_registry).await);
+    let tool_registry = Arc::new(tool_registry);
+
+    // Nobody is there to ask, so the policy answers approval requests
+    let denied = Arc::new(AtomicUsize::new(0));
+    let approval_callback: ApprovalCallback = {
+        let policy = policy.clone();
+        let denied = denied.clone();
+        let tool_registry = tool_registry.clone();
+        Arc::new(move |info| {
+            let approved = tool_registry
+                .get(&info.tool_name)
+                .is_some_and(|tool| policy.approves(tool.as_ref(), &info.tool_input));
+            if !approved {
+                denied.fetch_add(1, Ordering::SeqCst);
+                eprintln!(
+                    "⛔ Denied '{}' (use --allow {} or --auto-approve to permit it)",
+                    info.tool_name, info.tool_name
+                );
+            }
+            Box::pin(async move { Ok(ApprovalDecision::from(approved)) })
+        })
+    };
+
+    // In stream-json mode, events go to stdout as they happen
+    let (event_tx, printer) = if matches!(format, OutputFormat::StreamJson) {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<AgentEvent>();
+        let printer = tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                println!("{}", event.to_json_line());
+            }
+        });
+        (Some(tx), Some(printer))
+    } else {
+        (None, None)
+    };
 
     // Create service context and agent service
     let service_context = ServiceContext::new(db.pool().clone());
     let agent_service = AgentService::new(provider.clone(), service_context.clone())
-        .with_tool_registry(Arc::new(tool_registry))
+        .with_tool_registry(tool_registry)
+        .with_auto_approve_tools(policy.auto_approve)
+        .with_approval_callback(Some(approval_callback))
+        .with_lsp_manager(Some(lsp_manager.clone()))
+        .with_project_context(project_context(config, &working_directory))
+        .with_repo_map(repo_map(config, &working_directory))
+        .with_working_directory(working_directory)
+        .with_auto_commit(auto_commit(config))
+        .with_event_sender(event_tx)
         .with_system_prompt(SYSTEM_PROMPT.to_string())
         .with_max_tool_iterations(20);
 
-    // Create or get session
+    // Create or get session; its history is reloaded as context
     let session_service = SessionService::new(service_context);
 
-    let session = session_service
-        .create_session(Some("CLI Run".to_string()))
-        .await?;
+    let session = match session.resolve(&session_service).await? {
+        Some(session) => session,
+        None => {
+            session_service
+                .create_session(Some("CLI Run".to_string()))
+                .await?
+        }
+    };
 
-    // Send message
-    println!("🤔 Processing...\n");
-    let response = agent_service.send_message(session.id, prompt, None).await?;
+    // Send message; progress goes to stderr so stdout holds only the output
+    eprintln!("🤔 Processing...\n");
+    let response = agent_service
+        .send_message_with_tools_and_mode(session.id, prompt, None, policy.read_only)
+        .await;
+    mcp_manager.shutdown().await;
+    lsp_manager.shutdown().await;
+
+    // Dropping the service closes the event channel; wait for the last lines
+    drop(agent_service);
+    if let Some(printer) = printer {
+        let _ = printer.await;
+        if let Err(e) = &response {
+            let event = AgentEvent::Error {
+                message: e.to_string(),
+            };
+            println!("{}", event.to_json_line());
+        }
+    }
 
-    // Format and display output
-    match format {
+    let response = match response {
+        Ok(response) => response,
+        Err(e @ AgentError::MaxIterationsExceeded(_)) => {
+            return Err(ExitError {
+                code: exit_code::MAX_ITERATIONS,
+                message: e.to_string(),
+            }
+            .into())
+        }
+        Err(e @ AgentError::Provider(_)) => {
+            return Err(ExitError {
+                code: exit_code::PROVIDER_ERROR,
+                message: e.to_string(),
+            }
+            .into())
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    // Record the model, so sessions can be found by it
+    if session.model.is_none() {
+        if let Some(mut session) = session_service.get_session(session.id).await? {
+            session.model = Some(response.model.clone());
+            if let Err(e) = session_service.update_session(&session).await {
+                tracing::warn!("Failed to update session model: {}", e);
+            }
+        }
+    }
+
+    // Format the output; in text mode only the answer goes to stdout
+    let total_tokens = response.usage.input_tokens + response.usage.output_tokens;
+    let output = match format {
         OutputFormat::Text => {
-            println!("{}", response.content);
-            println!();
-            println!(
-                "📊 Tokens: {}",
-                response.usage.input_tokens + response.usage.output_tokens
-            );
-            println!("💰 Cost: ${:.6}", response.cost);
+            eprintln!("\n📊 Tokens: {}", total_tokens);
+            eprintln!("💰 Cost: ${:.6}", response.cost);
+            Some(format!("{}\n", response.content))
         }
         OutputFormat::Json => {
             let output = serde_json::json!({
@@ -859,27 +1747,221 @@ async fn cmd_run(
                 "cost": response.cost,
                 "model": response.model,
             });
-            println!("{}", serde_json::to_string_pretty(&output)?);
+            Some(format!("{}\n", serde_json::to_string_pretty(&output)?))
         }
-        OutputFormat::Markdown => {
-            println!("# Response\n");
-            println!("{}\n", response.content);
-            println!("---");
-            println!(
-                "**Tokens:** {}",
-                response.usage.input_tokens + response.usage.output_tokens
-            );
-            println!("**Cost:** ${:.6}", response.cost);
+        OutputFormat::Markdown => Some(format!(
+            "# Response\n\n{}\n\n---\n**Tokens:** {}\n**Cost:** ${:.6}\n",
+            response.content, total_tokens, response.cost
+        )),
+        // Already written as the `final` event
+        OutputFormat::StreamJson => None,
+    };
+    match (output, &inputs.output) {
+        (Some(output), Some(path)) => {
+            std::fs::write(path, output)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            eprintln!("✅ Wrote answer to {}", path.display());
+        }
+        (Some(output), None) => print!("{}", output),
+        (None, _) => {}
+    }
+
+    if policy.auto_approve {
+        eprintln!("\n⚠️  Auto-approve mode was enabled");
+    }
+
+    let denied = denied.load(Ordering::SeqCst);
+    if denied > 0 {
+        return Err(ExitError {
+            code: exit_code::TOOL_DENIED,
+            message: format!("{} tool call(s) denied by the approval policy", denied),
         }
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Add piped stdin and attached files to a prompt, each in its own block
+pub fn attach_context(prompt: &str, stdin: Option<&str>, files: &[(PathBuf, String)]) -> String {
+    let mut output = prompt.to_string();
+    if let Some(stdin) = stdin {
+        output.push_str(&format!("\n\n<stdin>\n{}\n</stdin>", stdin.trim_end()));
+    }
+    for (path, content) in files {
+        output.push_str(&format!(
+            "\n\n<file path=\"{}\">\n{}\n</file>",
+            path.display(),
+            content.trim_end()
+        ));
+    }
+    output
+}
+
+/// Run the prompts of a batch file, appending to the results file
+async fn cmd_batch(
+    config: &crate::config::Config,
+    input: &std::path::Path,
+    output: &std::path::Path,
+    jobs: usize,
+    timeout: u64,
+    retry_failed: bool,
+) -> Result<()> {
+    use crate::{db::Database, services::ServiceContext};
+    use futures::StreamExt;
+    use std::io::Write;
+
+    let text = std::fs::read_to_string(input)
+        .with_context(|| format!("Failed to read {}", input.display()))?;
+    let items = batch::parse_items(&text)?;
+    let previous = match std::fs::read_to_string(output) {
+        Ok(previous) => previous,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", output.display()));
+        }
+    };
+    let finished = batch::finished_ids(&previous, retry_failed);
+    let pending: Vec<_> = items
+        .iter()
+        .filter(|item| !finished.contains(&item.id))
+        .collect();
+    if pending.is_empty() {
+        eprintln!(
+            "✅ All {} prompt(s) already have results in {}",
+            items.len(),
+            output.display()
+        );
+        return Ok(());
+    }
+    if pending.len() < items.len() {
+        eprintln!(
+            "⏭️  Skipping {} prompt(s) with results in {}",
+            items.len() - pending.len(),
+            output.display()
+        );
     }
 
-    if auto_approve {
-        println!("\n⚠️  Auto-approve mode was enabled");
+    let db = Database::connect(&config.database.path).await?;
+    db.run_migrations().await?;
+    let provider = crate::llm::provider::create_provider(config)?;
+    // MCP servers are started once and their tools offered to every prompt
+    let mut mcp_tools = crate::llm::tools::ToolRegistry::new();
+    let mcp_manager = Arc::new(start_mcp_servers(config, &mut mcp_tools).await);
+
+    let mut results_file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(output)
+        .with_context(|| format!("Failed to open {}", output.display()))?;
+    // Finish a line cut short by an interruption
+    if !previous.is_empty() && !previous.ends_with('\n') {
+        writeln!(results_file)?;
     }
 
+    let context = batch::BatchContext::new(
+        config,
+        ServiceContext::new(db.pool().clone()),
+        provider,
+        mcp_manager.clone(),
+        std::time::Duration::from_secs(timeout),
+    );
+    let mut results = futures::stream::iter(&pending)
+        .map(|item| batch::run_item(&context, item))
+        .buffer_unordered(jobs.max(1));
+
+    let (mut done, mut unsuccessful, mut cost) = (0, 0, 0.0);
+    while let Some(result) = results.next().await {
+        writeln!(results_file, "{}", serde_json::to_string(&result)?)
+            .and_then(|_| results_file.flush())
+            .with_context(|| format!("Failed to write {}", output.display()))?;
+
+        done += 1;
+        cost += result.cost.unwrap_or_default();
+        let icon = match result.status {
+            batch::BatchStatus::Success => "✅",
+            batch::BatchStatus::Failed => "❌",
+            batch::BatchStatus::TimedOut => "⏱️ ",
+        };
+        if result.status != batch::BatchStatus::Success {
+            unsuccessful += 1;
+        }
+        eprint!(
+            "[{}/{}] {} {} ({:.1}s",
+            done,
+            pending.len(),
+            icon,
+            result.id,
+            result.duration_ms as f64 / 1000.0
+        );
+        match (&result.error, result.cost) {
+            (Some(error), _) => eprintln!("): {}", error),
+            (None, Some(cost)) => eprintln!(", ${:.6})", cost),
+            (None, None) => eprintln!(")"),
+        }
+    }
+    drop(results);
+    context.shutdown().await;
+    mcp_manager.shutdown().await;
+
+    eprintln!(
+        "\n📊 {} succeeded, {} failed or timed out; cost ${:.6}. Results in {}",
+        done - unsuccessful,
+        unsuccessful,
+        cost,
+        output.display()
+    );
+    if unsuccessful > 0 {
+        return Err(ExitError {
+            code: exit_code::BATCH_FAILED,
+            message: format!(
+                "{} prompt(s) failed; run again with --retry-failed to retry them",
+                unsuccessful
+            ),
+        }
+        .into());
+    }
     Ok(())
 }
 
+/// MCP commands
+async fn cmd_mcp(config: &crate::config::Config, operation: McpCommands) -> Result<()> {
+    use crate::llm::tools::ToolExecutionContext;
+    use crate::mcp::{McpServer, ServeOptions};
+
+    match operation {
+        McpCommands::Serve {
+            read_only,
+            auto_approve,
+            allow,
+            deny,
+        } => {
+            // Stdout carries the protocol; nothing else may be printed to it.
+            // Configured MCP servers are not started so servers can't loop
+            // back into each other.
+            let working_directory =
+                std::env::current_dir().context("Failed to get current directory")?;
+            let lsp_manager = lsp_manager(config, &working_directory);
+            let registry = Arc::new(build_tool_registry(config, &lsp_manager));
+            let context = ToolExecutionContext::new(uuid::Uuid::new_v4())
+                .with_working_directory(working_directory);
+            let options = ServeOptions {
+                read_only,
+                auto_approve,
+                allow,
+                deny,
+            };
+
+            tracing::info!("Serving {} tool(s) over MCP stdio", registry.count());
+            let result = Arc::new(McpServer::new(registry, context, options))
+                .serve(tokio::io::stdin(), tokio::io::stdout())
+                .await;
+            lsp_manager.shutdown().await;
+            result.context("MCP server failed")
+        }
+    }
+}
+
 /// Keyring management commands
 async fn cmd_keyring(operation: KeyringCommands) -> Result<()> {
     use crate::config::secrets::SecretString;
@@ -945,10 +2027,9 @@ async fn cmd_keyring(operation: KeyringCommands) -> Result<()> {
         KeyringCommands::List => {
             println!("🔐 API Keys in OS Keyring\n");
 
-            let providers = ["anthropic", "openai", "gemini", "azure"];
             let mut found_any = false;
 
-            for provider in &providers {
+            for provider in completion::KEYRING_PROVIDERS {
                 let key_name = format!("{}_api_key", provider);
                 if let Some(secret) = SecretString::from_keyring_optional(&key_name) {
                     let masked = format!(
@@ -0,0 +1,292 @@
+//! Config File Checks and Migration
+//!
+//! Unknown keys are ignored when a config file is loaded, so a misspelled
+//! key silently does nothing. [`unknown_keys`] lists them.
+//!
+//! Older releases used a different layout that is no longer read:
+//!
+//! ```toml
+//! [llm]
+//! provider = "anthropic"
+//! model = "claude-sonnet-4"
+//!
+//! [secrets]
+//! anthropic_api_key = "sk-ant-..."
+//! ```
+//!
+//! [`migrate_legacy_layout`] moves those settings to `[providers.<name>]`,
+//! keeping the rest of the file, comments included, as it was.
+
+use super::Config;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use toml_edit::{value, DocumentMut, Item, Table};
+
+/// Providers with a `[providers.<name>]` table
+const PROVIDERS: &[&str] = &[
+    "anthropic",
+    "openai",
+    "qwen",
+    "gemini",
+    "bedrock",
+    "azure",
+    "vertex",
+];
+
+/// Keys of `[llm]` and the provider settings they become
+const LLM_KEYS: &[(&str, &str)] = &[("model", "default_model"), ("base_url", "base_url")];
+
+/// Parse a config file, listing the keys the configuration doesn't know,
+/// e.g. `providers.anthropic.modle`
+pub fn unknown_keys(text: &str) -> Result<Vec<String>> {
+    let mut unknown = Vec::new();
+    let _: Config = serde_ignored::deserialize(toml::Deserializer::new(text), |path| {
+        unknown.push(key_path(&path))
+    })?;
+    Ok(unknown)
+}
+
+/// `providers.anthropic.modle` rather than serde_ignored's
+/// `providers.anthropic.?.modle`, which marks the `Option` in between
+fn key_path(path: &serde_ignored::Path) -> String {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => String::new(),
+        Path::Seq { parent, index } => format!("{}[{}]", key_path(parent), index),
+        Path::Map { parent, key } => match key_path(parent) {
+            parent if parent.is_empty() => key.clone(),
+            parent => format!("{}.{}", parent, key),
+        },
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => key_path(parent),
+    }
+}
+
+/// Rewrite the old `[llm]` and `[secrets]` tables as provider settings,
+/// describing each change made
+///
+/// Settings already present under `[providers]` win over the old ones.
+/// Anything that can't be moved is left in place, for [`unknown_keys`] to
+/// report.
+pub fn migrate_legacy_layout(doc: &mut DocumentMut) -> Vec<String> {
+    let mut changes = Vec::new();
+    migrate_llm(doc, &mut changes);
+    migrate_secrets(doc, &mut changes);
+    changes
+}
+
+fn migrate_llm(doc: &mut DocumentMut, changes: &mut Vec<String>) {
+    let Some(llm) = doc.get("llm").and_then(Item::as_table_like) else {
+        return;
+    };
+    let Some(provider) = llm
+        .get("provider")
+        .and_then(Item::as_str)
+        .filter(|provider| PROVIDERS.contains(provider))
+        .map(str::to_string)
+    else {
+        return;
+    };
+    let settings: Vec<(String, Item)> = llm
+        .iter()
+        .filter(|(key, _)| *key != "provider")
+        .map(|(key, item)| (key.to_string(), item.clone()))
+        .collect();
+
+    let table = provider_table(doc, &provider);
+    if !table.contains_key("enabled") {
+        table.insert("enabled", value(true));
+    }
+    let mut kept = Vec::new();
+    for (key, item) in settings {
+        match LLM_KEYS.iter().find(|(old, _)| *old == key) {
+            Some((_, new)) if table.contains_key(new) => changes.push(format!(
+                "Dropped [llm] {}: [providers.{}] already sets {}",
+                key, provider, new
+            )),
+            Some((_, new)) => {
+                table.insert(new, item);
+                changes.push(format!(
+                    "Moved [llm] {} to [providers.{}] {}",
+                    key, provider, new
+                ));
+            }
+            None => kept.push(key),
+        }
+    }
+    changes.push(format!("Enabled [providers.{}]", provider));
+
+    if kept.is_empty() {
+        doc.remove("llm");
+    } else {
+        let llm = doc["llm"].as_table_like_mut().expect("checked above");
+        for key in LLM_KEYS.iter().map(|(old, _)| *old).chain(["provider"]) {
+            llm.remove(key);
+        }
+        changes.push(format!(
+            "Left [llm] {} in place: no such setting",
+            kept.join(", ")
+        ));
+    }
+}
+
+fn migrate_secrets(doc: &mut DocumentMut, changes: &mut Vec<String>) {
+    let Some(secrets) = doc.get("secrets").and_then(Item::as_table_like) else {
+        return;
+    };
+    let keys: Vec<(String, String, Item)> = secrets
+        .iter()
+        .filter_map(|(key, item)| {
+            let provider = key.strip_suffix("_api_key")?;
+            PROVIDERS
+                .contains(&provider)
+                .then(|| (key.to_string(), provider.to_string(), item.clone()))
+        })
+        .collect();
+    if keys.is_empty() {
+        return;
+    }
+
+    for (key, provider, item) in keys {
+        let table = provider_table(doc, &provider);
+        if table.contains_key("api_key") {
+            changes.push(format!(
+                "Dropped [secrets] {}: [providers.{}] already sets api_key",
+                key, provider
+            ));
+        } else {
+            table.insert("api_key", item);
+            changes.push(format!(
+                "Moved [secrets] {} to [providers.{}] api_key",
+                key, provider
+            ));
+        }
+        doc["secrets"]
+            .as_table_like_mut()
+            .expect("checked above")
+            .remove(&key);
+    }
+
+    let secrets = doc["secrets"].as_table_like().expect("checked above");
+    if secrets.is_empty() {
+        doc.remove("secrets");
+    } else {
+        let kept: Vec<&str> = secrets.iter().map(|(key, _)| key).collect();
+        changes.push(format!(
+            "Left [secrets] {} in place: no such provider",
+            kept.join(", ")
+        ));
+    }
+}
+
+/// The `[providers.<name>]` table, created if missing
+fn provider_table<'a>(doc: &'a mut DocumentMut, provider: &str) -> &'a mut Table {
+    let providers = doc
+        .entry("providers")
+        .or_insert_with(|| {
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        })
+        .as_table_mut()
+        .expect("[providers] is a table");
+    providers
+        .entry(provider)
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_mut()
+        .expect("provider settings are a table")
+}
+
+/// Migrate a config file in place, keeping the original next to it as
+/// `<file>.bak`, which is returned
+pub fn migrate_file(path: &Path) -> Result<(PathBuf, Vec<String>)> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {:?}", path))?;
+    let mut doc: DocumentMut = text
+        .parse()
+        .with_context(|| format!("Failed to parse config file: {:?}", path))?;
+    let changes = migrate_legacy_layout(&mut doc);
+
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    let backup = PathBuf::from(backup);
+    std::fs::copy(path, &backup)
+        .with_context(|| format!("Failed to back up config file to {:?}", backup))?;
+    std::fs::write(path, doc.to_string())
+        .with_context(|| format!("Failed to write config file: {:?}", path))?;
+    Ok((backup, changes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_keys() {
+        let unknown = unknown_keys(
+            r#"
+[database]
+path = "crustly.db"
+
+[providers.anthropic]
+modle = "claude"
+
+[llm]
+provider = "anthropic"
+"#,
+        )
+        .unwrap();
+        assert_eq!(unknown, vec!["providers.anthropic.modle", "llm"]);
+
+        assert!(unknown_keys("[logging]\nlevel = 3\n").is_err());
+    }
+
+    #[test]
+    fn test_migrate_legacy_layout() {
+        let mut doc: DocumentMut = r#"# My settings
+[llm]
+provider = "anthropic"
+model = "claude-sonnet-4"
+temperature = 0.2
+
+[secrets]
+anthropic_api_key = "sk-ant-test"
+openai_api_key = "sk-test"
+github_token = "ghp"
+
+[providers.openai]
+api_key = "sk-kept"
+"#
+        .parse()
+        .unwrap();
+
+        let changes = migrate_legacy_layout(&mut doc);
+        assert_eq!(
+            changes,
+            vec![
+                "Moved [llm] model to [providers.anthropic] default_model",
+                "Enabled [providers.anthropic]",
+                "Left [llm] temperature in place: no such setting",
+                "Moved [secrets] anthropic_api_key to [providers.anthropic] api_key",
+                "Dropped [secrets] openai_api_key: [providers.openai] already sets api_key",
+                "Left [secrets] github_token in place: no such provider",
+            ]
+        );
+
+        let text = doc.to_string();
+        assert!(text.starts_with("# My settings"));
+        let config: Config = toml::from_str(&text).unwrap();
+        let anthropic = config.providers.anthropic.unwrap();
+        assert_eq!(anthropic.default_model.as_deref(), Some("claude-sonnet-4"));
+        assert_eq!(anthropic.api_key.as_deref(), Some("sk-ant-test"));
+        assert_eq!(
+            config.providers.openai.unwrap().api_key.as_deref(),
+            Some("sk-kept")
+        );
+        assert_eq!(unknown_keys(&text).unwrap(), vec!["llm", "secrets"]);
+
+        // Nothing left to do
+        assert!(migrate_legacy_layout(&mut text.parse().unwrap()).is_empty());
+    }
+}
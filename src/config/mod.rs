@@ -3,15 +3,18 @@
 //! Handles application configuration loading, validation, and management.
 
 pub mod crabrace;
+pub mod migrate;
 pub mod secrets;
 pub mod update;
 
 pub use crabrace::{CrabraceConfig, CrabraceIntegration};
+pub use migrate::{migrate_file, migrate_legacy_layout, unknown_keys};
 pub use secrets::{ProviderSecrets, SecretString};
 pub use update::{ProviderUpdater, UpdateResult};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -37,6 +40,30 @@ pub struct Config {
     /// LLM provider configurations
     #[serde(default)]
     pub providers: ProviderConfigs,
+
+    /// Tool execution options
+    #[serde(default)]
+    pub tools: ToolsConfig,
+
+    /// Model Context Protocol servers
+    #[serde(default)]
+    pub mcp: McpConfig,
+
+    /// Language servers
+    #[serde(default)]
+    pub lsp: LspConfig,
+
+    /// Project instruction files in the system prompt
+    #[serde(default)]
+    pub context_files: ContextFilesConfig,
+
+    /// Repository map in the system prompt
+    #[serde(default)]
+    pub repo_map: RepoMapConfig,
+
+    /// Git commits of agent turns
+    #[serde(default)]
+    pub auto_commit: AutoCommitConfig,
 }
 
 /// Debug configuration options
@@ -189,6 +216,422 @@ fn default_log_level() -> String {
     "info".to_string()
 }
 
+/// Tool execution configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolsConfig {
+    /// Maximum characters of tool output sent to the model before truncation
+    #[serde(default = "default_max_output_chars")]
+    pub max_output_chars: usize,
+
+    /// Maximum lines of tool output sent to the model before truncation
+    #[serde(default = "default_max_output_lines")]
+    pub max_output_lines: usize,
+}
+
+impl Default for ToolsConfig {
+    fn default() -> Self {
+        Self {
+            max_output_chars: default_max_output_chars(),
+            max_output_lines: default_max_output_lines(),
+        }
+    }
+}
+
+fn default_max_output_chars() -> usize {
+    crate::llm::tools::output::DEFAULT_MAX_OUTPUT_CHARS
+}
+
+fn default_max_output_lines() -> usize {
+    crate::llm::tools::output::DEFAULT_MAX_OUTPUT_LINES
+}
+
+/// Project instruction files configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextFilesConfig {
+    /// Add .crustly.md, CLAUDE.md, AGENTS.md and .cursorrules files to the
+    /// system prompt
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Size limit of one file, with its includes, in characters
+    #[serde(default = "default_context_max_file_chars")]
+    pub max_file_chars: usize,
+
+    /// Size limit of all files together, in characters
+    #[serde(default = "default_context_max_total_chars")]
+    pub max_total_chars: usize,
+}
+
+impl Default for ContextFilesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_file_chars: default_context_max_file_chars(),
+            max_total_chars: default_context_max_total_chars(),
+        }
+    }
+}
+
+fn default_context_max_file_chars() -> usize {
+    crate::llm::prompt::context_files::DEFAULT_MAX_FILE_CHARS
+}
+
+fn default_context_max_total_chars() -> usize {
+    crate::llm::prompt::context_files::DEFAULT_MAX_TOTAL_CHARS
+}
+
+/// Repository map configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoMapConfig {
+    /// Add a map of the working directory to the system prompt
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Approximate size limit of the map, in tokens
+    #[serde(default = "default_repo_map_max_tokens")]
+    pub max_tokens: usize,
+}
+
+impl Default for RepoMapConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_tokens: default_repo_map_max_tokens(),
+        }
+    }
+}
+
+fn default_repo_map_max_tokens() -> usize {
+    crate::llm::prompt::repo_map::DEFAULT_MAX_TOKENS
+}
+
+/// Auto-commit configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoCommitConfig {
+    /// Commit the files each agent turn changes
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Branch the commits go to; HEAD stays where it is
+    #[serde(default = "default_auto_commit_branch")]
+    pub branch: String,
+
+    /// Author and committer of the commits
+    #[serde(default = "default_auto_commit_author_name")]
+    pub author_name: String,
+
+    #[serde(default = "default_auto_commit_author_email")]
+    pub author_email: String,
+}
+
+impl Default for AutoCommitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            branch: default_auto_commit_branch(),
+            author_name: default_auto_commit_author_name(),
+            author_email: default_auto_commit_author_email(),
+        }
+    }
+}
+
+fn default_auto_commit_branch() -> String {
+    "crustly/auto".to_string()
+}
+
+fn default_auto_commit_author_name() -> String {
+    "Crustly".to_string()
+}
+
+fn default_auto_commit_author_email() -> String {
+    "crustly@localhost".to_string()
+}
+
+/// Model Context Protocol configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct McpConfig {
+    /// MCP servers, keyed by name (`[mcp.servers.<name>]`)
+    #[serde(default)]
+    pub servers: BTreeMap<String, McpServerConfig>,
+}
+
+/// A single MCP server
+///
+/// Local servers set `command` and are spoken to over stdio; remote servers
+/// set `url` instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerConfig {
+    /// Command that starts the server (stdio transport)
+    #[serde(default)]
+    pub command: Option<String>,
+
+    /// URL of a remote server (HTTP transports)
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// Transport to use; inferred from `command` / `url` when unset
+    #[serde(default)]
+    pub transport: Option<McpTransportKind>,
+
+    /// Arguments passed to the command
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Extra environment variables for the server process
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+
+    /// Working directory for the server process
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+
+    /// Extra HTTP headers sent to a remote server
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// Bearer token for a remote server (prefer the keyring or `bearer_token_env`)
+    #[serde(default, skip_serializing)]
+    pub bearer_token: Option<SecretString>,
+
+    /// Environment variable holding the bearer token
+    #[serde(default)]
+    pub bearer_token_env: Option<String>,
+
+    /// Timeout for each request to the server, in seconds
+    #[serde(default = "default_mcp_timeout_secs")]
+    pub timeout_secs: u64,
+
+    /// Server enabled
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// How to reach an MCP server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpTransportKind {
+    /// Child process speaking over stdin/stdout
+    Stdio,
+    /// Streamable HTTP
+    Http,
+    /// Legacy HTTP+SSE
+    Sse,
+}
+
+impl McpServerConfig {
+    /// Configuration for a local server started with `command`
+    pub fn stdio(command: impl Into<String>) -> Self {
+        Self {
+            command: Some(command.into()),
+            ..Self::empty()
+        }
+    }
+
+    /// Configuration for a remote server at `url`
+    pub fn remote(url: impl Into<String>, transport: McpTransportKind) -> Self {
+        Self {
+            url: Some(url.into()),
+            transport: Some(transport),
+            ..Self::empty()
+        }
+    }
+
+    fn empty() -> Self {
+        Self {
+            command: None,
+            url: None,
+            transport: None,
+            args: Vec::new(),
+            env: BTreeMap::new(),
+            cwd: None,
+            headers: BTreeMap::new(),
+            bearer_token: None,
+            bearer_token_env: None,
+            timeout_secs: default_mcp_timeout_secs(),
+            enabled: true,
+        }
+    }
+
+    /// Transport to use for this server
+    pub fn transport_kind(&self) -> McpTransportKind {
+        match self.transport {
+            Some(kind) => kind,
+            None if self.url.is_some() => McpTransportKind::Http,
+            None => McpTransportKind::Stdio,
+        }
+    }
+
+    /// Keyring entry holding the bearer token of server `name`
+    ///
+    /// Matches the entry written by `crustly keyring set mcp-<name> <token>`.
+    pub fn keyring_key(name: &str) -> String {
+        format!("mcp-{}_api_key", name.to_lowercase())
+    }
+
+    /// Resolve the bearer token for server `name`
+    ///
+    /// Uses the inline `bearer_token` if set, then the OS keyring, then the
+    /// `bearer_token_env` variable.
+    pub fn resolve_bearer_token(&self, name: &str) -> Option<SecretString> {
+        if let Some(token) = &self.bearer_token {
+            return Some(token.clone());
+        }
+        let key = Self::keyring_key(name);
+        match &self.bearer_token_env {
+            Some(env_var) => SecretString::load_with_fallback(&key, env_var),
+            None => SecretString::from_keyring_optional(&key),
+        }
+    }
+}
+
+fn default_mcp_timeout_secs() -> u64 {
+    crate::mcp::client::DEFAULT_REQUEST_TIMEOUT_SECS
+}
+
+/// Language Server Protocol configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LspConfig {
+    /// Enable language servers
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Language servers, keyed by name (`[lsp.servers.<name>]`)
+    ///
+    /// A server with the name of a built-in one (`rust`, `python`, `go`)
+    /// replaces it.
+    #[serde(default)]
+    pub servers: BTreeMap<String, LspServerConfig>,
+
+    /// Longest wait for diagnostics after a tool edits a file, in
+    /// milliseconds; 0 leaves them out of the tool result
+    #[serde(default = "default_lsp_diagnostics_wait_ms")]
+    pub diagnostics_wait_ms: u64,
+}
+
+impl Default for LspConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            servers: BTreeMap::new(),
+            diagnostics_wait_ms: default_lsp_diagnostics_wait_ms(),
+        }
+    }
+}
+
+impl LspConfig {
+    /// Built-in servers merged with the configured ones, enabled only
+    pub fn effective_servers(&self) -> BTreeMap<String, LspServerConfig> {
+        if !self.enabled {
+            return BTreeMap::new();
+        }
+        let mut servers = LspServerConfig::builtin();
+        servers.extend(self.servers.clone());
+        servers.retain(|_, server| server.enabled);
+        servers
+    }
+}
+
+/// A single language server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LspServerConfig {
+    /// Command that starts the server
+    #[serde(default)]
+    pub command: String,
+
+    /// Arguments passed to the command
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Extra environment variables for the server process
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+
+    /// File extensions handled by the server, without the dot
+    #[serde(default)]
+    pub extensions: Vec<String>,
+
+    /// Files marking the root of a workspace (e.g. `Cargo.toml`)
+    #[serde(default)]
+    pub root_markers: Vec<String>,
+
+    /// Language identifier sent with opened files; defaults to the server name
+    #[serde(default)]
+    pub language_id: Option<String>,
+
+    /// Server-specific options sent with the initialize request
+    #[serde(default)]
+    pub initialization_options: Option<serde_json::Value>,
+
+    /// Timeout for each request to the server, in seconds
+    #[serde(default = "default_lsp_timeout_secs")]
+    pub timeout_secs: u64,
+
+    /// Server enabled
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+impl LspServerConfig {
+    /// Configuration for a server started with `command`, handling `extensions`
+    pub fn new(command: impl Into<String>, extensions: &[&str]) -> Self {
+        Self {
+            command: command.into(),
+            args: Vec::new(),
+            env: BTreeMap::new(),
+            extensions: extensions.iter().map(|e| e.to_string()).collect(),
+            root_markers: Vec::new(),
+            language_id: None,
+            initialization_options: None,
+            timeout_secs: default_lsp_timeout_secs(),
+            enabled: true,
+        }
+    }
+
+    /// Servers available without configuration
+    pub fn builtin() -> BTreeMap<String, Self> {
+        let mut servers = BTreeMap::new();
+        servers.insert(
+            "rust".to_string(),
+            Self {
+                root_markers: vec!["Cargo.toml".to_string()],
+                ..Self::new("rust-analyzer", &["rs"])
+            },
+        );
+        servers.insert(
+            "python".to_string(),
+            Self {
+                args: vec!["--stdio".to_string()],
+                root_markers: [
+                    "pyproject.toml",
+                    "setup.py",
+                    "setup.cfg",
+                    "pyrightconfig.json",
+                ]
+                .iter()
+                .map(|m| m.to_string())
+                .collect(),
+                ..Self::new("pyright-langserver", &["py", "pyi"])
+            },
+        );
+        servers.insert(
+            "go".to_string(),
+            Self {
+                root_markers: vec!["go.work".to_string(), "go.mod".to_string()],
+                ..Self::new("gopls", &["go"])
+            },
+        );
+        servers
+    }
+}
+
+fn default_lsp_timeout_secs() -> u64 {
+    crate::lsp::client::DEFAULT_REQUEST_TIMEOUT_SECS
+}
+
+fn default_lsp_diagnostics_wait_ms() -> u64 {
+    3000
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -202,6 +645,12 @@ impl Default for Config {
             },
             debug: DebugConfig::default(),
             providers: ProviderConfigs::default(),
+            tools: ToolsConfig::default(),
+            mcp: McpConfig::default(),
+            lsp: LspConfig::default(),
+            context_files: ContextFilesConfig::default(),
+            repo_map: RepoMapConfig::default(),
+            auto_commit: AutoCommitConfig::default(),
         }
     }
 }
@@ -270,12 +719,12 @@ impl Config {
     }
 
     /// Get the system config path: ~/.config/crustly/config.toml
-    fn system_config_path() -> Option<PathBuf> {
+    pub fn system_config_path() -> Option<PathBuf> {
         dirs::config_dir().map(|dir| dir.join("crustly").join("config.toml"))
     }
 
     /// Get the local config path: ./crustly.toml
-    fn local_config_path() -> PathBuf {
+    pub fn local_config_path() -> PathBuf {
         PathBuf::from("./crustly.toml")
     }
 
@@ -300,6 +749,12 @@ impl Config {
             logging: overlay.logging,
             debug: overlay.debug,
             providers: overlay.providers,
+            tools: overlay.tools,
+            mcp: overlay.mcp,
+            lsp: overlay.lsp,
+            context_files: overlay.context_files,
+            repo_map: overlay.repo_map,
+            auto_commit: overlay.auto_commit,
         }
     }
 
@@ -329,6 +784,19 @@ impl Config {
             config.debug.profiling = profiling.parse().unwrap_or(false);
         }
 
+        // Tool options
+        if let Ok(max_chars) = std::env::var("CRUSTLY_TOOL_OUTPUT_MAX_CHARS") {
+            if let Ok(max_chars) = max_chars.parse() {
+                config.tools.max_output_chars = max_chars;
+            }
+        }
+
+        if let Ok(max_lines) = std::env::var("CRUSTLY_TOOL_OUTPUT_MAX_LINES") {
+            if let Ok(max_lines) = max_lines.parse() {
+                config.tools.max_output_lines = max_lines;
+            }
+        }
+
         // Crabrace options
         if let Ok(enabled) = std::env::var("CRUSTLY_CRABRACE_ENABLED") {
             config.crabrace.enabled = enabled.parse().unwrap_or(true);
@@ -488,6 +956,41 @@ impl Config {
             );
         }
 
+        // Validate MCP servers
+        for (name, server) in &self.mcp.servers {
+            match (server.transport_kind(), &server.command, &server.url) {
+                (McpTransportKind::Stdio, Some(command), None) => {
+                    if command.trim().is_empty() {
+                        anyhow::bail!("MCP server '{}' has an empty command", name);
+                    }
+                }
+                (McpTransportKind::Http | McpTransportKind::Sse, None, Some(url)) => {
+                    if !(url.starts_with("http://") || url.starts_with("https://")) {
+                        anyhow::bail!("MCP server '{}' URL must use http or https: {}", name, url);
+                    }
+                }
+                (McpTransportKind::Stdio, _, _) => {
+                    anyhow::bail!("MCP server '{}' needs a command (and no url)", name);
+                }
+                _ => {
+                    anyhow::bail!("MCP server '{}' needs a url (and no command)", name);
+                }
+            }
+        }
+
+        // Validate language servers
+        for (name, server) in &self.lsp.servers {
+            if !server.enabled {
+                continue;
+            }
+            if server.command.trim().is_empty() {
+                anyhow::bail!("Language server '{}' has an empty command", name);
+            }
+            if server.extensions.is_empty() {
+                anyhow::bail!("Language server '{}' handles no file extensions", name);
+            }
+        }
+
         // Validate Crabrace URL if enabled
         if self.crabrace.enabled && self.crabrace.base_url.is_empty() {
             anyhow::bail!("Crabrace is enabled but base_url is empty");
@@ -578,6 +1081,84 @@ enabled = false
         assert!(!config.crabrace.enabled);
     }
 
+    #[test]
+    fn test_mcp_servers_from_toml() {
+        let toml_content = r#"
+[mcp.servers.files]
+command = "npx"
+args = ["-y", "@modelcontextprotocol/server-filesystem", "."]
+
+[mcp.servers.local]
+command = "./my-server"
+env = { TOKEN = "abc" }
+timeout_secs = 5
+enabled = false
+
+[mcp.servers.remote]
+url = "https://mcp.example.com/mcp"
+headers = { "X-Team" = "tools" }
+bearer_token_env = "REMOTE_MCP_TOKEN"
+
+[mcp.servers.legacy]
+url = "http://localhost:9000/sse"
+transport = "sse"
+        "#;
+
+        let config: Config = toml::from_str(toml_content).unwrap();
+        let files = &config.mcp.servers["files"];
+        assert_eq!(files.command.as_deref(), Some("npx"));
+        assert_eq!(files.transport_kind(), McpTransportKind::Stdio);
+        assert_eq!(files.args.len(), 3);
+        assert!(files.enabled);
+        assert_eq!(files.timeout_secs, 60);
+
+        let local = &config.mcp.servers["local"];
+        assert_eq!(local.env["TOKEN"], "abc");
+        assert_eq!(local.timeout_secs, 5);
+        assert!(!local.enabled);
+
+        let remote = &config.mcp.servers["remote"];
+        assert_eq!(remote.transport_kind(), McpTransportKind::Http);
+        assert_eq!(remote.headers["X-Team"], "tools");
+        assert_eq!(remote.bearer_token_env.as_deref(), Some("REMOTE_MCP_TOKEN"));
+        assert_eq!(
+            config.mcp.servers["legacy"].transport_kind(),
+            McpTransportKind::Sse
+        );
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_mcp_server_validation() {
+        let mut config = Config::default();
+        let mut server = McpServerConfig::stdio("server");
+        server.url = Some("https://example.com/mcp".to_string());
+        config.mcp.servers.insert("both".to_string(), server);
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.mcp.servers.insert(
+            "sse".to_string(),
+            McpServerConfig::remote("ftp://example.com", McpTransportKind::Sse),
+        );
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_mcp_bearer_token_is_not_saved() {
+        let mut server = McpServerConfig::remote("https://example.com", McpTransportKind::Http);
+        server.bearer_token = Some(SecretString::from_str("secret"));
+        let toml = toml::to_string(&server).unwrap();
+        assert!(!toml.contains("bearer_token"));
+        assert_eq!(
+            server
+                .resolve_bearer_token("example")
+                .unwrap()
+                .expose_secret(),
+            "secret"
+        );
+    }
+
     #[test]
     fn test_config_save_and_load() {
         let temp_file = NamedTempFile::new().unwrap();
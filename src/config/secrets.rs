@@ -101,6 +101,19 @@ impl SecretString {
         Ok(())
     }
 
+    /// Check that the OS keyring can be used
+    ///
+    /// Looks up an entry that needn't exist: finding nothing is fine,
+    /// failing to reach the credential store is not.
+    pub fn check_keyring() -> Result<()> {
+        let entry = Entry::new(KEYRING_SERVICE, "crustly-keyring-check")
+            .context("Failed to access keyring")?;
+        match entry.get_password() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e).context("Keyring is not available"),
+        }
+    }
+
     /// Load secret with fallback priority: keyring → env → none
     ///
     /// This is the recommended way to load API keys. It tries:
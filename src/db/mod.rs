@@ -17,6 +17,21 @@ use std::path::Path;
 /// Type alias for database pool
 pub type Pool = SqlitePool;
 
+/// Migrations applied to a database, from [`Database::migration_status`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    /// Number of migrations applied as this build knows them
+    pub applied: usize,
+    /// Migrations not applied yet
+    pub pending: Vec<String>,
+    /// Migrations that started but didn't finish
+    pub failed: Vec<String>,
+    /// Migrations changed since they were applied
+    pub modified: Vec<String>,
+    /// Applied migrations this build doesn't know, from a newer release
+    pub unknown: Vec<String>,
+}
+
 /// Database connection manager
 pub struct Database {
     pool: SqlitePool,
@@ -93,6 +108,67 @@ impl Database {
         Ok(())
     }
 
+    /// Open an existing database file without writing to it
+    pub async fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let url = format!("sqlite://{}?mode=ro", path.as_ref().to_string_lossy());
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect(&url)
+            .await
+            .context("Failed to open database")?;
+        Ok(Self { pool })
+    }
+
+    /// Run SQLite's integrity check, returning the problems found
+    pub async fn integrity_problems(&self) -> Result<Vec<String>> {
+        let rows: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to check database integrity")?;
+        Ok(rows.into_iter().filter(|row| row != "ok").collect())
+    }
+
+    /// Compare the migrations applied to the database with those this
+    /// build knows
+    pub async fn migration_status(&self) -> Result<MigrationStatus> {
+        let has_table: bool = sqlx::query_scalar(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        let applied: Vec<(i64, String, bool, Vec<u8>)> = if has_table {
+            sqlx::query_as("SELECT version, description, success, checksum FROM _sqlx_migrations")
+                .fetch_all(&self.pool)
+                .await
+                .context("Failed to read applied migrations")?
+        } else {
+            Vec::new()
+        };
+
+        let migrator = sqlx::migrate!("./migrations");
+        let mut status = MigrationStatus::default();
+        for migration in migrator.iter() {
+            let name = format!("{} {}", migration.version, migration.description);
+            match applied
+                .iter()
+                .find(|(version, ..)| *version == migration.version)
+            {
+                None => status.pending.push(name),
+                Some((_, _, false, _)) => status.failed.push(name),
+                Some((.., checksum)) if *checksum != *migration.checksum => {
+                    status.modified.push(name)
+                }
+                Some(_) => status.applied += 1,
+            }
+        }
+        status.unknown = applied
+            .iter()
+            .filter(|(version, ..)| migrator.iter().all(|m| m.version != *version))
+            .map(|(version, description, ..)| format!("{} {}", version, description))
+            .collect();
+        Ok(status)
+    }
+
     /// Close the database connection
     pub async fn close(self) -> Result<()> {
         self.pool.close().await;
@@ -149,4 +225,29 @@ mod tests {
         let pool = Pool::connect_in_memory().await.unwrap();
         assert!(pool.is_connected());
     }
+
+    #[tokio::test]
+    async fn test_migration_status() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("crustly.db");
+        let db = Database::connect(&path).await.unwrap();
+        let status = db.migration_status().await.unwrap();
+        assert_eq!(status.applied, 0);
+        assert!(!status.pending.is_empty());
+
+        db.run_migrations().await.unwrap();
+        db.close().await.unwrap();
+
+        let db = Database::open_read_only(&path).await.unwrap();
+        let status = db.migration_status().await.unwrap();
+        assert!(status.applied > 0);
+        assert_eq!(
+            status,
+            MigrationStatus {
+                applied: status.applied,
+                ..Default::default()
+            }
+        );
+        assert!(db.integrity_problems().await.unwrap().is_empty());
+    }
 }
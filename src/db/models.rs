@@ -31,6 +31,7 @@ pub struct Message {
     pub created_at: DateTime<Utc>,
     pub token_count: Option<i32>,
     pub cost: Option<f64>,
+    pub commit_hash: Option<String>, // Auto-commit made at the end of the turn
 }
 
 /// File model
@@ -44,6 +45,31 @@ pub struct File {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Snapshot of a file taken before a tool modified it
+///
+/// Stored in the `files` table with a `checkpoint_id` pointing at the user
+/// message that started the turn.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSnapshot {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub checkpoint_id: Uuid,
+    pub path: std::path::PathBuf,
+    pub content: Option<Vec<u8>>, // None if the file did not exist
+    pub created_at: DateTime<Utc>,
+    pub commit_hash: Option<String>, // Auto-commit that recorded the turn
+}
+
+/// Summary of the files snapshotted during one turn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub id: Uuid, // User message that started the turn
+    pub session_id: Uuid,
+    pub prompt: Option<String>,
+    pub file_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
 /// Attachment model
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Attachment {
@@ -139,6 +165,7 @@ impl Message {
             created_at: Utc::now(),
             token_count: None,
             cost: None,
+            commit_hash: None,
         }
     }
 }
@@ -197,6 +224,7 @@ impl<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> for Message {
                 .ok_or_else(|| sqlx::Error::Decode("Invalid timestamp for created_at".into()))?,
             token_count: row.try_get("token_count")?,
             cost: row.try_get("cost")?,
+            commit_hash: row.try_get("commit_hash")?,
         })
     }
 }
@@ -220,6 +248,43 @@ impl<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> for File {
     }
 }
 
+impl<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> for FileSnapshot {
+    fn from_row(row: &'r sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        Ok(FileSnapshot {
+            id: Uuid::parse_str(row.try_get("id")?)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            session_id: Uuid::parse_str(row.try_get("session_id")?)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            checkpoint_id: Uuid::parse_str(row.try_get("checkpoint_id")?)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            path: std::path::PathBuf::from(row.try_get::<String, _>("path")?),
+            content: row.try_get("snapshot_data")?,
+            created_at: DateTime::from_timestamp(row.try_get("created_at")?, 0)
+                .ok_or_else(|| sqlx::Error::Decode("Invalid timestamp for created_at".into()))?,
+            commit_hash: row.try_get("commit_hash")?,
+        })
+    }
+}
+
+impl<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> for Checkpoint {
+    fn from_row(row: &'r sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        Ok(Checkpoint {
+            id: Uuid::parse_str(row.try_get("checkpoint_id")?)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            session_id: Uuid::parse_str(row.try_get("session_id")?)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            prompt: row.try_get("prompt")?,
+            file_count: row.try_get("file_count")?,
+            created_at: DateTime::from_timestamp(row.try_get("created_at")?, 0)
+                .ok_or_else(|| sqlx::Error::Decode("Invalid timestamp for created_at".into()))?,
+        })
+    }
+}
+
 impl<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> for Plan {
     fn from_row(row: &'r sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
         use sqlx::Row;
@@ -2,7 +2,7 @@
 //!
 //! Database operations for file tracking.
 
-use crate::db::models::File;
+use crate::db::models::{Checkpoint, File, FileSnapshot};
 use anyhow::{Context, Result};
 use sqlx::SqlitePool;
 use std::path::Path;
@@ -34,7 +34,7 @@ impl FileRepository {
     /// Find all files for a session
     pub async fn find_by_session(&self, session_id: Uuid) -> Result<Vec<File>> {
         let files = sqlx::query_as::<_, File>(
-            "SELECT * FROM files WHERE session_id = ? ORDER BY created_at DESC",
+            "SELECT * FROM files WHERE session_id = ? AND checkpoint_id IS NULL ORDER BY created_at DESC",
         )
         .bind(session_id.to_string())
         .fetch_all(&self.pool)
@@ -47,13 +47,14 @@ impl FileRepository {
     /// Find file by path in a session
     pub async fn find_by_path(&self, session_id: Uuid, path: &Path) -> Result<Option<File>> {
         let path_str = path.to_string_lossy();
-        let file =
-            sqlx::query_as::<_, File>("SELECT * FROM files WHERE session_id = ? AND path = ?")
-                .bind(session_id.to_string())
-                .bind(path_str.as_ref())
-                .fetch_optional(&self.pool)
-                .await
-                .context("Failed to find file by path")?;
+        let file = sqlx::query_as::<_, File>(
+            "SELECT * FROM files WHERE session_id = ? AND path = ? AND checkpoint_id IS NULL",
+        )
+        .bind(session_id.to_string())
+        .bind(path_str.as_ref())
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to find file by path")?;
 
         Ok(file)
     }
@@ -124,15 +125,162 @@ impl FileRepository {
 
     /// Count files in a session
     pub async fn count_by_session(&self, session_id: Uuid) -> Result<i64> {
-        let result: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM files WHERE session_id = ?")
-            .bind(session_id.to_string())
-            .fetch_one(&self.pool)
-            .await
-            .context("Failed to count files")?;
+        let result: (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM files WHERE session_id = ? AND checkpoint_id IS NULL",
+        )
+        .bind(session_id.to_string())
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to count files")?;
 
         Ok(result.0)
     }
 
+    /// Record a snapshot of a file taken before it was modified
+    pub async fn create_snapshot(&self, snapshot: &FileSnapshot) -> Result<()> {
+        let path_str = snapshot.path.to_string_lossy();
+        let now = snapshot.created_at.timestamp();
+
+        sqlx::query(
+            r#"
+            INSERT INTO files (id, session_id, checkpoint_id, path, snapshot_data, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(snapshot.id.to_string())
+        .bind(snapshot.session_id.to_string())
+        .bind(snapshot.checkpoint_id.to_string())
+        .bind(path_str.as_ref())
+        .bind(&snapshot.content)
+        .bind(now)
+        .bind(now)
+        .execute(&self.pool)
+        .await
+        .context("Failed to create file snapshot")?;
+
+        tracing::debug!(
+            "Created snapshot of {:?} for checkpoint {}",
+            snapshot.path,
+            snapshot.checkpoint_id
+        );
+        Ok(())
+    }
+
+    /// Check whether a file was already snapshotted for a checkpoint
+    pub async fn has_snapshot(&self, checkpoint_id: Uuid, path: &Path) -> Result<bool> {
+        let path_str = path.to_string_lossy();
+        let result: (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM files WHERE checkpoint_id = ? AND path = ?")
+                .bind(checkpoint_id.to_string())
+                .bind(path_str.as_ref())
+                .fetch_one(&self.pool)
+                .await
+                .context("Failed to check file snapshot")?;
+
+        Ok(result.0 > 0)
+    }
+
+    /// Record the commit that auto-commit mode made for a checkpoint's files
+    pub async fn set_checkpoint_commit(
+        &self,
+        checkpoint_id: Uuid,
+        commit_hash: &str,
+    ) -> Result<()> {
+        sqlx::query("UPDATE files SET commit_hash = ? WHERE checkpoint_id = ?")
+            .bind(commit_hash)
+            .bind(checkpoint_id.to_string())
+            .execute(&self.pool)
+            .await
+            .context("Failed to link file snapshots to commit")?;
+
+        tracing::debug!(
+            "Linked checkpoint {} to commit {}",
+            checkpoint_id,
+            commit_hash
+        );
+        Ok(())
+    }
+
+    /// Find the snapshots taken during a checkpoint, oldest first
+    pub async fn find_snapshots(&self, checkpoint_id: Uuid) -> Result<Vec<FileSnapshot>> {
+        let snapshots = sqlx::query_as::<_, FileSnapshot>(
+            "SELECT * FROM files WHERE checkpoint_id = ? ORDER BY rowid ASC",
+        )
+        .bind(checkpoint_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to find file snapshots")?;
+
+        Ok(snapshots)
+    }
+
+    /// List checkpoints for a session, newest first
+    pub async fn list_checkpoints(&self, session_id: Uuid) -> Result<Vec<Checkpoint>> {
+        let checkpoints = sqlx::query_as::<_, Checkpoint>(
+            r#"
+            SELECT f.checkpoint_id, f.session_id, m.content AS prompt,
+                   COUNT(DISTINCT f.path) AS file_count, MIN(f.created_at) AS created_at
+            FROM files f
+            LEFT JOIN messages m ON m.id = f.checkpoint_id
+            WHERE f.session_id = ? AND f.checkpoint_id IS NOT NULL
+            GROUP BY f.checkpoint_id
+            ORDER BY MIN(f.rowid) DESC
+            "#,
+        )
+        .bind(session_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list checkpoints")?;
+
+        Ok(checkpoints)
+    }
+
+    /// Find the snapshots taken during a checkpoint and every later one, oldest first
+    pub async fn find_snapshots_since(
+        &self,
+        session_id: Uuid,
+        checkpoint_id: Uuid,
+    ) -> Result<Vec<FileSnapshot>> {
+        let snapshots = sqlx::query_as::<_, FileSnapshot>(
+            r#"
+            SELECT * FROM files
+            WHERE session_id = ?1 AND checkpoint_id IS NOT NULL
+              AND rowid >= (SELECT MIN(rowid) FROM files WHERE session_id = ?1 AND checkpoint_id = ?2)
+            ORDER BY rowid ASC
+            "#,
+        )
+        .bind(session_id.to_string())
+        .bind(checkpoint_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to find file snapshots")?;
+
+        Ok(snapshots)
+    }
+
+    /// Delete the snapshots taken during a checkpoint and every later one
+    pub async fn delete_snapshots_since(
+        &self,
+        session_id: Uuid,
+        checkpoint_id: Uuid,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM files
+            WHERE session_id = ?1 AND checkpoint_id IS NOT NULL
+              AND rowid >= (SELECT MIN(rowid) FROM files WHERE session_id = ?1 AND checkpoint_id = ?2)
+            "#,
+        )
+        .bind(session_id.to_string())
+        .bind(checkpoint_id.to_string())
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete file snapshots")?;
+
+        tracing::debug!("Deleted snapshots since checkpoint: {}", checkpoint_id);
+        Ok(())
+    }
+
     /// Delete all file records for a session
     pub async fn delete_by_session(&self, session_id: Uuid) -> Result<()> {
         sqlx::query("DELETE FROM files WHERE session_id = ?")
@@ -77,13 +77,14 @@ impl MessageRepository {
         sqlx::query(
             r#"
             UPDATE messages
-            SET content = ?, token_count = ?, cost = ?
+            SET content = ?, token_count = ?, cost = ?, commit_hash = ?
             WHERE id = ?
             "#,
         )
         .bind(&message.content)
         .bind(message.token_count)
         .bind(message.cost)
+        .bind(&message.commit_hash)
         .bind(message.id.to_string())
         .execute(&self.pool)
         .await
@@ -42,6 +42,20 @@ impl SessionRepository {
         Ok(session)
     }
 
+    /// Find sessions whose ID starts with `prefix`, most recent first
+    pub async fn find_by_prefix(&self, prefix: &str) -> Result<Vec<Session>> {
+        let sessions = sqlx::query_as::<_, Session>(
+            "SELECT * FROM sessions WHERE substr(id, 1, ?) = ? ORDER BY updated_at DESC",
+        )
+        .bind(prefix.len() as i64)
+        .bind(prefix)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to find sessions by prefix")?;
+
+        Ok(sessions)
+    }
+
     /// Create a new session
     pub async fn create(&self, session: &Session) -> Result<()> {
         sqlx::query(
@@ -198,6 +212,19 @@ impl SessionRepository {
         Ok(())
     }
 
+    /// Set the model of a session, leaving its other fields alone
+    pub async fn set_model(&self, id: Uuid, model: &str) -> Result<()> {
+        sqlx::query("UPDATE sessions SET model = ?, updated_at = ? WHERE id = ?")
+            .bind(model)
+            .bind(Utc::now().timestamp())
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .context("Failed to set session model")?;
+
+        Ok(())
+    }
+
     /// Update session statistics
     pub async fn update_stats(&self, id: Uuid, token_delta: i32, cost_delta: f64) -> Result<()> {
         let updated_at = Utc::now();
@@ -11,7 +11,7 @@
 //! - **Local-First:** SQLite storage for privacy and offline functionality
 //! - **Modern TUI:** Built with Ratatui for responsive terminal interface
 //! - **Tool System:** Extensible tools for file operations, shell commands, and more
-//! - **Context Files:** Automatic loading of .cursorrules, .claudemd files
+//! - **Context Files:** Automatic loading of .crustly.md, CLAUDE.md, AGENTS.md and .cursorrules files
 //! - **Session Management:** Persistent chat sessions with token/cost tracking
 //!
 //! ## Quick Start
@@ -34,6 +34,7 @@ pub mod db;
 pub mod error;
 pub mod llm;
 pub mod logging;
+pub mod outline;
 pub mod services;
 pub mod tui;
 pub mod utils;
@@ -0,0 +1,361 @@
+//! Auto-Commit
+//!
+//! Records each agent turn that changed files as a git commit on a
+//! dedicated branch. The commit is built from the branch tip (or HEAD, for
+//! the first one) with the turn's files taken from the working tree, so
+//! the checked-out branch, the index and the user's other changes are left
+//! alone. The branch becomes a history of what the agent did, one commit
+//! per turn, to inspect or revert with ordinary git commands.
+
+use git2::{IndexEntry, IndexTime, Repository};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Longest commit subject, in characters
+const MAX_SUBJECT_CHARS: usize = 72;
+
+/// Longest summary of the response in the commit body, in characters
+const MAX_SUMMARY_CHARS: usize = 1000;
+
+/// Commits agent turns to a dedicated branch
+///
+/// Clones share a lock, so agents running side by side commit one at a
+/// time and each commit builds on the one before.
+#[derive(Debug, Clone)]
+pub struct AutoCommit {
+    branch: String,
+    author_name: String,
+    author_email: String,
+    lock: Arc<Mutex<()>>,
+}
+
+impl AutoCommit {
+    /// Commit to `branch` as the given author
+    pub fn new(branch: String, author_name: String, author_email: String) -> Self {
+        Self {
+            branch,
+            author_name,
+            author_email,
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Branch the commits go to
+    pub fn branch(&self) -> &str {
+        &self.branch
+    }
+
+    /// Commit the current content of `files` for a turn
+    ///
+    /// Deleted files are removed from the commit. Returns the commit hash,
+    /// or `None` when the files match the branch already.
+    pub fn commit(
+        &self,
+        working_directory: &Path,
+        files: &[PathBuf],
+        message: &str,
+    ) -> std::result::Result<Option<String>, git2::Error> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let repo = Repository::discover(working_directory)?;
+        let root = repo
+            .workdir()
+            .ok_or_else(|| git2::Error::from_str("Bare repositories are not supported"))?
+            .canonicalize()
+            .map_err(|e| git2::Error::from_str(&e.to_string()))?;
+
+        let reference = format!("refs/heads/{}", self.branch);
+        let tip = match repo.find_reference(&reference) {
+            Ok(reference) => Some(reference.peel_to_commit()?),
+            Err(_) => repo.head().ok().and_then(|head| head.peel_to_commit().ok()),
+        };
+
+        let mut index = git2::Index::new()?;
+        if let Some(tip) = &tip {
+            index.read_tree(&tip.tree()?)?;
+        }
+        let mut changed = Vec::new();
+        for file in files {
+            let Some(relative) = relative_path(&root, file) else {
+                tracing::debug!("Not committing {:?}: outside the repository", file);
+                continue;
+            };
+            if file.is_file() {
+                index.add(&entry(&repo, file, &relative)?)?;
+            } else {
+                index.remove_path(Path::new(&relative))?;
+            }
+            changed.push(relative);
+        }
+
+        let tree = repo.find_tree(index.write_tree_to(&repo)?)?;
+        if tip.as_ref().is_some_and(|tip| tip.tree_id() == tree.id()) {
+            return Ok(None);
+        }
+
+        let signature = git2::Signature::now(&self.author_name, &self.author_email)?;
+        let parents: Vec<&git2::Commit> = tip.iter().collect();
+        let id = repo.commit(
+            Some(&reference),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )?;
+
+        // Someone working on the branch itself: keep their index in step
+        let on_branch = repo
+            .head()
+            .ok()
+            .is_some_and(|head| head.name() == Some(reference.as_str()));
+        if on_branch {
+            let mut index = repo.index()?;
+            for relative in &changed {
+                let path = Path::new(relative);
+                if root.join(path).is_file() {
+                    index.add_path(path)?;
+                } else {
+                    index.remove_path(path)?;
+                }
+            }
+            index.write()?;
+        }
+
+        Ok(Some(id.to_string()))
+    }
+}
+
+/// Path of `file` in the repository, with `/` separators
+fn relative_path(root: &Path, file: &Path) -> Option<String> {
+    // The file may be gone, so resolve its directory
+    let parent = file.parent()?.canonicalize().ok()?;
+    let relative = parent.join(file.file_name()?);
+    let relative = relative.strip_prefix(root).ok()?;
+    let parts: Vec<_> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    Some(parts.join("/"))
+}
+
+/// Index entry for the working tree content of `file`
+fn entry(
+    repo: &Repository,
+    file: &Path,
+    relative: &str,
+) -> std::result::Result<IndexEntry, git2::Error> {
+    let metadata = std::fs::metadata(file).map_err(|e| git2::Error::from_str(&e.to_string()))?;
+    #[cfg(unix)]
+    let executable = {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o111 != 0
+    };
+    #[cfg(not(unix))]
+    let executable = false;
+
+    Ok(IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode: if executable { 0o100755 } else { 0o100644 },
+        uid: 0,
+        gid: 0,
+        file_size: metadata.len() as u32,
+        id: repo.blob_path(file)?,
+        flags: 0,
+        flags_extended: 0,
+        path: relative.as_bytes().to_vec(),
+    })
+}
+
+/// Commit message for a turn: the prompt as subject, the response as body
+pub fn commit_message(
+    prompt: &str,
+    response: &str,
+    files: &[PathBuf],
+    working_directory: &Path,
+    session_id: Uuid,
+) -> String {
+    let first_line = prompt
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("Agent changes");
+    let mut subject = format!("crustly: {}", first_line);
+    if subject.chars().count() > MAX_SUBJECT_CHARS {
+        subject = subject.chars().take(MAX_SUBJECT_CHARS - 3).collect();
+        subject.push_str("...");
+    }
+
+    let mut message = subject;
+    let summary = response.trim().split("\n\n").next().unwrap_or("").trim();
+    if !summary.is_empty() {
+        message.push_str("\n\n");
+        if summary.chars().count() > MAX_SUMMARY_CHARS {
+            message.extend(summary.chars().take(MAX_SUMMARY_CHARS));
+            message.push_str("...");
+        } else {
+            message.push_str(summary);
+        }
+    }
+
+    message.push_str("\n\nFiles:");
+    for file in files {
+        let display = file.strip_prefix(working_directory).unwrap_or(file);
+        message.push_str(&format!("\n  {}", display.display()));
+    }
+    message.push_str(&format!("\n\nCrustly-Session: {}\n", session_id));
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Repository with one commit on its default branch
+    fn repository() -> (TempDir, Repository) {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join("keep.txt"), "keep\n").unwrap();
+        std::fs::write(dir.path().join("gone.txt"), "gone\n").unwrap();
+        {
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new("keep.txt")).unwrap();
+            index.add_path(Path::new("gone.txt")).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let signature = git2::Signature::now("User", "user@example.com").unwrap();
+            repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+                .unwrap();
+        }
+        (dir, repo)
+    }
+
+    #[test]
+    fn test_commit_to_dedicated_branch() {
+        let (dir, repo) = repository();
+        let auto_commit = AutoCommit::new(
+            "crustly/auto".to_string(),
+            "Crustly".to_string(),
+            "crustly@localhost".to_string(),
+        );
+        let head_before = repo.head().unwrap().target().unwrap();
+
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/new.rs"), "fn new() {}\n").unwrap();
+        std::fs::remove_file(dir.path().join("gone.txt")).unwrap();
+        // Not part of the turn
+        std::fs::write(dir.path().join("keep.txt"), "user edit\n").unwrap();
+
+        let files = vec![dir.path().join("src/new.rs"), dir.path().join("gone.txt")];
+        let hash = auto_commit
+            .commit(dir.path(), &files, "crustly: add new\n")
+            .unwrap()
+            .unwrap();
+
+        // The checked-out branch and index are untouched
+        assert_eq!(repo.head().unwrap().target().unwrap(), head_before);
+        assert!(repo
+            .index()
+            .unwrap()
+            .get_path(Path::new("gone.txt"), 0)
+            .is_some());
+
+        let commit = repo
+            .find_reference("refs/heads/crustly/auto")
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        assert_eq!(commit.id().to_string(), hash);
+        assert_eq!(commit.author().name(), Some("Crustly"));
+        assert_eq!(commit.parent_id(0).unwrap(), head_before);
+        let tree = commit.tree().unwrap();
+        assert!(tree.get_path(Path::new("src/new.rs")).is_ok());
+        assert!(tree.get_path(Path::new("gone.txt")).is_err());
+        let keep = tree.get_path(Path::new("keep.txt")).unwrap();
+        let blob = repo.find_blob(keep.id()).unwrap();
+        assert_eq!(blob.content(), b"keep\n");
+
+        // Nothing new to commit
+        assert_eq!(
+            auto_commit.commit(dir.path(), &files, "again").unwrap(),
+            None
+        );
+
+        // The next turn builds on the branch
+        std::fs::write(dir.path().join("src/new.rs"), "fn newer() {}\n").unwrap();
+        let next = auto_commit
+            .commit(dir.path(), &files[..1], "crustly: update\n")
+            .unwrap()
+            .unwrap();
+        let next = repo
+            .find_commit(git2::Oid::from_str(&next).unwrap())
+            .unwrap();
+        assert_eq!(next.parent_id(0).unwrap().to_string(), hash);
+    }
+
+    #[test]
+    fn test_concurrent_commits_are_serialized() {
+        let (dir, repo) = repository();
+        let auto_commit = AutoCommit::new(
+            "crustly/auto".to_string(),
+            "Crustly".to_string(),
+            "crustly@localhost".to_string(),
+        );
+
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let auto_commit = auto_commit.clone();
+                let root = dir.path().to_path_buf();
+                std::thread::spawn(move || {
+                    let file = root.join(format!("file{}.txt", i));
+                    std::fs::write(&file, "content\n").unwrap();
+                    auto_commit
+                        .commit(&root, &[file], &format!("crustly: file {}\n", i))
+                        .unwrap()
+                        .unwrap()
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // No commit was lost: each builds on the one before
+        let tip = repo
+            .find_reference("refs/heads/crustly/auto")
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        let tree = tip.tree().unwrap();
+        for i in 0..4 {
+            assert!(tree.get_path(Path::new(&format!("file{}.txt", i))).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_commit_message() {
+        let root = Path::new("/project");
+        let message = commit_message(
+            "\nFix the parser\nIt fails on empty input",
+            "Handled the empty case.\n\nDetails nobody reads.",
+            &[root.join("src/parser.rs")],
+            root,
+            Uuid::nil(),
+        );
+        assert_eq!(
+            message,
+            "crustly: Fix the parser\n\nHandled the empty case.\n\nFiles:\n  src/parser.rs\n\n\
+             Crustly-Session: 00000000-0000-0000-0000-000000000000\n"
+        );
+
+        let long = commit_message(&"x".repeat(100), "", &[], root, Uuid::nil());
+        assert_eq!(
+            long.lines().next().unwrap().chars().count(),
+            MAX_SUBJECT_CHARS
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Agent Events
+//!
+//! Events reported while the agent works through a turn, for callers that
+//! want to follow along (e.g. `crustly run --format stream-json`).
+//!
+//! # Stream-JSON schema
+//!
+//! Each event serializes to one JSON object per line, tagged by `type` and
+//! carrying the schema `version` ([`EVENT_SCHEMA_VERSION`]). The version is
+//! bumped whenever a field is removed or changes meaning; new fields and new
+//! event types may be added without a bump, so consumers should ignore what
+//! they don't know.
+//!
+//! | `type`        | Fields                                                        |
+//! |---------------|---------------------------------------------------------------|
+//! | `turn_start`  | `session_id`, `model`                                         |
+//! | `text`        | `text`                                                        |
+//! | `tool_call`   | `id`, `name`, `input`                                         |
+//! | `tool_result` | `id`, `content`, `is_error`                                   |
+//! | `usage`       | `input_tokens`, `output_tokens` (of one model call)           |
+//! | `final`       | `content`, `usage`, `cost`, `model`, `commit_hash`            |
+//! | `error`       | `message`                                                     |
+//!
+//! Text is not streamed token by token: each `text` event carries one
+//! complete text block of a model response, and the concatenated blocks of
+//! the last response equal the `final` content.
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// Version of the stream-JSON event schema
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// Something that happened during an agent turn
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentEvent {
+    /// The turn started
+    TurnStart { session_id: Uuid, model: String },
+    /// A complete text block written by the model
+    Text { text: String },
+    /// The model called a tool
+    ToolCall {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    /// A tool call finished (or was refused)
+    ToolResult {
+        id: String,
+        content: String,
+        is_error: bool,
+    },
+    /// Tokens used by one model call
+    Usage {
+        input_tokens: u32,
+        output_tokens: u32,
+    },
+    /// The turn finished
+    Final {
+        content: String,
+        usage: EventUsage,
+        cost: f64,
+        model: String,
+        commit_hash: Option<String>,
+    },
+    /// The turn failed
+    Error { message: String },
+}
+
+/// Token totals of a turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct EventUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+}
+
+impl AgentEvent {
+    /// The event as one line of stream-JSON, without the trailing newline
+    pub fn to_json_line(&self) -> String {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Value::Object(map) = &mut value {
+            map.insert("version".to_string(), EVENT_SCHEMA_VERSION.into());
+        }
+        value.to_string()
+    }
+}
+
+/// Channel used to deliver agent events
+pub type AgentEventSender = mpsc::UnboundedSender<AgentEvent>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_line() {
+        let event = AgentEvent::Error {
+            message: "Provider error: rate limited".to_string(),
+        };
+        assert_eq!(
+            event.to_json_line(),
+            r#"{"message":"Provider error: rate limited","type":"error","version":1}"#
+        );
+    }
+}
@@ -3,13 +3,18 @@
 //! Provides high-level agent functionality for managing conversations,
 //! executing tools, and coordinating with LLM providers.
 
+pub mod auto_commit;
 pub mod context;
 pub mod error;
+pub mod events;
 pub mod service;
 
 // Re-exports
+pub use auto_commit::AutoCommit;
 pub use context::AgentContext;
 pub use error::{AgentError, Result};
+pub use events::{AgentEvent, AgentEventSender};
 pub use service::{
-    AgentResponse, AgentService, AgentStreamResponse, ApprovalCallback, ToolApprovalInfo,
+    AgentResponse, AgentService, AgentStreamResponse, ApprovalCallback, ApprovalDecision,
+    ToolApprovalInfo, ToolPreview,
 };
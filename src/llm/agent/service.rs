@@ -5,11 +5,16 @@
 
 use super::context::AgentContext;
 use super::error::{AgentError, Result};
+use super::events::{AgentEvent, AgentEventSender, EventUsage};
+use crate::llm::agent::auto_commit::{self, AutoCommit};
+use crate::llm::prompt::{ProjectContext, RepoMap};
 use crate::llm::provider::{
     ContentBlock, LLMRequest, LLMResponse, Message, Provider, ProviderStream, StopReason,
 };
-use crate::llm::tools::{ToolExecutionContext, ToolRegistry};
-use crate::services::{MessageService, ServiceContext, SessionService};
+use crate::llm::tools::patch::unified_diff;
+use crate::llm::tools::{ToolExecutionContext, ToolProgressSender, ToolRegistry};
+use crate::lsp::LspManager;
+use crate::services::{FileService, MessageService, ServiceContext, SessionService};
 use serde_json::Value;
 use std::future::Future;
 use std::pin::Pin;
@@ -27,12 +32,45 @@ pub struct ToolApprovalInfo {
     pub tool_input: Value,
     /// Tool capabilities
     pub capabilities: Vec<String>,
+    /// Preview of the file changes the tool would make, if it supports previews
+    pub preview: Option<ToolPreview>,
+}
+
+/// Preview of the file changes a tool call would make
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolPreview {
+    /// Unified diff of the proposed changes
+    Diff(String),
+    /// The preview could not be computed (e.g. the edit does not apply)
+    Unavailable(String),
+}
+
+/// Outcome of an approval request
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApprovalDecision {
+    /// Run the tool with its original input
+    Approved,
+    /// Run the tool with input edited by the user
+    ApprovedWithInput(Value),
+    /// Do not run the tool
+    Denied,
+}
+
+impl From<bool> for ApprovalDecision {
+    fn from(approved: bool) -> Self {
+        if approved {
+            Self::Approved
+        } else {
+            Self::Denied
+        }
+    }
 }
 
 /// Type alias for approval callback function
-/// Returns true if approved, false if denied
 pub type ApprovalCallback = Arc<
-    dyn Fn(ToolApprovalInfo) -> Pin<Box<dyn Future<Output = Result<bool>> + Send>> + Send + Sync,
+    dyn Fn(ToolApprovalInfo) -> Pin<Box<dyn Future<Output = Result<ApprovalDecision>> + Send>>
+        + Send
+        + Sync,
 >;
 
 /// Agent Service for managing AI conversations
@@ -60,6 +98,24 @@ pub struct AgentService {
 
     /// Working directory for tool execution
     working_directory: std::path::PathBuf,
+
+    /// Sink for live tool output (stdout/stderr lines, percent complete)
+    progress_tx: Option<ToolProgressSender>,
+
+    /// Language servers to notify when tools change files
+    lsp_manager: Option<Arc<LspManager>>,
+
+    /// Project instruction files appended to the system prompt
+    project_context: Option<Arc<ProjectContext>>,
+
+    /// Repository map appended to the system prompt
+    repo_map: Option<Arc<RepoMap>>,
+
+    /// Commits each turn that changed files, when enabled
+    auto_commit: Option<AutoCommit>,
+
+    /// Sink for the events of a turn (text, tool calls and results, usage)
+    event_tx: Option<AgentEventSender>,
 }
 
 impl AgentService {
@@ -74,6 +130,12 @@ impl AgentService {
             auto_approve_tools: false,
             approval_callback: None,
             working_directory: std::env::current_dir().unwrap_or_default(),
+            progress_tx: None,
+            lsp_manager: None,
+            project_context: None,
+            repo_map: None,
+            auto_commit: None,
+            event_tx: None,
         }
     }
 
@@ -113,6 +175,126 @@ impl AgentService {
         self
     }
 
+    /// Set the sink that receives live tool progress
+    pub fn with_progress_sender(mut self, progress_tx: Option<ToolProgressSender>) -> Self {
+        self.progress_tx = progress_tx;
+        self
+    }
+
+    /// Set the language servers to keep in sync with files tools write
+    pub fn with_lsp_manager(mut self, lsp_manager: Option<Arc<LspManager>>) -> Self {
+        self.lsp_manager = lsp_manager;
+        self
+    }
+
+    /// Set the project instruction files to include in the system prompt
+    pub fn with_project_context(mut self, project_context: Option<Arc<ProjectContext>>) -> Self {
+        self.project_context = project_context;
+        self
+    }
+
+    /// Project instruction files included in the system prompt, if any
+    pub fn project_context(&self) -> Option<&Arc<ProjectContext>> {
+        self.project_context.as_ref()
+    }
+
+    /// Set the repository map to include in the system prompt
+    pub fn with_repo_map(mut self, repo_map: Option<Arc<RepoMap>>) -> Self {
+        self.repo_map = repo_map;
+        self
+    }
+
+    /// Set the auto-commit mode for turns that change files
+    pub fn with_auto_commit(mut self, auto_commit: Option<AutoCommit>) -> Self {
+        self.auto_commit = auto_commit;
+        self
+    }
+
+    /// Set the sink that receives the events of each turn
+    pub fn with_event_sender(mut self, event_tx: Option<AgentEventSender>) -> Self {
+        self.event_tx = event_tx;
+        self
+    }
+
+    /// Report an event (no-op when no sink is configured)
+    fn emit(&self, event: AgentEvent) {
+        if let Some(ref tx) = self.event_tx {
+            // The receiver going away just means nobody is listening anymore
+            let _ = tx.send(event);
+        }
+    }
+
+    /// System prompt for a request, with the project instruction files
+    /// reread and the repository map refreshed
+    async fn system_prompt(&self) -> Option<String> {
+        let mut sections: Vec<String> = self.default_system_prompt.iter().cloned().collect();
+
+        if let Some(project_context) = self.project_context.clone() {
+            let instructions = tokio::task::spawn_blocking(move || {
+                project_context.render(&project_context.load())
+            })
+            .await
+            .ok()
+            .flatten();
+            if let Some(instructions) = instructions {
+                sections.push(format!(
+                    "# Project Instructions\n\nInstructions from the user and the project's \
+                     instruction files, from the most general to the most specific. Follow \
+                     them; where they conflict, the more specific ones win.\n\n{}",
+                    instructions
+                ));
+            }
+        }
+
+        // The map only supplements a base prompt
+        let map = match &self.repo_map {
+            Some(repo_map) if self.default_system_prompt.is_some() => repo_map.render().await,
+            _ => None,
+        };
+        if let Some(map) = map {
+            sections.push(format!(
+                "# Repository Map\n\nFiles in the working directory, with the symbols \
+                 other files use most:\n\n{}",
+                map
+            ));
+        }
+
+        if sections.is_empty() {
+            return None;
+        }
+        Some(sections.join("\n\n"))
+    }
+
+    /// Preview the file changes a tool call would make, without running it
+    ///
+    /// Returns `None` for unknown tools and tools that do not support previews.
+    pub async fn preview_tool_call(
+        &self,
+        tool_name: &str,
+        tool_input: &Value,
+    ) -> Option<ToolPreview> {
+        let tool = self.tool_registry.get(tool_name)?;
+        let context = ToolExecutionContext::new(Uuid::nil())
+            .with_working_directory(self.working_directory.clone());
+
+        match tool.preview(tool_input, &context).await {
+            Ok(changes) if changes.is_empty() => None,
+            Ok(changes) => Some(ToolPreview::Diff(
+                changes
+                    .iter()
+                    .map(|change| {
+                        unified_diff(
+                            &change.path,
+                            change.before.as_deref().unwrap_or(""),
+                            &change.after,
+                        )
+                    })
+                    .collect(),
+            )),
+            Err(e) => Some(ToolPreview::Unavailable(e.to_string())),
+        }
+    }
+
     /// Get the provider name
     pub fn provider_name(&self) -> &str {
         self.provider.name()
@@ -182,6 +364,7 @@ impl AgentService {
             message_id: assistant_db_msg.id,
             content: assistant_text,
             stop_reason: response.stop_reason,
+            commit_hash: None,
             usage: response.usage,
             cost,
             model: response.model,
@@ -245,6 +428,27 @@ impl AgentService {
         model: Option<String>,
         read_only_mode: bool,
     ) -> Result<AgentResponse> {
+        self.send_message_with_tool_filter(session_id, user_message, model, read_only_mode, None)
+            .await
+    }
+
+    /// Send a message with automatic tool execution, offering the model only
+    /// the tools named in `allowed_tools` (all registered tools when `None`)
+    ///
+    /// Calls to any other tool are refused with an error result.
+    pub async fn send_message_with_tool_filter(
+        &self,
+        session_id: Uuid,
+        user_message: String,
+        model: Option<String>,
+        read_only_mode: bool,
+        allowed_tools: Option<&[String]>,
+    ) -> Result<AgentResponse> {
+        let is_allowed = |name: &str| match allowed_tools {
+            Some(allowed) => allowed.iter().any(|tool| tool == name),
+            None => true,
+        };
+
         // Get or create session
         let session_service = SessionService::new(self.context.clone());
         let _session = session_service
@@ -267,8 +471,8 @@ impl AgentService {
             AgentContext::from_db_messages(session_id, db_messages, context_window as usize);
 
         // Add system prompt if available
-        if let Some(system_prompt) = &self.default_system_prompt {
-            context.system_prompt = Some(system_prompt.clone());
+        if let Some(system_prompt) = self.system_prompt().await {
+            context.system_prompt = Some(system_prompt);
         }
 
         // Add user message
@@ -276,16 +480,25 @@ impl AgentService {
         context.add_message(user_msg);
 
         // Save user message to database
-        let _user_db_msg = message_service
-            .create_message(session_id, "user".to_string(), user_message)
+        let user_db_msg = message_service
+            .create_message(session_id, "user".to_string(), user_message.clone())
             .await
             .map_err(|e| AgentError::Database(e.to_string()))?;
 
+        // File changes made during this turn are checkpointed under the user message
+        let checkpoint_id = user_db_msg.id;
+
         // Create tool execution context
         let tool_context = ToolExecutionContext::new(session_id)
             .with_auto_approve(self.auto_approve_tools)
             .with_working_directory(self.working_directory.clone())
-            .with_read_only_mode(read_only_mode);
+            .with_read_only_mode(read_only_mode)
+            .with_progress_sender(self.progress_tx.clone());
+
+        self.emit(AgentEvent::TurnStart {
+            session_id,
+            model: model_name.clone(),
+        });
 
         // Tool execution loop
         let mut iteration = 0;
@@ -309,7 +522,8 @@ impl AgentService {
             let tool_count = self.tool_registry.count();
             tracing::debug!("Tool registry contains {} tools", tool_count);
             if tool_count > 0 {
-                let tool_defs = self.tool_registry.get_tool_definitions();
+                let mut tool_defs = self.tool_registry.get_tool_definitions();
+                tool_defs.retain(|tool| is_allowed(&tool.name));
                 tracing::debug!("Adding {} tool definitions to request", tool_defs.len());
                 request = request.with_tools(tool_defs);
             } else {
@@ -327,6 +541,24 @@ impl AgentService {
             total_input_tokens += response.usage.input_tokens;
             total_output_tokens += response.usage.output_tokens;
 
+            for block in &response.content {
+                match block {
+                    ContentBlock::Text { text } => {
+                        self.emit(AgentEvent::Text { text: text.clone() })
+                    }
+                    ContentBlock::ToolUse { id, name, input } => self.emit(AgentEvent::ToolCall {
+                        id: id.clone(),
+                        name: name.clone(),
+                        input: input.clone(),
+                    }),
+                    _ => {}
+                }
+            }
+            self.emit(AgentEvent::Usage {
+                input_tokens: response.usage.input_tokens,
+                output_tokens: response.usage.output_tokens,
+            });
+
             // Check if response contains tool use
             tracing::debug!("Response has {} content blocks", response.content.len());
             for (i, block) in response.content.iter().enumerate() {
@@ -522,8 +754,13 @@ impl AgentService {
 
             // Execute tools and build response message
             let mut tool_results = Vec::new();
+            let mut reported_results = 0;
 
             for (tool_id, tool_name, tool_input) in tool_uses {
+                // The previous call's result, however it got there
+                self.emit_tool_results(&tool_results[reported_results..]);
+                reported_results = tool_results.len();
+
                 tracing::info!(
                     "Executing tool '{}' (iteration {}/{})",
                     tool_name,
@@ -531,9 +768,22 @@ impl AgentService {
                     self.max_tool_iterations
                 );
 
+                if !is_allowed(&tool_name) {
+                    tracing::warn!(
+                        "Refusing call to tool '{}' outside the allowed set",
+                        tool_name
+                    );
+                    tool_results.push(ContentBlock::ToolResult {
+                        tool_use_id: tool_id,
+                        content: format!("Tool '{}' is not allowed for this request", tool_name),
+                        is_error: Some(true),
+                    });
+                    continue;
+                }
+
                 // Check if approval is needed
                 let needs_approval = if let Some(tool) = self.tool_registry.get(&tool_name) {
-                    tool.requires_approval()
+                    tool.call_requires_approval(&tool_input)
                         && !self.auto_approve_tools
                         && !tool_context.auto_approve
                 } else {
@@ -550,10 +800,11 @@ impl AgentService {
                                 tool_description: tool.description().to_string(),
                                 tool_input: tool_input.clone(),
                                 capabilities: tool
-                                    .capabilities()
+                                    .call_capabilities(&tool_input)
                                     .iter()
                                     .map(|c| format!("{:?}", c))
                                     .collect(),
+                                preview: self.preview_tool_call(&tool_name, &tool_input).await,
                             }
                         } else {
                             // Tool not found, skip approval
@@ -568,51 +819,80 @@ impl AgentService {
                         // Call approval callback
                         tracing::info!("Requesting user approval for tool '{}'", tool_name);
                         match approval_callback(tool_info).await {
-                            Ok(approved) => {
-                                if !approved {
-                                    tracing::warn!("User denied approval for tool '{}'", tool_name);
-                                    tool_results.push(ContentBlock::ToolResult {
-                                        tool_use_id: tool_id,
-                                        content: "User denied permission to execute this tool"
-                                            .to_string(),
-                                        is_error: Some(true),
-                                    });
-                                    continue;
-                                }
+                            Ok(decision) => {
+                                let (tool_input, edit_note) = match decision {
+                                    ApprovalDecision::Approved => (tool_input, None),
+                                    ApprovalDecision::ApprovedWithInput(edited) => {
+                                        let note = format!(
+                                            "Note: the user edited this tool call before approving it. \
+                                             Input used: {}\n\n",
+                                            edited
+                                        );
+                                        (edited, Some(note))
+                                    }
+                                    ApprovalDecision::Denied => {
+                                        tracing::warn!(
+                                            "User denied approval for tool '{}'",
+                                            tool_name
+                                        );
+                                        tool_results.push(ContentBlock::ToolResult {
+                                            tool_use_id: tool_id,
+                                            content: "User denied permission to execute this tool"
+                                                .to_string(),
+                                            is_error: Some(true),
+                                        });
+                                        continue;
+                                    }
+                                };
                                 tracing::info!("User approved tool '{}'", tool_name);
                                 // Create approved context for this tool execution
-                                let approved_tool_context = ToolExecutionContext {
-                                    session_id: tool_context.session_id,
-                                    working_directory: tool_context.working_directory.clone(),
-                                    env_vars: tool_context.env_vars.clone(),
-                                    auto_approve: true, // User approved this execution
-                                    timeout_secs: tool_context.timeout_secs,
-                                    read_only_mode: tool_context.read_only_mode,
-                                };
+                                let approved_tool_context =
+                                    tool_context.clone().with_auto_approve(true); // User approved this execution
+
+                                let changed_files = self
+                                    .modified_files(&tool_name, &tool_input, &approved_tool_context)
+                                    .await;
+                                self.snapshot_before_tool(
+                                    session_id,
+                                    checkpoint_id,
+                                    &tool_name,
+                                    &changed_files,
+                                )
+                                .await;
 
                                 // Execute the tool with approved context
-                                match self
+                                let execution = self
                                     .tool_registry
                                     .execute(&tool_name, tool_input, &approved_tool_context)
-                                    .await
-                                {
+                                    .await;
+                                let diagnostics = self.sync_changed_files(&changed_files).await;
+                                match execution {
                                     Ok(result) => {
+                                        let content = if result.success {
+                                            with_diagnostics(result.output, diagnostics)
+                                        } else {
+                                            result.error.unwrap_or_else(|| {
+                                                "Tool execution failed".to_string()
+                                            })
+                                        };
                                         tool_results.push(ContentBlock::ToolResult {
                                             tool_use_id: tool_id,
-                                            content: if result.success {
-                                                result.output
-                                            } else {
-                                                result.error.unwrap_or_else(|| {
-                                                    "Tool execution failed".to_string()
-                                                })
-                                            },
+                                            content: format!(
+                                                "{}{}",
+                                                edit_note.unwrap_or_default(),
+                                                content
+                                            ),
                                             is_error: Some(!result.success),
                                         });
                                     }
                                     Err(e) => {
                                         tool_results.push(ContentBlock::ToolResult {
                                             tool_use_id: tool_id,
-                                            content: format!("Tool execution error: {}", e),
+                                            content: format!(
+                                                "{}Tool execution error: {}",
+                                                edit_note.unwrap_or_default(),
+                                                e
+                                            ),
                                             is_error: Some(true),
                                         });
                                     }
@@ -645,17 +925,24 @@ impl AgentService {
                     }
                 }
 
+                let changed_files = self
+                    .modified_files(&tool_name, &tool_input, &tool_context)
+                    .await;
+                self.snapshot_before_tool(session_id, checkpoint_id, &tool_name, &changed_files)
+                    .await;
+
                 // Execute the tool
-                match self
+                let execution = self
                     .tool_registry
                     .execute(&tool_name, tool_input, &tool_context)
-                    .await
-                {
+                    .await;
+                let diagnostics = self.sync_changed_files(&changed_files).await;
+                match execution {
                     Ok(result) => {
                         tool_results.push(ContentBlock::ToolResult {
                             tool_use_id: tool_id,
                             content: if result.success {
-                                result.output
+                                with_diagnostics(result.output, diagnostics)
                             } else {
                                 result
                                     .error
@@ -674,6 +961,8 @@ impl AgentService {
                 }
             }
 
+            self.emit_tool_results(&tool_results[reported_results..]);
+
             // Add assistant message with tool use to context
             let assistant_msg = Message {
                 role: crate::llm::provider::Role::Assistant,
@@ -725,10 +1014,35 @@ impl AgentService {
             .await
             .map_err(|e| AgentError::Database(e.to_string()))?;
 
+        let commit_hash = if read_only_mode {
+            None
+        } else {
+            self.commit_turn(
+                session_id,
+                checkpoint_id,
+                assistant_db_msg.id,
+                &user_message,
+                &assistant_text,
+            )
+            .await
+        };
+
+        self.emit(AgentEvent::Final {
+            content: assistant_text.clone(),
+            usage: EventUsage {
+                input_tokens: total_input_tokens,
+                output_tokens: total_output_tokens,
+            },
+            cost,
+            model: response.model.clone(),
+            commit_hash: commit_hash.clone(),
+        });
+
         Ok(AgentResponse {
             message_id: assistant_db_msg.id,
             content: assistant_text,
             stop_reason: response.stop_reason,
+            commit_hash,
             usage: crate::llm::provider::TokenUsage {
                 input_tokens: total_input_tokens,
                 output_tokens: total_output_tokens,
@@ -738,6 +1052,152 @@ impl AgentService {
         })
     }
 
+    /// Report tool results as events
+    fn emit_tool_results(&self, results: &[ContentBlock]) {
+        for block in results {
+            if let ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            } = block
+            {
+                self.emit(AgentEvent::ToolResult {
+                    id: tool_use_id.clone(),
+                    content: content.clone(),
+                    is_error: is_error.unwrap_or(false),
+                });
+            }
+        }
+    }
+
+    /// Files a tool call will modify, resolved against the working directory
+    async fn modified_files(
+        &self,
+        tool_name: &str,
+        tool_input: &Value,
+        tool_context: &ToolExecutionContext,
+    ) -> Vec<std::path::PathBuf> {
+        if tool_context.read_only_mode {
+            return Vec::new();
+        }
+        let Some(tool) = self.tool_registry.get(tool_name) else {
+            return Vec::new();
+        };
+
+        tool.modified_paths(tool_input, tool_context)
+            .await
+            .into_iter()
+            .map(|path| {
+                let path = std::path::Path::new(&path);
+                if path.is_absolute() {
+                    path.to_path_buf()
+                } else {
+                    tool_context.working_directory.join(path)
+                }
+            })
+            .collect()
+    }
+
+    /// Snapshot the files a tool is about to modify so the turn can be undone
+    async fn snapshot_before_tool(
+        &self,
+        session_id: Uuid,
+        checkpoint_id: Uuid,
+        tool_name: &str,
+        paths: &[std::path::PathBuf],
+    ) {
+        let file_service = FileService::new(self.context.clone());
+        for path in paths {
+            if let Err(e) = file_service
+                .snapshot_file(session_id, checkpoint_id, path)
+                .await
+            {
+                tracing::warn!(
+                    "Failed to checkpoint {:?} before '{}': {}",
+                    path,
+                    tool_name,
+                    e
+                );
+            }
+        }
+    }
+
+    /// Commit the files a turn changed, in auto-commit mode
+    ///
+    /// Links the commit to the turn's assistant message and file snapshots.
+    /// Failures are logged rather than failing the turn.
+    async fn commit_turn(
+        &self,
+        session_id: Uuid,
+        checkpoint_id: Uuid,
+        message_id: Uuid,
+        prompt: &str,
+        response: &str,
+    ) -> Option<String> {
+        let auto_commit = self.auto_commit.clone()?;
+        let file_service = FileService::new(self.context.clone());
+        let files = match file_service.checkpoint_paths(checkpoint_id).await {
+            Ok(files) if !files.is_empty() => files,
+            Ok(_) => return None,
+            Err(e) => {
+                tracing::warn!("Failed to list the files changed this turn: {}", e);
+                return None;
+            }
+        };
+
+        let message = auto_commit::commit_message(
+            prompt,
+            response,
+            &files,
+            &self.working_directory,
+            session_id,
+        );
+        let branch = auto_commit.branch().to_string();
+        let working_directory = self.working_directory.clone();
+        let commit = tokio::task::spawn_blocking(move || {
+            auto_commit.commit(&working_directory, &files, &message)
+        })
+        .await;
+        let hash = match commit {
+            Ok(Ok(hash)) => hash?,
+            Ok(Err(e)) => {
+                tracing::warn!("Auto-commit failed: {}", e.message());
+                return None;
+            }
+            Err(e) => {
+                tracing::warn!("Auto-commit task failed: {}", e);
+                return None;
+            }
+        };
+
+        tracing::info!("Committed turn as {} on {}", hash, branch);
+        let message_service = MessageService::new(self.context.clone());
+        if let Err(e) = message_service
+            .set_commit_hash(message_id, hash.clone())
+            .await
+        {
+            tracing::warn!("Failed to link message {} to commit: {}", message_id, e);
+        }
+        if let Err(e) = file_service.link_commit(checkpoint_id, &hash).await {
+            tracing::warn!(
+                "Failed to link checkpoint {} to commit: {}",
+                checkpoint_id,
+                e
+            );
+        }
+        Some(hash)
+    }
+
+    /// Send the new content of files a tool changed to the language servers
+    ///
+    /// Returns the diagnostics they published for the files, if any.
+    async fn sync_changed_files(&self, paths: &[std::path::PathBuf]) -> Option<String> {
+        if paths.is_empty() {
+            return None;
+        }
+        self.lsp_manager.as_ref()?.files_edited(paths).await
+    }
+
     /// Helper to prepare message context for LLM requests
     ///
     /// This extracts the common setup logic shared between send_message() and
@@ -770,8 +1230,8 @@ impl AgentService {
             AgentContext::from_db_messages(session_id, db_messages, context_window as usize);
 
         // Add system prompt if available
-        if let Some(system_prompt) = &self.default_system_prompt {
-            context.system_prompt = Some(system_prompt.clone());
+        if let Some(system_prompt) = self.system_prompt().await {
+            context.system_prompt = Some(system_prompt);
         }
 
         // Add user message
@@ -830,6 +1290,9 @@ pub struct AgentResponse {
     /// Stop reason
     pub stop_reason: Option<StopReason>,
 
+    /// Commit recording the turn's file changes, in auto-commit mode
+    pub commit_hash: Option<String>,
+
     /// Token usage
     pub usage: crate::llm::provider::TokenUsage,
 
@@ -855,6 +1318,14 @@ pub struct AgentStreamResponse {
     pub model: String,
 }
 
+/// Tool output followed by the diagnostics of the files it changed
+fn with_diagnostics(output: String, diagnostics: Option<String>) -> String {
+    match diagnostics {
+        Some(diagnostics) => format!("{}\n\n{}", output, diagnostics),
+        None => output,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1135,4 +1606,81 @@ mod tests {
         assert!(response.usage.input_tokens >= 25); // 10 + 15
         assert!(response.usage.output_tokens >= 45); // 20 + 25
     }
+
+    #[tokio::test]
+    async fn test_tool_filter_refuses_other_tools() {
+        let db = Database::connect_in_memory().await.unwrap();
+        db.run_migrations().await.unwrap();
+        let context = ServiceContext::new(db.pool().clone());
+
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(MockTool));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let agent_service =
+            AgentService::new(Arc::new(MockProviderWithTools::new()), context.clone())
+                .with_tool_registry(Arc::new(registry))
+                .with_auto_approve_tools(true)
+                .with_event_sender(Some(tx));
+
+        let session = SessionService::new(context)
+            .create_session(Some("Test Session".to_string()))
+            .await
+            .unwrap();
+        agent_service
+            .send_message_with_tool_filter(
+                session.id,
+                "Use the test tool".to_string(),
+                None,
+                false,
+                Some(&["read_file".to_string()]),
+            )
+            .await
+            .unwrap();
+
+        let mut refused = false;
+        while let Ok(event) = rx.try_recv() {
+            if let AgentEvent::ToolResult {
+                content, is_error, ..
+            } = event
+            {
+                assert!(is_error);
+                assert_eq!(content, "Tool 'test_tool' is not allowed for this request");
+                refused = true;
+            }
+        }
+        assert!(refused);
+    }
+
+    #[tokio::test]
+    async fn test_tool_loop_events() {
+        let db = Database::connect_in_memory().await.unwrap();
+        db.run_migrations().await.unwrap();
+        let context = ServiceContext::new(db.pool().clone());
+
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(MockTool));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let agent_service =
+            AgentService::new(Arc::new(MockProviderWithTools::new()), context.clone())
+                .with_tool_registry(Arc::new(registry))
+                .with_event_sender(Some(tx));
+
+        let session = SessionService::new(context)
+            .create_session(Some("Test Session".to_string()))
+            .await
+            .unwrap();
+        agent_service
+            .send_message_with_tools(session.id, "Use the test tool".to_string(), None)
+            .await
+            .unwrap();
+
+        let mut lines = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            lines.push(event.to_json_line());
+        }
+        let stream = lines
+            .join("\n")
+            .replace(&session.id.to_string(), "[session]");
+        insta::assert_snapshot!(stream);
+    }
 }
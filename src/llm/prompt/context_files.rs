@@ -0,0 +1,383 @@
+//! Project Context Files
+//!
+//! Instructions for the model that live with the project: `.crustly.md`,
+//! `CLAUDE.md`, `AGENTS.md` and `.cursorrules`, found in the working
+//! directory and each parent up to the repository root, plus a user-global
+//! `CRUSTLY.md` in the Crustly config directory. The files are read afresh
+//! for every request, so edits apply from the next message on.
+//!
+//! A line holding only `@path` is replaced by the contents of that file,
+//! relative to the file it appears in (or `~/` for the home directory).
+//! Includes nest up to a few levels; cycles are ignored. Only files under the
+//! repository root can be included, plus the config directory from the
+//! global file, so a checked-out project can't pull in e.g. `~/.ssh` keys.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Instruction file names looked for in each directory, in load order
+pub const FILE_NAMES: &[&str] = &[".crustly.md", "CLAUDE.md", "AGENTS.md", ".cursorrules"];
+
+/// Name of the user-global instruction file in the config directory
+pub const GLOBAL_FILE_NAME: &str = "CRUSTLY.md";
+
+/// Default size limit of one file, with its includes, in characters
+pub const DEFAULT_MAX_FILE_CHARS: usize = 16_000;
+
+/// Default size limit of all files together, in characters
+pub const DEFAULT_MAX_TOTAL_CHARS: usize = 32_000;
+
+/// Deepest chain of `@path` includes followed
+const MAX_INCLUDE_DEPTH: usize = 5;
+
+/// An instruction file as it goes into the system prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextFile {
+    pub path: PathBuf,
+    /// Content with includes expanded, cut to the size limits
+    pub content: String,
+    /// Size before the limits were applied, in characters
+    pub chars: usize,
+    /// Whether the size limits cut the content
+    pub truncated: bool,
+    /// Files pulled in with `@path`
+    pub includes: Vec<PathBuf>,
+}
+
+/// Finds and reads the instruction files for a working directory
+#[derive(Debug, Clone)]
+pub struct ProjectContext {
+    working_directory: PathBuf,
+    global_file: Option<PathBuf>,
+    max_file_chars: usize,
+    max_total_chars: usize,
+}
+
+impl ProjectContext {
+    /// Instructions for `working_directory`, within the given size limits
+    pub fn new(working_directory: PathBuf, max_file_chars: usize, max_total_chars: usize) -> Self {
+        Self {
+            working_directory,
+            global_file: Self::default_global_file(),
+            max_file_chars,
+            max_total_chars,
+        }
+    }
+
+    /// Use another user-global file, or none
+    pub fn with_global_file(mut self, global_file: Option<PathBuf>) -> Self {
+        self.global_file = global_file;
+        self
+    }
+
+    /// `CRUSTLY.md` in the Crustly config directory
+    pub fn default_global_file() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("crustly").join(GLOBAL_FILE_NAME))
+    }
+
+    /// Paths of the instruction files that exist, from the most general
+    /// (user-global, repository root) to the most specific
+    pub fn discover(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self.global_file.iter().cloned().collect();
+        for directory in self.directories().into_iter().rev() {
+            paths.extend(FILE_NAMES.iter().map(|name| directory.join(name)));
+        }
+
+        let mut seen = HashSet::new();
+        paths
+            .into_iter()
+            .filter(|path| path.is_file())
+            .filter(|path| seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone())))
+            .collect()
+    }
+
+    /// The working directory and its parents up to the repository root
+    fn directories(&self) -> Vec<&Path> {
+        let mut directories: Vec<&Path> = Vec::new();
+        for directory in self.working_directory.ancestors() {
+            directories.push(directory);
+            if directory.join(".git").exists() {
+                break;
+            }
+        }
+        // Outside a repository only the working directory itself counts
+        if !directories
+            .last()
+            .is_some_and(|directory| directory.join(".git").exists())
+        {
+            directories.truncate(1);
+        }
+        directories
+    }
+
+    /// Directories that files included from `path` may come from
+    fn include_roots(&self, path: &Path) -> Vec<PathBuf> {
+        let mut roots: Vec<&Path> = self.directories().last().copied().into_iter().collect();
+        if self.global_file.as_deref() == Some(path) {
+            roots.extend(path.parent());
+        }
+        roots
+            .into_iter()
+            .filter_map(|root| root.canonicalize().ok())
+            .collect()
+    }
+
+    /// Read the instruction files, applying the size limits
+    ///
+    /// The total budget goes to the most specific files first, since they
+    /// refine the general ones.
+    pub fn load(&self) -> Vec<ContextFile> {
+        let mut files: Vec<ContextFile> = self
+            .discover()
+            .into_iter()
+            .filter_map(|path| {
+                let text = std::fs::read_to_string(&path)
+                    .map_err(|e| tracing::debug!("Cannot read {:?}: {}", path, e))
+                    .ok()?;
+                let mut includes = Vec::new();
+                let mut visited = HashSet::from([path.canonicalize().unwrap_or(path.clone())]);
+                let roots = self.include_roots(&path);
+                let content = expand_includes(&text, &path, &roots, 0, &mut visited, &mut includes);
+                Some(ContextFile {
+                    chars: content.chars().count(),
+                    path,
+                    content,
+                    truncated: false,
+                    includes,
+                })
+            })
+            .collect();
+
+        let mut budget = self.max_total_chars;
+        for file in files.iter_mut().rev() {
+            let limit = self.max_file_chars.min(budget);
+            if file.chars > limit {
+                file.content = file.content.chars().take(limit).collect();
+                file.truncated = true;
+            }
+            budget -= file.chars.min(limit);
+        }
+        files
+    }
+
+    /// The files as a system prompt section, or `None` when there are none
+    pub fn render(&self, files: &[ContextFile]) -> Option<String> {
+        let mut sections = Vec::new();
+        for file in files {
+            let content = file.content.trim();
+            if content.is_empty() {
+                continue;
+            }
+            let mut section = format!("## {}\n\n{}", self.display_path(&file.path), content);
+            if file.truncated {
+                section.push_str("\n\n[... truncated]");
+            }
+            sections.push(section);
+        }
+        if sections.is_empty() {
+            return None;
+        }
+        Some(sections.join("\n\n"))
+    }
+
+    /// Path relative to the working directory when inside it
+    pub fn display_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.working_directory)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    }
+}
+
+/// Replace `@path` lines with the files they name, if under one of `roots`
+fn expand_includes(
+    text: &str,
+    file: &Path,
+    roots: &[PathBuf],
+    depth: usize,
+    visited: &mut HashSet<PathBuf>,
+    includes: &mut Vec<PathBuf>,
+) -> String {
+    let base = file.parent().unwrap_or(Path::new("."));
+    let mut in_code_block = false;
+    let mut lines = Vec::new();
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            in_code_block = !in_code_block;
+        }
+        let target = trimmed
+            .strip_prefix('@')
+            .filter(|target| !in_code_block && !target.is_empty() && !target.contains(' '));
+        let Some(target) = target else {
+            lines.push(line.to_string());
+            continue;
+        };
+
+        let path = match target.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+            None => base.join(target),
+        };
+        let canonical = match path.canonicalize() {
+            Ok(canonical) => canonical,
+            Err(e) => {
+                tracing::debug!("Cannot include {:?} from {:?}: {}", path, file, e);
+                lines.push(line.to_string());
+                continue;
+            }
+        };
+        if !roots.iter().any(|root| canonical.starts_with(root)) {
+            tracing::debug!(
+                "Not including {:?} from {:?}: outside the project",
+                path,
+                file
+            );
+            continue;
+        }
+        if depth >= MAX_INCLUDE_DEPTH || visited.contains(&canonical) {
+            tracing::debug!("Not including {:?} from {:?}", path, file);
+            continue;
+        }
+        match std::fs::read_to_string(&canonical) {
+            Ok(included) => {
+                visited.insert(canonical);
+                includes.push(path.clone());
+                lines.push(expand_includes(
+                    &included,
+                    &path,
+                    roots,
+                    depth + 1,
+                    visited,
+                    includes,
+                ));
+            }
+            Err(e) => {
+                tracing::debug!("Cannot include {:?} from {:?}: {}", path, file, e);
+                lines.push(line.to_string());
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn context(dir: &Path) -> ProjectContext {
+        ProjectContext::new(
+            dir.to_path_buf(),
+            DEFAULT_MAX_FILE_CHARS,
+            DEFAULT_MAX_TOTAL_CHARS,
+        )
+        .with_global_file(None)
+    }
+
+    #[test]
+    fn test_discovery_up_to_repository_root() {
+        let outer = TempDir::new().unwrap();
+        let root = outer.path().join("repo");
+        let nested = root.join("crates/core");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir(root.join(".git")).unwrap();
+        // Above the repository root: ignored
+        std::fs::write(outer.path().join("AGENTS.md"), "outside").unwrap();
+        std::fs::write(root.join("AGENTS.md"), "root agents").unwrap();
+        std::fs::write(root.join(".crustly.md"), "root crustly").unwrap();
+        std::fs::write(nested.join(".cursorrules"), "nested rules").unwrap();
+        let global = outer.path().join("CRUSTLY.md");
+        std::fs::write(&global, "global").unwrap();
+
+        let project = context(&nested).with_global_file(Some(global.clone()));
+        assert_eq!(
+            project.discover(),
+            vec![
+                global,
+                root.join(".crustly.md"),
+                root.join("AGENTS.md"),
+                nested.join(".cursorrules"),
+            ]
+        );
+
+        let rendered = project.render(&project.load()).unwrap();
+        assert!(rendered.find("global").unwrap() < rendered.find("root crustly").unwrap());
+        assert!(rendered.contains("## .cursorrules\n\nnested rules"));
+        assert!(!rendered.contains("outside"));
+
+        // Outside a repository, only the working directory is searched
+        let plain = outer.path().join("plain");
+        std::fs::create_dir(&plain).unwrap();
+        assert!(context(&plain).discover().is_empty());
+    }
+
+    #[test]
+    fn test_includes() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("docs")).unwrap();
+        std::fs::write(
+            dir.path().join("CLAUDE.md"),
+            "Intro\n@docs/style.md\n```\n@docs/style.md\n```\n@missing.md\nOutro",
+        )
+        .unwrap();
+        // Includes are relative to the including file; the cycle back stops
+        std::fs::write(dir.path().join("docs/style.md"), "Use tabs\n@../CLAUDE.md").unwrap();
+
+        let files = context(dir.path()).load();
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            files[0].content,
+            "Intro\nUse tabs\n```\n@docs/style.md\n```\n@missing.md\nOutro"
+        );
+        assert_eq!(files[0].includes, vec![dir.path().join("docs/style.md")]);
+    }
+
+    #[test]
+    fn test_size_limits_favor_specific_files() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join(".git")).unwrap();
+        let global = dir.path().join("global.md");
+        std::fs::write(&global, "g".repeat(50)).unwrap();
+        std::fs::write(dir.path().join("AGENTS.md"), "a".repeat(30)).unwrap();
+
+        let context =
+            ProjectContext::new(dir.path().to_path_buf(), 20, 35).with_global_file(Some(global));
+        let files = context.load();
+        assert_eq!(files.len(), 2);
+        // The project file gets its share first, capped per file
+        assert_eq!(files[1].content, "a".repeat(20));
+        assert!(files[1].truncated);
+        assert_eq!(files[0].content, "g".repeat(15));
+        assert_eq!(files[0].chars, 50);
+
+        let rendered = context.render(&files).unwrap();
+        assert!(rendered.ends_with("[... truncated]"));
+    }
+
+    #[test]
+    fn test_includes_stay_in_project() {
+        let outer = TempDir::new().unwrap();
+        let root = outer.path().join("repo");
+        let config = outer.path().join("config");
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir(&config).unwrap();
+        std::fs::write(outer.path().join("secret"), "id_rsa").unwrap();
+        std::fs::write(config.join("style.md"), "global style").unwrap();
+        std::fs::write(
+            root.join("AGENTS.md"),
+            "Rules\n@../secret\n@../config/style.md",
+        )
+        .unwrap();
+        let global = config.join(GLOBAL_FILE_NAME);
+        std::fs::write(&global, "Mine\n@style.md").unwrap();
+
+        let files = context(&root).with_global_file(Some(global)).load();
+        assert_eq!(files.len(), 2);
+        // The global file may include from the config directory
+        assert_eq!(files[0].content, "Mine\nglobal style");
+        // A project file can't reach outside the repository
+        assert_eq!(files[1].content, "Rules");
+        assert!(files[1].includes.is_empty());
+    }
+}
@@ -1 +1,11 @@
-//! TODO: Implement src/llm/prompt/mod.rs
+//! Prompt Construction
+//!
+//! Context added to the system prompt: project instruction files and the
+//! repository map.
+
+pub mod context_files;
+pub mod repo_map;
+
+// Re-exports
+pub use context_files::{ContextFile, ProjectContext};
+pub use repo_map::RepoMap;
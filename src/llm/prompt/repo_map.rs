@@ -0,0 +1,445 @@
+//! Repository Map
+//!
+//! A compact map of the working directory for the system prompt: its files,
+//! with the top-level symbols that other files refer to most. It spares the
+//! model a round of `ls` and `glob` calls before each task.
+//!
+//! Symbols come from the tree-sitter outlines of source files and are
+//! ranked by the number of other files mentioning their name. Each file's
+//! analysis is cached on disk with its modification time and size, so a
+//! refresh only parses the files that changed.
+
+use crate::outline::{self, Language};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+/// Default size limit of the map, in tokens
+pub const DEFAULT_MAX_TOKENS: usize = 1024;
+
+/// Maximum number of files walked
+const MAX_FILES: usize = 10_000;
+
+/// Source files larger than this are listed but not parsed
+const MAX_FILE_SIZE: u64 = 512 * 1024;
+
+/// Longest signature shown, in characters
+const MAX_SIGNATURE_CHARS: usize = 120;
+
+/// Bumped when the cached data changes shape
+const CACHE_VERSION: u32 = 1;
+
+/// Analysis of one source file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileEntry {
+    /// Modification time, in milliseconds since the epoch
+    modified: u64,
+    size: u64,
+    symbols: Vec<MapSymbol>,
+    /// Distinct identifiers in the file, for ranking
+    identifiers: Vec<String>,
+}
+
+/// A top-level symbol
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MapSymbol {
+    name: String,
+    signature: String,
+    line: usize,
+}
+
+/// On-disk cache, keyed by path relative to the root
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Cache {
+    version: u32,
+    files: BTreeMap<String, FileEntry>,
+}
+
+#[derive(Default)]
+struct State {
+    cache: Option<Cache>,
+    /// Files of the last walk and the map rendered from them
+    paths: Vec<String>,
+    rendered: Option<String>,
+}
+
+/// Repository map of one directory
+pub struct RepoMap {
+    root: PathBuf,
+    max_tokens: usize,
+    cache_path: Option<PathBuf>,
+    state: Mutex<State>,
+}
+
+impl RepoMap {
+    /// Map of `root`, limited to about `max_tokens` tokens
+    pub fn new(root: PathBuf, max_tokens: usize) -> Self {
+        Self {
+            root,
+            max_tokens,
+            cache_path: None,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Keep the analysis of files in `path` across sessions
+    pub fn with_cache_path(mut self, path: Option<PathBuf>) -> Self {
+        self.cache_path = path;
+        self
+    }
+
+    /// Cache file for `root` in the user's cache directory
+    pub fn default_cache_path(root: &Path) -> Option<PathBuf> {
+        // FNV-1a: stable across builds, unlike the std hasher
+        let hash = root
+            .to_string_lossy()
+            .bytes()
+            .fold(0xcbf29ce484222325u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            });
+        dirs::cache_dir().map(|dir| {
+            dir.join("crustly")
+                .join("repo-map")
+                .join(format!("{:016x}.json", hash))
+        })
+    }
+
+    /// Current map, without blocking the async runtime
+    pub async fn render(self: &Arc<Self>) -> Option<String> {
+        let map = self.clone();
+        tokio::task::spawn_blocking(move || map.refresh())
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Repository map failed: {}", e);
+                None
+            })
+    }
+
+    /// Bring the map up to date and return it
+    ///
+    /// Only files whose modification time or size changed since the last
+    /// refresh are parsed again. Returns `None` for an empty directory.
+    pub fn refresh(&self) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        let mut cache = match state.cache.take() {
+            Some(cache) => cache,
+            None => self.load_cache(),
+        };
+
+        let (paths, truncated) = self.walk();
+        let mut files = BTreeMap::new();
+        let mut changed = false;
+        for relative in &paths {
+            let path = self.root.join(relative);
+            if Language::from_path(&path).is_none() {
+                continue;
+            }
+            let Ok(metadata) = std::fs::metadata(&path) else {
+                continue;
+            };
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |since| since.as_millis() as u64);
+            let size = metadata.len();
+
+            let entry = match cache.files.remove(relative) {
+                Some(entry) if entry.modified == modified && entry.size == size => entry,
+                _ => {
+                    changed = true;
+                    analyze(&path, modified, size)
+                }
+            };
+            files.insert(relative.clone(), entry);
+        }
+        // Anything left was deleted
+        changed |= !cache.files.is_empty();
+        cache.files = files;
+
+        if changed {
+            self.save_cache(&cache);
+        }
+        if changed || state.rendered.is_none() || state.paths != paths {
+            state.rendered = render(&paths, &cache.files, self.max_tokens, truncated);
+            state.paths = paths;
+        }
+        state.cache = Some(cache);
+        state.rendered.clone()
+    }
+
+    /// Files under the root, relative and sorted, honoring .gitignore
+    fn walk(&self) -> (Vec<String>, bool) {
+        let mut paths = Vec::new();
+        let mut truncated = false;
+        for entry in ignore::WalkBuilder::new(&self.root)
+            .require_git(false)
+            .build()
+            .flatten()
+        {
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let Ok(relative) = entry.path().strip_prefix(&self.root) else {
+                continue;
+            };
+            if paths.len() == MAX_FILES {
+                truncated = true;
+                break;
+            }
+            let parts: Vec<_> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect();
+            paths.push(parts.join("/"));
+        }
+        paths.sort();
+        (paths, truncated)
+    }
+
+    fn load_cache(&self) -> Cache {
+        let Some(path) = &self.cache_path else {
+            return Cache::default();
+        };
+        std::fs::read(path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<Cache>(&data).ok())
+            .filter(|cache| cache.version == CACHE_VERSION)
+            .unwrap_or_default()
+    }
+
+    fn save_cache(&self, cache: &Cache) {
+        let Some(path) = &self.cache_path else {
+            return;
+        };
+        let cache = Cache {
+            version: CACHE_VERSION,
+            files: cache.files.clone(),
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(path, serde_json::to_vec(&cache).unwrap_or_default()));
+        if let Err(e) = result {
+            tracing::debug!("Failed to save repository map cache {:?}: {}", path, e);
+        }
+    }
+}
+
+/// Parse a source file for its top-level symbols and identifiers
+fn analyze(path: &Path, modified: u64, size: u64) -> FileEntry {
+    let mut entry = FileEntry {
+        modified,
+        size,
+        symbols: Vec::new(),
+        identifiers: Vec::new(),
+    };
+    let Some(language) = Language::from_path(path) else {
+        return entry;
+    };
+    if size > MAX_FILE_SIZE {
+        return entry;
+    }
+    let Ok(source) = std::fs::read_to_string(path) else {
+        return entry;
+    };
+
+    entry.symbols = outline::outline(&source, language)
+        .into_iter()
+        // The methods of an impl block are not top-level, and its type is
+        // listed where it is defined
+        .filter(|symbol| symbol.kind != "impl")
+        .map(|symbol| MapSymbol {
+            name: symbol.name,
+            signature: symbol.signature,
+            line: symbol.start_line,
+        })
+        .collect();
+    entry.identifiers = source
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.len() > 1 && !word.starts_with(|c: char| c.is_ascii_digit()))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    entry
+}
+
+/// Render the map within `max_tokens`
+///
+/// Symbols other files refer to come first, most referenced first; then
+/// the remaining files, then unreferenced symbols, as long as they fit.
+fn render(
+    paths: &[String],
+    files: &BTreeMap<String, FileEntry>,
+    max_tokens: usize,
+    truncated: bool,
+) -> Option<String> {
+    if paths.is_empty() {
+        return None;
+    }
+
+    // Number of files each identifier appears in
+    let mut mentions: HashMap<&str, usize> = HashMap::new();
+    for entry in files.values() {
+        for identifier in &entry.identifiers {
+            *mentions.entry(identifier).or_default() += 1;
+        }
+    }
+
+    let mut ranked: Vec<(usize, &str, &MapSymbol)> = files
+        .iter()
+        .flat_map(|(path, entry)| {
+            let mentions = &mentions;
+            entry.symbols.iter().map(move |symbol| {
+                let name = symbol.name.rsplit('.').next().unwrap_or(&symbol.name);
+                // Its own file mentions it too
+                let references = mentions.get(name).map_or(0, |n| n.saturating_sub(1));
+                (references, path.as_str(), symbol)
+            })
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then(a.1.cmp(b.1))
+            .then(a.2.line.cmp(&b.2.line))
+    });
+
+    // Budget in characters, at about four per token
+    let budget = max_tokens * 4;
+    let mut used = 0;
+    let mut shown: BTreeMap<&str, Vec<&MapSymbol>> = BTreeMap::new();
+
+    let referenced = ranked.iter().filter(|(references, _, _)| *references > 0);
+    let unreferenced = ranked.iter().filter(|(references, _, _)| *references == 0);
+    for (_, path, symbol) in referenced {
+        add_symbol(&mut shown, &mut used, budget, path, symbol);
+    }
+    for path in paths {
+        if !shown.contains_key(path.as_str()) && used + path.len() < budget {
+            used += path.len() + 1;
+            shown.insert(path, Vec::new());
+        }
+    }
+    for (_, path, symbol) in unreferenced {
+        add_symbol(&mut shown, &mut used, budget, path, symbol);
+    }
+
+    let mut lines = Vec::new();
+    for (path, mut symbols) in shown.iter().map(|(p, s)| (p, s.clone())) {
+        lines.push(path.to_string());
+        symbols.sort_by_key(|symbol| symbol.line);
+        for symbol in symbols {
+            lines.push(format!("  {}", short_signature(symbol)));
+        }
+    }
+    let hidden = paths.len() - shown.len();
+    if hidden > 0 || truncated {
+        lines.push(format!("... and {} more files", hidden));
+    }
+    Some(lines.join("\n"))
+}
+
+fn add_symbol<'a>(
+    shown: &mut BTreeMap<&'a str, Vec<&'a MapSymbol>>,
+    used: &mut usize,
+    budget: usize,
+    path: &'a str,
+    symbol: &'a MapSymbol,
+) {
+    let mut cost = short_signature(symbol).len() + 3;
+    if !shown.contains_key(path) {
+        cost += path.len() + 1;
+    }
+    if *used + cost <= budget {
+        *used += cost;
+        shown.entry(path).or_default().push(symbol);
+    }
+}
+
+fn short_signature(symbol: &MapSymbol) -> String {
+    if symbol.signature.chars().count() > MAX_SIGNATURE_CHARS {
+        let cut: String = symbol.signature.chars().take(MAX_SIGNATURE_CHARS).collect();
+        format!("{}...", cut)
+    } else {
+        symbol.signature.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn project() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let write = |path: &str, content: &str| {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "src/lib.rs",
+            "pub struct Store;\n\nimpl Store {\n    pub fn get(&self) {}\n}\n\npub fn unused_helper() {}\n",
+        );
+        write("src/main.rs", "fn main() {\n    let store = Store;\n}\n");
+        write("src/cli.rs", "pub fn run(store: Store) {}\n");
+        write("README.md", "# Demo\n");
+        write(".gitignore", "target/\n");
+        write("target/debug/build.rs", "fn generated() {}\n");
+        dir
+    }
+
+    #[test]
+    fn test_map_lists_files_and_ranked_symbols() {
+        let dir = project();
+        let map = RepoMap::new(dir.path().to_path_buf(), DEFAULT_MAX_TOKENS)
+            .refresh()
+            .unwrap();
+        assert_eq!(
+            map,
+            "README.md\n\
+             src/cli.rs\n  pub fn run(store: Store)\n\
+             src/lib.rs\n  pub struct Store\n  pub fn unused_helper()\n\
+             src/main.rs\n  fn main()"
+        );
+    }
+
+    #[test]
+    fn test_map_respects_token_budget() {
+        let dir = project();
+        // Room for the most referenced symbol only
+        let map = RepoMap::new(dir.path().to_path_buf(), 8).refresh().unwrap();
+        assert_eq!(map, "src/lib.rs\n  pub struct Store\n... and 3 more files");
+    }
+
+    #[test]
+    fn test_cache_is_reused_and_refreshed() {
+        let dir = project();
+        let cache_dir = TempDir::new().unwrap();
+        let cache_path = cache_dir.path().join("map.json");
+        let new_map = || {
+            RepoMap::new(dir.path().to_path_buf(), DEFAULT_MAX_TOKENS)
+                .with_cache_path(Some(cache_path.clone()))
+        };
+        new_map().refresh();
+
+        // Unchanged files are taken from the cache, not parsed again
+        let data = std::fs::read_to_string(&cache_path).unwrap();
+        std::fs::write(&cache_path, data.replace("fn main()", "fn cached_main()")).unwrap();
+        let map = new_map();
+        assert!(map.refresh().unwrap().contains("fn cached_main()"));
+
+        // Changed and deleted files are picked up
+        std::fs::write(dir.path().join("src/main.rs"), "fn start() {}\n").unwrap();
+        std::fs::remove_file(dir.path().join("src/cli.rs")).unwrap();
+        let refreshed = map.refresh().unwrap();
+        assert!(refreshed.contains("src/main.rs\n  fn start()"));
+        assert!(!refreshed.contains("cli.rs"));
+        assert!(!std::fs::read_to_string(&cache_path)
+            .unwrap()
+            .contains("cli.rs"));
+    }
+}
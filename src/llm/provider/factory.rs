@@ -14,6 +14,8 @@ use std::sync::Arc;
 
 /// Create a provider based on configuration with fallback priority
 ///
+/// The choice is reported on stderr, keeping stdout free for output.
+///
 /// Priority order:
 /// 1. Qwen (if configured with credentials)
 /// 2. OpenAI (if configured with credentials)
@@ -33,6 +35,22 @@ pub fn create_provider(config: &Config) -> Result<Arc<dyn Provider>> {
     create_anthropic(config)
 }
 
+/// Create one provider by name, as `create_provider` would
+///
+/// Returns `None` for providers that aren't configured with credentials
+/// or that Crustly can't create.
+pub fn create_named_provider(config: &Config, name: &str) -> Result<Option<Arc<dyn Provider>>> {
+    match name {
+        "qwen" => try_create_qwen(config),
+        "openai" => try_create_openai(config),
+        "anthropic" => match &config.providers.anthropic {
+            Some(anthropic) if anthropic.api_key.is_some() => create_anthropic(config).map(Some),
+            _ => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
 /// Try to create Qwen provider if configured
 fn try_create_qwen(config: &Config) -> Result<Option<Arc<dyn Provider>>> {
     let qwen_config = match &config.providers.qwen {
@@ -43,7 +61,7 @@ fn try_create_qwen(config: &Config) -> Result<Option<Arc<dyn Provider>>> {
     // Local Qwen (vLLM, LM Studio, etc.)
     if let Some(base_url) = &qwen_config.base_url {
         tracing::info!("Using local Qwen at: {}", base_url);
-        println!("🏠 Using local Qwen at: {}\n", base_url);
+        eprintln!("🏠 Using local Qwen at: {}\n", base_url);
 
         let provider = configure_qwen(QwenProvider::local(base_url.clone()), qwen_config);
         return Ok(Some(Arc::new(provider)));
@@ -56,12 +74,12 @@ fn try_create_qwen(config: &Config) -> Result<Option<Arc<dyn Provider>>> {
         let provider_base = match region {
             "cn" => {
                 tracing::info!("Using DashScope China (Beijing)");
-                println!("☁️  Using DashScope China (Beijing)\n");
+                eprintln!("☁️  Using DashScope China (Beijing)\n");
                 QwenProvider::dashscope_cn(api_key.clone())
             }
             _ => {
                 tracing::info!("Using DashScope International (Singapore)");
-                println!("☁️  Using DashScope International (Singapore)\n");
+                eprintln!("☁️  Using DashScope International (Singapore)\n");
                 QwenProvider::dashscope_intl(api_key.clone())
             }
         };
@@ -86,7 +104,7 @@ fn configure_qwen(mut provider: QwenProvider, config: &QwenProviderConfig) -> Qw
         tracing::info!("Using tool parser: {:?}", tool_parser);
 
         if tool_parser == ToolCallParser::NativeQwen {
-            println!("🔧 Using native Qwen function calling (✿FUNCTION✿ markers)\n");
+            eprintln!("🔧 Using native Qwen function calling (✿FUNCTION✿ markers)\n");
         }
     }
 
@@ -94,7 +112,7 @@ fn configure_qwen(mut provider: QwenProvider, config: &QwenProviderConfig) -> Qw
     if config.enable_thinking {
         provider = provider.with_thinking(true);
         tracing::info!("🧠 Qwen3 thinking mode enabled");
-        println!("🧠 Thinking mode: enabled\n");
+        eprintln!("🧠 Thinking mode: enabled\n");
 
         if let Some(budget) = config.thinking_budget {
             provider = provider.with_thinking_budget(budget);
@@ -105,7 +123,7 @@ fn configure_qwen(mut provider: QwenProvider, config: &QwenProviderConfig) -> Qw
     // Set custom model
     if let Some(model) = &config.default_model {
         tracing::info!("Using custom default model: {}", model);
-        println!("📦 Model: {}\n", model);
+        eprintln!("📦 Model: {}\n", model);
         provider = provider.with_default_model(model.clone());
     }
 
@@ -122,7 +140,7 @@ fn try_create_openai(config: &Config) -> Result<Option<Arc<dyn Provider>>> {
     // Local LLM (LM Studio, Ollama, etc.)
     if let Some(base_url) = &openai_config.base_url {
         tracing::info!("Using local LLM at: {}", base_url);
-        println!("🏠 Using local LLM at: {}\n", base_url);
+        eprintln!("🏠 Using local LLM at: {}\n", base_url);
 
         let provider = configure_openai(OpenAIProvider::local(base_url.clone()), openai_config);
         return Ok(Some(Arc::new(provider)));
@@ -131,7 +149,7 @@ fn try_create_openai(config: &Config) -> Result<Option<Arc<dyn Provider>>> {
     // Official OpenAI API
     if let Some(api_key) = &openai_config.api_key {
         tracing::info!("Using OpenAI provider");
-        println!("🤖 Using OpenAI provider\n");
+        eprintln!("🤖 Using OpenAI provider\n");
 
         let provider = configure_openai(OpenAIProvider::new(api_key.clone()), openai_config);
         return Ok(Some(Arc::new(provider)));
@@ -144,7 +162,7 @@ fn try_create_openai(config: &Config) -> Result<Option<Arc<dyn Provider>>> {
 fn configure_openai(mut provider: OpenAIProvider, config: &ProviderConfig) -> OpenAIProvider {
     if let Some(model) = &config.default_model {
         tracing::info!("Using custom default model: {}", model);
-        println!("📦 Model: {}\n", model);
+        eprintln!("📦 Model: {}\n", model);
         provider = provider.with_default_model(model.clone());
     }
     provider
@@ -163,7 +181,7 @@ fn create_anthropic(config: &Config) -> Result<Arc<dyn Provider>> {
         .clone();
 
     tracing::info!("Using Anthropic provider");
-    println!("🤖 Using Anthropic Claude\n");
+    eprintln!("🤖 Using Anthropic Claude\n");
 
     Ok(Arc::new(AnthropicProvider::new(api_key)))
 }
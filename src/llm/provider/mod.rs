@@ -22,6 +22,6 @@ pub mod qwen;
 
 pub use anthropic::AnthropicProvider;
 pub use azure::AzureOpenAIProvider;
-pub use factory::create_provider;
+pub use factory::{create_named_provider, create_provider};
 pub use openai::OpenAIProvider;
 pub use qwen::{QwenProvider, ThinkingConfig, ToolCallParser};
@@ -0,0 +1,826 @@
+//! Apply Patch Tool
+//!
+//! Applies a unified diff or a list of structured edits across several files
+//! at once. Every change is validated in memory before anything is written,
+//! so a multi-file refactor either lands completely or not at all.
+
+use super::error::{validate_path_safety, Result, ToolError};
+use super::patch::{apply_hunks, parse_unified_diff, unified_diff, DEFAULT_MAX_FUZZ};
+use super::r#trait::{Tool, ToolCapability, ToolExecutionContext, ToolResult};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use tokio::fs;
+
+/// Apply patch tool
+pub struct ApplyPatchTool;
+
+/// A structured edit, for callers that prefer not to write diffs
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "operation")]
+enum PatchEdit {
+    /// Replace old_text with new_text
+    #[serde(rename = "replace")]
+    Replace {
+        path: String,
+        old_text: String,
+        new_text: String,
+        #[serde(default)]
+        replace_all: bool,
+    },
+
+    /// Create a new file
+    #[serde(rename = "create")]
+    Create { path: String, content: String },
+
+    /// Delete a file
+    #[serde(rename = "delete")]
+    Delete { path: String },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ApplyPatchInput {
+    /// Unified diff covering one or more files
+    #[serde(default)]
+    patch: Option<String>,
+
+    /// Structured edits applied after the patch
+    #[serde(default)]
+    edits: Vec<PatchEdit>,
+
+    /// Validate and report without writing
+    #[serde(default)]
+    dry_run: bool,
+
+    /// Maximum context lines that may be ignored when placing a hunk
+    #[serde(default = "default_max_fuzz")]
+    max_fuzz: usize,
+}
+
+fn default_max_fuzz() -> usize {
+    DEFAULT_MAX_FUZZ
+}
+
+/// Pending change to one file
+#[derive(Debug)]
+struct FileChange {
+    /// Path as given in the patch, for reporting
+    display: String,
+    /// Content on disk before the patch (`None` if the file does not exist)
+    original: Option<String>,
+    /// Content after the patch (`None` if the file is deleted)
+    updated: Option<String>,
+}
+
+/// Changes to all files, validated but not yet written
+#[derive(Debug, Default)]
+struct PatchPlan {
+    changes: BTreeMap<PathBuf, FileChange>,
+    report: Vec<String>,
+    conflicts: usize,
+    hunks_applied: usize,
+}
+
+impl PatchPlan {
+    /// Load the current (possibly already patched) content of a file
+    async fn current(&mut self, path: &Path, display: &str) -> Result<Option<String>> {
+        if let Some(change) = self.changes.get(path) {
+            return Ok(change.updated.clone());
+        }
+
+        let original = if path.is_file() {
+            Some(fs::read_to_string(path).await.map_err(ToolError::Io)?)
+        } else {
+            None
+        };
+        self.changes.insert(
+            path.to_path_buf(),
+            FileChange {
+                display: display.to_string(),
+                original: original.clone(),
+                updated: original.clone(),
+            },
+        );
+        Ok(original)
+    }
+
+    fn set(&mut self, path: &Path, updated: Option<String>) {
+        if let Some(change) = self.changes.get_mut(path) {
+            change.updated = updated;
+        }
+    }
+
+    fn conflict(&mut self, message: String) {
+        self.conflicts += 1;
+        self.report.push(format!("  ✗ {}", message));
+    }
+
+    /// Files whose content actually changes
+    fn effective_changes(&self) -> impl Iterator<Item = (&PathBuf, &FileChange)> {
+        self.changes.iter().filter(|(_, c)| c.original != c.updated)
+    }
+
+    /// Unified diff of all changes
+    fn diff(&self) -> String {
+        self.effective_changes()
+            .map(|(_, c)| {
+                unified_diff(
+                    &c.display,
+                    c.original.as_deref().unwrap_or_default(),
+                    c.updated.as_deref().unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Resolve a patch path inside the working directory
+///
+/// Unlike `validate_path_safety`, the parent directory may not exist yet, so
+/// patches can create files in new directories.
+fn resolve_patch_path(
+    requested: &str,
+    working_directory: &Path,
+) -> std::result::Result<PathBuf, String> {
+    let requested_path = Path::new(requested);
+    if requested_path
+        .components()
+        .any(|c| matches!(c, Component::ParentDir))
+    {
+        return Err(format!("Access denied: '{}' contains '..'", requested));
+    }
+
+    let path = if requested_path.is_absolute() {
+        requested_path.to_path_buf()
+    } else {
+        working_directory.join(requested_path)
+    };
+
+    // Validate against the nearest ancestor that exists
+    let mut existing = path.as_path();
+    while !existing.exists() {
+        existing = existing
+            .parent()
+            .ok_or_else(|| format!("Invalid path: {}", requested))?;
+    }
+    match validate_path_safety(&existing.to_string_lossy(), working_directory) {
+        Ok(_) => Ok(path),
+        Err(ToolError::PermissionDenied(_)) => Err(format!(
+            "Access denied: Path '{}' is outside the working directory",
+            requested
+        )),
+        Err(e) => Err(format!("Invalid path '{}': {}", requested, e)),
+    }
+}
+
+#[async_trait]
+impl Tool for ApplyPatchTool {
+    fn name(&self) -> &str {
+        "apply_patch"
+    }
+
+    fn description(&self) -> &str {
+        "Apply changes to multiple files in one atomic step. Accepts a unified diff ('patch') and/or \
+         a list of structured edits ('edits': replace/create/delete). All hunks are validated first; \
+         if any hunk conflicts nothing is written. Returns per-hunk results and the resulting diff."
+    }
+
+    fn input_schema(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "patch": {
+                    "type": "string",
+                    "description": "Unified diff with '--- a/path' / '+++ b/path' headers and '@@' hunks. Use /dev/null to create or delete files."
+                },
+                "edits": {
+                    "type": "array",
+                    "description": "Structured edits, applied after the patch",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "operation": {
+                                "type": "string",
+                                "enum": ["replace", "create", "delete"]
+                            },
+                            "path": {
+                                "type": "string",
+                                "description": "File path relative to the working directory"
+                            },
+                            "old_text": {
+                                "type": "string",
+                                "description": "Text to find (for 'replace'); must be unique unless replace_all is set"
+                            },
+                            "new_text": {
+                                "type": "string",
+                                "description": "Replacement text (for 'replace')"
+                            },
+                            "replace_all": {
+                                "type": "boolean",
+                                "description": "Replace every occurrence (for 'replace', default: false)"
+                            },
+                            "content": {
+                                "type": "string",
+                                "description": "File content (for 'create')"
+                            }
+                        },
+                        "required": ["operation", "path"]
+                    }
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Validate and show the resulting diff without writing (default: false)",
+                    "default": false
+                },
+                "max_fuzz": {
+                    "type": "integer",
+                    "description": "Maximum context lines that may be ignored when placing a hunk (default: 2)",
+                    "default": 2,
+                    "minimum": 0,
+                    "maximum": 3
+                }
+            }
+        })
+    }
+
+    fn capabilities(&self) -> Vec<ToolCapability> {
+        vec![
+            ToolCapability::ReadFiles,
+            ToolCapability::WriteFiles,
+            ToolCapability::SystemModification,
+        ]
+    }
+
+    fn requires_approval(&self) -> bool {
+        true // Patching files requires approval
+    }
+
+    async fn modified_paths(&self, input: &Value, _context: &ToolExecutionContext) -> Vec<String> {
+        let Ok(input) = serde_json::from_value::<ApplyPatchInput>(input.clone()) else {
+            return Vec::new();
+        };
+
+        let mut paths = Vec::new();
+        if let Some(Ok(file_patches)) = input.patch.as_deref().map(parse_unified_diff) {
+            for file_patch in file_patches {
+                paths.extend(file_patch.old_path);
+                paths.extend(file_patch.new_path);
+            }
+        }
+        paths.extend(input.edits.into_iter().map(|edit| match edit {
+            PatchEdit::Replace { path, .. }
+            | PatchEdit::Create { path, .. }
+            | PatchEdit::Delete { path } => path,
+        }));
+        paths.dedup();
+        paths
+    }
+
+    fn validate_input(&self, input: &Value) -> Result<()> {
+        let input: ApplyPatchInput = serde_json::from_value(input.clone())
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid input: {}", e)))?;
+
+        let has_patch = input.patch.as_deref().is_some_and(|p| !p.trim().is_empty());
+        if !has_patch && input.edits.is_empty() {
+            return Err(ToolError::InvalidInput(
+                "Provide a 'patch', a list of 'edits', or both".to_string(),
+            ));
+        }
+
+        if input.max_fuzz > 3 {
+            return Err(ToolError::InvalidInput(
+                "max_fuzz must be between 0 and 3".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn execute(&self, input: Value, context: &ToolExecutionContext) -> Result<ToolResult> {
+        // Check if in read-only mode (Plan mode)
+        if context.read_only_mode {
+            return Ok(ToolResult::error(
+                "Patch operations are not allowed in Plan mode. \
+                 Please approve the plan and switch to execution mode (Ctrl+A) to edit files."
+                    .to_string(),
+            ));
+        }
+
+        let input: ApplyPatchInput = serde_json::from_value(input)?;
+        let mut plan = PatchPlan::default();
+
+        if let Some(patch) = input.patch.as_deref().filter(|p| !p.trim().is_empty()) {
+            let file_patches = match parse_unified_diff(patch) {
+                Ok(p) => p,
+                Err(e) => return Ok(ToolResult::error(format!("Invalid patch: {}", e))),
+            };
+            for file_patch in file_patches {
+                self.plan_file_patch(&mut plan, &file_patch, input.max_fuzz, context)
+                    .await?;
+            }
+        }
+
+        for edit in &input.edits {
+            self.plan_edit(&mut plan, edit, context).await?;
+        }
+
+        let files_changed = plan.effective_changes().count();
+        let report = plan.report.join("\n");
+
+        if plan.conflicts > 0 {
+            return Ok(ToolResult::error(format!(
+                "Patch not applied: {} conflict(s). No files were modified.\n\n{}",
+                plan.conflicts, report
+            )));
+        }
+
+        let diff = plan.diff();
+        if input.dry_run {
+            return Ok(ToolResult::success(format!(
+                "Dry run: patch applies cleanly to {} file(s).\n\n{}\n\nResulting diff:\n{}",
+                files_changed, report, diff
+            ))
+            .with_metadata("files_changed".to_string(), files_changed.to_string())
+            .with_metadata("dry_run".to_string(), "true".to_string()));
+        }
+
+        let writes: Vec<FileWrite<'_>> = plan
+            .effective_changes()
+            .map(|(path, change)| FileWrite {
+                path,
+                original: change.original.as_deref(),
+                updated: change.updated.as_deref(),
+            })
+            .collect();
+        if let Err(e) = write_files(&writes).await {
+            return Ok(ToolResult::error(format!(
+                "Failed to write patch, all files were restored: {}",
+                e
+            )));
+        }
+
+        Ok(ToolResult::success(format!(
+            "Applied patch to {} file(s) ({} hunk(s)).\n\n{}\n\nResulting diff:\n{}",
+            files_changed, plan.hunks_applied, report, diff
+        ))
+        .with_metadata("files_changed".to_string(), files_changed.to_string())
+        .with_metadata("hunks_applied".to_string(), plan.hunks_applied.to_string()))
+    }
+}
+
+impl ApplyPatchTool {
+    /// Validate one file's hunks and record the result in the plan
+    async fn plan_file_patch(
+        &self,
+        plan: &mut PatchPlan,
+        file_patch: &super::patch::FilePatch,
+        max_fuzz: usize,
+        context: &ToolExecutionContext,
+    ) -> Result<()> {
+        let source_name = file_patch
+            .old_path
+            .as_deref()
+            .unwrap_or_else(|| file_patch.path());
+        let source = match resolve_patch_path(source_name, &context.working_directory) {
+            Ok(p) => p,
+            Err(msg) => {
+                plan.conflict(format!("{}: {}", source_name, msg));
+                return Ok(());
+            }
+        };
+
+        let current = plan.current(&source, source_name).await?;
+        let base = match (&current, file_patch.is_create()) {
+            (Some(_), true) => {
+                plan.conflict(format!("{}: file already exists", source_name));
+                return Ok(());
+            }
+            (None, false) => {
+                plan.conflict(format!("{}: file not found", source_name));
+                return Ok(());
+            }
+            (Some(content), false) => content.clone(),
+            (None, true) => String::new(),
+        };
+
+        plan.report.push(format!("{}:", file_patch.path()));
+        let (updated, outcomes) = apply_hunks(&base, &file_patch.hunks, max_fuzz);
+        for (i, outcome) in outcomes.iter().enumerate() {
+            if outcome.is_applied() {
+                plan.hunks_applied += 1;
+                plan.report.push(format!("  ✓ hunk {}: {}", i + 1, outcome));
+            } else {
+                plan.conflict(format!("hunk {}: {}", i + 1, outcome));
+            }
+        }
+        let Some(updated) = updated else {
+            return Ok(());
+        };
+
+        if file_patch.is_delete() {
+            plan.set(&source, None);
+            return Ok(());
+        }
+
+        // Renames write the new path and remove the old one
+        let target_name = file_patch.path();
+        if target_name != source_name {
+            let target = match resolve_patch_path(target_name, &context.working_directory) {
+                Ok(p) => p,
+                Err(msg) => {
+                    plan.conflict(format!("{}: {}", target_name, msg));
+                    return Ok(());
+                }
+            };
+            if plan.current(&target, target_name).await?.is_some() {
+                plan.conflict(format!("{}: rename target already exists", target_name));
+                return Ok(());
+            }
+            plan.set(&source, None);
+            plan.set(&target, Some(updated));
+        } else {
+            plan.set(&source, Some(updated));
+        }
+
+        Ok(())
+    }
+
+    /// Validate one structured edit and record the result in the plan
+    async fn plan_edit(
+        &self,
+        plan: &mut PatchPlan,
+        edit: &PatchEdit,
+        context: &ToolExecutionContext,
+    ) -> Result<()> {
+        let name = match edit {
+            PatchEdit::Replace { path, .. }
+            | PatchEdit::Create { path, .. }
+            | PatchEdit::Delete { path } => path.as_str(),
+        };
+        let path = match resolve_patch_path(name, &context.working_directory) {
+            Ok(p) => p,
+            Err(msg) => {
+                plan.conflict(format!("{}: {}", name, msg));
+                return Ok(());
+            }
+        };
+        let current = plan.current(&path, name).await?;
+
+        match (edit, current) {
+            (
+                PatchEdit::Replace {
+                    old_text,
+                    new_text,
+                    replace_all,
+                    ..
+                },
+                Some(content),
+            ) => {
+                let occurrences = if old_text.is_empty() {
+                    0
+                } else {
+                    content.matches(old_text.as_str()).count()
+                };
+                if occurrences == 0 {
+                    plan.conflict(format!("{}: replace: text not found", name));
+                } else if occurrences > 1 && !replace_all {
+                    plan.conflict(format!(
+                        "{}: replace: text matches {} times; add more context or set replace_all",
+                        name, occurrences
+                    ));
+                } else {
+                    let updated = if *replace_all {
+                        content.replace(old_text.as_str(), new_text)
+                    } else {
+                        content.replacen(old_text.as_str(), new_text, 1)
+                    };
+                    plan.set(&path, Some(updated));
+                    plan.report.push(format!(
+                        "{}:\n  ✓ replace: {} occurrence(s)",
+                        name, occurrences
+                    ));
+                }
+            }
+            (PatchEdit::Create { content, .. }, None) => {
+                plan.set(&path, Some(content.clone()));
+                plan.report.push(format!("{}:\n  ✓ create", name));
+            }
+            (PatchEdit::Delete { .. }, Some(_)) => {
+                plan.set(&path, None);
+                plan.report.push(format!("{}:\n  ✓ delete", name));
+            }
+            (PatchEdit::Create { .. }, Some(_)) => {
+                plan.conflict(format!("{}: create: file already exists", name));
+            }
+            (PatchEdit::Replace { .. } | PatchEdit::Delete { .. }, None) => {
+                plan.conflict(format!("{}: file not found", name));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Temporary sibling path used while writing a file
+fn staging_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.crustly-patch", name))
+}
+
+/// Content change to one file, written by [`write_files`]
+pub(super) struct FileWrite<'a> {
+    pub path: &'a Path,
+    /// Content before the change (`None` if the file does not exist)
+    pub original: Option<&'a str>,
+    /// Content after the change (`None` if the file is deleted)
+    pub updated: Option<&'a str>,
+}
+
+/// Write all changes, restoring the original files if any step fails
+///
+/// Existing files keep their permissions, and a symlink has its target
+/// rewritten rather than being replaced by a regular file.
+pub(super) async fn write_files(changes: &[FileWrite<'_>]) -> std::io::Result<()> {
+    // Resolve the file each change actually writes to
+    let mut targets: Vec<PathBuf> = Vec::with_capacity(changes.len());
+    for change in changes {
+        let target = match change.updated {
+            Some(_) => fs::canonicalize(change.path)
+                .await
+                .unwrap_or_else(|_| change.path.to_path_buf()),
+            None => change.path.to_path_buf(),
+        };
+        targets.push(target);
+    }
+
+    // Stage new contents next to their targets
+    let mut staged: Vec<&Path> = Vec::new();
+    for (change, target) in changes.iter().zip(&targets) {
+        let Some(updated) = change.updated else {
+            continue;
+        };
+        let result = async {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            let staging = staging_path(target);
+            fs::write(&staging, updated).await?;
+            if let Ok(metadata) = fs::metadata(target).await {
+                fs::set_permissions(&staging, metadata.permissions()).await?;
+            }
+            Ok::<_, std::io::Error>(())
+        }
+        .await;
+        if let Err(e) = result {
+            let _ = fs::remove_file(staging_path(target)).await;
+            for path in &staged {
+                let _ = fs::remove_file(staging_path(path)).await;
+            }
+            return Err(e);
+        }
+        staged.push(target);
+    }
+
+    // Move staged files into place, then remove deleted files
+    let mut done: Vec<&FileWrite<'_>> = Vec::new();
+    for (change, target) in changes.iter().zip(&targets) {
+        let result = match change.updated {
+            Some(_) => fs::rename(staging_path(target), target).await,
+            None => fs::remove_file(target).await,
+        };
+        if let Err(e) = result {
+            tracing::warn!(
+                "Writing {} failed, rolling back: {}",
+                change.path.display(),
+                e
+            );
+            for change in done {
+                let _ = match change.original {
+                    Some(original) => fs::write(change.path, original).await,
+                    None => fs::remove_file(change.path).await,
+                };
+            }
+            for path in &staged {
+                let _ = fs::remove_file(staging_path(path)).await;
+            }
+            return Err(e);
+        }
+        done.push(change);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    fn setup() -> (TempDir, ToolExecutionContext) {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        std::fs::write(temp_dir.path().join("b.txt"), "alpha\nbeta\n").unwrap();
+        let context = ToolExecutionContext::new(Uuid::new_v4())
+            .with_working_directory(temp_dir.path().to_path_buf());
+        (temp_dir, context)
+    }
+
+    #[tokio::test]
+    async fn test_apply_multi_file_patch() {
+        let (temp_dir, context) = setup();
+        let patch = "--- a/a.txt\n+++ b/a.txt\n@@ -1,3 +1,3 @@\n one\n-two\n+TWO\n three\n\
+                     --- a/b.txt\n+++ b/b.txt\n@@ -1,2 +1,2 @@\n-alpha\n+ALPHA\n beta\n\
+                     --- /dev/null\n+++ b/new/c.txt\n@@ -0,0 +1 @@\n+gamma\n";
+
+        let result = ApplyPatchTool
+            .execute(serde_json::json!({ "patch": patch }), &context)
+            .await
+            .unwrap();
+
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.metadata.get("files_changed").unwrap(), "3");
+        assert!(result.output.contains("+TWO"));
+        let read = |p: &str| std::fs::read_to_string(temp_dir.path().join(p)).unwrap();
+        assert_eq!(read("a.txt"), "one\nTWO\nthree\n");
+        assert_eq!(read("b.txt"), "ALPHA\nbeta\n");
+        assert_eq!(read("new/c.txt"), "gamma\n");
+    }
+
+    #[tokio::test]
+    async fn test_conflict_leaves_files_untouched() {
+        let (temp_dir, context) = setup();
+        let patch = "--- a/a.txt\n+++ b/a.txt\n@@ -1,1 +1,1 @@\n-one\n+ONE\n\
+                     --- a/b.txt\n+++ b/b.txt\n@@ -1,1 +1,1 @@\n-missing\n+MISSING\n";
+
+        let result = ApplyPatchTool
+            .execute(serde_json::json!({ "patch": patch }), &context)
+            .await
+            .unwrap();
+
+        assert!(!result.success);
+        let error = result.error.unwrap();
+        assert!(error.contains("1 conflict"));
+        assert!(error.contains("hunk 1: CONFLICT"));
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("a.txt")).unwrap(),
+            "one\ntwo\nthree\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_structured_edits() {
+        let (temp_dir, context) = setup();
+        let input = serde_json::json!({
+            "edits": [
+                { "operation": "replace", "path": "a.txt", "old_text": "two", "new_text": "2" },
+                { "operation": "delete", "path": "b.txt" },
+                { "operation": "create", "path": "c.txt", "content": "new\n" }
+            ]
+        });
+
+        let result = ApplyPatchTool.execute(input, &context).await.unwrap();
+
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("a.txt")).unwrap(),
+            "one\n2\nthree\n"
+        );
+        assert!(!temp_dir.path().join("b.txt").exists());
+        assert!(temp_dir.path().join("c.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_ambiguous_replace_is_conflict() {
+        let (_temp_dir, context) = setup();
+        let input = serde_json::json!({
+            "edits": [{ "operation": "replace", "path": "a.txt", "old_text": "o", "new_text": "0" }]
+        });
+
+        let result = ApplyPatchTool.execute(input, &context).await.unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("matches 2 times"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_does_not_write() {
+        let (temp_dir, context) = setup();
+        let input = serde_json::json!({
+            "patch": "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-one\n+ONE\n",
+            "dry_run": true
+        });
+
+        let result = ApplyPatchTool.execute(input, &context).await.unwrap();
+        assert!(result.success);
+        assert!(result.output.contains("Dry run"));
+        assert!(result.output.contains("+ONE"));
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("a.txt")).unwrap(),
+            "one\ntwo\nthree\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejects_paths_outside_working_directory() {
+        let (_temp_dir, context) = setup();
+        let input = serde_json::json!({
+            "edits": [{ "operation": "create", "path": "../escape.txt", "content": "x" }]
+        });
+
+        let result = ApplyPatchTool.execute(input, &context).await.unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("Access denied"));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_mode() {
+        let (_temp_dir, context) = setup();
+        let context = context.with_read_only_mode(true);
+        let input = serde_json::json!({
+            "edits": [{ "operation": "delete", "path": "a.txt" }]
+        });
+
+        let result = ApplyPatchTool.execute(input, &context).await.unwrap();
+        assert!(!result.success);
+    }
+
+    #[test]
+    fn test_validate_requires_patch_or_edits() {
+        assert!(ApplyPatchTool
+            .validate_input(&serde_json::json!({}))
+            .is_err());
+        assert!(ApplyPatchTool
+            .validate_input(&serde_json::json!({ "patch": "--- a/x" }))
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_write_files_failure_leaves_files_untouched() {
+        let (temp_dir, _context) = setup();
+        let a = temp_dir.path().join("a.txt");
+        // b.txt is a file, so nothing can be created below it
+        let blocked = temp_dir.path().join("b.txt").join("c.txt");
+
+        let writes = [
+            FileWrite {
+                path: &a,
+                original: Some("one\ntwo\nthree\n"),
+                updated: Some("changed\n"),
+            },
+            FileWrite {
+                path: &blocked,
+                original: None,
+                updated: Some("new\n"),
+            },
+        ];
+        assert!(write_files(&writes).await.is_err());
+
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "one\ntwo\nthree\n");
+        assert!(!staging_path(&a).exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_write_files_keeps_permissions_and_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (temp_dir, _context) = setup();
+        let script = temp_dir.path().join("run.sh");
+        std::fs::write(&script, "echo one\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let link = temp_dir.path().join("link.txt");
+        std::os::unix::fs::symlink(temp_dir.path().join("a.txt"), &link).unwrap();
+
+        let writes = [
+            FileWrite {
+                path: &script,
+                original: Some("echo one\n"),
+                updated: Some("echo two\n"),
+            },
+            FileWrite {
+                path: &link,
+                original: Some("one\ntwo\nthree\n"),
+                updated: Some("changed\n"),
+            },
+        ];
+        write_files(&writes).await.unwrap();
+
+        let mode = std::fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+        assert_eq!(std::fs::read_to_string(&script).unwrap(), "echo two\n");
+
+        assert!(std::fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("a.txt")).unwrap(),
+            "changed\n"
+        );
+    }
+}
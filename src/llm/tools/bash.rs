@@ -3,6 +3,7 @@
 //! Allows executing shell commands in the system.
 
 use super::error::{Result, ToolError};
+use super::process::run_with_progress;
 use super::r#trait::{Tool, ToolCapability, ToolExecutionContext, ToolResult};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -222,12 +223,13 @@ impl Tool for BashTool {
             ("sh", "-c")
         };
 
-        // Execute command with timeout
-        let command_future = Command::new(shell)
+        // Execute command with timeout, streaming output lines as they arrive
+        let mut command = Command::new(shell);
+        command
             .arg(shell_arg)
             .arg(&input.command)
-            .current_dir(&working_dir)
-            .output();
+            .current_dir(&working_dir);
+        let command_future = run_with_progress(command, context, self.name());
 
         let output = match timeout(Duration::from_secs(context.timeout_secs), command_future).await
         {
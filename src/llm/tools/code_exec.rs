@@ -3,6 +3,7 @@
 //! Execute code in various languages within a sandboxed environment.
 
 use super::error::{Result, ToolError};
+use super::process::run_with_progress;
 use super::r#trait::{Tool, ToolCapability, ToolExecutionContext, ToolResult};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -196,7 +197,7 @@ impl Tool for CodeExecTool {
 
         // Execute with timeout
         let exec_timeout = Duration::from_secs(input.timeout_secs);
-        let output_future = cmd.output();
+        let output_future = run_with_progress(cmd, context, self.name());
 
         let output = match timeout(exec_timeout, output_future).await {
             Ok(Ok(output)) => output,
@@ -3,7 +3,7 @@
 //! Intelligently modify portions of files (find/replace, line-based edits).
 
 use super::error::{validate_file_path, Result, ToolError};
-use super::r#trait::{Tool, ToolCapability, ToolExecutionContext, ToolResult};
+use super::r#trait::{ProposedChange, Tool, ToolCapability, ToolExecutionContext, ToolResult};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -141,6 +141,32 @@ impl Tool for EditTool {
         true // Editing files requires approval
     }
 
+    async fn modified_paths(&self, input: &Value, _context: &ToolExecutionContext) -> Vec<String> {
+        input
+            .get("path")
+            .and_then(Value::as_str)
+            .map(|p| vec![p.to_string()])
+            .unwrap_or_default()
+    }
+
+    async fn preview(
+        &self,
+        input: &Value,
+        context: &ToolExecutionContext,
+    ) -> Result<Vec<ProposedChange>> {
+        let input: EditInput = serde_json::from_value(input.clone())?;
+        let path = validate_file_path(&input.path, &context.working_directory)
+            .map_err(ToolError::InvalidInput)?;
+        let content = fs::read_to_string(&path).await.map_err(ToolError::Io)?;
+        let after = apply_operation(&content, input.operation).map_err(ToolError::InvalidInput)?;
+
+        Ok(vec![ProposedChange {
+            path: input.path,
+            before: Some(content),
+            after,
+        }])
+    }
+
     fn validate_input(&self, input: &Value) -> Result<()> {
         let _: EditInput = serde_json::from_value(input.clone())
             .map_err(|e| ToolError::InvalidInput(format!("Invalid input: {}", e)))?;
@@ -165,9 +191,21 @@ impl Tool for EditTool {
             Err(msg) => return Ok(ToolResult::error(msg)),
         };
 
+        // An invalid pattern is bad input, unlike a pattern that doesn't match
+        if let EditOperation::RegexReplace { pattern, .. } = &input.operation {
+            regex::Regex::new(pattern)
+                .map_err(|e| ToolError::InvalidInput(format!("Invalid regex: {}", e)))?;
+        }
+
         // Read file content
         let content = fs::read_to_string(&path).await.map_err(ToolError::Io)?;
 
+        // Perform edit operation
+        let new_content = match apply_operation(&content, input.operation) {
+            Ok(new_content) => new_content,
+            Err(msg) => return Ok(ToolResult::error(msg)),
+        };
+
         // Create backup if requested
         if input.create_backup {
             let backup_path = path.with_extension(format!(
@@ -179,124 +217,183 @@ impl Tool for EditTool {
                 .map_err(ToolError::Io)?;
         }
 
-        // Perform edit operation
-        let new_content = match input.operation {
-            EditOperation::Replace { old_text, new_text } => {
-                if !content.contains(&old_text) {
-                    return Ok(ToolResult::error(format!(
-                        "Text not found in file: '{}'",
-                        old_text
-                    )));
-                }
-                content.replace(&old_text, &new_text)
-            }
+        // Write modified content
+        fs::write(&path, &new_content)
+            .await
+            .map_err(ToolError::Io)?;
 
-            EditOperation::ReplaceLines {
-                start_line,
-                end_line,
-                new_text,
-            } => {
-                let lines: Vec<&str> = content.lines().collect();
-                if start_line >= lines.len() || end_line >= lines.len() {
-                    return Ok(ToolResult::error(format!(
-                        "Line range {}-{} out of bounds (file has {} lines)",
-                        start_line,
-                        end_line,
-                        lines.len()
-                    )));
-                }
-                if start_line > end_line {
-                    return Ok(ToolResult::error(
-                        "start_line must be <= end_line".to_string(),
-                    ));
-                }
+        let lines_before = content.lines().count();
+        let lines_after = new_content.lines().count();
 
-                let mut new_lines = Vec::new();
-                new_lines.extend_from_slice(&lines[..start_line]);
-                new_lines.push(&new_text);
-                if end_line + 1 < lines.len() {
-                    new_lines.extend_from_slice(&lines[end_line + 1..]);
-                }
-                new_lines.join("\n")
+        Ok(ToolResult::success(format!(
+            "Successfully edited {}. Lines: {} → {}",
+            path.display(),
+            lines_before,
+            lines_after
+        )))
+    }
+}
+
+/// Apply an edit operation to file content
+///
+/// Returns a user-facing error message if the operation does not apply.
+fn apply_operation(content: &str, operation: EditOperation) -> std::result::Result<String, String> {
+    match operation {
+        EditOperation::Replace { old_text, new_text } => {
+            if !content.contains(&old_text) {
+                return Err(format!("Text not found in file: '{}'", old_text));
             }
+            Ok(content.replace(&old_text, &new_text))
+        }
 
-            EditOperation::InsertLine { line, text } => {
-                let lines: Vec<&str> = content.lines().collect();
-                if line > lines.len() {
-                    return Ok(ToolResult::error(format!(
-                        "Line {} out of bounds (file has {} lines)",
-                        line,
-                        lines.len()
-                    )));
-                }
+        EditOperation::ReplaceLines {
+            start_line,
+            end_line,
+            new_text,
+        } => {
+            let lines: Vec<&str> = content.lines().collect();
+            check_line_range(start_line, end_line, lines.len())?;
+
+            let mut new_lines = Vec::new();
+            new_lines.extend_from_slice(&lines[..start_line]);
+            new_lines.push(&new_text);
+            if end_line + 1 < lines.len() {
+                new_lines.extend_from_slice(&lines[end_line + 1..]);
+            }
+            Ok(new_lines.join("\n"))
+        }
 
-                let mut new_lines = Vec::new();
-                new_lines.extend_from_slice(&lines[..line]);
-                new_lines.push(&text);
-                new_lines.extend_from_slice(&lines[line..]);
-                new_lines.join("\n")
+        EditOperation::InsertLine { line, text } => {
+            let lines: Vec<&str> = content.lines().collect();
+            if line > lines.len() {
+                return Err(format!(
+                    "Line {} out of bounds (file has {} lines)",
+                    line,
+                    lines.len()
+                ));
             }
 
-            EditOperation::DeleteLines {
-                start_line,
-                end_line,
-            } => {
-                let lines: Vec<&str> = content.lines().collect();
-                if start_line >= lines.len() || end_line >= lines.len() {
-                    return Ok(ToolResult::error(format!(
-                        "Line range {}-{} out of bounds (file has {} lines)",
-                        start_line,
-                        end_line,
-                        lines.len()
-                    )));
-                }
-                if start_line > end_line {
-                    return Ok(ToolResult::error(
-                        "start_line must be <= end_line".to_string(),
-                    ));
-                }
+            let mut new_lines = Vec::new();
+            new_lines.extend_from_slice(&lines[..line]);
+            new_lines.push(&text);
+            new_lines.extend_from_slice(&lines[line..]);
+            Ok(new_lines.join("\n"))
+        }
 
-                let mut new_lines = Vec::new();
-                new_lines.extend_from_slice(&lines[..start_line]);
-                if end_line + 1 < lines.len() {
-                    new_lines.extend_from_slice(&lines[end_line + 1..]);
-                }
-                new_lines.join("\n")
+        EditOperation::DeleteLines {
+            start_line,
+            end_line,
+        } => {
+            let lines: Vec<&str> = content.lines().collect();
+            check_line_range(start_line, end_line, lines.len())?;
+
+            let mut new_lines = Vec::new();
+            new_lines.extend_from_slice(&lines[..start_line]);
+            if end_line + 1 < lines.len() {
+                new_lines.extend_from_slice(&lines[end_line + 1..]);
             }
+            Ok(new_lines.join("\n"))
+        }
 
-            EditOperation::RegexReplace {
-                pattern,
-                replacement,
-            } => {
-                let regex = regex::Regex::new(&pattern)
-                    .map_err(|e| ToolError::InvalidInput(format!("Invalid regex: {}", e)))?;
-
-                if !regex.is_match(&content) {
-                    return Ok(ToolResult::error(format!(
-                        "Pattern not found in file: '{}'",
-                        pattern
-                    )));
-                }
+        EditOperation::RegexReplace {
+            pattern,
+            replacement,
+        } => {
+            let regex = regex::Regex::new(&pattern).map_err(|e| format!("Invalid regex: {}", e))?;
 
-                regex
-                    .replace_all(&content, replacement.as_str())
-                    .to_string()
+            if !regex.is_match(content) {
+                return Err(format!("Pattern not found in file: '{}'", pattern));
             }
-        };
 
-        // Write modified content
-        fs::write(&path, &new_content)
-            .await
-            .map_err(ToolError::Io)?;
+            Ok(regex.replace_all(content, replacement.as_str()).to_string())
+        }
+    }
+}
 
-        let lines_before = content.lines().count();
-        let lines_after = new_content.lines().count();
+/// Check an inclusive line range against the number of lines in a file
+fn check_line_range(
+    start_line: usize,
+    end_line: usize,
+    line_count: usize,
+) -> std::result::Result<(), String> {
+    if start_line >= line_count || end_line >= line_count {
+        return Err(format!(
+            "Line range {}-{} out of bounds (file has {} lines)",
+            start_line, end_line, line_count
+        ));
+    }
+    if start_line > end_line {
+        return Err("start_line must be <= end_line".to_string());
+    }
+    Ok(())
+}
 
-        Ok(ToolResult::success(format!(
-            "Successfully edited {}. Lines: {} → {}",
-            path.display(),
-            lines_before,
-            lines_after
-        )))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_edit_preview_does_not_write() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("main.rs");
+        std::fs::write(&file_path, "let a = 1;\nlet b = 2;\n").unwrap();
+
+        let tool = EditTool;
+        let context = ToolExecutionContext::new(Uuid::new_v4())
+            .with_working_directory(temp_dir.path().to_path_buf());
+        let input = serde_json::json!({
+            "path": "main.rs",
+            "operation": "replace",
+            "old_text": "a = 1",
+            "new_text": "a = 10"
+        });
+
+        let changes = tool.preview(&input, &context).await.unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].after, "let a = 10;\nlet b = 2;\n");
+        assert_eq!(
+            std::fs::read_to_string(&file_path).unwrap(),
+            "let a = 1;\nlet b = 2;\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_edit_preview_reports_missing_text() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("main.rs"), "let a = 1;\n").unwrap();
+
+        let tool = EditTool;
+        let context = ToolExecutionContext::new(Uuid::new_v4())
+            .with_working_directory(temp_dir.path().to_path_buf());
+        let input = serde_json::json!({
+            "path": "main.rs",
+            "operation": "replace",
+            "old_text": "missing",
+            "new_text": "x"
+        });
+
+        let err = tool.preview(&input, &context).await.unwrap_err();
+        assert!(err.to_string().contains("Text not found"));
+    }
+
+    #[tokio::test]
+    async fn test_edit_invalid_regex_is_input_error() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("main.rs"), "let a = 1;\n").unwrap();
+
+        let tool = EditTool;
+        let context = ToolExecutionContext::new(Uuid::new_v4())
+            .with_working_directory(temp_dir.path().to_path_buf());
+        let input = serde_json::json!({
+            "path": "main.rs",
+            "operation": "regex_replace",
+            "pattern": "(unclosed",
+            "replacement": "x"
+        });
+
+        let err = tool.execute(input, &context).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)));
     }
 }
@@ -0,0 +1,968 @@
+//! Git Tool
+//!
+//! Structured access to the repository through libgit2: status, diffs, log,
+//! blame and commits for reading, and staging, committing, branching,
+//! stashing and worktrees for changing it. Read operations are allowed in
+//! Plan mode and run without approval; the others need `WriteFiles` and go
+//! through approval like any other edit.
+
+use super::error::{validate_path_safety, Result, ToolError};
+use super::r#trait::{Tool, ToolCapability, ToolExecutionContext, ToolResult};
+use async_trait::async_trait;
+use git2::{
+    BlameOptions, BranchType, Commit, Diff, DiffFormat, DiffOptions, ErrorCode, IndexAddOption,
+    Repository, Sort, StashFlags, Status, StatusOptions, WorktreeAddOptions,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Commits listed by `log` unless `max_count` is given
+const DEFAULT_LOG_COUNT: usize = 20;
+
+/// Most commits `log` lists
+const MAX_LOG_COUNT: usize = 500;
+
+/// Git tool
+pub struct GitTool;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Operation {
+    Status,
+    Diff,
+    Log,
+    Blame,
+    Show,
+    Branch,
+    Stage,
+    Commit,
+    Stash,
+    Worktree,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct GitInput {
+    operation: Operation,
+
+    /// Pathspecs limiting `diff`, `log` and `stage`
+    #[serde(default)]
+    paths: Vec<String>,
+
+    /// Revision: base of `diff`, start of `log`, object for `show`
+    #[serde(default)]
+    rev: Option<String>,
+
+    /// `diff`: staged changes instead of unstaged ones
+    #[serde(default)]
+    staged: bool,
+
+    /// `log`: number of commits
+    #[serde(default)]
+    max_count: Option<usize>,
+
+    /// `blame`: file; `worktree`: directory to create
+    #[serde(default)]
+    path: Option<String>,
+
+    /// `blame`: line range, starting at 1
+    #[serde(default)]
+    start_line: Option<usize>,
+    #[serde(default)]
+    end_line: Option<usize>,
+
+    /// `branch`: branch to create; `worktree`: branch to check out
+    #[serde(default)]
+    name: Option<String>,
+
+    /// `commit` and `stash` message
+    #[serde(default)]
+    message: Option<String>,
+}
+
+impl GitInput {
+    /// Whether the call changes the repository or working tree
+    fn is_mutating(&self) -> bool {
+        match self.operation {
+            Operation::Status
+            | Operation::Diff
+            | Operation::Log
+            | Operation::Blame
+            | Operation::Show => false,
+            Operation::Branch => self.name.is_some(),
+            Operation::Stage | Operation::Commit | Operation::Stash | Operation::Worktree => true,
+        }
+    }
+}
+
+/// Repository containing the working directory
+struct Repo {
+    repo: Repository,
+    /// Working directory, relative to the repository root
+    prefix: PathBuf,
+}
+
+impl Repo {
+    fn open(working_directory: &Path) -> std::result::Result<Self, git2::Error> {
+        let repo = Repository::discover(working_directory)?;
+        let root = repo
+            .workdir()
+            .ok_or_else(|| git2::Error::from_str("Bare repositories are not supported"))?
+            .canonicalize()
+            .map_err(|e| git2::Error::from_str(&e.to_string()))?;
+        let prefix = working_directory
+            .canonicalize()
+            .ok()
+            .and_then(|dir| dir.strip_prefix(&root).ok().map(Path::to_path_buf))
+            .unwrap_or_default();
+        Ok(Self { repo, prefix })
+    }
+
+    /// Path relative to the working directory, as a path in the repository
+    fn repo_path(&self, path: &str) -> String {
+        let joined = self.prefix.join(path);
+        let parts: Vec<_> = joined
+            .components()
+            .filter(|c| !matches!(c, std::path::Component::CurDir))
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect();
+        if parts.is_empty() {
+            // The whole repository
+            return "*".to_string();
+        }
+        parts.join("/")
+    }
+
+    fn pathspecs(&self, paths: &[String]) -> Vec<String> {
+        paths.iter().map(|path| self.repo_path(path)).collect()
+    }
+
+    fn diff_options(&self, paths: &[String]) -> DiffOptions {
+        let mut options = DiffOptions::new();
+        for pathspec in self.pathspecs(paths) {
+            options.pathspec(pathspec);
+        }
+        options
+    }
+
+    /// HEAD commit, or `None` before the first commit
+    fn head_commit(&self) -> std::result::Result<Option<Commit<'_>>, git2::Error> {
+        match self.repo.head() {
+            Ok(head) => Ok(Some(head.peel_to_commit()?)),
+            Err(e) if e.code() == ErrorCode::UnbornBranch || e.code() == ErrorCode::NotFound => {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn commit(&self, rev: &str) -> std::result::Result<Commit<'_>, git2::Error> {
+        self.repo.revparse_single(rev)?.peel_to_commit()
+    }
+
+    fn signature(&self) -> std::result::Result<git2::Signature<'static>, git2::Error> {
+        self.repo.signature().map_err(|_| {
+            git2::Error::from_str(
+                "No author configured. Set user.name and user.email with `git config`.",
+            )
+        })
+    }
+
+    fn status(&self) -> std::result::Result<String, git2::Error> {
+        let mut output = match self.repo.head() {
+            Ok(head) if head.is_branch() => {
+                format!("On branch {}\n", head.shorthand().unwrap_or("HEAD"))
+            }
+            Ok(head) => format!(
+                "HEAD detached at {}\n",
+                short_id(head.peel_to_commit()?.id())
+            ),
+            Err(_) => "No commits yet\n".to_string(),
+        };
+
+        let mut options = StatusOptions::new();
+        options.include_untracked(true).recurse_untracked_dirs(true);
+        let statuses = self.repo.statuses(Some(&mut options))?;
+        if statuses.is_empty() {
+            output.push_str("Nothing to commit, working tree clean");
+            return Ok(output);
+        }
+        for entry in statuses.iter() {
+            output.push_str(&format!(
+                "{} {}\n",
+                status_code(entry.status()),
+                entry.path().unwrap_or("?")
+            ));
+        }
+        output.push_str(
+            "\n(XY: X = staged, Y = unstaged; paths are relative to the repository root)",
+        );
+        Ok(output)
+    }
+
+    fn diff(&self, input: &GitInput) -> std::result::Result<String, git2::Error> {
+        let mut options = self.diff_options(&input.paths);
+        let diff = match (&input.rev, input.staged) {
+            (Some(rev), _) => {
+                let tree = self.commit(rev)?.tree()?;
+                self.repo
+                    .diff_tree_to_workdir_with_index(Some(&tree), Some(&mut options))?
+            }
+            (None, true) => {
+                let tree = match self.head_commit()? {
+                    Some(commit) => Some(commit.tree()?),
+                    None => None,
+                };
+                self.repo
+                    .diff_tree_to_index(tree.as_ref(), None, Some(&mut options))?
+            }
+            (None, false) => {
+                options.include_untracked(true).show_untracked_content(true);
+                self.repo.diff_index_to_workdir(None, Some(&mut options))?
+            }
+        };
+        let patch = patch_text(&diff)?;
+        Ok(if patch.is_empty() {
+            "No changes".to_string()
+        } else {
+            patch
+        })
+    }
+
+    fn log(&self, input: &GitInput) -> std::result::Result<String, git2::Error> {
+        let start = match &input.rev {
+            Some(rev) => self.commit(rev)?,
+            None => match self.head_commit()? {
+                Some(commit) => commit,
+                None => return Ok("No commits yet".to_string()),
+            },
+        };
+        let count = input
+            .max_count
+            .unwrap_or(DEFAULT_LOG_COUNT)
+            .clamp(1, MAX_LOG_COUNT);
+
+        let mut walk = self.repo.revwalk()?;
+        walk.push(start.id())?;
+        walk.set_sorting(Sort::TIME)?;
+
+        let mut lines = Vec::new();
+        for id in walk {
+            if lines.len() == count {
+                break;
+            }
+            let commit = self.repo.find_commit(id?)?;
+            if !input.paths.is_empty() && !self.touches(&commit, &input.paths)? {
+                continue;
+            }
+            lines.push(format!(
+                "{} {} {}: {}",
+                short_id(commit.id()),
+                date(commit.time()),
+                commit.author().name().unwrap_or("unknown"),
+                commit.summary().unwrap_or("")
+            ));
+        }
+        Ok(if lines.is_empty() {
+            "No matching commits".to_string()
+        } else {
+            lines.join("\n")
+        })
+    }
+
+    /// Whether `commit` changed any of `paths` relative to its first parent
+    fn touches(&self, commit: &Commit, paths: &[String]) -> std::result::Result<bool, git2::Error> {
+        let parent_tree = match commit.parents().next() {
+            Some(parent) => Some(parent.tree()?),
+            None => None,
+        };
+        let diff = self.repo.diff_tree_to_tree(
+            parent_tree.as_ref(),
+            Some(&commit.tree()?),
+            Some(&mut self.diff_options(paths)),
+        )?;
+        Ok(diff.deltas().len() > 0)
+    }
+
+    fn blame(&self, input: &GitInput) -> std::result::Result<String, git2::Error> {
+        let path = input
+            .path
+            .as_deref()
+            .ok_or_else(|| git2::Error::from_str("'path' is required for blame"))?;
+        let repo_path = self.repo_path(path);
+        let head = self
+            .head_commit()?
+            .ok_or_else(|| git2::Error::from_str("No commits yet"))?;
+        let blob = head
+            .tree()?
+            .get_path(Path::new(&repo_path))?
+            .to_object(&self.repo)?
+            .peel_to_blob()?;
+        let content = String::from_utf8_lossy(blob.content()).into_owned();
+        let line_count = content.lines().count();
+
+        let start = input.start_line.unwrap_or(1).max(1);
+        let end = input.end_line.unwrap_or(line_count).min(line_count);
+        if start > end {
+            return Err(git2::Error::from_str(&format!(
+                "Line range is outside the file ({} lines at HEAD)",
+                line_count
+            )));
+        }
+        let mut options = BlameOptions::new();
+        options.min_line(start).max_line(end);
+        let blame = self
+            .repo
+            .blame_file(Path::new(&repo_path), Some(&mut options))?;
+
+        let mut lines = Vec::new();
+        for (index, text) in content.lines().enumerate().take(end).skip(start - 1) {
+            let number = index + 1;
+            let origin = blame.get_line(number).map(|hunk| {
+                let signature = hunk.final_signature();
+                format!(
+                    "{} {} {}",
+                    short_id(hunk.final_commit_id()),
+                    date(signature.when()),
+                    signature.name().unwrap_or("unknown")
+                )
+            });
+            lines.push(format!(
+                "{} {:>5}| {}",
+                origin.unwrap_or_else(|| "?".to_string()),
+                number,
+                text
+            ));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    fn show(&self, input: &GitInput) -> std::result::Result<String, git2::Error> {
+        let rev = input.rev.as_deref().unwrap_or("HEAD");
+        let object = self.repo.revparse_single(rev)?;
+        // `rev:path` names a file at a revision
+        if let Some(blob) = object.as_blob() {
+            return Ok(String::from_utf8_lossy(blob.content()).into_owned());
+        }
+
+        let commit = object.peel_to_commit()?;
+        let author = commit.author();
+        let mut output = format!(
+            "commit {}\nAuthor: {} <{}>\nDate:   {}\n\n",
+            commit.id(),
+            author.name().unwrap_or("unknown"),
+            author.email().unwrap_or(""),
+            date(commit.time())
+        );
+        for line in commit.message().unwrap_or("").trim_end().lines() {
+            output.push_str(&format!("    {}\n", line));
+        }
+
+        let parent_tree = match commit.parents().next() {
+            Some(parent) => Some(parent.tree()?),
+            None => None,
+        };
+        let diff = self.repo.diff_tree_to_tree(
+            parent_tree.as_ref(),
+            Some(&commit.tree()?),
+            Some(&mut self.diff_options(&input.paths)),
+        )?;
+        output.push('\n');
+        output.push_str(&patch_text(&diff)?);
+        Ok(output)
+    }
+
+    fn branch(&self, input: &GitInput) -> std::result::Result<String, git2::Error> {
+        if let Some(name) = &input.name {
+            let target = match &input.rev {
+                Some(rev) => self.commit(rev)?,
+                None => self
+                    .head_commit()?
+                    .ok_or_else(|| git2::Error::from_str("No commits yet"))?,
+            };
+            self.repo.branch(name, &target, false)?;
+            return Ok(format!(
+                "Created branch {} at {}",
+                name,
+                short_id(target.id())
+            ));
+        }
+
+        let mut lines = Vec::new();
+        for branch in self.repo.branches(Some(BranchType::Local))? {
+            let (branch, _) = branch?;
+            let name = branch.name()?.unwrap_or("?").to_string();
+            let target = branch
+                .get()
+                .peel_to_commit()
+                .map(|commit| {
+                    format!(
+                        "{} {}",
+                        short_id(commit.id()),
+                        commit.summary().unwrap_or("")
+                    )
+                })
+                .unwrap_or_default();
+            let marker = if branch.is_head() { "*" } else { " " };
+            lines.push(format!("{} {} {}", marker, name, target));
+        }
+        Ok(if lines.is_empty() {
+            "No branches yet".to_string()
+        } else {
+            lines.join("\n")
+        })
+    }
+
+    fn stage(&self, input: &GitInput) -> std::result::Result<String, git2::Error> {
+        if input.paths.is_empty() {
+            return Err(git2::Error::from_str(
+                "'paths' is required for stage; use [\".\"] to stage everything",
+            ));
+        }
+        let pathspecs = self.pathspecs(&input.paths);
+        let mut index = self.repo.index()?;
+        index.add_all(pathspecs.iter(), IndexAddOption::DEFAULT, None)?;
+        // Stage deletions too
+        index.update_all(pathspecs.iter(), None)?;
+        index.write()?;
+
+        let mut options = StatusOptions::new();
+        let staged = self
+            .repo
+            .statuses(Some(&mut options))?
+            .iter()
+            .filter(|entry| is_staged(entry.status()))
+            .count();
+        Ok(format!(
+            "Staged {}; {} file(s) now staged for commit",
+            input.paths.join(", "),
+            staged
+        ))
+    }
+
+    fn commit_index(&self, input: &GitInput) -> std::result::Result<String, git2::Error> {
+        let message = input
+            .message
+            .as_deref()
+            .filter(|message| !message.trim().is_empty())
+            .ok_or_else(|| git2::Error::from_str("'message' is required for commit"))?;
+        let signature = self.signature()?;
+
+        let mut index = self.repo.index()?;
+        let tree = self.repo.find_tree(index.write_tree()?)?;
+        let parent = self.head_commit()?;
+        let unchanged = match &parent {
+            Some(parent) => parent.tree_id() == tree.id(),
+            None => tree.is_empty(),
+        };
+        if unchanged {
+            return Err(git2::Error::from_str(
+                "Nothing staged to commit. Stage changes with the 'stage' operation first.",
+            ));
+        }
+
+        let parents: Vec<&Commit> = parent.iter().collect();
+        let id = self.repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )?;
+        let summary = message.lines().next().unwrap_or("");
+        Ok(format!("Committed {} {}", short_id(id), summary))
+    }
+
+    fn stash(&mut self, input: &GitInput) -> std::result::Result<String, git2::Error> {
+        let signature = self.signature()?;
+        let message = input.message.as_deref().unwrap_or("crustly stash");
+        match self
+            .repo
+            .stash_save(&signature, message, Some(StashFlags::INCLUDE_UNTRACKED))
+        {
+            Ok(id) => Ok(format!(
+                "Stashed local changes as {} \"{}\"; restore them with `git stash pop`",
+                short_id(id),
+                message
+            )),
+            Err(e) if e.code() == ErrorCode::NotFound => {
+                Ok("No local changes to stash".to_string())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn worktree(
+        &self,
+        input: &GitInput,
+        working_directory: &Path,
+    ) -> std::result::Result<String, git2::Error> {
+        let requested = input
+            .path
+            .as_deref()
+            .ok_or_else(|| git2::Error::from_str("'path' is required for worktree"))?;
+        let path = validate_path_safety(requested, working_directory)
+            .map_err(|e| git2::Error::from_str(&e.to_string()))?;
+        if path.exists() {
+            return Err(git2::Error::from_str(&format!(
+                "{} already exists",
+                path.display()
+            )));
+        }
+        let dir_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| git2::Error::from_str("Invalid worktree path"))?;
+        let branch_name = input.name.clone().unwrap_or_else(|| dir_name.clone());
+
+        // Check out the branch, creating it at HEAD if needed
+        let branch = match self.repo.find_branch(&branch_name, BranchType::Local) {
+            Ok(branch) => branch,
+            Err(e) if e.code() == ErrorCode::NotFound => {
+                let head = self
+                    .head_commit()?
+                    .ok_or_else(|| git2::Error::from_str("No commits yet"))?;
+                self.repo.branch(&branch_name, &head, false)?
+            }
+            Err(e) => return Err(e),
+        };
+        let reference = branch.into_reference();
+        let mut options = WorktreeAddOptions::new();
+        options.reference(Some(&reference));
+        self.repo.worktree(&dir_name, &path, Some(&options))?;
+
+        Ok(format!(
+            "Created worktree {} on branch {}",
+            path.display(),
+            branch_name
+        ))
+    }
+}
+
+/// Two-letter status, as in `git status --short`
+fn status_code(status: Status) -> String {
+    if status.contains(Status::CONFLICTED) {
+        return "UU".to_string();
+    }
+    if status.contains(Status::WT_NEW) {
+        return "??".to_string();
+    }
+    let staged = if status.contains(Status::INDEX_NEW) {
+        'A'
+    } else if status.contains(Status::INDEX_MODIFIED) {
+        'M'
+    } else if status.contains(Status::INDEX_DELETED) {
+        'D'
+    } else if status.contains(Status::INDEX_RENAMED) {
+        'R'
+    } else if status.contains(Status::INDEX_TYPECHANGE) {
+        'T'
+    } else {
+        ' '
+    };
+    let unstaged = if status.contains(Status::WT_MODIFIED) {
+        'M'
+    } else if status.contains(Status::WT_DELETED) {
+        'D'
+    } else if status.contains(Status::WT_RENAMED) {
+        'R'
+    } else if status.contains(Status::WT_TYPECHANGE) {
+        'T'
+    } else {
+        ' '
+    };
+    format!("{}{}", staged, unstaged)
+}
+
+fn is_staged(status: Status) -> bool {
+    status.intersects(
+        Status::INDEX_NEW
+            | Status::INDEX_MODIFIED
+            | Status::INDEX_DELETED
+            | Status::INDEX_RENAMED
+            | Status::INDEX_TYPECHANGE,
+    )
+}
+
+/// Unified diff of `diff`
+fn patch_text(diff: &Diff) -> std::result::Result<String, git2::Error> {
+    let mut text = String::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            text.push(line.origin());
+        }
+        text.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })?;
+    Ok(text.trim_end().to_string())
+}
+
+fn short_id(id: git2::Oid) -> String {
+    id.to_string()[..7].to_string()
+}
+
+fn date(time: git2::Time) -> String {
+    chrono::DateTime::from_timestamp(time.seconds(), 0)
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+fn run(input: &GitInput, working_directory: &Path) -> std::result::Result<String, git2::Error> {
+    let mut repo = Repo::open(working_directory)?;
+    match input.operation {
+        Operation::Status => repo.status(),
+        Operation::Diff => repo.diff(input),
+        Operation::Log => repo.log(input),
+        Operation::Blame => repo.blame(input),
+        Operation::Show => repo.show(input),
+        Operation::Branch => repo.branch(input),
+        Operation::Stage => repo.stage(input),
+        Operation::Commit => repo.commit_index(input),
+        Operation::Stash => repo.stash(input),
+        Operation::Worktree => repo.worktree(input, working_directory),
+    }
+}
+
+#[async_trait]
+impl Tool for GitTool {
+    fn name(&self) -> &str {
+        "git"
+    }
+
+    fn description(&self) -> &str {
+        "Work with the git repository. Read operations: 'status', 'diff' (unstaged changes, \
+         'staged' changes, or changes since 'rev'; limited to 'paths'), 'log' (from 'rev', \
+         touching 'paths', 'max_count' commits), 'blame' ('path', 'start_line', 'end_line'), \
+         'show' (a commit's message and diff, or 'rev:path' for a file at a revision) and \
+         'branch' (list branches). Write operations, which need approval: 'branch' with 'name' \
+         (create a branch at HEAD or 'rev'), 'stage' ('paths'), 'commit' ('message'), 'stash' \
+         (save local changes, including untracked files) and 'worktree' (check out branch \
+         'name' in a new directory 'path')."
+    }
+
+    fn input_schema(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["status", "diff", "log", "blame", "show", "branch", "stage", "commit", "stash", "worktree"],
+                    "description": "Git operation to perform"
+                },
+                "paths": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Files or directories limiting diff, log and show, or to stage"
+                },
+                "rev": {
+                    "type": "string",
+                    "description": "Revision (commit hash, branch, tag, HEAD~2): base for diff, start for log, object for show, start for a new branch"
+                },
+                "staged": {
+                    "type": "boolean",
+                    "description": "diff: show staged instead of unstaged changes",
+                    "default": false
+                },
+                "max_count": {
+                    "type": "integer",
+                    "description": "log: number of commits (default: 20)",
+                    "minimum": 1
+                },
+                "path": {
+                    "type": "string",
+                    "description": "blame: file to annotate; worktree: directory to create"
+                },
+                "start_line": {
+                    "type": "integer",
+                    "description": "blame: first line, starting at 1",
+                    "minimum": 1
+                },
+                "end_line": {
+                    "type": "integer",
+                    "description": "blame: last line",
+                    "minimum": 1
+                },
+                "name": {
+                    "type": "string",
+                    "description": "branch: branch to create; worktree: branch to check out (created at HEAD if missing)"
+                },
+                "message": {
+                    "type": "string",
+                    "description": "commit and stash message"
+                }
+            },
+            "required": ["operation"]
+        })
+    }
+
+    fn capabilities(&self) -> Vec<ToolCapability> {
+        vec![ToolCapability::ReadFiles, ToolCapability::WriteFiles]
+    }
+
+    fn call_capabilities(&self, input: &Value) -> Vec<ToolCapability> {
+        match serde_json::from_value::<GitInput>(input.clone()) {
+            Ok(input) if !input.is_mutating() => vec![ToolCapability::ReadFiles],
+            _ => self.capabilities(),
+        }
+    }
+
+    fn call_requires_approval(&self, input: &Value) -> bool {
+        !serde_json::from_value::<GitInput>(input.clone()).is_ok_and(|input| !input.is_mutating())
+    }
+
+    fn validate_input(&self, input: &Value) -> Result<()> {
+        let _: GitInput = serde_json::from_value(input.clone())
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid input: {}", e)))?;
+        Ok(())
+    }
+
+    async fn execute(&self, input: Value, context: &ToolExecutionContext) -> Result<ToolResult> {
+        let input: GitInput = serde_json::from_value(input)?;
+        if context.read_only_mode && input.is_mutating() {
+            return Ok(ToolResult::error(
+                "This git operation changes the repository and is not allowed in Plan mode. \
+                 Please approve the plan and switch to execution mode (Ctrl+A) first."
+                    .to_string(),
+            ));
+        }
+
+        let working_directory = context.working_directory.clone();
+        let output = tokio::task::spawn_blocking(move || run(&input, &working_directory))
+            .await
+            .map_err(|e| ToolError::Execution(format!("Git task failed: {}", e)))?;
+
+        Ok(match output {
+            Ok(output) => ToolResult::success(output),
+            Err(e) => ToolResult::error(format!("git: {}", e.message())),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    /// Repository with one commit of `src/lib.rs`
+    fn repository() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "fn one() {}\nfn two() {}\n").unwrap();
+        std::fs::write(dir.path().join("README.md"), "# Demo\n").unwrap();
+        dir
+    }
+
+    async fn git(dir: &TempDir, input: Value) -> ToolResult {
+        let context = ToolExecutionContext::new(Uuid::new_v4())
+            .with_working_directory(dir.path().to_path_buf());
+        GitTool.execute(input, &context).await.unwrap()
+    }
+
+    async fn commit_all(dir: &TempDir, message: &str) {
+        let result = git(
+            dir,
+            serde_json::json!({"operation": "stage", "paths": ["."]}),
+        )
+        .await;
+        assert!(result.success, "{:?}", result.error);
+        let result = git(
+            dir,
+            serde_json::json!({"operation": "commit", "message": message}),
+        )
+        .await;
+        assert!(result.success, "{:?}", result.error);
+    }
+
+    #[tokio::test]
+    async fn test_status_stage_commit_and_log() {
+        let dir = repository();
+        let result = git(&dir, serde_json::json!({"operation": "status"})).await;
+        assert!(result.output.starts_with("No commits yet\n"));
+        assert!(result.output.contains("?? src/lib.rs"));
+
+        commit_all(&dir, "Initial commit").await;
+        let result = git(&dir, serde_json::json!({"operation": "status"})).await;
+        assert!(result.output.contains("Nothing to commit"));
+
+        std::fs::write(
+            dir.path().join("src/lib.rs"),
+            "fn one() {}\nfn three() {}\n",
+        )
+        .unwrap();
+        std::fs::remove_file(dir.path().join("README.md")).unwrap();
+        let result = git(&dir, serde_json::json!({"operation": "status"})).await;
+        assert!(result.output.contains(" M src/lib.rs"));
+        assert!(result.output.contains(" D README.md"));
+
+        commit_all(&dir, "Replace two\n\nWith three.").await;
+        let result = git(&dir, serde_json::json!({"operation": "status"})).await;
+        assert!(
+            result.output.contains("Nothing to commit"),
+            "{}",
+            result.output
+        );
+
+        let result = git(&dir, serde_json::json!({"operation": "log"})).await;
+        let lines: Vec<_> = result.output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("Test: Replace two"));
+        assert!(lines[1].ends_with("Test: Initial commit"));
+
+        let result = git(
+            &dir,
+            serde_json::json!({"operation": "log", "paths": ["README.md"], "max_count": 5}),
+        )
+        .await;
+        assert_eq!(result.output.lines().count(), 2);
+
+        // Nothing left to commit
+        let result = git(
+            &dir,
+            serde_json::json!({"operation": "commit", "message": "Empty"}),
+        )
+        .await;
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("Nothing staged"));
+    }
+
+    #[tokio::test]
+    async fn test_diff_show_and_blame() {
+        let dir = repository();
+        commit_all(&dir, "Initial commit").await;
+        std::fs::write(
+            dir.path().join("src/lib.rs"),
+            "fn one() {}\nfn three() {}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("README.md"), "# Demo app\n").unwrap();
+
+        let result = git(
+            &dir,
+            serde_json::json!({"operation": "diff", "paths": ["src"]}),
+        )
+        .await;
+        assert!(result.output.contains("-fn two() {}\n+fn three() {}"));
+        assert!(!result.output.contains("README.md"));
+
+        let result = git(
+            &dir,
+            serde_json::json!({"operation": "diff", "staged": true}),
+        )
+        .await;
+        assert_eq!(result.output, "No changes");
+
+        git(
+            &dir,
+            serde_json::json!({"operation": "stage", "paths": ["src/lib.rs"]}),
+        )
+        .await;
+        let result = git(
+            &dir,
+            serde_json::json!({"operation": "diff", "staged": true}),
+        )
+        .await;
+        assert!(result.output.contains("+fn three() {}"));
+        assert!(!result.output.contains("README.md"));
+
+        let result = git(&dir, serde_json::json!({"operation": "show"})).await;
+        assert!(result.output.contains("Author: Test <test@example.com>"));
+        assert!(result.output.contains("    Initial commit"));
+        assert!(result.output.contains("+fn two() {}"));
+
+        let result = git(
+            &dir,
+            serde_json::json!({"operation": "show", "rev": "HEAD:README.md"}),
+        )
+        .await;
+        assert_eq!(result.output, "# Demo\n");
+
+        let result = git(
+            &dir,
+            serde_json::json!({"operation": "blame", "path": "src/lib.rs", "start_line": 2}),
+        )
+        .await;
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.output.lines().count(), 1);
+        assert!(result.output.contains(" Test     2| fn two() {}"));
+    }
+
+    #[tokio::test]
+    async fn test_branch_stash_and_worktree() {
+        let dir = repository();
+        commit_all(&dir, "Initial commit").await;
+
+        let result = git(
+            &dir,
+            serde_json::json!({"operation": "branch", "name": "feature"}),
+        )
+        .await;
+        assert!(result.success, "{:?}", result.error);
+        let result = git(&dir, serde_json::json!({"operation": "branch"})).await;
+        assert!(result.output.contains("  feature "));
+        assert!(result.output.contains("* master ") || result.output.contains("* main "));
+
+        std::fs::write(dir.path().join("README.md"), "# Changed\n").unwrap();
+        let result = git(&dir, serde_json::json!({"operation": "stash"})).await;
+        assert!(result.output.starts_with("Stashed local changes"));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("README.md")).unwrap(),
+            "# Demo\n"
+        );
+        let result = git(&dir, serde_json::json!({"operation": "stash"})).await;
+        assert_eq!(result.output, "No local changes to stash");
+
+        let result = git(
+            &dir,
+            serde_json::json!({"operation": "worktree", "path": "fix-tree", "name": "fix"}),
+        )
+        .await;
+        assert!(result.success, "{:?}", result.error);
+        assert!(dir.path().join("fix-tree/src/lib.rs").exists());
+        let result = git(&dir, serde_json::json!({"operation": "branch"})).await;
+        assert!(result.output.contains("  fix "));
+    }
+
+    #[tokio::test]
+    async fn test_plan_mode_and_approval() {
+        let dir = repository();
+        let context = ToolExecutionContext::new(Uuid::new_v4())
+            .with_working_directory(dir.path().to_path_buf())
+            .with_read_only_mode(true);
+
+        let status = serde_json::json!({"operation": "status"});
+        assert!(
+            GitTool
+                .execute(status.clone(), &context)
+                .await
+                .unwrap()
+                .success
+        );
+        assert!(!GitTool.call_requires_approval(&status));
+        assert_eq!(
+            GitTool.call_capabilities(&status),
+            vec![ToolCapability::ReadFiles]
+        );
+
+        let stage = serde_json::json!({"operation": "stage", "paths": ["."]});
+        let result = GitTool.execute(stage.clone(), &context).await.unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("Plan mode"));
+        assert!(GitTool.call_requires_approval(&stage));
+        assert!(GitTool
+            .call_capabilities(&stage)
+            .contains(&ToolCapability::WriteFiles));
+
+        // Listing branches is read-only, creating one is not
+        assert!(!GitTool.call_requires_approval(&serde_json::json!({"operation": "branch"})));
+        assert!(GitTool.call_requires_approval(
+            &serde_json::json!({"operation": "branch", "name": "feature"})
+        ));
+    }
+}
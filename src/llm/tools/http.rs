@@ -3,7 +3,7 @@
 //! Make HTTP requests to external APIs (REST endpoints, webhooks, etc.)
 
 use super::error::{Result, ToolError};
-use super::r#trait::{Tool, ToolCapability, ToolExecutionContext, ToolResult};
+use super::r#trait::{Tool, ToolCapability, ToolExecutionContext, ToolProgress, ToolResult};
 use async_trait::async_trait;
 use reqwest::{header::HeaderMap, Client, Method};
 use serde::{Deserialize, Serialize};
@@ -168,7 +168,7 @@ impl Tool for HttpClientTool {
         Ok(())
     }
 
-    async fn execute(&self, input: Value, _context: &ToolExecutionContext) -> Result<ToolResult> {
+    async fn execute(&self, input: Value, context: &ToolExecutionContext) -> Result<ToolResult> {
         let input: HttpInput = serde_json::from_value(input)?;
 
         let method = parse_method(&input.method)?;
@@ -213,7 +213,11 @@ impl Tool for HttpClientTool {
         }
 
         // Execute request
-        let response = request.send().await.map_err(|e| {
+        context.report_progress(
+            self.name(),
+            ToolProgress::Status(format!("{} {}", method, input.url)),
+        );
+        let mut response = request.send().await.map_err(|e| {
             if e.is_timeout() {
                 ToolError::Timeout(input.timeout_secs)
             } else if e.is_connect() {
@@ -236,11 +240,34 @@ impl Tool for HttpClientTool {
             }
         }
 
-        // Get response body
-        let body_text = response
-            .text()
+        context.report_progress(
+            self.name(),
+            ToolProgress::Status(format!(
+                "{} {}",
+                status_code,
+                status.canonical_reason().unwrap_or("Unknown")
+            )),
+        );
+
+        // Get response body, reporting download progress when the size is known
+        let content_length = response.content_length();
+        let mut body = Vec::new();
+        let mut last_percent = None;
+        while let Some(chunk) = response
+            .chunk()
             .await
-            .map_err(|e| ToolError::Execution(format!("Failed to read response body: {}", e)))?;
+            .map_err(|e| ToolError::Execution(format!("Failed to read response body: {}", e)))?
+        {
+            body.extend_from_slice(&chunk);
+            if let Some(total) = content_length.filter(|t| *t > 0) {
+                let percent = ((body.len() as u64 * 100) / total).min(100) as u8;
+                if last_percent != Some(percent) {
+                    last_percent = Some(percent);
+                    context.report_progress(self.name(), ToolProgress::Percent(percent));
+                }
+            }
+        }
+        let body_text = String::from_utf8_lossy(&body).to_string();
 
         // Try to parse as JSON, fallback to text
         let body_json: Option<Value> = serde_json::from_str(&body_text).ok();
@@ -0,0 +1,866 @@
+//! Language Server Tools
+//!
+//! Semantic code navigation through the configured language servers:
+//! `lsp` answers go-to-definition, find-references, hover and workspace
+//! symbol queries, and `rename_symbol` renames a symbol across the project.
+
+use super::apply_patch::{write_files, FileWrite};
+use super::error::{validate_file_path, Result, ToolError};
+use super::r#trait::{ProposedChange, Tool, ToolCapability, ToolExecutionContext, ToolResult};
+use crate::lsp::text::{apply_edits, find_symbol};
+use crate::lsp::{LspClient, LspManager};
+use async_trait::async_trait;
+use lsp_types::request::{
+    GotoDefinition, HoverRequest, References, Rename, WorkspaceSymbolRequest,
+};
+use lsp_types::{
+    DocumentChangeOperation, DocumentChanges, GotoDefinitionParams, GotoDefinitionResponse,
+    HoverContents, HoverParams, Location, MarkedString, OneOf, PartialResultParams, Position,
+    ReferenceContext, ReferenceParams, RenameParams, TextDocumentIdentifier,
+    TextDocumentPositionParams, TextEdit, Url, WorkDoneProgressParams, WorkspaceEdit,
+    WorkspaceSymbolParams, WorkspaceSymbolResponse,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Maximum number of locations listed in a result
+const MAX_LOCATIONS: usize = 100;
+
+/// Semantic code navigation tool
+pub struct LspTool {
+    manager: Arc<LspManager>,
+}
+
+impl LspTool {
+    /// Create the tool on top of a language server manager
+    pub fn new(manager: Arc<LspManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum LspOperation {
+    Definition,
+    References,
+    Hover,
+    Symbols,
+}
+
+#[derive(Debug, Deserialize)]
+struct LspInput {
+    operation: LspOperation,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(flatten)]
+    position: PositionInput,
+    #[serde(default)]
+    query: Option<String>,
+}
+
+/// Where in a file a symbol is
+#[derive(Debug, Deserialize)]
+struct PositionInput {
+    /// Line number, starting at 1
+    #[serde(default)]
+    line: Option<u32>,
+    /// Column, starting at 1, in characters
+    #[serde(default)]
+    column: Option<u32>,
+    /// Symbol name, located on `line` (or its first occurrence in the file)
+    #[serde(default)]
+    symbol: Option<String>,
+}
+
+impl PositionInput {
+    /// LSP position in `text`
+    fn locate(&self, text: &str) -> std::result::Result<Position, String> {
+        let Some(line_number) = self.line else {
+            let symbol = self
+                .symbol
+                .as_deref()
+                .ok_or("Give a `line` or a `symbol` to locate")?;
+            return text
+                .lines()
+                .enumerate()
+                .find_map(|(index, line)| {
+                    find_symbol(line, symbol).map(|column| Position::new(index as u32, column))
+                })
+                .ok_or_else(|| format!("`{}` does not occur in the file", symbol));
+        };
+
+        let index = line_number
+            .checked_sub(1)
+            .ok_or("Line numbers start at 1")?;
+        let line = text.lines().nth(index as usize).ok_or_else(|| {
+            format!(
+                "Line {} is past the end of the file ({} lines)",
+                line_number,
+                text.lines().count()
+            )
+        })?;
+
+        let character = match (self.column, &self.symbol) {
+            (Some(column), _) => line
+                .chars()
+                .take(column.saturating_sub(1) as usize)
+                .map(char::len_utf16)
+                .sum::<usize>() as u32,
+            (None, Some(symbol)) => find_symbol(line, symbol)
+                .ok_or_else(|| format!("`{}` does not occur on line {}", symbol, line_number))?,
+            (None, None) => line
+                .chars()
+                .take_while(|c| c.is_whitespace())
+                .map(char::len_utf16)
+                .sum::<usize>() as u32,
+        };
+        Ok(Position::new(index, character))
+    }
+}
+
+/// Open `path` on its server and resolve the requested position
+async fn prepare(
+    manager: &LspManager,
+    path: &str,
+    position: &PositionInput,
+    context: &ToolExecutionContext,
+) -> std::result::Result<(Arc<LspClient>, TextDocumentPositionParams), String> {
+    let path = validate_file_path(path, &context.working_directory)?;
+    let text = fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let position = position.locate(&text)?;
+
+    let client = manager.client_for(&path).await.map_err(|e| e.to_string())?;
+    let uri = client.sync_file(&path).await.map_err(|e| e.to_string())?;
+    Ok((
+        client,
+        TextDocumentPositionParams::new(TextDocumentIdentifier::new(uri), position),
+    ))
+}
+
+/// Formats locations as `path:line:column  code`
+struct LocationFormatter<'a> {
+    working_directory: &'a Path,
+    files: HashMap<Url, Option<Vec<String>>>,
+}
+
+impl<'a> LocationFormatter<'a> {
+    fn new(working_directory: &'a Path) -> Self {
+        Self {
+            working_directory,
+            files: HashMap::new(),
+        }
+    }
+
+    async fn format(&mut self, location: &Location) -> String {
+        let path = location
+            .uri
+            .to_file_path()
+            .unwrap_or_else(|_| PathBuf::from(location.uri.as_str()));
+        let display = path
+            .strip_prefix(self.working_directory)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+
+        if !self.files.contains_key(&location.uri) {
+            let lines = fs::read_to_string(&path)
+                .await
+                .ok()
+                .map(|text| text.lines().map(str::to_string).collect());
+            self.files.insert(location.uri.clone(), lines);
+        }
+        let start = location.range.start;
+        let code = self.files[&location.uri]
+            .as_ref()
+            .and_then(|lines| lines.get(start.line as usize))
+            .map(|line| line.trim())
+            .unwrap_or_default();
+
+        format!(
+            "{}:{}:{}  {}",
+            display,
+            start.line + 1,
+            start.character + 1,
+            code
+        )
+        .trim_end()
+        .to_string()
+    }
+
+    async fn list(&mut self, locations: &[Location]) -> String {
+        let mut output = Vec::new();
+        for location in locations.iter().take(MAX_LOCATIONS) {
+            output.push(self.format(location).await);
+        }
+        if locations.len() > MAX_LOCATIONS {
+            output.push(format!("... and {} more", locations.len() - MAX_LOCATIONS));
+        }
+        output.join("\n")
+    }
+}
+
+fn hover_text(contents: HoverContents) -> String {
+    let marked = |marked: MarkedString| match marked {
+        MarkedString::String(text) => text,
+        MarkedString::LanguageString(code) => {
+            format!("```{}\n{}\n```", code.language, code.value)
+        }
+    };
+    match contents {
+        HoverContents::Scalar(value) => marked(value),
+        HoverContents::Array(values) => values
+            .into_iter()
+            .map(marked)
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        HoverContents::Markup(markup) => markup.value,
+    }
+}
+
+/// Note added to empty results, as servers answer before indexing finishes
+const STILL_INDEXING: &str =
+    "If the project was just opened, the language server may still be indexing; try again shortly.";
+
+impl LspTool {
+    async fn run(&self, input: LspInput, context: &ToolExecutionContext) -> Result<ToolResult> {
+        if let LspOperation::Symbols = input.operation {
+            return self.symbols(input, context).await;
+        }
+
+        let Some(path) = input.path.as_deref() else {
+            return Ok(ToolResult::error(
+                "`path` is required for this operation".to_string(),
+            ));
+        };
+        let (client, position) = match prepare(&self.manager, path, &input.position, context).await
+        {
+            Ok(prepared) => prepared,
+            Err(message) => return Ok(ToolResult::error(message)),
+        };
+        let mut formatter = LocationFormatter::new(&context.working_directory);
+
+        let output = match input.operation {
+            LspOperation::Definition => {
+                let response = client
+                    .request::<GotoDefinition>(GotoDefinitionParams {
+                        text_document_position_params: position,
+                        work_done_progress_params: WorkDoneProgressParams::default(),
+                        partial_result_params: PartialResultParams::default(),
+                    })
+                    .await
+                    .map_err(lsp_error)?;
+                let locations = match response {
+                    None => Vec::new(),
+                    Some(GotoDefinitionResponse::Scalar(location)) => vec![location],
+                    Some(GotoDefinitionResponse::Array(locations)) => locations,
+                    Some(GotoDefinitionResponse::Link(links)) => links
+                        .into_iter()
+                        .map(|link| Location::new(link.target_uri, link.target_selection_range))
+                        .collect(),
+                };
+                if locations.is_empty() {
+                    format!("No definition found. {}", STILL_INDEXING)
+                } else {
+                    formatter.list(&locations).await
+                }
+            }
+            LspOperation::References => {
+                let locations = client
+                    .request::<References>(ReferenceParams {
+                        text_document_position: position,
+                        work_done_progress_params: WorkDoneProgressParams::default(),
+                        partial_result_params: PartialResultParams::default(),
+                        context: ReferenceContext {
+                            include_declaration: true,
+                        },
+                    })
+                    .await
+                    .map_err(lsp_error)?
+                    .unwrap_or_default();
+                if locations.is_empty() {
+                    format!("No references found. {}", STILL_INDEXING)
+                } else {
+                    format!(
+                        "{} reference(s):\n{}",
+                        locations.len(),
+                        formatter.list(&locations).await
+                    )
+                }
+            }
+            LspOperation::Hover => {
+                let hover = client
+                    .request::<HoverRequest>(HoverParams {
+                        text_document_position_params: position,
+                        work_done_progress_params: WorkDoneProgressParams::default(),
+                    })
+                    .await
+                    .map_err(lsp_error)?;
+                match hover {
+                    Some(hover) => hover_text(hover.contents),
+                    None => "No information at this position.".to_string(),
+                }
+            }
+            LspOperation::Symbols => unreachable!("handled above"),
+        };
+
+        Ok(ToolResult::success(output))
+    }
+
+    async fn symbols(&self, input: LspInput, context: &ToolExecutionContext) -> Result<ToolResult> {
+        let Some(query) = input.query.filter(|q| !q.trim().is_empty()) else {
+            return Ok(ToolResult::error(
+                "`query` is required for the symbols operation".to_string(),
+            ));
+        };
+
+        let clients = match input.path.as_deref() {
+            Some(path) => {
+                let path = match validate_file_path(path, &context.working_directory) {
+                    Ok(path) => path,
+                    Err(message) => return Ok(ToolResult::error(message)),
+                };
+                vec![self.manager.client_for(&path).await.map_err(lsp_error)?]
+            }
+            None => self.manager.workspace_clients().await,
+        };
+        if clients.is_empty() {
+            return Ok(ToolResult::error(
+                "No language server found for this project. Pass `path` of a source file \
+                 to pick one."
+                    .to_string(),
+            ));
+        }
+
+        let mut symbols = Vec::new();
+        for client in clients {
+            let response = client
+                .request::<WorkspaceSymbolRequest>(WorkspaceSymbolParams {
+                    query: query.clone(),
+                    work_done_progress_params: WorkDoneProgressParams::default(),
+                    partial_result_params: PartialResultParams::default(),
+                })
+                .await
+                .map_err(lsp_error)?;
+            match response {
+                Some(WorkspaceSymbolResponse::Flat(found)) => {
+                    symbols.extend(
+                        found
+                            .into_iter()
+                            .map(|s| (s.name, s.kind, s.container_name, Some(s.location))),
+                    );
+                }
+                Some(WorkspaceSymbolResponse::Nested(found)) => {
+                    symbols.extend(found.into_iter().map(|s| {
+                        let location = match s.location {
+                            OneOf::Left(location) => Some(location),
+                            OneOf::Right(_) => None,
+                        };
+                        (s.name, s.kind, s.container_name, location)
+                    }));
+                }
+                None => {}
+            }
+        }
+        if symbols.is_empty() {
+            return Ok(ToolResult::success(format!(
+                "No symbols match `{}`. {}",
+                query, STILL_INDEXING
+            )));
+        }
+
+        let mut formatter = LocationFormatter::new(&context.working_directory);
+        let mut output = vec![format!("{} symbol(s):", symbols.len())];
+        for (name, kind, container, location) in symbols.iter().take(MAX_LOCATIONS) {
+            let name = match container {
+                Some(container) if !container.is_empty() => format!("{}::{}", container, name),
+                _ => name.clone(),
+            };
+            let location = match location {
+                Some(location) => formatter.format(location).await,
+                None => String::new(),
+            };
+            output.push(
+                format!("{:?} {}  {}", kind, name, location)
+                    .trim_end()
+                    .to_string(),
+            );
+        }
+        if symbols.len() > MAX_LOCATIONS {
+            output.push(format!("... and {} more", symbols.len() - MAX_LOCATIONS));
+        }
+        Ok(ToolResult::success(output.join("\n")))
+    }
+}
+
+fn lsp_error(error: crate::lsp::LspError) -> ToolError {
+    ToolError::Execution(error.to_string())
+}
+
+#[async_trait]
+impl Tool for LspTool {
+    fn name(&self) -> &str {
+        "lsp"
+    }
+
+    fn description(&self) -> &str {
+        "Semantic code navigation through language servers (rust-analyzer, pyright, gopls, ...). \
+         Operations: 'definition' (where a symbol is defined), 'references' (everywhere it is \
+         used), 'hover' (type and documentation) and 'symbols' (search symbols by name across \
+         the project). Prefer this over grep to find where a function or type is defined or used. \
+         Locate the symbol with 'path' plus 'line' and 'symbol' (or 'column')."
+    }
+
+    fn input_schema(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["definition", "references", "hover", "symbols"],
+                    "description": "Query to run"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "File containing the symbol (required except for 'symbols')"
+                },
+                "line": {
+                    "type": "integer",
+                    "description": "Line of the symbol, starting at 1",
+                    "minimum": 1
+                },
+                "column": {
+                    "type": "integer",
+                    "description": "Column of the symbol, starting at 1",
+                    "minimum": 1
+                },
+                "symbol": {
+                    "type": "string",
+                    "description": "Name of the symbol; located on 'line', or its first occurrence in the file"
+                },
+                "query": {
+                    "type": "string",
+                    "description": "Symbol name to search for (for 'symbols')"
+                }
+            },
+            "required": ["operation"]
+        })
+    }
+
+    fn capabilities(&self) -> Vec<ToolCapability> {
+        vec![ToolCapability::ReadFiles]
+    }
+
+    fn requires_approval(&self) -> bool {
+        false // Read-only queries
+    }
+
+    fn validate_input(&self, input: &Value) -> Result<()> {
+        let _: LspInput = serde_json::from_value(input.clone())
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid input: {}", e)))?;
+        Ok(())
+    }
+
+    async fn execute(&self, input: Value, context: &ToolExecutionContext) -> Result<ToolResult> {
+        let input: LspInput = serde_json::from_value(input)?;
+        self.run(input, context).await
+    }
+}
+
+/// Symbol rename tool
+pub struct RenameSymbolTool {
+    manager: Arc<LspManager>,
+    /// Plan of the pending call, shared by `preview`, `modified_paths` and `execute`
+    pending: Mutex<Option<PendingRename>>,
+}
+
+impl RenameSymbolTool {
+    /// Create the tool on top of a language server manager
+    pub fn new(manager: Arc<LspManager>) -> Self {
+        Self {
+            manager,
+            pending: Mutex::new(None),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RenameInput {
+    path: String,
+    #[serde(flatten)]
+    position: PositionInput,
+    new_name: String,
+}
+
+/// New content of one file touched by a rename
+#[derive(Clone)]
+struct FileRename {
+    path: PathBuf,
+    before: String,
+    after: String,
+    edits: usize,
+}
+
+/// Rename planned for a call that has not been executed yet
+struct PendingRename {
+    session_id: Uuid,
+    input: Value,
+    renames: Vec<FileRename>,
+}
+
+impl RenameSymbolTool {
+    /// Ask the server for the rename and compute the new file contents
+    async fn plan(
+        &self,
+        input: &RenameInput,
+        context: &ToolExecutionContext,
+    ) -> std::result::Result<Vec<FileRename>, String> {
+        if input.new_name.trim().is_empty() {
+            return Err("`new_name` must not be empty".to_string());
+        }
+        let (client, position) =
+            prepare(&self.manager, &input.path, &input.position, context).await?;
+        let edit = client
+            .request::<Rename>(RenameParams {
+                text_document_position: position,
+                new_name: input.new_name.clone(),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            })
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or("The language server found nothing to rename at this position")?;
+
+        let mut renames = Vec::new();
+        for (uri, edits) in text_edits(edit)? {
+            let path = uri
+                .to_file_path()
+                .map_err(|_| format!("Cannot rename in {}", uri))?;
+            if !path.starts_with(&context.working_directory) {
+                return Err(format!(
+                    "The rename would change {}, outside the working directory",
+                    path.display()
+                ));
+            }
+            let before = fs::read_to_string(&path)
+                .await
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            let after = apply_edits(&before, &edits);
+            renames.push(FileRename {
+                path,
+                before,
+                after,
+                edits: edits.len(),
+            });
+        }
+        Ok(renames)
+    }
+
+    /// Plan a call, reusing the plan made for the same call if the files are unchanged
+    ///
+    /// The server is asked once per call, and the files snapshotted before the
+    /// call are the ones `execute` writes.
+    async fn planned(
+        &self,
+        raw: &Value,
+        input: &RenameInput,
+        context: &ToolExecutionContext,
+    ) -> std::result::Result<Vec<FileRename>, String> {
+        let cached = self
+            .pending
+            .lock()
+            .await
+            .as_ref()
+            .filter(|pending| pending.session_id == context.session_id && &pending.input == raw)
+            .map(|pending| pending.renames.clone());
+        if let Some(renames) = cached {
+            let mut current = true;
+            for rename in &renames {
+                if fs::read_to_string(&rename.path).await.ok().as_ref() != Some(&rename.before) {
+                    current = false;
+                    break;
+                }
+            }
+            if current {
+                return Ok(renames);
+            }
+        }
+
+        let renames = self.plan(input, context).await?;
+        *self.pending.lock().await = Some(PendingRename {
+            session_id: context.session_id,
+            input: raw.clone(),
+            renames: renames.clone(),
+        });
+        Ok(renames)
+    }
+
+    fn display_path(&self, path: &Path, context: &ToolExecutionContext) -> String {
+        path.strip_prefix(&context.working_directory)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    }
+}
+
+/// Text edits of a workspace edit, grouped by file
+///
+/// Servers may send the same rename in both forms; `document_changes` is
+/// preferred, as the specification asks, and `changes` is only used without it.
+fn text_edits(edit: WorkspaceEdit) -> std::result::Result<BTreeMap<Url, Vec<TextEdit>>, String> {
+    let mut files: BTreeMap<Url, Vec<TextEdit>> = BTreeMap::new();
+    let document_edits = match edit.document_changes {
+        None => {
+            for (uri, edits) in edit.changes.unwrap_or_default() {
+                files.entry(uri).or_default().extend(edits);
+            }
+            return Ok(files);
+        }
+        Some(DocumentChanges::Edits(edits)) => edits,
+        Some(DocumentChanges::Operations(operations)) => operations
+            .into_iter()
+            .map(|operation| match operation {
+                DocumentChangeOperation::Edit(edit) => Ok(edit),
+                DocumentChangeOperation::Op(_) => Err(
+                    "The rename also creates, renames or deletes files, which is not supported"
+                        .to_string(),
+                ),
+            })
+            .collect::<std::result::Result<_, _>>()?,
+    };
+    for document in document_edits {
+        files.entry(document.text_document.uri).or_default().extend(
+            document.edits.into_iter().map(|edit| match edit {
+                OneOf::Left(edit) => edit,
+                OneOf::Right(annotated) => annotated.text_edit,
+            }),
+        );
+    }
+    Ok(files)
+}
+
+#[async_trait]
+impl Tool for RenameSymbolTool {
+    fn name(&self) -> &str {
+        "rename_symbol"
+    }
+
+    fn description(&self) -> &str {
+        "Rename a symbol (function, type, variable, ...) everywhere it is used, using the \
+         language server. Safer than search and replace: only real references are changed. \
+         Locate the symbol with 'path' plus 'line' and 'symbol' (or 'column')."
+    }
+
+    fn input_schema(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File containing the symbol"
+                },
+                "line": {
+                    "type": "integer",
+                    "description": "Line of the symbol, starting at 1",
+                    "minimum": 1
+                },
+                "column": {
+                    "type": "integer",
+                    "description": "Column of the symbol, starting at 1",
+                    "minimum": 1
+                },
+                "symbol": {
+                    "type": "string",
+                    "description": "Current name of the symbol; located on 'line', or its first occurrence in the file"
+                },
+                "new_name": {
+                    "type": "string",
+                    "description": "New name for the symbol"
+                }
+            },
+            "required": ["path", "new_name"]
+        })
+    }
+
+    fn capabilities(&self) -> Vec<ToolCapability> {
+        vec![ToolCapability::ReadFiles, ToolCapability::WriteFiles]
+    }
+
+    fn requires_approval(&self) -> bool {
+        true // Rewrites files
+    }
+
+    fn validate_input(&self, input: &Value) -> Result<()> {
+        let _: RenameInput = serde_json::from_value(input.clone())
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid input: {}", e)))?;
+        Ok(())
+    }
+
+    async fn modified_paths(&self, raw: &Value, context: &ToolExecutionContext) -> Vec<String> {
+        let Ok(input) = serde_json::from_value::<RenameInput>(raw.clone()) else {
+            return Vec::new();
+        };
+        match self.planned(raw, &input, context).await {
+            Ok(renames) => renames
+                .into_iter()
+                .map(|rename| rename.path.to_string_lossy().to_string())
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    async fn preview(
+        &self,
+        raw: &Value,
+        context: &ToolExecutionContext,
+    ) -> Result<Vec<ProposedChange>> {
+        let input: RenameInput = serde_json::from_value(raw.clone())?;
+        let renames = self
+            .planned(raw, &input, context)
+            .await
+            .map_err(ToolError::Execution)?;
+        Ok(renames
+            .into_iter()
+            .map(|rename| ProposedChange {
+                path: self.display_path(&rename.path, context),
+                before: Some(rename.before),
+                after: rename.after,
+            })
+            .collect())
+    }
+
+    async fn execute(&self, raw: Value, context: &ToolExecutionContext) -> Result<ToolResult> {
+        if context.read_only_mode {
+            return Ok(ToolResult::error(
+                "Renames are not allowed in Plan mode. \
+                 Please approve the plan and switch to execution mode (Ctrl+A) to edit files."
+                    .to_string(),
+            ));
+        }
+
+        let input: RenameInput = serde_json::from_value(raw.clone())?;
+        let planned = self.planned(&raw, &input, context).await;
+        self.pending.lock().await.take();
+        let renames = match planned {
+            Ok(renames) => renames,
+            Err(message) => return Ok(ToolResult::error(message)),
+        };
+
+        let writes: Vec<FileWrite<'_>> = renames
+            .iter()
+            .map(|rename| FileWrite {
+                path: &rename.path,
+                original: Some(&rename.before),
+                updated: Some(&rename.after),
+            })
+            .collect();
+        if let Err(e) = write_files(&writes).await {
+            return Ok(ToolResult::error(format!(
+                "Failed to write rename, all files were restored: {}",
+                e
+            )));
+        }
+
+        let mut summary = Vec::new();
+        let mut total = 0;
+        for rename in &renames {
+            total += rename.edits;
+            summary.push(format!(
+                "  {} ({} edit(s))",
+                self.display_path(&rename.path, context),
+                rename.edits
+            ));
+        }
+
+        let mut output = format!(
+            "Renamed to `{}`: {} edit(s) in {} file(s)\n{}",
+            input.new_name,
+            total,
+            renames.len(),
+            summary.join("\n")
+        );
+
+        // Keep the servers' documents current and report what they find
+        let paths: Vec<PathBuf> = renames.into_iter().map(|rename| rename.path).collect();
+        if let Some(diagnostics) = self.manager.files_edited(&paths).await {
+            output.push_str("\n\n");
+            output.push_str(&diagnostics);
+        }
+
+        Ok(ToolResult::success(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(line: Option<u32>, column: Option<u32>, symbol: Option<&str>) -> PositionInput {
+        PositionInput {
+            line,
+            column,
+            symbol: symbol.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_locate() {
+        let text = "fn main() {\n    let total = add(1, 2);\n}\n";
+        assert_eq!(
+            position(Some(2), None, Some("add")).locate(text).unwrap(),
+            Position::new(1, 16)
+        );
+        assert_eq!(
+            position(Some(2), Some(9), None).locate(text).unwrap(),
+            Position::new(1, 8)
+        );
+        // First non-blank character of the line
+        assert_eq!(
+            position(Some(2), None, None).locate(text).unwrap(),
+            Position::new(1, 4)
+        );
+        // First occurrence in the file
+        assert_eq!(
+            position(None, None, Some("total")).locate(text).unwrap(),
+            Position::new(1, 8)
+        );
+
+        assert!(position(Some(0), None, None).locate(text).is_err());
+        assert!(position(Some(9), None, None).locate(text).is_err());
+        assert!(position(Some(1), None, Some("add")).locate(text).is_err());
+        assert!(position(None, None, None).locate(text).is_err());
+    }
+
+    #[test]
+    fn test_text_edits_prefers_document_changes() {
+        let uri = Url::parse("file:///work/a.rs").unwrap();
+        let edit = TextEdit {
+            range: lsp_types::Range::new(Position::new(0, 3), Position::new(0, 6)),
+            new_text: "total".to_string(),
+        };
+        let mut changes = HashMap::new();
+        changes.insert(uri.clone(), vec![edit.clone()]);
+        let workspace_edit = WorkspaceEdit {
+            changes: Some(changes),
+            document_changes: Some(DocumentChanges::Edits(vec![lsp_types::TextDocumentEdit {
+                text_document: lsp_types::OptionalVersionedTextDocumentIdentifier {
+                    uri: uri.clone(),
+                    version: None,
+                },
+                edits: vec![OneOf::Left(edit)],
+            }])),
+            change_annotations: None,
+        };
+
+        let files = text_edits(workspace_edit).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[&uri].len(), 1);
+        assert_eq!(apply_edits("fn add() {}", &files[&uri]), "fn total() {}");
+    }
+}
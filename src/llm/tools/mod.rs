@@ -4,10 +4,14 @@
 //! including file operations, shell commands, and more.
 
 pub mod error;
+pub mod output;
+pub mod patch;
+pub mod process;
 pub mod registry;
 mod r#trait;
 
 // Tool implementations - Phase 1: Essential File Operations
+pub mod apply_patch;
 pub mod bash;
 pub mod edit;
 pub mod glob;
@@ -24,11 +28,19 @@ pub mod web_search;
 
 // Tool implementations - Phase 3: Workflow & Integration
 pub mod context;
+pub mod git;
 pub mod http;
+pub mod lsp;
+pub mod outline;
 pub mod plan_tool;
+pub mod read_output;
 pub mod task;
 
 // Re-exports
 pub use error::{Result, ToolError};
-pub use r#trait::{Tool, ToolCapability, ToolExecutionContext, ToolResult};
+pub use output::OutputPolicy;
+pub use r#trait::{
+    ProposedChange, Tool, ToolCapability, ToolExecutionContext, ToolProgress, ToolProgressEvent,
+    ToolProgressSender, ToolResult,
+};
 pub use registry::ToolRegistry;
@@ -3,7 +3,7 @@
 //! Modify Jupyter notebook files (.ipynb) cell by cell.
 
 use super::error::{Result, ToolError};
-use super::r#trait::{Tool, ToolCapability, ToolExecutionContext, ToolResult};
+use super::r#trait::{ProposedChange, Tool, ToolCapability, ToolExecutionContext, ToolResult};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -152,6 +152,46 @@ impl Tool for NotebookEditTool {
         true // Modifying notebooks requires approval
     }
 
+    async fn modified_paths(&self, input: &Value, _context: &ToolExecutionContext) -> Vec<String> {
+        input
+            .get("path")
+            .and_then(Value::as_str)
+            .map(|p| vec![p.to_string()])
+            .unwrap_or_default()
+    }
+
+    async fn preview(
+        &self,
+        input: &Value,
+        context: &ToolExecutionContext,
+    ) -> Result<Vec<ProposedChange>> {
+        let input: NotebookInput = serde_json::from_value(input.clone())?;
+        let path = if PathBuf::from(&input.path).is_absolute() {
+            PathBuf::from(&input.path)
+        } else {
+            context.working_directory.join(&input.path)
+        };
+        if !path.exists() {
+            return Err(ToolError::FileNotFound(path.display().to_string()));
+        }
+
+        let content = fs::read_to_string(&path).await.map_err(ToolError::Io)?;
+        let mut notebook: Notebook = serde_json::from_str(&content)
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid notebook format: {}", e)))?;
+
+        // Compare re-serialized notebooks so formatting differences in the
+        // original file don't drown out the actual cell changes
+        let before = serde_json::to_string_pretty(&notebook)?;
+        apply_operation(&mut notebook, input.operation).map_err(ToolError::InvalidInput)?;
+        let after = serde_json::to_string_pretty(&notebook)?;
+
+        Ok(vec![ProposedChange {
+            path: input.path,
+            before: Some(before),
+            after,
+        }])
+    }
+
     fn validate_input(&self, input: &Value) -> Result<()> {
         let _: NotebookInput = serde_json::from_value(input.clone())
             .map_err(|e| ToolError::InvalidInput(format!("Invalid input: {}", e)))?;
@@ -205,119 +245,133 @@ impl Tool for NotebookEditTool {
         }
 
         // Perform operation
-        let result_message = match input.operation {
-            NotebookOperation::AddCell {
-                cell_type,
-                source,
-                position,
-            } => {
-                // Validate cell type
-                if !["code", "markdown", "raw"].contains(&cell_type.as_str()) {
-                    return Ok(ToolResult::error(format!(
-                        "Invalid cell type: {}. Must be 'code', 'markdown', or 'raw'",
-                        cell_type
-                    )));
-                }
+        let result_message = match apply_operation(&mut notebook, input.operation) {
+            Ok(message) => message,
+            Err(msg) => return Ok(ToolResult::error(msg)),
+        };
 
-                let new_cell = Cell {
-                    cell_type: cell_type.clone(),
-                    source,
-                    metadata: serde_json::json!({}),
-                    outputs: if cell_type == "code" {
-                        Some(vec![])
-                    } else {
-                        None
-                    },
-                    execution_count: if cell_type == "code" {
-                        Some(Value::Null)
-                    } else {
-                        None
-                    },
-                };
-
-                if let Some(pos) = position {
-                    if pos > notebook.cells.len() {
-                        return Ok(ToolResult::error(format!(
-                            "Position {} out of bounds (notebook has {} cells)",
-                            pos,
-                            notebook.cells.len()
-                        )));
-                    }
-                    notebook.cells.insert(pos, new_cell);
-                    format!("Added {} cell at position {}", cell_type, pos)
-                } else {
-                    notebook.cells.push(new_cell);
-                    format!(
-                        "Added {} cell at end (position {})",
-                        cell_type,
-                        notebook.cells.len() - 1
-                    )
-                }
-            }
+        // Write modified notebook
+        let new_content = serde_json::to_string_pretty(&notebook)
+            .map_err(|e| ToolError::Execution(format!("Failed to serialize notebook: {}", e)))?;
 
-            NotebookOperation::EditCell { index, source } => {
-                if index >= notebook.cells.len() {
-                    return Ok(ToolResult::error(format!(
-                        "Cell index {} out of bounds (notebook has {} cells)",
-                        index,
-                        notebook.cells.len()
-                    )));
-                }
+        fs::write(&path, new_content).await.map_err(ToolError::Io)?;
 
-                notebook.cells[index].source = source;
-                // Clear outputs when editing code cells
-                if notebook.cells[index].cell_type == "code" {
-                    notebook.cells[index].outputs = Some(vec![]);
-                    notebook.cells[index].execution_count = Some(Value::Null);
-                }
+        Ok(ToolResult::success(format!(
+            "{}. Notebook saved: {}",
+            result_message,
+            path.display()
+        )))
+    }
+}
 
-                format!(
-                    "Edited cell {} ({})",
-                    index, notebook.cells[index].cell_type
-                )
+/// Apply an operation to a parsed notebook
+///
+/// Returns a summary of the change, or a user-facing error message.
+fn apply_operation(
+    notebook: &mut Notebook,
+    operation: NotebookOperation,
+) -> std::result::Result<String, String> {
+    let message = match operation {
+        NotebookOperation::AddCell {
+            cell_type,
+            source,
+            position,
+        } => {
+            // Validate cell type
+            if !["code", "markdown", "raw"].contains(&cell_type.as_str()) {
+                return Err(format!(
+                    "Invalid cell type: {}. Must be 'code', 'markdown', or 'raw'",
+                    cell_type
+                ));
             }
 
-            NotebookOperation::DeleteCell { index } => {
-                if index >= notebook.cells.len() {
-                    return Ok(ToolResult::error(format!(
-                        "Cell index {} out of bounds (notebook has {} cells)",
-                        index,
+            let new_cell = Cell {
+                cell_type: cell_type.clone(),
+                source,
+                metadata: serde_json::json!({}),
+                outputs: if cell_type == "code" {
+                    Some(vec![])
+                } else {
+                    None
+                },
+                execution_count: if cell_type == "code" {
+                    Some(Value::Null)
+                } else {
+                    None
+                },
+            };
+
+            if let Some(pos) = position {
+                if pos > notebook.cells.len() {
+                    return Err(format!(
+                        "Position {} out of bounds (notebook has {} cells)",
+                        pos,
                         notebook.cells.len()
-                    )));
+                    ));
                 }
-
-                let removed_cell = notebook.cells.remove(index);
+                notebook.cells.insert(pos, new_cell);
+                format!("Added {} cell at position {}", cell_type, pos)
+            } else {
+                notebook.cells.push(new_cell);
                 format!(
-                    "Deleted cell {} ({}, {} cells remaining)",
+                    "Added {} cell at end (position {})",
+                    cell_type,
+                    notebook.cells.len() - 1
+                )
+            }
+        }
+
+        NotebookOperation::EditCell { index, source } => {
+            if index >= notebook.cells.len() {
+                return Err(format!(
+                    "Cell index {} out of bounds (notebook has {} cells)",
                     index,
-                    removed_cell.cell_type,
                     notebook.cells.len()
-                )
+                ));
             }
 
-            NotebookOperation::ClearOutputs => {
-                let mut cleared_count = 0;
-                for cell in &mut notebook.cells {
-                    if cell.cell_type == "code" {
-                        cell.outputs = Some(vec![]);
-                        cell.execution_count = Some(Value::Null);
-                        cleared_count += 1;
-                    }
-                }
-                format!("Cleared outputs from {} code cells", cleared_count)
+            notebook.cells[index].source = source;
+            // Clear outputs when editing code cells
+            if notebook.cells[index].cell_type == "code" {
+                notebook.cells[index].outputs = Some(vec![]);
+                notebook.cells[index].execution_count = Some(Value::Null);
             }
-        };
 
-        // Write modified notebook
-        let new_content = serde_json::to_string_pretty(&notebook)
-            .map_err(|e| ToolError::Execution(format!("Failed to serialize notebook: {}", e)))?;
+            format!(
+                "Edited cell {} ({})",
+                index, notebook.cells[index].cell_type
+            )
+        }
 
-        fs::write(&path, new_content).await.map_err(ToolError::Io)?;
+        NotebookOperation::DeleteCell { index } => {
+            if index >= notebook.cells.len() {
+                return Err(format!(
+                    "Cell index {} out of bounds (notebook has {} cells)",
+                    index,
+                    notebook.cells.len()
+                ));
+            }
 
-        Ok(ToolResult::success(format!(
-            "{}. Notebook saved: {}",
-            result_message,
-            path.display()
-        )))
-    }
+            let removed_cell = notebook.cells.remove(index);
+            format!(
+                "Deleted cell {} ({}, {} cells remaining)",
+                index,
+                removed_cell.cell_type,
+                notebook.cells.len()
+            )
+        }
+
+        NotebookOperation::ClearOutputs => {
+            let mut cleared_count = 0;
+            for cell in &mut notebook.cells {
+                if cell.cell_type == "code" {
+                    cell.outputs = Some(vec![]);
+                    cell.execution_count = Some(Value::Null);
+                    cleared_count += 1;
+                }
+            }
+            format!("Cleared outputs from {} code cells", cleared_count)
+        }
+    };
+    Ok(message)
 }
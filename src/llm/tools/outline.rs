@@ -0,0 +1,354 @@
+//! Code Outline Tool
+//!
+//! Lists the symbols of source files (functions, types, impl blocks,
+//! classes, methods) with their signatures and line ranges, and reads the
+//! source of a single symbol. Much cheaper than reading whole files to find
+//! one function.
+
+use super::error::{validate_directory_path, validate_file_path, Result, ToolError};
+use super::r#trait::{Tool, ToolCapability, ToolExecutionContext, ToolResult};
+use crate::outline::{self, Language, Symbol};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Maximum number of files outlined in a directory
+const MAX_FILES: usize = 300;
+
+/// Files larger than this are skipped
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Output is cut off past this many characters
+const MAX_OUTPUT_CHARS: usize = 40_000;
+
+/// Maximum number of symbols returned by `read`
+const MAX_READ_MATCHES: usize = 5;
+
+/// Code outline tool
+pub struct CodeOutlineTool;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct OutlineInput {
+    /// File or directory to outline (defaults to the working directory)
+    #[serde(default)]
+    path: Option<String>,
+
+    /// Symbol whose source to return instead of the outline
+    #[serde(default)]
+    read: Option<String>,
+}
+
+/// A parsed source file
+struct SourceFile {
+    display: String,
+    source: String,
+    symbols: Vec<Symbol>,
+}
+
+impl SourceFile {
+    fn parse(path: &Path, base: &Path) -> Option<Self> {
+        let language = Language::from_path(path)?;
+        if std::fs::metadata(path).ok()?.len() > MAX_FILE_SIZE {
+            return None;
+        }
+        let source = std::fs::read_to_string(path).ok()?;
+        let symbols = outline::outline(&source, language);
+        Some(Self {
+            display: path
+                .strip_prefix(base)
+                .unwrap_or(path)
+                .display()
+                .to_string(),
+            source,
+            symbols,
+        })
+    }
+
+    fn outline(&self, output: &mut String) {
+        output.push_str(&format!(
+            "{} ({} lines)\n",
+            self.display,
+            self.source.lines().count()
+        ));
+        push_symbols(&self.symbols, 1, output);
+    }
+
+    fn lines(&self, symbol: &Symbol) -> String {
+        self.source
+            .lines()
+            .skip(symbol.start_line - 1)
+            .take(symbol.end_line + 1 - symbol.start_line)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn push_symbols(symbols: &[Symbol], depth: usize, output: &mut String) {
+    for symbol in symbols {
+        output.push_str(&format!(
+            "{}L{}-{}  {}\n",
+            "  ".repeat(depth),
+            symbol.start_line,
+            symbol.end_line,
+            symbol.signature
+        ));
+        push_symbols(&symbol.children, depth + 1, output);
+    }
+}
+
+/// Supported source files under `dir`, honoring .gitignore
+///
+/// Returns the files and whether the listing was cut at `MAX_FILES`.
+fn source_files(dir: &Path) -> (Vec<PathBuf>, bool) {
+    let mut files = Vec::new();
+    let mut truncated = false;
+    for entry in ignore::WalkBuilder::new(dir)
+        .require_git(false)
+        .build()
+        .flatten()
+    {
+        let path = entry.into_path();
+        if path.is_file() && Language::from_path(&path).is_some() {
+            if files.len() == MAX_FILES {
+                truncated = true;
+                break;
+            }
+            files.push(path);
+        }
+    }
+    files.sort();
+    (files, truncated)
+}
+
+fn outline_files(files: &[PathBuf], base: &Path, truncated: bool) -> String {
+    let mut output = String::new();
+    for (index, path) in files.iter().enumerate() {
+        let Some(file) = SourceFile::parse(path, base) else {
+            continue;
+        };
+        if output.len() > MAX_OUTPUT_CHARS {
+            output.push_str(&format!(
+                "\n... {} more file(s) not shown; outline a subdirectory or a single file\n",
+                files.len() - index
+            ));
+            return output;
+        }
+        file.outline(&mut output);
+    }
+    if truncated {
+        output.push_str(&format!(
+            "\n... stopped after {} files; outline a subdirectory for the rest\n",
+            MAX_FILES
+        ));
+    }
+    output
+}
+
+fn read_symbol(files: &[PathBuf], base: &Path, name: &str) -> Option<String> {
+    let mut matches = Vec::new();
+    let mut total = 0;
+    for path in files {
+        let Some(file) = SourceFile::parse(path, base) else {
+            continue;
+        };
+        for symbol in Symbol::find(&file.symbols, name) {
+            total += 1;
+            if matches.len() < MAX_READ_MATCHES {
+                matches.push(format!(
+                    "{}:{}-{}\n{}",
+                    file.display,
+                    symbol.start_line,
+                    symbol.end_line,
+                    file.lines(symbol)
+                ));
+            }
+        }
+    }
+    if matches.is_empty() {
+        return None;
+    }
+
+    let mut output = matches.join("\n\n");
+    if total > MAX_READ_MATCHES {
+        output.push_str(&format!(
+            "\n\n... {} more match(es); qualify the name (e.g. `Type::method`) or give a file",
+            total - MAX_READ_MATCHES
+        ));
+    }
+    Some(output)
+}
+
+#[async_trait]
+impl Tool for CodeOutlineTool {
+    fn name(&self) -> &str {
+        "code_outline"
+    }
+
+    fn description(&self) -> &str {
+        "Outline source code: lists the functions, types, impl blocks, classes and methods of a \
+         file or directory with their signatures and line ranges. With 'read', returns the full \
+         source of one symbol (e.g. 'parse', 'Config::load', 'Server.Start') instead. Use this \
+         before reading large files: it costs far fewer tokens. Supports Rust, Python, \
+         TypeScript/JavaScript and Go."
+    }
+
+    fn input_schema(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File or directory to outline (defaults to the working directory)"
+                },
+                "read": {
+                    "type": "string",
+                    "description": "Name of a symbol to return the source of, optionally qualified with its type or module ('Config::load')"
+                }
+            }
+        })
+    }
+
+    fn capabilities(&self) -> Vec<ToolCapability> {
+        vec![ToolCapability::ReadFiles]
+    }
+
+    fn requires_approval(&self) -> bool {
+        false // Read-only
+    }
+
+    fn validate_input(&self, input: &Value) -> Result<()> {
+        let _: OutlineInput = serde_json::from_value(input.clone())
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid input: {}", e)))?;
+        Ok(())
+    }
+
+    async fn execute(&self, input: Value, context: &ToolExecutionContext) -> Result<ToolResult> {
+        let input: OutlineInput = serde_json::from_value(input)?;
+        let requested = input.path.as_deref().unwrap_or(".");
+        let base = context.working_directory.clone();
+
+        let is_dir = base.join(requested).is_dir();
+        let validated = if is_dir {
+            validate_directory_path(requested, &base)
+        } else {
+            validate_file_path(requested, &base)
+        };
+        let path = match validated {
+            Ok(path) => path,
+            Err(message) => return Ok(ToolResult::error(message)),
+        };
+        if !is_dir && Language::from_path(&path).is_none() {
+            return Ok(ToolResult::error(format!(
+                "No outline support for {}. Supported languages: {}.",
+                path.display(),
+                outline::SUPPORTED
+            )));
+        }
+
+        let read = input.read.filter(|name| !name.trim().is_empty());
+        let output = tokio::task::spawn_blocking(move || {
+            let (files, truncated) = if is_dir {
+                source_files(&path)
+            } else {
+                (vec![path], false)
+            };
+            match read {
+                Some(name) => read_symbol(&files, &base, &name).ok_or_else(|| {
+                    format!(
+                        "No symbol named `{}` found. Call code_outline without 'read' to list the symbols.",
+                        name
+                    )
+                }),
+                None if files.is_empty() => Ok(format!(
+                    "No source files found. Supported languages: {}.",
+                    outline::SUPPORTED
+                )),
+                None => Ok(outline_files(&files, &base, truncated)),
+            }
+        })
+        .await
+        .map_err(|e| ToolError::Execution(format!("Outline task failed: {}", e)))?;
+
+        Ok(match output {
+            Ok(output) => ToolResult::success(output.trim_end().to_string()),
+            Err(message) => ToolResult::error(message),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    fn project() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::create_dir_all(dir.path().join("target")).unwrap();
+        std::fs::write(
+            dir.path().join("src/lib.rs"),
+            "pub struct Config;\n\nimpl Config {\n    pub fn load() -> Self {\n        Config\n    }\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("app.py"),
+            "def load(path):\n    return open(path)\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("README.md"), "# Demo\n").unwrap();
+        // Ignored by .gitignore
+        std::fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+        std::fs::write(dir.path().join("target/gen.rs"), "fn generated() {}\n").unwrap();
+        dir
+    }
+
+    async fn run(dir: &TempDir, input: Value) -> ToolResult {
+        let context = ToolExecutionContext::new(Uuid::new_v4())
+            .with_working_directory(dir.path().to_path_buf());
+        CodeOutlineTool.execute(input, &context).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_outline_directory() {
+        let dir = project();
+        let result = run(&dir, serde_json::json!({})).await;
+        assert!(result.success);
+        assert_eq!(
+            result.output,
+            "app.py (2 lines)\n  L1-2  def load(path)\n\
+             src/lib.rs (7 lines)\n  L1-1  pub struct Config\n  L3-7  impl Config\n    L4-6  pub fn load() -> Self"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_symbol() {
+        let dir = project();
+        let result = run(
+            &dir,
+            serde_json::json!({"path": "src/lib.rs", "read": "Config::load"}),
+        )
+        .await;
+        assert_eq!(
+            result.output,
+            "src/lib.rs:4-6\n    pub fn load() -> Self {\n        Config\n    }"
+        );
+
+        // Across the directory
+        let result = run(&dir, serde_json::json!({"read": "load"})).await;
+        assert!(result.output.starts_with("app.py:1-2\n"));
+        assert!(result.output.contains("src/lib.rs:4-6\n"));
+
+        let result = run(&dir, serde_json::json!({"read": "missing"})).await;
+        assert!(!result.success);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_file() {
+        let dir = project();
+        let result = run(&dir, serde_json::json!({"path": "README.md"})).await;
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("No outline support"));
+    }
+}
@@ -0,0 +1,358 @@
+//! Tool Output Policy
+//!
+//! Keeps tool results within a size budget before they reach the model.
+//! Oversized output is truncated with its head and tail preserved, and the
+//! full text is spilled to a per-session artifact store in the user cache
+//! directory, so the model can page through it with the `read_output` tool.
+//! A session's artifacts are removed with the session.
+
+use super::error::{Result, ToolError};
+use super::r#trait::{ToolExecutionContext, ToolResult};
+use std::path::PathBuf;
+use tokio::fs;
+use uuid::Uuid;
+
+/// Default maximum number of characters returned to the model
+pub const DEFAULT_MAX_OUTPUT_CHARS: usize = 30_000;
+
+/// Default maximum number of lines returned to the model
+pub const DEFAULT_MAX_OUTPUT_LINES: usize = 1_000;
+
+/// Share of the budget given to the head of the output (the rest goes to the tail)
+const HEAD_SHARE_PERCENT: usize = 60;
+
+/// Size limits applied to every tool result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputPolicy {
+    /// Maximum characters kept in a result
+    pub max_chars: usize,
+    /// Maximum lines kept in a result
+    pub max_lines: usize,
+}
+
+impl Default for OutputPolicy {
+    fn default() -> Self {
+        Self {
+            max_chars: DEFAULT_MAX_OUTPUT_CHARS,
+            max_lines: DEFAULT_MAX_OUTPUT_LINES,
+        }
+    }
+}
+
+impl OutputPolicy {
+    /// Create a policy with explicit limits
+    pub fn new(max_chars: usize, max_lines: usize) -> Self {
+        Self {
+            max_chars: max_chars.max(1),
+            max_lines: max_lines.max(1),
+        }
+    }
+
+    /// Check whether text exceeds the policy
+    pub fn exceeds(&self, text: &str) -> bool {
+        text.chars().count() > self.max_chars || text.lines().count() > self.max_lines
+    }
+
+    /// Truncate text, keeping its head and tail within the budget
+    ///
+    /// `artifact_id` is mentioned in the omission marker so the model knows
+    /// where to find the full output.
+    pub fn truncate(&self, text: &str, artifact_id: Option<&str>) -> String {
+        let lines: Vec<&str> = text.lines().collect();
+        let total_lines = lines.len();
+        let total_chars = text.chars().count();
+
+        let head_lines_budget = (self.max_lines * HEAD_SHARE_PERCENT / 100).max(1);
+        let tail_lines_budget = self.max_lines.saturating_sub(head_lines_budget);
+        let head_chars_budget = (self.max_chars * HEAD_SHARE_PERCENT / 100).max(1);
+        let tail_chars_budget = self.max_chars.saturating_sub(head_chars_budget);
+
+        let head = take_lines(lines.iter().copied(), head_lines_budget, head_chars_budget);
+        let remaining = &lines[head.len().min(total_lines)..];
+        let mut tail = take_lines(
+            remaining.iter().rev().copied(),
+            tail_lines_budget,
+            tail_chars_budget,
+        );
+        tail.reverse();
+
+        let kept_lines = head.len() + tail.len();
+        let kept_chars: usize = head
+            .iter()
+            .chain(tail.iter())
+            .map(|l| l.chars().count() + 1)
+            .sum();
+        let omitted_lines = total_lines.saturating_sub(kept_lines);
+        let omitted_chars = total_chars.saturating_sub(kept_chars);
+
+        let where_to_find = match artifact_id {
+            Some(id) => format!(
+                "Full output ({} lines) saved as artifact '{}'. \
+                 Use the read_output tool with artifact_id=\"{}\" to page through it.",
+                total_lines, id, id
+            ),
+            None => "Full output could not be saved.".to_string(),
+        };
+
+        let mut result = head.join("\n");
+        result.push_str(&format!(
+            "\n\n... [{} lines ({} chars) omitted. {}] ...\n\n",
+            omitted_lines, omitted_chars, where_to_find
+        ));
+        result.push_str(&tail.join("\n"));
+        result
+    }
+
+    /// Apply the policy to a tool result, spilling oversized text to the artifact store
+    pub async fn apply(
+        &self,
+        tool_name: &str,
+        mut result: ToolResult,
+        context: &ToolExecutionContext,
+    ) -> ToolResult {
+        if self.exceeds(&result.output) {
+            let output = std::mem::take(&mut result.output);
+            let (truncated, artifact_id) = self.spill(tool_name, &output, context).await;
+            result.output = truncated;
+            if let Some(id) = artifact_id {
+                result.metadata.insert("artifact_id".to_string(), id);
+            }
+            result
+                .metadata
+                .insert("truncated".to_string(), "true".to_string());
+        }
+
+        if let Some(error) = result.error.take() {
+            if self.exceeds(&error) {
+                let (truncated, artifact_id) = self.spill(tool_name, &error, context).await;
+                result.error = Some(truncated);
+                if let Some(id) = artifact_id {
+                    result.metadata.insert("error_artifact_id".to_string(), id);
+                }
+                result
+                    .metadata
+                    .insert("truncated".to_string(), "true".to_string());
+            } else {
+                result.error = Some(error);
+            }
+        }
+
+        result
+    }
+
+    async fn spill(
+        &self,
+        tool_name: &str,
+        text: &str,
+        context: &ToolExecutionContext,
+    ) -> (String, Option<String>) {
+        let artifact_id = match store_artifact(context, tool_name, text).await {
+            Ok(id) => Some(id),
+            Err(e) => {
+                tracing::warn!("Failed to store output of '{}': {}", tool_name, e);
+                None
+            }
+        };
+
+        tracing::info!(
+            "Truncated output of '{}' ({} chars) to fit the output policy",
+            tool_name,
+            text.len()
+        );
+
+        (self.truncate(text, artifact_id.as_deref()), artifact_id)
+    }
+}
+
+/// Take lines from an iterator until either budget is exhausted
+///
+/// A single line longer than the character budget is cut at a char boundary.
+fn take_lines<'a>(
+    lines: impl Iterator<Item = &'a str>,
+    max_lines: usize,
+    max_chars: usize,
+) -> Vec<&'a str> {
+    let mut taken = Vec::new();
+    let mut chars = 0;
+
+    for line in lines {
+        if taken.len() >= max_lines {
+            break;
+        }
+        let len = line.chars().count() + 1;
+        if chars + len > max_chars {
+            if taken.is_empty() {
+                let cut = line
+                    .char_indices()
+                    .nth(max_chars)
+                    .map(|(i, _)| i)
+                    .unwrap_or(line.len());
+                taken.push(&line[..cut]);
+            }
+            break;
+        }
+        chars += len;
+        taken.push(line);
+    }
+
+    taken
+}
+
+/// Directory holding the stored outputs of all sessions
+///
+/// Kept in the user cache directory (`~/.cache/crustly/artifacts` on Linux)
+/// rather than the project, so runs leave nothing behind in the repository.
+pub fn artifacts_root() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("crustly")
+        .join("artifacts")
+}
+
+/// Directory holding stored outputs for a session
+pub fn artifact_dir(session_id: Uuid) -> PathBuf {
+    artifacts_root().join(session_id.to_string())
+}
+
+/// Delete the stored outputs of a session, if it has any
+pub async fn remove_artifacts(session_id: Uuid) -> std::io::Result<()> {
+    match fs::remove_dir_all(artifact_dir(session_id)).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Check that an artifact ID cannot escape the artifact directory
+fn validate_artifact_id(id: &str) -> Result<()> {
+    if id.is_empty()
+        || id.len() > 128
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(ToolError::InvalidInput(format!(
+            "Invalid artifact ID: '{}'",
+            id
+        )));
+    }
+    Ok(())
+}
+
+/// Store full tool output and return its artifact ID
+pub async fn store_artifact(
+    context: &ToolExecutionContext,
+    tool_name: &str,
+    content: &str,
+) -> Result<String> {
+    let prefix: String = tool_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(32)
+        .collect();
+    let id = format!("{}-{}", prefix, &Uuid::new_v4().simple().to_string()[..12]);
+
+    let dir = artifact_dir(context.session_id);
+    fs::create_dir_all(&dir).await.map_err(ToolError::Io)?;
+    fs::write(dir.join(format!("{}.txt", id)), content)
+        .await
+        .map_err(ToolError::Io)?;
+
+    tracing::debug!("Stored tool output artifact {}", id);
+    Ok(id)
+}
+
+/// Load a previously stored artifact
+pub async fn load_artifact(context: &ToolExecutionContext, id: &str) -> Result<String> {
+    validate_artifact_id(id)?;
+
+    let path = artifact_dir(context.session_id).join(format!("{}.txt", id));
+    if !path.exists() {
+        return Err(ToolError::FileNotFound(format!(
+            "No stored output with artifact ID '{}' in this session",
+            id
+        )));
+    }
+
+    fs::read_to_string(&path).await.map_err(ToolError::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn numbered_lines(count: usize) -> String {
+        (0..count)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_small_output_is_not_truncated() {
+        let policy = OutputPolicy::default();
+        assert!(!policy.exceeds("hello\nworld"));
+    }
+
+    #[test]
+    fn test_truncate_keeps_head_and_tail() {
+        let policy = OutputPolicy::new(100_000, 10);
+        let text = numbered_lines(100);
+
+        assert!(policy.exceeds(&text));
+        let truncated = policy.truncate(&text, Some("bash-abc"));
+
+        assert!(truncated.starts_with("line 0\nline 1"));
+        assert!(truncated.ends_with("line 99"));
+        assert!(truncated.contains("90 lines"));
+        assert!(truncated.contains("artifact_id=\"bash-abc\""));
+        assert!(!truncated.contains("line 50\n"));
+    }
+
+    #[test]
+    fn test_truncate_single_huge_line() {
+        let policy = OutputPolicy::new(100, 10);
+        let text = "x".repeat(10_000);
+
+        let truncated = policy.truncate(&text, None);
+        assert!(truncated.len() < 400);
+        assert!(truncated.contains("could not be saved"));
+    }
+
+    #[tokio::test]
+    async fn test_apply_spills_to_artifact_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let context = ToolExecutionContext::new(Uuid::new_v4())
+            .with_working_directory(temp_dir.path().to_path_buf());
+        let policy = OutputPolicy::new(100_000, 10);
+        let text = numbered_lines(50);
+
+        let result = policy
+            .apply("bash", ToolResult::success(text.clone()), &context)
+            .await;
+
+        assert_eq!(result.metadata.get("truncated"), Some(&"true".to_string()));
+        let id = result.metadata.get("artifact_id").unwrap();
+        assert!(id.starts_with("bash-"));
+        assert_eq!(load_artifact(&context, id).await.unwrap(), text);
+        // Nothing is written to the project
+        assert!(!temp_dir.path().join(".crustly").exists());
+
+        remove_artifacts(context.session_id).await.unwrap();
+        assert!(!artifact_dir(context.session_id).exists());
+        assert!(load_artifact(&context, id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_artifact_rejects_traversal() {
+        let context = ToolExecutionContext::new(Uuid::new_v4());
+        let result = load_artifact(&context, "../../etc/passwd").await;
+        assert!(matches!(result, Err(ToolError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,601 @@
+//! Unified Diff Parsing and Application
+//!
+//! Parses unified diffs into per-file hunks and applies them to in-memory
+//! file contents. Hunks that no longer line up exactly are located by
+//! searching nearby lines (offset), ignoring whitespace, and finally by
+//! dropping outer context lines (fuzz), like `patch(1)`.
+
+use similar::TextDiff;
+
+/// Default number of context lines that may be ignored when placing a hunk
+pub const DEFAULT_MAX_FUZZ: usize = 2;
+
+/// A single line of a hunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkLine {
+    /// Unchanged line that must be present
+    Context(String),
+    /// Line that is removed
+    Remove(String),
+    /// Line that is added
+    Add(String),
+}
+
+/// A hunk of a unified diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// 1-based start line in the original file
+    pub old_start: usize,
+    /// 1-based start line in the new file
+    pub new_start: usize,
+    /// Lines of the hunk in order
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    /// Lines the hunk expects to find in the original file
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Remove(s) => Some(s.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    /// Lines the hunk produces in the new file
+    fn new_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Add(s) => Some(s.as_str()),
+                HunkLine::Remove(_) => None,
+            })
+            .collect()
+    }
+
+    /// Number of leading and trailing context lines
+    fn context_bounds(&self) -> (usize, usize) {
+        let leading = self
+            .lines
+            .iter()
+            .take_while(|l| matches!(l, HunkLine::Context(_)))
+            .count();
+        let trailing = self
+            .lines
+            .iter()
+            .rev()
+            .take_while(|l| matches!(l, HunkLine::Context(_)))
+            .count();
+        (leading, trailing)
+    }
+}
+
+/// All hunks for one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePatch {
+    /// Path before the change (`None` for created files)
+    pub old_path: Option<String>,
+    /// Path after the change (`None` for deleted files)
+    pub new_path: Option<String>,
+    /// Hunks in file order
+    pub hunks: Vec<Hunk>,
+}
+
+impl FilePatch {
+    /// Path the patch applies to
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Whether the patch creates a new file
+    pub fn is_create(&self) -> bool {
+        self.old_path.is_none()
+    }
+
+    /// Whether the patch deletes the file
+    pub fn is_delete(&self) -> bool {
+        self.new_path.is_none()
+    }
+}
+
+/// How a hunk was placed in the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkOutcome {
+    /// Applied; `offset` is the distance from the line in the hunk header
+    Applied {
+        line: usize,
+        offset: isize,
+        fuzz: usize,
+        whitespace: bool,
+    },
+    /// Context could not be found
+    Conflict { reason: String },
+}
+
+impl HunkOutcome {
+    /// Whether the hunk could be applied
+    pub fn is_applied(&self) -> bool {
+        matches!(self, HunkOutcome::Applied { .. })
+    }
+}
+
+impl std::fmt::Display for HunkOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HunkOutcome::Applied {
+                line,
+                offset,
+                fuzz,
+                whitespace,
+            } => {
+                write!(f, "applied at line {}", line)?;
+                if *offset != 0 {
+                    write!(f, " (offset {:+})", offset)?;
+                }
+                if *fuzz > 0 {
+                    write!(f, " (fuzz {})", fuzz)?;
+                }
+                if *whitespace {
+                    write!(f, " (ignoring whitespace)")?;
+                }
+                Ok(())
+            }
+            HunkOutcome::Conflict { reason } => write!(f, "CONFLICT: {}", reason),
+        }
+    }
+}
+
+/// Parse a unified diff into per-file patches
+pub fn parse_unified_diff(diff: &str) -> Result<Vec<FilePatch>, String> {
+    let mut patches: Vec<FilePatch> = Vec::new();
+    let mut lines = diff.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let Some(old) = line.strip_prefix("--- ") else {
+            // Skip `diff --git`, `index` and other preamble lines
+            continue;
+        };
+        let new_line = lines
+            .next()
+            .ok_or_else(|| format!("Missing '+++' header after '{}'", line))?;
+        let new = new_line
+            .strip_prefix("+++ ")
+            .ok_or_else(|| format!("Expected '+++' header, found '{}'", new_line))?;
+
+        let mut patch = FilePatch {
+            old_path: parse_header_path(old),
+            new_path: parse_header_path(new),
+            hunks: Vec::new(),
+        };
+        if patch.old_path.is_none() && patch.new_path.is_none() {
+            return Err("Patch header has no file path".to_string());
+        }
+
+        while let Some(next) = lines.peek() {
+            if !next.starts_with("@@") {
+                break;
+            }
+            let header = lines.next().unwrap_or_default();
+            let (old_start, old_count, new_start, new_count) = parse_hunk_header(header)?;
+
+            let mut hunk = Hunk {
+                old_start,
+                new_start,
+                lines: Vec::new(),
+            };
+            let (mut old_seen, mut new_seen) = (0, 0);
+            while old_seen < old_count || new_seen < new_count {
+                let Some(body) = lines.next() else {
+                    return Err(format!("Hunk '{}' ends early", header));
+                };
+                if let Some(rest) = body.strip_prefix('+') {
+                    hunk.lines.push(HunkLine::Add(rest.to_string()));
+                    new_seen += 1;
+                } else if let Some(rest) = body.strip_prefix('-') {
+                    hunk.lines.push(HunkLine::Remove(rest.to_string()));
+                    old_seen += 1;
+                } else if body.starts_with('\\') {
+                    // "\ No newline at end of file"
+                } else {
+                    // Some tools strip the leading space from blank context lines
+                    let rest = body.strip_prefix(' ').unwrap_or(body);
+                    hunk.lines.push(HunkLine::Context(rest.to_string()));
+                    old_seen += 1;
+                    new_seen += 1;
+                }
+            }
+            while lines.peek().is_some_and(|l| l.starts_with('\\')) {
+                lines.next();
+            }
+            patch.hunks.push(hunk);
+        }
+
+        if patch.hunks.is_empty() {
+            return Err(format!("No hunks for '{}'", patch.path()));
+        }
+        patches.push(patch);
+    }
+
+    if patches.is_empty() {
+        return Err("No file headers ('--- a/path' / '+++ b/path') found in patch".to_string());
+    }
+    Ok(patches)
+}
+
+/// Extract the path from a `---`/`+++` header, stripping `a/`/`b/` prefixes
+fn parse_header_path(header: &str) -> Option<String> {
+    let path = header.split('\t').next().unwrap_or_default().trim();
+    if path == "/dev/null" || path.is_empty() {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// Parse `@@ -l,s +l,s @@` into (old_start, old_count, new_start, new_count)
+fn parse_hunk_header(header: &str) -> Result<(usize, usize, usize, usize), String> {
+    let invalid = || format!("Invalid hunk header: '{}'", header);
+    let ranges = header
+        .strip_prefix("@@")
+        .and_then(|rest| rest.split("@@").next())
+        .ok_or_else(invalid)?;
+
+    let mut parts = ranges.split_whitespace();
+    let old = parts
+        .next()
+        .and_then(|p| p.strip_prefix('-'))
+        .ok_or_else(invalid)?;
+    let new = parts
+        .next()
+        .and_then(|p| p.strip_prefix('+'))
+        .ok_or_else(invalid)?;
+
+    let parse_range = |range: &str| -> Result<(usize, usize), String> {
+        let mut split = range.splitn(2, ',');
+        let start = split
+            .next()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(invalid)?;
+        let count = match split.next() {
+            Some(c) => c.parse().map_err(|_| invalid())?,
+            None => 1,
+        };
+        Ok((start, count))
+    };
+
+    let (old_start, old_count) = parse_range(old)?;
+    let (new_start, new_count) = parse_range(new)?;
+    Ok((old_start, old_count, new_start, new_count))
+}
+
+/// Apply hunks to file content
+///
+/// Returns the new content if every hunk applied, along with the outcome of
+/// each hunk. Nothing is returned on conflict so callers can stay atomic.
+pub fn apply_hunks(
+    content: &str,
+    hunks: &[Hunk],
+    max_fuzz: usize,
+) -> (Option<String>, Vec<HunkOutcome>) {
+    let trailing_newline = content.is_empty() || content.ends_with('\n');
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let mut outcomes = Vec::with_capacity(hunks.len());
+    // Shift between header line numbers and current positions
+    let mut delta: isize = 0;
+    // Hunks must not overlap already patched regions
+    let mut min_line = 0;
+    let mut failed = false;
+
+    for hunk in hunks {
+        // `-n,0` headers name the line *after which* lines are inserted
+        let header_pos = if hunk.old_lines().is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let expected = (header_pos as isize + delta).max(0) as usize;
+
+        match locate_hunk(&lines, hunk, expected, min_line, max_fuzz) {
+            Some(placement) => {
+                let new_lines = hunk.new_lines();
+                let new_lines =
+                    &new_lines[placement.skip_head..new_lines.len() - placement.skip_tail];
+                let old_len = placement.len;
+                lines.splice(
+                    placement.start..placement.start + old_len,
+                    new_lines.iter().map(|s| s.to_string()),
+                );
+
+                outcomes.push(HunkOutcome::Applied {
+                    line: placement.start + 1,
+                    offset: placement.start as isize - (expected + placement.skip_head) as isize,
+                    fuzz: placement.skip_head.max(placement.skip_tail),
+                    whitespace: placement.whitespace,
+                });
+
+                delta += new_lines.len() as isize - old_len as isize;
+                min_line = placement.start + new_lines.len();
+            }
+            None => {
+                failed = true;
+                outcomes.push(HunkOutcome::Conflict {
+                    reason: format!(
+                        "could not find the {} expected line(s) near line {}",
+                        hunk.old_lines().len(),
+                        hunk.old_start
+                    ),
+                });
+            }
+        }
+    }
+
+    if failed {
+        return (None, outcomes);
+    }
+
+    let mut result = lines.join("\n");
+    if trailing_newline && !result.is_empty() {
+        result.push('\n');
+    }
+    (Some(result), outcomes)
+}
+
+/// Where a hunk matched in the file
+struct Placement {
+    start: usize,
+    len: usize,
+    skip_head: usize,
+    skip_tail: usize,
+    whitespace: bool,
+}
+
+/// Find the best position for a hunk, preferring exact matches close to `expected`
+fn locate_hunk(
+    lines: &[String],
+    hunk: &Hunk,
+    expected: usize,
+    min_line: usize,
+    max_fuzz: usize,
+) -> Option<Placement> {
+    let old_lines = hunk.old_lines();
+    let (leading, trailing) = hunk.context_bounds();
+
+    for fuzz in 0..=max_fuzz {
+        let skip_head = fuzz.min(leading);
+        let skip_tail = fuzz.min(trailing);
+        if fuzz > 0 && skip_head == 0 && skip_tail == 0 {
+            break;
+        }
+        // Never fuzz away every expected line, or the hunk would land blindly
+        if fuzz > 0 && skip_head + skip_tail >= old_lines.len() {
+            break;
+        }
+        let needle = &old_lines[skip_head..old_lines.len() - skip_tail];
+        let anchor = expected + skip_head;
+
+        for whitespace in [false, true] {
+            if let Some(start) = search_near(lines, needle, anchor, min_line, whitespace) {
+                return Some(Placement {
+                    start,
+                    len: needle.len(),
+                    skip_head,
+                    skip_tail,
+                    whitespace,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// Search outward from `anchor` for `needle`
+fn search_near(
+    lines: &[String],
+    needle: &[&str],
+    anchor: usize,
+    min_line: usize,
+    ignore_whitespace: bool,
+) -> Option<usize> {
+    if needle.is_empty() {
+        // Pure insertion: trust the header position
+        return Some(anchor.clamp(min_line, lines.len()));
+    }
+    if needle.len() > lines.len() {
+        return None;
+    }
+
+    let last_start = lines.len() - needle.len();
+    let matches_at = |start: usize| {
+        needle.iter().enumerate().all(|(i, expected)| {
+            let actual = lines[start + i].as_str();
+            if ignore_whitespace {
+                normalize_whitespace(actual) == normalize_whitespace(expected)
+            } else {
+                actual == *expected
+            }
+        })
+    };
+
+    let anchor = anchor.min(last_start);
+    for distance in 0..=last_start {
+        let after = anchor + distance;
+        if after <= last_start && after >= min_line && matches_at(after) {
+            return Some(after);
+        }
+        if distance > 0 && distance <= anchor {
+            let before = anchor - distance;
+            if before >= min_line && matches_at(before) {
+                return Some(before);
+            }
+        }
+    }
+
+    None
+}
+
+fn normalize_whitespace(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Render a unified diff between two versions of a file
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str =
+        "fn main() {\n    let a = 1;\n    let b = 2;\n    println!(\"{}\", a + b);\n}\n";
+
+    #[test]
+    fn test_parse_unified_diff() {
+        let diff = "diff --git a/src/main.rs b/src/main.rs\n\
+                    index 123..456 100644\n\
+                    --- a/src/main.rs\n\
+                    +++ b/src/main.rs\n\
+                    @@ -1,3 +1,3 @@\n \
+                    fn main() {\n\
+                    -    let a = 1;\n\
+                    +    let a = 10;\n     \
+                    let b = 2;\n\
+                    --- /dev/null\n\
+                    +++ b/NEW.md\n\
+                    @@ -0,0 +1 @@\n\
+                    +hello\n";
+
+        let patches = parse_unified_diff(diff).unwrap();
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].path(), "src/main.rs");
+        assert_eq!(patches[0].hunks[0].lines.len(), 4);
+        assert!(patches[1].is_create());
+        assert_eq!(patches[1].path(), "NEW.md");
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        assert!(parse_unified_diff("not a diff").is_err());
+        assert!(parse_unified_diff("--- a/x\n+++ b/x\n@@ bogus @@\n").is_err());
+    }
+
+    #[test]
+    fn test_apply_exact() {
+        let hunk = Hunk {
+            old_start: 2,
+            new_start: 2,
+            lines: vec![
+                HunkLine::Context("    let a = 1;".to_string()),
+                HunkLine::Remove("    let b = 2;".to_string()),
+                HunkLine::Add("    let b = 3;".to_string()),
+            ],
+        };
+
+        let (result, outcomes) = apply_hunks(ORIGINAL, &[hunk], DEFAULT_MAX_FUZZ);
+        assert!(result.unwrap().contains("let b = 3;"));
+        assert_eq!(
+            outcomes[0],
+            HunkOutcome::Applied {
+                line: 2,
+                offset: 0,
+                fuzz: 0,
+                whitespace: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_apply_with_offset_and_whitespace() {
+        let hunk = Hunk {
+            old_start: 10,
+            new_start: 10,
+            lines: vec![
+                HunkLine::Remove("let a = 1;".to_string()),
+                HunkLine::Add("    let a = 5;".to_string()),
+            ],
+        };
+
+        let (result, outcomes) = apply_hunks(ORIGINAL, &[hunk], DEFAULT_MAX_FUZZ);
+        assert!(result.unwrap().contains("let a = 5;"));
+        match &outcomes[0] {
+            HunkOutcome::Applied {
+                line,
+                offset,
+                whitespace,
+                ..
+            } => {
+                assert_eq!(*line, 2);
+                assert_eq!(*offset, -8);
+                assert!(*whitespace);
+            }
+            other => panic!("unexpected outcome: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_apply_with_fuzz() {
+        let hunk = Hunk {
+            old_start: 1,
+            new_start: 1,
+            lines: vec![
+                HunkLine::Context("fn start() {".to_string()),
+                HunkLine::Remove("    let a = 1;".to_string()),
+                HunkLine::Add("    let a = 7;".to_string()),
+                HunkLine::Context("    let b = 2;".to_string()),
+            ],
+        };
+
+        let (result, outcomes) = apply_hunks(ORIGINAL, &[hunk], DEFAULT_MAX_FUZZ);
+        assert!(result.unwrap().contains("let a = 7;"));
+        assert!(matches!(outcomes[0], HunkOutcome::Applied { fuzz: 1, .. }));
+    }
+
+    #[test]
+    fn test_apply_conflict() {
+        let hunk = Hunk {
+            old_start: 1,
+            new_start: 1,
+            lines: vec![
+                HunkLine::Remove("does not exist".to_string()),
+                HunkLine::Add("replacement".to_string()),
+            ],
+        };
+
+        let (result, outcomes) = apply_hunks(ORIGINAL, &[hunk], DEFAULT_MAX_FUZZ);
+        assert!(result.is_none());
+        assert!(!outcomes[0].is_applied());
+    }
+
+    #[test]
+    fn test_apply_to_empty_file() {
+        let hunk = Hunk {
+            old_start: 0,
+            new_start: 1,
+            lines: vec![HunkLine::Add("hello".to_string())],
+        };
+
+        let (result, _) = apply_hunks("", &[hunk], 0);
+        assert_eq!(result.unwrap(), "hello\n");
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let diff = unified_diff("x.txt", "a\nb\n", "a\nc\n");
+        assert!(diff.contains("--- a/x.txt"));
+        assert!(diff.contains("-b"));
+        assert!(diff.contains("+c"));
+    }
+}
@@ -0,0 +1,108 @@
+//! Child Process Execution
+//!
+//! Runs a child process while forwarding its stdout/stderr line by line to the
+//! execution context's progress sink, so long-running commands show live output.
+
+use super::r#trait::{ToolExecutionContext, ToolProgress};
+use std::process::{Output, Stdio};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+
+/// Spawn `cmd`, stream its output as progress events and wait for it to exit
+///
+/// The full stdout/stderr are still collected and returned, matching
+/// `Command::output()`. The child is killed if the returned future is dropped
+/// (e.g. when wrapped in a timeout).
+pub async fn run_with_progress(
+    mut cmd: Command,
+    context: &ToolExecutionContext,
+    tool_name: &str,
+) -> std::io::Result<Output> {
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = cmd.spawn()?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let (stdout, stderr, status) = tokio::join!(
+        pump(stdout, context, tool_name, ToolProgress::Stdout),
+        pump(stderr, context, tool_name, ToolProgress::Stderr),
+        child.wait(),
+    );
+
+    Ok(Output {
+        status: status?,
+        stdout: stdout?,
+        stderr: stderr?,
+    })
+}
+
+/// Read a stream to the end, reporting each line and returning the raw bytes
+async fn pump<R>(
+    stream: Option<R>,
+    context: &ToolExecutionContext,
+    tool_name: &str,
+    wrap: fn(String) -> ToolProgress,
+) -> std::io::Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut collected = Vec::new();
+    let Some(stream) = stream else {
+        return Ok(collected);
+    };
+
+    let mut reader = BufReader::new(stream);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).await? == 0 {
+            break;
+        }
+        collected.extend_from_slice(&line);
+
+        if context.progress_tx.is_some() {
+            let text = String::from_utf8_lossy(&line);
+            context.report_progress(
+                tool_name,
+                wrap(text.trim_end_matches(['\r', '\n']).to_string()),
+            );
+        }
+    }
+
+    Ok(collected)
+}
+
+#[cfg(test)]
+#[cfg(not(target_os = "windows"))]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_run_with_progress_streams_lines() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let context = ToolExecutionContext::new(Uuid::new_v4()).with_progress_sender(Some(tx));
+
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("echo one; echo two; echo oops >&2");
+
+        let output = run_with_progress(cmd, &context, "bash").await.unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "one\ntwo\n");
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "oops\n");
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            assert_eq!(event.tool_name, "bash");
+            events.push(event.progress);
+        }
+        assert!(events.contains(&ToolProgress::Stdout("one".to_string())));
+        assert!(events.contains(&ToolProgress::Stdout("two".to_string())));
+        assert!(events.contains(&ToolProgress::Stderr("oops".to_string())));
+    }
+}
@@ -0,0 +1,300 @@
+//! Read Output Tool
+//!
+//! Pages through tool output that was too large to return in full and was
+//! stored in the session's artifact store.
+
+use super::error::{Result, ToolError};
+use super::output::load_artifact;
+use super::r#trait::{Tool, ToolCapability, ToolExecutionContext, ToolResult};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Default number of lines returned per page
+const DEFAULT_LINE_COUNT: usize = 200;
+
+/// Maximum number of lines returned per page
+const MAX_LINE_COUNT: usize = 500;
+
+/// Maximum number of characters returned per page
+const MAX_PAGE_CHARS: usize = 20_000;
+
+/// Read stored output tool
+pub struct ReadOutputTool;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ReadOutputInput {
+    /// Artifact ID from a truncated tool result
+    artifact_id: String,
+
+    /// Optional: Start line (0-indexed)
+    #[serde(default)]
+    start_line: usize,
+
+    /// Optional: Char offset into the start line, for lines longer than a page
+    #[serde(default)]
+    start_char: usize,
+
+    /// Optional: Number of lines to read
+    #[serde(default = "default_line_count")]
+    line_count: usize,
+}
+
+fn default_line_count() -> usize {
+    DEFAULT_LINE_COUNT
+}
+
+#[async_trait]
+impl Tool for ReadOutputTool {
+    fn name(&self) -> &str {
+        "read_output"
+    }
+
+    fn description(&self) -> &str {
+        "Read a page of tool output that was truncated because it was too large. \
+         Use the artifact_id given in the truncation notice and page with start_line and line_count; \
+         lines too long for one page are continued with start_char."
+    }
+
+    fn input_schema(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "artifact_id": {
+                    "type": "string",
+                    "description": "Artifact ID from the truncation notice"
+                },
+                "start_line": {
+                    "type": "integer",
+                    "description": "Optional: Starting line number (0-indexed, default: 0)",
+                    "minimum": 0
+                },
+                "start_char": {
+                    "type": "integer",
+                    "description": "Optional: Character offset into start_line, used to continue a line cut at the page limit (default: 0)",
+                    "minimum": 0
+                },
+                "line_count": {
+                    "type": "integer",
+                    "description": "Optional: Number of lines to read (default: 200, max: 500)",
+                    "minimum": 1,
+                    "maximum": MAX_LINE_COUNT
+                }
+            },
+            "required": ["artifact_id"]
+        })
+    }
+
+    fn capabilities(&self) -> Vec<ToolCapability> {
+        vec![ToolCapability::ReadFiles]
+    }
+
+    fn requires_approval(&self) -> bool {
+        false // Only reads output the session already produced
+    }
+
+    fn validate_input(&self, input: &Value) -> Result<()> {
+        let input: ReadOutputInput = serde_json::from_value(input.clone())
+            .map_err(|e| ToolError::InvalidInput(format!("Invalid input: {}", e)))?;
+
+        if input.line_count == 0 || input.line_count > MAX_LINE_COUNT {
+            return Err(ToolError::InvalidInput(format!(
+                "line_count must be between 1 and {}",
+                MAX_LINE_COUNT
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn execute(&self, input: Value, context: &ToolExecutionContext) -> Result<ToolResult> {
+        let input: ReadOutputInput = serde_json::from_value(input)?;
+
+        let content = match load_artifact(context, &input.artifact_id).await {
+            Ok(content) => content,
+            Err(e @ (ToolError::FileNotFound(_) | ToolError::InvalidInput(_))) => {
+                return Ok(ToolResult::error(e.to_string()));
+            }
+            Err(e) => return Err(e),
+        };
+
+        let lines: Vec<&str> = content.lines().collect();
+        let total_lines = lines.len();
+
+        if input.start_line >= total_lines && total_lines > 0 {
+            return Ok(ToolResult::error(format!(
+                "Start line {} exceeds output length {}",
+                input.start_line, total_lines
+            )));
+        }
+
+        let first_line_chars = lines
+            .get(input.start_line)
+            .map_or(0, |line| line.chars().count());
+        if input.start_char > 0 && input.start_char >= first_line_chars {
+            return Ok(ToolResult::error(format!(
+                "Start char {} exceeds length {} of line {}",
+                input.start_char, first_line_chars, input.start_line
+            )));
+        }
+
+        // Whole lines are returned until the page is full; a line that does not
+        // fit on its own is cut at a char boundary and continued via start_char.
+        let mut page = String::new();
+        let mut page_chars = 0;
+        let mut end = input.start_line;
+        let mut next_char = 0;
+        let line_limit = input.line_count.min(MAX_LINE_COUNT);
+        while end < total_lines && end - input.start_line < line_limit {
+            let skip = if end == input.start_line {
+                input.start_char
+            } else {
+                0
+            };
+            let separator = usize::from(!page.is_empty());
+            let budget = MAX_PAGE_CHARS.saturating_sub(page_chars + separator);
+            let rest_chars = lines[end].chars().count().saturating_sub(skip);
+
+            if rest_chars > budget {
+                if page.is_empty() {
+                    page.extend(lines[end].chars().skip(skip).take(budget));
+                    next_char = skip + budget;
+                }
+                break;
+            }
+
+            if separator == 1 {
+                page.push('\n');
+            }
+            page.extend(lines[end].chars().skip(skip));
+            page_chars += separator + rest_chars;
+            end += 1;
+        }
+
+        let mut output = format!(
+            "Artifact {}: lines {}-{} of {}",
+            input.artifact_id, input.start_line, end, total_lines
+        );
+        if input.start_char > 0 {
+            output.push_str(&format!(" from char {}", input.start_char));
+        }
+        if next_char > 0 {
+            output.push_str(&format!(
+                " (line {} cut at char {}; continue with start_line={}, start_char={})",
+                end, next_char, end, next_char
+            ));
+        } else if end < total_lines {
+            output.push_str(&format!(" (continue with start_line={})", end));
+        }
+        output.push_str("\n\n");
+        output.push_str(&page);
+
+        Ok(ToolResult::success(output)
+            .with_metadata("artifact_id".to_string(), input.artifact_id)
+            .with_metadata("total_lines".to_string(), total_lines.to_string())
+            .with_metadata("next_line".to_string(), end.to_string())
+            .with_metadata("next_char".to_string(), next_char.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::tools::output::{remove_artifacts, store_artifact};
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_read_output_pages() {
+        let temp_dir = TempDir::new().unwrap();
+        let context = ToolExecutionContext::new(Uuid::new_v4())
+            .with_working_directory(temp_dir.path().to_path_buf());
+        let content = (0..10)
+            .map(|i| format!("row {}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let id = store_artifact(&context, "bash", &content).await.unwrap();
+
+        let tool = ReadOutputTool;
+        let input = serde_json::json!({ "artifact_id": id, "start_line": 2, "line_count": 3 });
+        let result = tool.execute(input, &context).await.unwrap();
+
+        assert!(result.success);
+        assert!(result.output.contains("lines 2-5 of 10"));
+        assert!(result.output.contains("start_line=5"));
+        assert!(result.output.contains("row 2\nrow 3\nrow 4"));
+        assert!(!result.output.contains("row 5"));
+        remove_artifacts(context.session_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_output_pages_within_long_line() {
+        let temp_dir = TempDir::new().unwrap();
+        let context = ToolExecutionContext::new(Uuid::new_v4())
+            .with_working_directory(temp_dir.path().to_path_buf());
+        let content = format!("{}{}\nnext", "é".repeat(25_000), "z".repeat(25_000));
+        let id = store_artifact(&context, "bash", &content).await.unwrap();
+        let tool = ReadOutputTool;
+
+        let first = tool
+            .execute(serde_json::json!({ "artifact_id": id }), &context)
+            .await
+            .unwrap();
+        assert!(first.success);
+        assert!(first.output.contains("start_line=0, start_char=20000"));
+        let page = first.output.split_once("\n\n").unwrap().1;
+        assert_eq!(page.chars().count(), MAX_PAGE_CHARS);
+        assert!(page.chars().all(|c| c == 'é'));
+
+        let second = tool
+            .execute(
+                serde_json::json!({ "artifact_id": id, "start_char": 20_000 }),
+                &context,
+            )
+            .await
+            .unwrap();
+        assert!(second.output.contains("start_line=0, start_char=40000"));
+        let page = second.output.split_once("\n\n").unwrap().1;
+        assert_eq!(page.chars().count(), MAX_PAGE_CHARS);
+        assert!(page.starts_with("ééééé"));
+        assert!(page.ends_with("zzzzz"));
+
+        let third = tool
+            .execute(
+                serde_json::json!({ "artifact_id": id, "start_char": 40_000 }),
+                &context,
+            )
+            .await
+            .unwrap();
+        assert!(third.output.contains("lines 0-2 of 2"));
+        assert!(third
+            .output
+            .ends_with(&format!("{}\nnext", "z".repeat(10_000))));
+        remove_artifacts(context.session_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_output_unknown_artifact() {
+        let temp_dir = TempDir::new().unwrap();
+        let context = ToolExecutionContext::new(Uuid::new_v4())
+            .with_working_directory(temp_dir.path().to_path_buf());
+
+        let tool = ReadOutputTool;
+        let input = serde_json::json!({ "artifact_id": "bash-000000000000" });
+        let result = tool.execute(input, &context).await.unwrap();
+
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("No stored output"));
+    }
+
+    #[test]
+    fn test_read_output_validation() {
+        let tool = ReadOutputTool;
+        assert!(tool
+            .validate_input(&serde_json::json!({ "artifact_id": "x", "line_count": 0 }))
+            .is_err());
+        assert!(tool
+            .validate_input(&serde_json::json!({ "artifact_id": "x" }))
+            .is_ok());
+    }
+}
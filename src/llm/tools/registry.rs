@@ -3,7 +3,8 @@
 //! Manages the collection of available tools that can be invoked by agents.
 
 use super::error::{Result, ToolError};
-use super::r#trait::{Tool, ToolExecutionContext, ToolResult};
+use super::output::OutputPolicy;
+use super::r#trait::{Tool, ToolExecutionContext, ToolProgress, ToolResult};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -11,6 +12,7 @@ use std::sync::Arc;
 /// Registry of available tools
 pub struct ToolRegistry {
     tools: HashMap<String, Arc<dyn Tool>>,
+    output_policy: OutputPolicy,
 }
 
 impl ToolRegistry {
@@ -18,9 +20,21 @@ impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             tools: HashMap::new(),
+            output_policy: OutputPolicy::default(),
         }
     }
 
+    /// Set the size policy applied to tool results
+    pub fn with_output_policy(mut self, policy: OutputPolicy) -> Self {
+        self.output_policy = policy;
+        self
+    }
+
+    /// Get the size policy applied to tool results
+    pub fn output_policy(&self) -> OutputPolicy {
+        self.output_policy
+    }
+
     /// Register a tool
     pub fn register(&mut self, tool: Arc<dyn Tool>) {
         let name = tool.name().to_string();
@@ -70,7 +84,7 @@ impl ToolRegistry {
         tool.validate_input(&input)?;
 
         // Check if approval is required
-        if tool.requires_approval() && !context.auto_approve {
+        if tool.call_requires_approval(&input) && !context.auto_approve {
             return Err(ToolError::ApprovalRequired(format!(
                 "Tool '{}' requires approval before execution",
                 name
@@ -79,7 +93,21 @@ impl ToolRegistry {
 
         // Execute the tool
         tracing::info!("Executing tool: {}", name);
-        let result = tool.execute(input, context).await?;
+        context.report_progress(name, ToolProgress::Started);
+        let result = tool.execute(input, context).await;
+        context.report_progress(
+            name,
+            ToolProgress::Finished {
+                success: result.as_ref().map(|r| r.success).unwrap_or(false),
+            },
+        );
+        let mut result = result?;
+
+        // Keep oversized output out of the context window. The pager tool is
+        // exempt, it already returns bounded pages of stored output.
+        if name != "read_output" {
+            result = self.output_policy.apply(name, result, context).await;
+        }
 
         if result.success {
             tracing::info!("Tool '{}' executed successfully", name);
@@ -220,6 +248,52 @@ mod tests {
         assert_eq!(result.output, "Mock execution successful");
     }
 
+    #[tokio::test]
+    async fn test_execute_reports_start_and_finish() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(MockTool {
+            name: "test_tool".to_string(),
+            requires_approval: false,
+        }));
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let context = ToolExecutionContext::new(Uuid::new_v4()).with_progress_sender(Some(tx));
+        let input = serde_json::json!({ "message": "test" });
+
+        registry
+            .execute("test_tool", input, &context)
+            .await
+            .unwrap();
+
+        assert_eq!(rx.try_recv().unwrap().progress, ToolProgress::Started);
+        assert_eq!(
+            rx.try_recv().unwrap().progress,
+            ToolProgress::Finished { success: true }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_applies_output_policy() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut registry = ToolRegistry::new().with_output_policy(OutputPolicy::new(10, 100));
+        registry.register(Arc::new(MockTool {
+            name: "test_tool".to_string(),
+            requires_approval: false,
+        }));
+
+        let context = ToolExecutionContext::new(Uuid::new_v4())
+            .with_working_directory(temp_dir.path().to_path_buf());
+        let input = serde_json::json!({ "message": "test" });
+
+        let result = registry
+            .execute("test_tool", input, &context)
+            .await
+            .unwrap();
+        assert_eq!(result.metadata.get("truncated"), Some(&"true".to_string()));
+        assert!(result.metadata.contains_key("artifact_id"));
+        assert!(result.output.contains("read_output"));
+    }
+
     #[tokio::test]
     async fn test_execute_nonexistent_tool() {
         let registry = ToolRegistry::new();
@@ -4,8 +4,38 @@ use super::error::Result;
 use async_trait::async_trait;
 use serde_json::Value;
 use std::collections::HashMap;
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
+/// Incremental progress reported by a tool while it is running
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolProgress {
+    /// Tool execution started
+    Started,
+    /// A line written to stdout
+    Stdout(String),
+    /// A line written to stderr
+    Stderr(String),
+    /// Percent complete (0-100)
+    Percent(u8),
+    /// Free-form status message
+    Status(String),
+    /// Tool execution finished
+    Finished { success: bool },
+}
+
+/// Progress update tagged with the tool that produced it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolProgressEvent {
+    /// Tool name
+    pub tool_name: String,
+    /// Progress payload
+    pub progress: ToolProgress,
+}
+
+/// Channel used to deliver progress updates to the UI
+pub type ToolProgressSender = mpsc::UnboundedSender<ToolProgressEvent>;
+
 /// Execution context for tools
 #[derive(Debug, Clone)]
 pub struct ToolExecutionContext {
@@ -26,6 +56,9 @@ pub struct ToolExecutionContext {
 
     /// Whether in read-only mode (Plan mode) - restricts write operations
     pub read_only_mode: bool,
+
+    /// Optional sink for live progress updates
+    pub progress_tx: Option<ToolProgressSender>,
 }
 
 impl ToolExecutionContext {
@@ -38,6 +71,7 @@ impl ToolExecutionContext {
             auto_approve: false,
             timeout_secs: 30,
             read_only_mode: false,
+            progress_tx: None,
         }
     }
 
@@ -64,6 +98,23 @@ impl ToolExecutionContext {
         self.read_only_mode = read_only;
         self
     }
+
+    /// Set the progress sink
+    pub fn with_progress_sender(mut self, progress_tx: Option<ToolProgressSender>) -> Self {
+        self.progress_tx = progress_tx;
+        self
+    }
+
+    /// Report progress for a tool (no-op when no sink is configured)
+    pub fn report_progress(&self, tool_name: &str, progress: ToolProgress) {
+        if let Some(ref tx) = self.progress_tx {
+            // The receiver going away just means nobody is watching anymore
+            let _ = tx.send(ToolProgressEvent {
+                tool_name: tool_name.to_string(),
+                progress,
+            });
+        }
+    }
 }
 
 /// Tool result
@@ -110,6 +161,21 @@ impl ToolResult {
     }
 }
 
+/// A change a tool call would make to one file
+///
+/// Produced by [`Tool::preview`] so the change can be reviewed before approval.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProposedChange {
+    /// Path as given in the tool input
+    pub path: String,
+
+    /// Content before the change (None if the file does not exist yet)
+    pub before: Option<String>,
+
+    /// Content after the change
+    pub after: String,
+}
+
 /// Tool capability flags
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ToolCapability {
@@ -156,6 +222,19 @@ pub trait Tool: Send + Sync {
             .any(|cap| dangerous_capabilities.contains(cap))
     }
 
+    /// Capabilities a particular call needs
+    ///
+    /// Tools with both read-only and mutating operations narrow the full
+    /// `capabilities()` down to what the call in `input` does.
+    fn call_capabilities(&self, _input: &Value) -> Vec<ToolCapability> {
+        self.capabilities()
+    }
+
+    /// Check if a particular call requires approval before execution
+    fn call_requires_approval(&self, _input: &Value) -> bool {
+        self.requires_approval()
+    }
+
     /// Execute the tool with given input
     async fn execute(&self, input: Value, context: &ToolExecutionContext) -> Result<ToolResult>;
 
@@ -164,6 +243,24 @@ pub trait Tool: Send + Sync {
         // Default implementation - no validation
         Ok(())
     }
+
+    /// Paths of files this call would modify, relative to the working directory or absolute
+    ///
+    /// Used to snapshot files before execution so the change can be undone.
+    async fn modified_paths(&self, _input: &Value, _context: &ToolExecutionContext) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Compute the file changes this call would make, without writing anything
+    ///
+    /// Returns an empty list for tools that do not support previews.
+    async fn preview(
+        &self,
+        _input: &Value,
+        _context: &ToolExecutionContext,
+    ) -> Result<Vec<ProposedChange>> {
+        Ok(Vec::new())
+    }
 }
 
 #[cfg(test)]
@@ -182,6 +279,21 @@ mod tests {
         assert_eq!(ctx.timeout_secs, 60);
     }
 
+    #[test]
+    fn test_report_progress() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let ctx = ToolExecutionContext::new(Uuid::new_v4()).with_progress_sender(Some(tx));
+
+        ctx.report_progress("bash", ToolProgress::Stdout("line".to_string()));
+
+        let event = rx.try_recv().unwrap();
+        assert_eq!(event.tool_name, "bash");
+        assert_eq!(event.progress, ToolProgress::Stdout("line".to_string()));
+
+        // Without a sink reporting is a no-op
+        ToolExecutionContext::new(Uuid::new_v4()).report_progress("bash", ToolProgress::Started);
+    }
+
     #[test]
     fn test_tool_result_success() {
         let result = ToolResult::success("Done!".to_string())
@@ -3,7 +3,7 @@
 //! Allows writing content to files on the filesystem.
 
 use super::error::{validate_path_safety, Result, ToolError};
-use super::r#trait::{Tool, ToolCapability, ToolExecutionContext, ToolResult};
+use super::r#trait::{ProposedChange, Tool, ToolCapability, ToolExecutionContext, ToolResult};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -69,6 +69,42 @@ impl Tool for WriteTool {
         true // Writing files requires approval
     }
 
+    async fn modified_paths(&self, input: &Value, _context: &ToolExecutionContext) -> Vec<String> {
+        input
+            .get("path")
+            .and_then(Value::as_str)
+            .map(|p| vec![p.to_string()])
+            .unwrap_or_default()
+    }
+
+    async fn preview(
+        &self,
+        input: &Value,
+        context: &ToolExecutionContext,
+    ) -> Result<Vec<ProposedChange>> {
+        let input: WriteInput = serde_json::from_value(input.clone())?;
+
+        // Same path check as execute; a missing parent means a new file
+        let before = match validate_path_safety(&input.path, &context.working_directory) {
+            Ok(path) if path.is_file() => {
+                Some(fs::read_to_string(&path).await.map_err(ToolError::Io)?)
+            }
+            Ok(_) => None,
+            Err(ToolError::InvalidInput(msg))
+                if msg.contains("Parent directory does not exist") =>
+            {
+                None
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(vec![ProposedChange {
+            path: input.path,
+            before,
+            after: input.content,
+        }])
+    }
+
     fn validate_input(&self, input: &Value) -> Result<()> {
         let _: WriteInput = serde_json::from_value(input.clone())
             .map_err(|e| ToolError::InvalidInput(format!("Invalid input: {}", e)))?;
@@ -255,6 +291,41 @@ mod tests {
         assert!(capabilities.contains(&ToolCapability::SystemModification));
     }
 
+    #[tokio::test]
+    async fn test_write_preview() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("existing.txt"), "old").unwrap();
+
+        let tool = WriteTool;
+        let context = ToolExecutionContext::new(Uuid::new_v4())
+            .with_working_directory(temp_dir.path().to_path_buf());
+
+        let input = serde_json::json!({ "path": "existing.txt", "content": "new" });
+        let changes = tool.preview(&input, &context).await.unwrap();
+        assert_eq!(changes[0].before.as_deref(), Some("old"));
+        assert_eq!(changes[0].after, "new");
+
+        let input = serde_json::json!({ "path": "fresh.txt", "content": "hi" });
+        let changes = tool.preview(&input, &context).await.unwrap();
+        assert_eq!(changes[0].before, None);
+        assert!(!temp_dir.path().join("fresh.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_write_preview_outside_working_directory() {
+        let outer = TempDir::new().unwrap();
+        let working_dir = outer.path().join("project");
+        std::fs::create_dir(&working_dir).unwrap();
+        std::fs::write(outer.path().join("secret.txt"), "token").unwrap();
+
+        let tool = WriteTool;
+        let context = ToolExecutionContext::new(Uuid::new_v4()).with_working_directory(working_dir);
+
+        let input = serde_json::json!({ "path": "../secret.txt", "content": "x" });
+        let err = tool.preview(&input, &context).await.unwrap_err();
+        assert!(matches!(err, ToolError::PermissionDenied(_)));
+    }
+
     #[tokio::test]
     async fn test_overwrite_existing_file() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,586 @@
+//! LSP Client
+//!
+//! Runs one language server as a child process for one workspace root:
+//! performs the initialize handshake, matches responses to requests, answers
+//! the requests a server sends to its client, keeps opened documents in sync
+//! with the files on disk and collects the diagnostics the server publishes.
+
+use super::codec::{encode_message, read_message};
+use super::error::{LspError, Result};
+use crate::config::LspServerConfig;
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+    Notification,
+};
+use lsp_types::request::Request;
+use lsp_types::{
+    Diagnostic, DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DidSaveTextDocumentParams, InitializeResult, PublishDiagnosticsParams, ServerCapabilities,
+    TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem, Url,
+    VersionedTextDocumentIdentifier,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{oneshot, watch, Mutex};
+
+/// Default timeout for a single request
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+
+/// Error code servers return while their analysis is out of date
+const CONTENT_MODIFIED: i64 = -32801;
+
+/// JSON-RPC error code for unknown methods
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Quiet period after which published diagnostics are considered settled
+const DIAGNOSTICS_SETTLE: Duration = Duration::from_millis(300);
+
+type PendingRequests = Arc<std::sync::Mutex<HashMap<i64, oneshot::Sender<Result<Value>>>>>;
+/// Latest diagnostics of each document, with the sequence number of the
+/// notification that published them
+type Diagnostics = Arc<std::sync::Mutex<HashMap<Url, (u64, Vec<Diagnostic>)>>>;
+type Writer = Arc<Mutex<ChildStdin>>;
+
+/// A document opened on the server
+struct OpenDocument {
+    version: i32,
+    text: String,
+}
+
+/// Client connection to one language server
+pub struct LspClient {
+    name: String,
+    root: PathBuf,
+    language_id: String,
+    writer: Writer,
+    child: Mutex<Child>,
+    pending: PendingRequests,
+    next_id: AtomicI64,
+    timeout: Duration,
+    capabilities: ServerCapabilities,
+    documents: Mutex<HashMap<Url, OpenDocument>>,
+    diagnostics: Diagnostics,
+    published: Arc<watch::Sender<u64>>,
+    trace: bool,
+}
+
+impl LspClient {
+    /// Start the server for the workspace at `root` and perform the handshake
+    ///
+    /// With `trace` set every message exchanged is logged.
+    pub async fn start(
+        name: &str,
+        config: &LspServerConfig,
+        root: &Path,
+        trace: bool,
+    ) -> Result<Self> {
+        let start_error = |message: String| LspError::Start {
+            server: name.to_string(),
+            message,
+        };
+
+        let mut child = Command::new(&config.command)
+            .args(&config.args)
+            .envs(&config.env)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| start_error(format!("failed to run '{}': {}", config.command, e)))?;
+
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take();
+        let (Some(stdin), Some(stdout)) = (stdin, stdout) else {
+            return Err(start_error("server stdio is not available".to_string()));
+        };
+
+        // Servers log to stderr
+        if let Some(stderr) = child.stderr.take() {
+            let name = name.to_string();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    tracing::debug!("[lsp:{}] {}", name, line);
+                }
+            });
+        }
+
+        let writer: Writer = Arc::new(Mutex::new(stdin));
+        let pending: PendingRequests = Arc::default();
+        let diagnostics: Diagnostics = Arc::default();
+        let published = Arc::new(watch::channel(0).0);
+        tokio::spawn(dispatch(
+            name.to_string(),
+            BufReader::new(stdout),
+            writer.clone(),
+            pending.clone(),
+            diagnostics.clone(),
+            published.clone(),
+            trace,
+        ));
+
+        let mut client = Self {
+            name: name.to_string(),
+            root: root.to_path_buf(),
+            language_id: config
+                .language_id
+                .clone()
+                .unwrap_or_else(|| name.to_string()),
+            writer,
+            child: Mutex::new(child),
+            pending,
+            next_id: AtomicI64::new(1),
+            timeout: Duration::from_secs(config.timeout_secs),
+            capabilities: ServerCapabilities::default(),
+            documents: Mutex::new(HashMap::new()),
+            diagnostics,
+            published,
+            trace,
+        };
+
+        let init = client
+            .initialize(config.initialization_options.clone())
+            .await?;
+        tracing::info!(
+            "Started language server '{}' ({}) for {}",
+            name,
+            init.server_info
+                .as_ref()
+                .map_or(config.command.as_str(), |info| info.name.as_str()),
+            root.display()
+        );
+        client.capabilities = init.capabilities;
+        Ok(client)
+    }
+
+    async fn initialize(&self, options: Option<Value>) -> Result<InitializeResult> {
+        let root_uri = Url::from_file_path(&self.root)
+            .map_err(|_| LspError::Protocol(format!("invalid root {:?}", self.root)))?;
+        let folder_name = self
+            .root
+            .file_name()
+            .map_or_else(|| "root".to_string(), |n| n.to_string_lossy().to_string());
+
+        let result = self
+            .request_value(
+                "initialize",
+                json!({
+                    "processId": std::process::id(),
+                    "clientInfo": { "name": "crustly", "version": env!("CARGO_PKG_VERSION") },
+                    "rootUri": root_uri,
+                    "rootPath": self.root,
+                    "workspaceFolders": [{ "uri": root_uri, "name": folder_name }],
+                    "initializationOptions": options,
+                    "capabilities": {
+                        "workspace": {
+                            "configuration": true,
+                            "symbol": {},
+                            "workspaceEdit": { "documentChanges": true }
+                        },
+                        "textDocument": {
+                            "synchronization": { "didSave": true },
+                            "definition": { "linkSupport": true },
+                            "references": {},
+                            "hover": { "contentFormat": ["markdown", "plaintext"] },
+                            "rename": { "prepareSupport": false },
+                            "publishDiagnostics": {}
+                        }
+                    }
+                }),
+            )
+            .await?;
+        let init: InitializeResult = serde_json::from_value(result)
+            .map_err(|e| LspError::Protocol(format!("invalid initialize result: {}", e)))?;
+
+        self.send(json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }))
+            .await?;
+        Ok(init)
+    }
+
+    /// Name of the server in the configuration
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Workspace root the server was started for
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Capabilities reported by the server
+    pub fn capabilities(&self) -> &ServerCapabilities {
+        &self.capabilities
+    }
+
+    /// Send a request and wait for its result
+    ///
+    /// Requests the server rejects because its analysis is out of date are
+    /// retried a few times.
+    pub async fn request<R: Request>(&self, params: R::Params) -> Result<R::Result> {
+        let params = serde_json::to_value(params)?;
+        let mut attempt = 0;
+        let result = loop {
+            match self.request_value(R::METHOD, params.clone()).await {
+                Err(LspError::Server { code, .. }) if code == CONTENT_MODIFIED && attempt < 3 => {
+                    attempt += 1;
+                    tokio::time::sleep(Duration::from_millis(300 * attempt)).await;
+                }
+                result => break result?,
+            }
+        };
+        serde_json::from_value(result)
+            .map_err(|e| LspError::Protocol(format!("invalid '{}' result: {}", R::METHOD, e)))
+    }
+
+    async fn request_value(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if let Err(e) = self.send(message).await {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(LspError::Closed(self.name.clone())),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                let _ = self
+                    .send(json!({
+                        "jsonrpc": "2.0",
+                        "method": "$/cancelRequest",
+                        "params": { "id": id }
+                    }))
+                    .await;
+                Err(LspError::Timeout {
+                    method: method.to_string(),
+                    secs: self.timeout.as_secs(),
+                })
+            }
+        }
+    }
+
+    /// Send a notification
+    pub async fn notify<N: Notification>(&self, params: N::Params) -> Result<()> {
+        self.send(json!({ "jsonrpc": "2.0", "method": N::METHOD, "params": params }))
+            .await
+    }
+
+    async fn send(&self, message: Value) -> Result<()> {
+        if self.trace {
+            tracing::debug!("[lsp:{}] --> {}", self.name, message);
+        }
+        let mut writer = self.writer.lock().await;
+        writer
+            .write_all(&encode_message(&message))
+            .await
+            .map_err(|_| LspError::Closed(self.name.clone()))?;
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// Bring the server's copy of `path` up to date with the file on disk
+    ///
+    /// Opens the document on first use; later calls send the new content if
+    /// it changed, and close the document if the file was deleted.
+    pub async fn sync_file(&self, path: &Path) -> Result<Url> {
+        self.sync(path).await.map(|(uri, _)| uri)
+    }
+
+    /// Like [`sync_file`](Self::sync_file), returning the document only if
+    /// the server was sent new content for it
+    pub async fn update_file(&self, path: &Path) -> Result<Option<Url>> {
+        let (uri, updated) = self.sync(path).await?;
+        Ok(updated.then_some(uri))
+    }
+
+    /// Sync `path`, returning its document and whether the server was told
+    /// about new content
+    async fn sync(&self, path: &Path) -> Result<(Url, bool)> {
+        let uri = Url::from_file_path(path)
+            .map_err(|_| LspError::Protocol(format!("invalid path {:?}", path)))?;
+        let text = tokio::fs::read_to_string(path).await;
+
+        let mut documents = self.documents.lock().await;
+        match (documents.get_mut(&uri), text) {
+            (None, Ok(text)) => {
+                self.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+                    text_document: TextDocumentItem::new(
+                        uri.clone(),
+                        self.language_id.clone(),
+                        1,
+                        text.clone(),
+                    ),
+                })
+                .await?;
+                documents.insert(uri.clone(), OpenDocument { version: 1, text });
+                Ok((uri, true))
+            }
+            (Some(document), Ok(text)) if document.text != text => {
+                document.version += 1;
+                document.text = text.clone();
+                let version = document.version;
+                self.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+                    text_document: VersionedTextDocumentIdentifier::new(uri.clone(), version),
+                    content_changes: vec![TextDocumentContentChangeEvent {
+                        range: None,
+                        range_length: None,
+                        text,
+                    }],
+                })
+                .await?;
+                // The new content is what is on disk
+                self.notify::<DidSaveTextDocument>(DidSaveTextDocumentParams {
+                    text_document: TextDocumentIdentifier::new(uri.clone()),
+                    text: None,
+                })
+                .await?;
+                Ok((uri, true))
+            }
+            (Some(_), Ok(_)) => Ok((uri, false)),
+            (Some(_), Err(_)) => {
+                documents.remove(&uri);
+                self.notify::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+                    text_document: TextDocumentIdentifier::new(uri.clone()),
+                })
+                .await?;
+                Ok((uri, false))
+            }
+            (None, Err(e)) => Err(e.into()),
+        }
+    }
+
+    /// Whether `path` is open on the server
+    pub async fn is_open(&self, path: &Path) -> bool {
+        match Url::from_file_path(path) {
+            Ok(uri) => self.documents.lock().await.contains_key(&uri),
+            Err(_) => false,
+        }
+    }
+
+    /// Latest diagnostics published for a document
+    pub fn diagnostics(&self, uri: &Url) -> Vec<Diagnostic> {
+        self.diagnostics
+            .lock()
+            .unwrap()
+            .get(uri)
+            .map(|(_, diagnostics)| diagnostics.clone())
+            .unwrap_or_default()
+    }
+
+    /// Sequence number of the last diagnostics notification received
+    ///
+    /// Taken before a change to wait for the diagnostics that follow it.
+    pub fn diagnostics_sequence(&self) -> u64 {
+        *self.published.borrow()
+    }
+
+    /// Diagnostics the server publishes for `uri` after `since`
+    ///
+    /// Servers often publish several times in a row (syntax first, then
+    /// semantic checks), so this waits until the document's diagnostics stop
+    /// changing for a moment. Returns `None` if nothing was published within
+    /// `wait`.
+    pub async fn wait_for_diagnostics(
+        &self,
+        uri: &Url,
+        since: u64,
+        wait: Duration,
+    ) -> Option<Vec<Diagnostic>> {
+        let deadline = tokio::time::Instant::now() + wait;
+        let mut updates = self.published.subscribe();
+        let mut latest: Option<(u64, tokio::time::Instant)> = None;
+
+        loop {
+            let sequence = self
+                .diagnostics
+                .lock()
+                .unwrap()
+                .get(uri)
+                .map(|(sequence, _)| *sequence)
+                .filter(|sequence| *sequence > since);
+            if let Some(sequence) = sequence {
+                if latest.map(|(seen, _)| seen) != Some(sequence) {
+                    latest = Some((sequence, tokio::time::Instant::now()));
+                }
+            }
+
+            let until = match latest {
+                Some((_, at)) => deadline.min(at + DIAGNOSTICS_SETTLE),
+                None => deadline,
+            };
+            match tokio::time::timeout_at(until, updates.changed()).await {
+                Ok(Ok(())) => continue,
+                // Timed out, or the server exited
+                Ok(Err(_)) | Err(_) => break,
+            }
+        }
+
+        latest.map(|_| self.diagnostics(uri))
+    }
+
+    /// Ask the server to exit, killing it if it doesn't
+    pub async fn shutdown(&self) {
+        let graceful = tokio::time::timeout(Duration::from_secs(2), async {
+            self.request_value("shutdown", Value::Null).await?;
+            self.send(json!({ "jsonrpc": "2.0", "method": "exit" }))
+                .await
+        })
+        .await;
+        if !matches!(graceful, Ok(Ok(()))) {
+            tracing::debug!("Language server '{}' did not shut down cleanly", self.name);
+        }
+
+        let mut child = self.child.lock().await;
+        if tokio::time::timeout(Duration::from_secs(2), child.wait())
+            .await
+            .is_err()
+        {
+            let _ = child.kill().await;
+        }
+    }
+}
+
+/// Read messages from the server until its output closes
+async fn dispatch(
+    name: String,
+    mut reader: BufReader<tokio::process::ChildStdout>,
+    writer: Writer,
+    pending: PendingRequests,
+    diagnostics: Diagnostics,
+    published: Arc<watch::Sender<u64>>,
+    trace: bool,
+) {
+    loop {
+        let message = match read_message(&mut reader).await {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(e) => {
+                tracing::warn!("Failed to read from language server '{}': {}", name, e);
+                break;
+            }
+        };
+        if trace {
+            tracing::debug!("[lsp:{}] <-- {}", name, message);
+        }
+
+        let method = message.get("method").and_then(Value::as_str);
+        let id = message.get("id").filter(|id| !id.is_null()).cloned();
+        match (method, id) {
+            // Response to one of our requests
+            (None, Some(id)) => {
+                let Some(tx) = id
+                    .as_i64()
+                    .and_then(|id| pending.lock().unwrap().remove(&id))
+                else {
+                    continue; // Timed out or cancelled
+                };
+                let result = match message.get("error") {
+                    Some(error) => Err(LspError::Server {
+                        code: error["code"].as_i64().unwrap_or_default(),
+                        message: error["message"].as_str().unwrap_or_default().to_string(),
+                    }),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = tx.send(result);
+            }
+            // Request from the server
+            (Some(method), Some(id)) => {
+                let response = match answer(method, &message["params"]) {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err(error) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": METHOD_NOT_FOUND, "message": error }
+                    }),
+                };
+                let mut writer = writer.lock().await;
+                if writer.write_all(&encode_message(&response)).await.is_err() {
+                    break;
+                }
+                let _ = writer.flush().await;
+            }
+            (Some("textDocument/publishDiagnostics"), None) => {
+                match serde_json::from_value::<PublishDiagnosticsParams>(message["params"].clone())
+                {
+                    Ok(params) => {
+                        let mut diagnostics = diagnostics.lock().unwrap();
+                        published.send_modify(|sequence| *sequence += 1);
+                        let sequence = *published.borrow();
+                        diagnostics.insert(params.uri, (sequence, params.diagnostics));
+                    }
+                    Err(e) => {
+                        tracing::debug!("Language server '{}' sent bad diagnostics: {}", name, e);
+                    }
+                }
+            }
+            (Some("window/logMessage" | "window/showMessage"), None) => {
+                tracing::debug!(
+                    "[lsp:{}] {}",
+                    name,
+                    message["params"]["message"].as_str().unwrap_or_default()
+                );
+            }
+            (Some(_), None) | (None, None) => {}
+        }
+    }
+
+    tracing::debug!("Language server '{}' closed its output", name);
+    // Fail everything still waiting on the closed connection
+    for (_, tx) in pending.lock().unwrap().drain() {
+        let _ = tx.send(Err(LspError::Closed(name.clone())));
+    }
+}
+
+/// Result for a request sent by the server
+fn answer(method: &str, params: &Value) -> std::result::Result<Value, String> {
+    match method {
+        // No client-side settings: let the server use its defaults
+        "workspace/configuration" => {
+            let items = params["items"].as_array().map_or(0, Vec::len);
+            Ok(Value::Array(vec![Value::Null; items]))
+        }
+        "client/registerCapability"
+        | "client/unregisterCapability"
+        | "window/workDoneProgress/create"
+        | "window/showMessageRequest"
+        | "workspace/semanticTokens/refresh"
+        | "workspace/inlayHint/refresh"
+        | "workspace/diagnostic/refresh"
+        | "workspace/codeLens/refresh" => Ok(Value::Null),
+        // Edits are applied by tools, never on the server's behalf
+        "workspace/applyEdit" => Ok(json!({ "applied": false })),
+        method => Err(format!("Method not supported by client: {}", method)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_answers_server_requests() {
+        let params = json!({ "items": [{ "section": "rust-analyzer" }, { "section": "x" }] });
+        assert_eq!(
+            answer("workspace/configuration", &params).unwrap(),
+            json!([null, null])
+        );
+        assert_eq!(
+            answer("workspace/applyEdit", &Value::Null).unwrap(),
+            json!({ "applied": false })
+        );
+        assert!(answer("unknown/method", &Value::Null).is_err());
+    }
+}
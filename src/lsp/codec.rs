@@ -0,0 +1,81 @@
+//! LSP Message Framing
+//!
+//! Language servers exchange JSON-RPC messages over stdio, each preceded by
+//! a `Content-Length` header and a blank line.
+
+use serde_json::Value;
+use std::io;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
+
+/// Encode a message with its header
+pub fn encode_message(message: &Value) -> Vec<u8> {
+    let body = message.to_string();
+    let mut bytes = format!("Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+    bytes.extend_from_slice(body.as_bytes());
+    bytes
+}
+
+/// Read the next message, or `None` at end of stream
+pub async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue; // Stray blank line between messages
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                let length = value.trim().parse::<usize>().map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid Content-Length: {}", value.trim()),
+                    )
+                })?;
+                content_length = Some(length);
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length.unwrap_or_default()];
+    reader.read_exact(&mut body).await?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let first = json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" });
+        let second = json!({ "jsonrpc": "2.0", "method": "exit", "params": { "text": "héllo" } });
+        let mut bytes = encode_message(&first);
+        // Extra headers are allowed and ignored
+        bytes.extend_from_slice(b"Content-Type: application/vscode-jsonrpc; charset=utf-8\r\n");
+        bytes.extend_from_slice(&encode_message(&second));
+
+        let mut reader = BufReader::new(bytes.as_slice());
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(first));
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(second));
+        assert_eq!(read_message(&mut reader).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_length() {
+        let mut reader = BufReader::new(&b"Content-Length: abc\r\n\r\n{}"[..]);
+        assert!(read_message(&mut reader).await.is_err());
+    }
+}
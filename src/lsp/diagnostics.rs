@@ -0,0 +1,123 @@
+//! Diagnostics Reports
+//!
+//! Formats the errors and warnings published for edited files so they can
+//! be appended to a tool result.
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+
+/// Maximum number of diagnostics listed per file
+const MAX_PER_FILE: usize = 20;
+
+/// Report of the errors and warnings in `files`
+///
+/// Each file is given by its display path. Hints and information messages
+/// are left out; a file without errors or warnings is reported as clean.
+pub fn report(files: &[(String, Vec<Diagnostic>)]) -> String {
+    let mut lines = vec!["Diagnostics after the edit:".to_string()];
+    for (path, diagnostics) in files {
+        let problems: Vec<&Diagnostic> = diagnostics
+            .iter()
+            .filter(|d| severity(d).is_some())
+            .collect();
+        if problems.is_empty() {
+            lines.push(format!("{}: no errors or warnings", path));
+            continue;
+        }
+        for diagnostic in problems.iter().take(MAX_PER_FILE) {
+            lines.push(format_diagnostic(path, diagnostic));
+        }
+        if problems.len() > MAX_PER_FILE {
+            lines.push(format!(
+                "{}: ... and {} more",
+                path,
+                problems.len() - MAX_PER_FILE
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+/// `error` or `warning`; a missing severity counts as an error
+fn severity(diagnostic: &Diagnostic) -> Option<&'static str> {
+    match diagnostic.severity {
+        None | Some(DiagnosticSeverity::ERROR) => Some("error"),
+        Some(DiagnosticSeverity::WARNING) => Some("warning"),
+        Some(_) => None,
+    }
+}
+
+fn format_diagnostic(path: &str, diagnostic: &Diagnostic) -> String {
+    let severity = severity(diagnostic).unwrap_or("error");
+    let code = match &diagnostic.code {
+        Some(NumberOrString::String(code)) => format!("[{}]", code),
+        Some(NumberOrString::Number(code)) => format!("[{}]", code),
+        None => String::new(),
+    };
+    // Only the first line: the rest is usually notes and help text
+    let message = diagnostic.message.lines().next().unwrap_or_default();
+    let start = diagnostic.range.start;
+    format!(
+        "{}:{}:{}: {}{}: {}",
+        path,
+        start.line + 1,
+        start.character + 1,
+        severity,
+        code,
+        message
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{Position, Range};
+
+    fn diagnostic(line: u32, severity: Option<DiagnosticSeverity>, message: &str) -> Diagnostic {
+        Diagnostic {
+            range: Range::new(Position::new(line, 4), Position::new(line, 9)),
+            severity,
+            message: message.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_report() {
+        let mut error = diagnostic(
+            2,
+            Some(DiagnosticSeverity::ERROR),
+            "mismatched types\nnote: ...",
+        );
+        error.code = Some(NumberOrString::String("E0308".to_string()));
+        let files = vec![
+            (
+                "src/main.rs".to_string(),
+                vec![
+                    error,
+                    diagnostic(5, Some(DiagnosticSeverity::HINT), "consider this"),
+                    diagnostic(7, Some(DiagnosticSeverity::WARNING), "unused variable: `x`"),
+                ],
+            ),
+            (
+                "src/lib.rs".to_string(),
+                vec![diagnostic(0, Some(DiagnosticSeverity::INFORMATION), "fyi")],
+            ),
+        ];
+
+        assert_eq!(
+            report(&files),
+            "Diagnostics after the edit:\n\
+             src/main.rs:3:5: error[E0308]: mismatched types\n\
+             src/main.rs:8:5: warning: unused variable: `x`\n\
+             src/lib.rs: no errors or warnings"
+        );
+    }
+
+    #[test]
+    fn test_report_is_capped_per_file() {
+        let diagnostics = (0..25).map(|line| diagnostic(line, None, "bad")).collect();
+        let report = report(&[("a.py".to_string(), diagnostics)]);
+        assert_eq!(report.lines().count(), 1 + MAX_PER_FILE + 1);
+        assert!(report.ends_with("a.py: ... and 5 more"));
+    }
+}
@@ -0,0 +1,42 @@
+//! LSP error types
+
+use thiserror::Error;
+
+/// LSP error types
+#[derive(Debug, Error)]
+pub enum LspError {
+    /// No configured language server handles the file
+    #[error("No language server is configured for {0}")]
+    NoServer(String),
+
+    /// The server could not be started
+    #[error("Failed to start language server '{server}': {message}")]
+    Start { server: String, message: String },
+
+    /// The connection to the server was closed
+    #[error("Language server '{0}' is not running")]
+    Closed(String),
+
+    /// The server returned a JSON-RPC error
+    #[error("Language server error {code}: {message}")]
+    Server { code: i64, message: String },
+
+    /// A request did not complete in time
+    #[error("Language server request '{method}' timed out after {secs}s")]
+    Timeout { method: String, secs: u64 },
+
+    /// The server sent something that does not follow the protocol
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+
+    /// I/O error
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// JSON error
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Result type for LSP operations
+pub type Result<T> = std::result::Result<T, LspError>;
@@ -0,0 +1,313 @@
+//! LSP Manager
+//!
+//! Picks the language server for a file by its extension and starts it on
+//! first use, one per server and workspace root. A server that fails to
+//! start (usually because it is not installed) is not tried again.
+//!
+//! Files changed by tools are synced to the servers, and the diagnostics
+//! published for them are collected into a report for the tool result.
+
+use super::client::LspClient;
+use super::diagnostics;
+use super::error::{LspError, Result};
+use crate::config::{LspConfig, LspServerConfig};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Manages the language servers of a session
+pub struct LspManager {
+    servers: BTreeMap<String, LspServerConfig>,
+    working_directory: PathBuf,
+    diagnostics_wait: Duration,
+    trace: bool,
+    clients: Mutex<HashMap<(String, PathBuf), Arc<LspClient>>>,
+    unavailable: std::sync::Mutex<HashMap<String, String>>,
+}
+
+impl LspManager {
+    /// Create a manager for the configured servers; none is started yet
+    pub fn new(config: &LspConfig, working_directory: PathBuf) -> Self {
+        Self {
+            servers: config.effective_servers(),
+            working_directory,
+            diagnostics_wait: Duration::from_millis(config.diagnostics_wait_ms),
+            trace: false,
+            clients: Mutex::new(HashMap::new()),
+            unavailable: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Log every message exchanged with the servers
+    pub fn with_trace(mut self, trace: bool) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// Directory relative paths are resolved against
+    pub fn working_directory(&self) -> &Path {
+        &self.working_directory
+    }
+
+    /// Name and configuration of the server handling `path`
+    pub fn server_for(&self, path: &Path) -> Option<(&str, &LspServerConfig)> {
+        let extension = path.extension()?.to_str()?;
+        self.servers
+            .iter()
+            .find(|(_, server)| server.extensions.iter().any(|e| e == extension))
+            .map(|(name, server)| (name.as_str(), server))
+    }
+
+    /// Workspace root for `path`
+    ///
+    /// The outermost directory with a root marker inside the working
+    /// directory, so one server covers a whole Cargo or Go workspace. Files
+    /// outside the working directory use their nearest marker.
+    fn workspace_root(&self, path: &Path, server: &LspServerConfig) -> PathBuf {
+        let has_marker = |dir: &Path| server.root_markers.iter().any(|m| dir.join(m).exists());
+        let mut markers = path.ancestors().skip(1).filter(|dir| has_marker(dir));
+
+        if path.starts_with(&self.working_directory) {
+            markers
+                .filter(|dir| dir.starts_with(&self.working_directory))
+                .last()
+                .unwrap_or(&self.working_directory)
+                .to_path_buf()
+        } else {
+            markers
+                .next()
+                .or_else(|| path.parent())
+                .unwrap_or(&self.working_directory)
+                .to_path_buf()
+        }
+    }
+
+    /// Client for the server handling `path`, starting it if needed
+    pub async fn client_for(&self, path: &Path) -> Result<Arc<LspClient>> {
+        let Some((name, server)) = self.server_for(path) else {
+            let kind = match path.extension() {
+                Some(extension) => format!(".{} files", extension.to_string_lossy()),
+                None => format!("{}", path.display()),
+            };
+            return Err(LspError::NoServer(kind));
+        };
+        let root = self.workspace_root(path, server);
+        self.start(name, server, root).await
+    }
+
+    async fn start(
+        &self,
+        name: &str,
+        server: &LspServerConfig,
+        root: PathBuf,
+    ) -> Result<Arc<LspClient>> {
+        if let Some(message) = self.unavailable.lock().unwrap().get(name) {
+            return Err(LspError::Start {
+                server: name.to_string(),
+                message: message.clone(),
+            });
+        }
+
+        // Held while starting so a server is never started twice
+        let mut clients = self.clients.lock().await;
+        let key = (name.to_string(), root);
+        if let Some(client) = clients.get(&key) {
+            return Ok(client.clone());
+        }
+
+        match LspClient::start(name, server, &key.1, self.trace).await {
+            Ok(client) => {
+                let client = Arc::new(client);
+                clients.insert(key, client.clone());
+                Ok(client)
+            }
+            Err(e) => {
+                tracing::warn!("{}", e);
+                let message = match &e {
+                    LspError::Start { message, .. } => message.clone(),
+                    other => other.to_string(),
+                };
+                self.unavailable
+                    .lock()
+                    .unwrap()
+                    .insert(name.to_string(), message);
+                Err(e)
+            }
+        }
+    }
+
+    /// Clients for the working directory, for queries not tied to a file
+    ///
+    /// Starts every server with a root marker in the working directory.
+    pub async fn workspace_clients(&self) -> Vec<Arc<LspClient>> {
+        let mut clients = Vec::new();
+        for (name, server) in &self.servers {
+            let has_marker = server
+                .root_markers
+                .iter()
+                .any(|m| self.working_directory.join(m).exists());
+            if !has_marker {
+                continue;
+            }
+            if let Ok(client) = self
+                .start(name, server, self.working_directory.clone())
+                .await
+            {
+                clients.push(client);
+            }
+        }
+        clients
+    }
+
+    /// Tell running servers that a file changed on disk
+    ///
+    /// Servers are not started for this; files no server has opened are
+    /// skipped.
+    pub async fn file_changed(&self, path: &Path) {
+        let Some((name, server)) = self.server_for(path) else {
+            return;
+        };
+        let key = (name.to_string(), self.workspace_root(path, server));
+        let Some(client) = self.clients.lock().await.get(&key).cloned() else {
+            return;
+        };
+        if !client.is_open(path).await {
+            return;
+        }
+        if let Err(e) = client.sync_file(path).await {
+            tracing::debug!("Failed to sync {:?} with '{}': {}", path, name, e);
+        }
+    }
+
+    /// Sync files a tool changed and report the diagnostics that follow
+    ///
+    /// Servers are started as needed, but one that has not published any
+    /// diagnostics yet is still indexing and is only sent the file: waiting on
+    /// it would hold up every edit until indexing ends. Otherwise this waits up
+    /// to the configured time for each server to publish; files whose content
+    /// did not change, deleted files and servers that stay silent are left
+    /// out of the report. Without a wait, this only syncs documents that are
+    /// already open.
+    pub async fn files_edited(&self, paths: &[PathBuf]) -> Option<String> {
+        if self.diagnostics_wait.is_zero() {
+            for path in paths {
+                self.file_changed(path).await;
+            }
+            return None;
+        }
+
+        let checks = paths.iter().map(|path| async move {
+            let client = self.client_for(path).await.ok()?;
+            let since = client.diagnostics_sequence();
+            let uri = match client.update_file(path).await {
+                Ok(uri) => uri?,
+                Err(e) => {
+                    tracing::debug!("Failed to sync {:?} with '{}': {}", path, client.name(), e);
+                    return None;
+                }
+            };
+            if since == 0 {
+                return None;
+            }
+            let diagnostics = client
+                .wait_for_diagnostics(&uri, since, self.diagnostics_wait)
+                .await?;
+            let display = path
+                .strip_prefix(&self.working_directory)
+                .unwrap_or(path)
+                .display()
+                .to_string();
+            Some((display, diagnostics))
+        });
+
+        let files: Vec<_> = futures::future::join_all(checks)
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+        (!files.is_empty()).then(|| diagnostics::report(&files))
+    }
+
+    /// Servers currently running
+    pub async fn running(&self) -> Vec<Arc<LspClient>> {
+        self.clients.lock().await.values().cloned().collect()
+    }
+
+    /// Stop all servers
+    pub async fn shutdown(&self) {
+        let clients: Vec<_> = self.clients.lock().await.drain().map(|(_, c)| c).collect();
+        for client in clients {
+            client.shutdown().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_server_for_extension() {
+        let manager = LspManager::new(&LspConfig::default(), PathBuf::from("/work"));
+        assert_eq!(
+            manager.server_for(Path::new("src/main.rs")).unwrap().0,
+            "rust"
+        );
+        assert_eq!(manager.server_for(Path::new("app.py")).unwrap().0, "python");
+        assert!(manager.server_for(Path::new("README.md")).is_none());
+        assert!(manager.server_for(Path::new("Makefile")).is_none());
+
+        let disabled = LspConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        let manager = LspManager::new(&disabled, PathBuf::from("/work"));
+        assert!(manager.server_for(Path::new("src/main.rs")).is_none());
+    }
+
+    #[test]
+    fn test_workspace_root_is_outermost_marker() {
+        let dir = TempDir::new().unwrap();
+        let work = dir.path().join("work");
+        std::fs::create_dir_all(work.join("crates/core/src")).unwrap();
+        std::fs::write(work.join("Cargo.toml"), "").unwrap();
+        std::fs::write(work.join("crates/core/Cargo.toml"), "").unwrap();
+        // Above the working directory: ignored
+        std::fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+
+        let manager = LspManager::new(&LspConfig::default(), work.clone());
+        let (_, rust) = manager.server_for(Path::new("x.rs")).unwrap();
+        let file = work.join("crates/core/src/lib.rs");
+        assert_eq!(manager.workspace_root(&file, rust), work);
+
+        // Without markers the working directory is used
+        let (_, go) = manager.server_for(Path::new("x.go")).unwrap();
+        assert_eq!(manager.workspace_root(&work.join("main.go"), go), work);
+    }
+
+    #[tokio::test]
+    async fn test_missing_server_is_not_retried() {
+        let mut config = LspConfig::default();
+        config.servers.insert(
+            "rust".to_string(),
+            LspServerConfig::new("/nonexistent/language-server", &["rs"]),
+        );
+        let dir = TempDir::new().unwrap();
+        let manager = LspManager::new(&config, dir.path().to_path_buf());
+
+        let file = dir.path().join("main.rs");
+        assert!(matches!(
+            manager.client_for(&file).await,
+            Err(LspError::Start { .. })
+        ));
+        assert!(manager.unavailable.lock().unwrap().contains_key("rust"));
+        assert!(matches!(
+            manager.client_for(&file).await,
+            Err(LspError::Start { .. })
+        ));
+        assert!(manager.running().await.is_empty());
+    }
+}
@@ -1,15 +1,30 @@
-//! Language Server Protocol Integration (Placeholder)
+//! Language Server Protocol Integration
 //!
-//! This module is a placeholder for LSP integration.
+//! Client for language servers such as rust-analyzer, pyright and gopls.
+//! Servers are started on first use for a file, one per workspace root, and
+//! kept in sync with the files tools write. The `lsp` and `rename_symbol`
+//! tools use them for go-to-definition, references, hover, workspace symbols
+//! and renames, and the diagnostics published after an edit are added to the
+//! editing tool's result.
 //!
-//! ## Planned Features
-//! - Semantic code understanding via LSP
-//! - Go-to-definition, find references
-//! - Code completion and analysis
-//! - Integration with AI tool system
+//! ```toml
+//! [lsp.servers.typescript]
+//! command = "typescript-language-server"
+//! args = ["--stdio"]
+//! extensions = ["ts", "tsx"]
+//! root_markers = ["package.json", "tsconfig.json"]
+//! ```
 //!
-//! ## Status
-//! 🔴 Not implemented - See FUTURE_FEATURES.md for roadmap
-//!
-//! ## Priority
-//! High - Would significantly improve AI code understanding
+//! Servers for Rust, Python and Go are built in.
+
+pub mod client;
+pub mod codec;
+pub mod diagnostics;
+pub mod error;
+pub mod manager;
+pub mod text;
+
+// Re-exports
+pub use client::LspClient;
+pub use error::{LspError, Result};
+pub use manager::LspManager;
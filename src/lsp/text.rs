@@ -0,0 +1,124 @@
+//! Text Positions
+//!
+//! LSP positions count lines from zero and columns in UTF-16 code units.
+//! These helpers convert them to byte offsets, locate symbols on a line and
+//! apply text edits.
+
+use lsp_types::{Position, TextEdit};
+
+/// Byte offset of `position` in `text`, clamped to the text
+pub fn position_to_offset(text: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(newline) => line_start += newline + 1,
+            None => return text.len(),
+        }
+    }
+
+    let line_end = text[line_start..]
+        .find('\n')
+        .map_or(text.len(), |newline| line_start + newline);
+    let mut units = 0;
+    for (index, c) in text[line_start..line_end].char_indices() {
+        if units >= position.character {
+            return line_start + index;
+        }
+        units += c.len_utf16() as u32;
+    }
+    line_end
+}
+
+/// UTF-16 column of the first whole-word occurrence of `symbol` in `line`
+pub fn find_symbol(line: &str, symbol: &str) -> Option<u32> {
+    if symbol.is_empty() {
+        return None;
+    }
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut start = 0;
+    while let Some(found) = line[start..].find(symbol) {
+        let begin = start + found;
+        let end = begin + symbol.len();
+        let before = line[..begin].chars().next_back();
+        let after = line[end..].chars().next();
+        if !before.is_some_and(is_word) && !after.is_some_and(is_word) {
+            return Some(line[..begin].encode_utf16().count() as u32);
+        }
+        start = begin + symbol.chars().next().map_or(1, char::len_utf8);
+    }
+    None
+}
+
+/// Apply edits to `text`
+///
+/// Edits refer to the original text and must not overlap, as the protocol
+/// requires.
+pub fn apply_edits(text: &str, edits: &[TextEdit]) -> String {
+    let mut ranges: Vec<(usize, usize, &str)> = edits
+        .iter()
+        .map(|edit| {
+            let start = position_to_offset(text, edit.range.start);
+            let end = position_to_offset(text, edit.range.end).max(start);
+            (start, end, edit.new_text.as_str())
+        })
+        .collect();
+    ranges.sort_by_key(|(start, end, _)| (*start, *end));
+
+    let mut result = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (start, end, new_text) in ranges {
+        let start = start.max(copied);
+        result.push_str(&text[copied..start]);
+        result.push_str(new_text);
+        copied = end.max(start);
+    }
+    result.push_str(&text[copied..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::Range;
+
+    fn edit(start: (u32, u32), end: (u32, u32), new_text: &str) -> TextEdit {
+        TextEdit {
+            range: Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1)),
+            new_text: new_text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_position_to_offset_counts_utf16() {
+        let text = "let a = 1;\nlet 😀b = 2;\n";
+        assert_eq!(position_to_offset(text, Position::new(0, 4)), 4);
+        // The emoji takes two UTF-16 units and four bytes
+        assert_eq!(position_to_offset(text, Position::new(1, 6)), 11 + 8);
+        // Past the end of a line or the text is clamped
+        assert_eq!(position_to_offset(text, Position::new(0, 99)), 10);
+        assert_eq!(position_to_offset(text, Position::new(9, 0)), text.len());
+    }
+
+    #[test]
+    fn test_find_symbol_matches_whole_words() {
+        assert_eq!(find_symbol("let total = sum(totals);", "total"), Some(4));
+        assert_eq!(find_symbol("subtotal + total", "total"), Some(11));
+        assert_eq!(find_symbol("é total", "total"), Some(2));
+        assert_eq!(find_symbol("totals", "total"), None);
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let text = "fn old() {}\nold();\nold();\n";
+        let edits = vec![
+            edit((2, 0), (2, 3), "new"),
+            edit((0, 3), (0, 6), "new"),
+            edit((1, 0), (1, 3), "new"),
+        ];
+        assert_eq!(apply_edits(text, &edits), "fn new() {}\nnew();\nnew();\n");
+
+        // Insertions at the same point keep their order
+        let edits = vec![edit((0, 0), (0, 0), "a"), edit((0, 0), (0, 0), "b")];
+        assert_eq!(apply_edits("x", &edits), "abx");
+    }
+}
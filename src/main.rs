@@ -1,9 +1,19 @@
 use anyhow::Result;
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use crustly::{cli, logging};
 
+fn main() -> Result<()> {
+    // Answer shell completion requests (`COMPLETE=<shell> crustly ...`)
+    // before anything else touches stdout
+    clap_complete::CompleteEnv::with_factory(cli::Cli::command)
+        .var(cli::completion::COMPLETE_VAR)
+        .complete();
+
+    run()
+}
+
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn run() -> Result<()> {
     // Parse CLI arguments first to check for debug flag
     let cli_args = cli::Cli::parse();
 
@@ -23,5 +33,16 @@ async fn main() -> Result<()> {
     }
 
     // Run CLI application
-    cli::run().await
+    match cli::run().await {
+        Err(e) => match e.downcast_ref::<cli::ExitError>() {
+            Some(exit) => {
+                eprintln!("Error: {}", exit.message);
+                // Flush the log files before exiting
+                drop(_guard);
+                std::process::exit(exit.code);
+            }
+            None => Err(e),
+        },
+        ok => ok,
+    }
 }
@@ -0,0 +1,445 @@
+//! MCP Client
+//!
+//! Speaks JSON-RPC to one MCP server over any [`Transport`]: performs the
+//! initialize handshake, matches responses to requests, and answers the few
+//! requests a server may send to the client.
+
+use super::error::{McpError, Result};
+use super::protocol::{
+    CallToolResult, GetPromptResult, Implementation, InitializeResult, JsonRpcMessage,
+    JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, ListPromptsResult, ListResourcesResult,
+    ListToolsResult, McpPrompt, McpResource, McpToolDefinition, ReadResourceResult,
+    METHOD_NOT_FOUND, PROTOCOL_VERSION,
+};
+use super::transport::{IncomingMessages, Transport};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex};
+
+/// Default timeout for a single request
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 60;
+
+type PendingRequests = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value>>>>>;
+
+/// Client connection to a single MCP server
+pub struct McpClient {
+    name: String,
+    transport: Arc<dyn Transport>,
+    pending: PendingRequests,
+    next_id: AtomicU64,
+    timeout: Duration,
+    server_info: Implementation,
+    capabilities: Value,
+    instructions: Option<String>,
+}
+
+impl McpClient {
+    /// Connect over a transport and perform the initialize handshake
+    pub async fn connect(
+        name: impl Into<String>,
+        transport: Arc<dyn Transport>,
+        incoming: IncomingMessages,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let name = name.into();
+        let pending: PendingRequests = Arc::new(Mutex::new(HashMap::new()));
+
+        // The dispatcher only holds a weak reference so dropping the client
+        // drops the transport (and stops a stdio server process)
+        tokio::spawn(dispatch(
+            name.clone(),
+            incoming,
+            Arc::downgrade(&transport),
+            pending.clone(),
+        ));
+
+        let mut client = Self {
+            name,
+            transport,
+            pending,
+            next_id: AtomicU64::new(1),
+            timeout,
+            server_info: Implementation::default(),
+            capabilities: Value::Null,
+            instructions: None,
+        };
+
+        let init = client.initialize().await?;
+
+        tracing::info!(
+            "Connected to MCP server '{}' ({} {}, protocol {})",
+            client.name,
+            init.server_info.name,
+            init.server_info.version,
+            init.protocol_version
+        );
+
+        client.server_info = init.server_info;
+        client.capabilities = init.capabilities;
+        client.instructions = init.instructions;
+        Ok(client)
+    }
+
+    /// Perform the initialize handshake
+    async fn initialize(&self) -> Result<InitializeResult> {
+        let result = self
+            .send_request(
+                "initialize",
+                Some(serde_json::json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {
+                        "name": "crustly",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                })),
+            )
+            .await?;
+        let init: InitializeResult = serde_json::from_value(result)
+            .map_err(|e| McpError::Protocol(format!("invalid initialize result: {}", e)))?;
+
+        self.notify("notifications/initialized", None).await?;
+        Ok(init)
+    }
+
+    /// Name of the server in the configuration
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Name and version reported by the server
+    pub fn server_info(&self) -> &Implementation {
+        &self.server_info
+    }
+
+    /// Capabilities reported by the server
+    pub fn capabilities(&self) -> &Value {
+        &self.capabilities
+    }
+
+    /// Usage instructions provided by the server, if any
+    pub fn instructions(&self) -> Option<&str> {
+        self.instructions.as_deref()
+    }
+
+    /// Send a request and wait for its result
+    ///
+    /// If the server reports that the session expired, the handshake is
+    /// repeated and the request retried once.
+    pub async fn request(&self, method: &str, params: Option<Value>) -> Result<Value> {
+        match self.send_request(method, params.clone()).await {
+            Err(McpError::SessionExpired) => {
+                tracing::info!(
+                    "MCP session with '{}' expired, initializing again",
+                    self.name
+                );
+                self.initialize().await?;
+                self.send_request(method, params).await
+            }
+            result => result,
+        }
+    }
+
+    async fn send_request(&self, method: &str, params: Option<Value>) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().await.insert(id, tx);
+
+        let request = JsonRpcRequest::new(id, method, params);
+        if let Err(e) = self.transport.send(serde_json::to_value(&request)?).await {
+            self.pending.lock().await.remove(&id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(McpError::Closed),
+            Err(_) => {
+                self.pending.lock().await.remove(&id);
+                // Let the server know it can stop working on the request
+                let _ = self
+                    .notify(
+                        "notifications/cancelled",
+                        Some(serde_json::json!({ "requestId": id, "reason": "timeout" })),
+                    )
+                    .await;
+                Err(McpError::Timeout {
+                    method: method.to_string(),
+                    secs: self.timeout.as_secs(),
+                })
+            }
+        }
+    }
+
+    /// Send a notification
+    pub async fn notify(&self, method: &str, params: Option<Value>) -> Result<()> {
+        let notification = JsonRpcNotification::new(method, params);
+        self.transport
+            .send(serde_json::to_value(&notification)?)
+            .await
+    }
+
+    /// Whether the server declared a capability (`tools`, `resources`, `prompts`, ...)
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities
+            .get(capability)
+            .is_some_and(|value| !value.is_null())
+    }
+
+    /// List all tools exposed by the server
+    pub async fn list_tools(&self) -> Result<Vec<McpToolDefinition>> {
+        self.list_all("tools/list", |page: ListToolsResult| {
+            (page.tools, page.next_cursor)
+        })
+        .await
+    }
+
+    /// List all resources exposed by the server
+    pub async fn list_resources(&self) -> Result<Vec<McpResource>> {
+        self.list_all("resources/list", |page: ListResourcesResult| {
+            (page.resources, page.next_cursor)
+        })
+        .await
+    }
+
+    /// List all prompt templates exposed by the server
+    pub async fn list_prompts(&self) -> Result<Vec<McpPrompt>> {
+        self.list_all("prompts/list", |page: ListPromptsResult| {
+            (page.prompts, page.next_cursor)
+        })
+        .await
+    }
+
+    /// Follow `nextCursor` through every page of a list request
+    async fn list_all<P, T>(
+        &self,
+        method: &str,
+        split: impl Fn(P) -> (Vec<T>, Option<String>),
+    ) -> Result<Vec<T>>
+    where
+        P: serde::de::DeserializeOwned,
+    {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let params = cursor
+                .as_ref()
+                .map(|cursor| serde_json::json!({ "cursor": cursor }));
+            let result = self.request(method, params).await?;
+            let page: P = serde_json::from_value(result)
+                .map_err(|e| McpError::Protocol(format!("invalid {} result: {}", method, e)))?;
+
+            let (page_items, next_cursor) = split(page);
+            items.extend(page_items);
+            match next_cursor {
+                Some(next) if !next.is_empty() => cursor = Some(next),
+                _ => break,
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// Read a resource
+    pub async fn read_resource(&self, uri: &str) -> Result<ReadResourceResult> {
+        let result = self
+            .request("resources/read", Some(serde_json::json!({ "uri": uri })))
+            .await?;
+        serde_json::from_value(result)
+            .map_err(|e| McpError::Protocol(format!("invalid resources/read result: {}", e)))
+    }
+
+    /// Render a prompt template with the given arguments
+    pub async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: &BTreeMap<String, String>,
+    ) -> Result<GetPromptResult> {
+        let result = self
+            .request(
+                "prompts/get",
+                Some(serde_json::json!({ "name": name, "arguments": arguments })),
+            )
+            .await?;
+        serde_json::from_value(result)
+            .map_err(|e| McpError::Protocol(format!("invalid prompts/get result: {}", e)))
+    }
+
+    /// Call a tool on the server
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<CallToolResult> {
+        let result = self
+            .request(
+                "tools/call",
+                Some(serde_json::json!({ "name": name, "arguments": arguments })),
+            )
+            .await?;
+        serde_json::from_value(result)
+            .map_err(|e| McpError::Protocol(format!("invalid tools/call result: {}", e)))
+    }
+
+    /// Close the connection
+    pub async fn close(&self) -> Result<()> {
+        self.transport.close().await
+    }
+}
+
+/// Route incoming messages until the transport closes
+async fn dispatch(
+    name: String,
+    mut incoming: IncomingMessages,
+    transport: Weak<dyn Transport>,
+    pending: PendingRequests,
+) {
+    while let Some(message) = incoming.recv().await {
+        match JsonRpcMessage::from_value(message) {
+            Ok(JsonRpcMessage::Response(response)) => {
+                let Some(id) = response.id.as_u64() else {
+                    tracing::warn!("MCP server '{}' sent a response with an unknown id", name);
+                    continue;
+                };
+                let Some(tx) = pending.lock().await.remove(&id) else {
+                    continue; // Timed out or cancelled
+                };
+                let result = match response.error {
+                    Some(error) => Err(McpError::Server {
+                        code: error.code,
+                        message: error.message,
+                    }),
+                    None => Ok(response.result.unwrap_or(Value::Null)),
+                };
+                let _ = tx.send(result);
+            }
+            Ok(JsonRpcMessage::Request(request)) => {
+                let response = match request.method.as_str() {
+                    "ping" => JsonRpcResponse::success(request.id, serde_json::json!({})),
+                    method => JsonRpcResponse::error(
+                        request.id,
+                        METHOD_NOT_FOUND,
+                        format!("Method not supported by client: {}", method),
+                    ),
+                };
+                if let (Ok(response), Some(transport)) =
+                    (serde_json::to_value(&response), transport.upgrade())
+                {
+                    let _ = transport.send(response).await;
+                }
+            }
+            Ok(JsonRpcMessage::Notification(notification)) => {
+                tracing::debug!(
+                    "MCP server '{}' notification: {}",
+                    name,
+                    notification.method
+                );
+            }
+            Err(e) => {
+                tracing::warn!("MCP server '{}' sent an invalid message: {}", name, e);
+            }
+        }
+    }
+
+    // Fail everything still waiting on the closed connection
+    for (_, tx) in pending.lock().await.drain() {
+        let _ = tx.send(Err(McpError::Closed));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use tokio::sync::mpsc;
+
+    /// In-memory server that answers requests from a handler
+    struct LoopbackTransport {
+        to_client: mpsc::UnboundedSender<Value>,
+        sent: std::sync::Mutex<Vec<Value>>,
+    }
+
+    #[async_trait]
+    impl Transport for LoopbackTransport {
+        async fn send(&self, message: Value) -> Result<()> {
+            self.sent.lock().unwrap().push(message.clone());
+            let Some(id) = message.get("id").cloned() else {
+                return Ok(());
+            };
+            let result = match message["method"].as_str() {
+                Some("initialize") => serde_json::json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": { "tools": {} },
+                    "serverInfo": { "name": "loopback", "version": "1.0" }
+                }),
+                Some("tools/list") if message["params"]["cursor"].is_null() => serde_json::json!({
+                    "tools": [{ "name": "first" }],
+                    "nextCursor": "page2"
+                }),
+                Some("tools/list") => serde_json::json!({ "tools": [{ "name": "second" }] }),
+                Some("slow") => return Ok(()),
+                _ => {
+                    let _ = self.to_client.send(serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": METHOD_NOT_FOUND, "message": "nope" }
+                    }));
+                    return Ok(());
+                }
+            };
+            let _ = self.to_client.send(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": result
+            }));
+            Ok(())
+        }
+
+        async fn close(&self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    async fn connect(timeout: Duration) -> (McpClient, Arc<LoopbackTransport>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let transport = Arc::new(LoopbackTransport {
+            to_client: tx,
+            sent: std::sync::Mutex::new(Vec::new()),
+        });
+        let client = McpClient::connect("loop", transport.clone(), rx, timeout)
+            .await
+            .unwrap();
+        (client, transport)
+    }
+
+    #[tokio::test]
+    async fn test_handshake_and_paginated_tools() {
+        let (client, transport) = connect(Duration::from_secs(5)).await;
+        assert_eq!(client.server_info().name, "loopback");
+
+        let sent = transport.sent.lock().unwrap().clone();
+        assert_eq!(sent[1]["method"], "notifications/initialized");
+
+        let tools = client.list_tools().await.unwrap();
+        let names: Vec<_> = tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["first", "second"]);
+    }
+
+    #[tokio::test]
+    async fn test_server_error_and_timeout() {
+        let (client, _) = connect(Duration::from_millis(100)).await;
+
+        let err = client.request("unknown", None).await.unwrap_err();
+        assert!(matches!(
+            err,
+            McpError::Server {
+                code: METHOD_NOT_FOUND,
+                ..
+            }
+        ));
+
+        let err = client.request("slow", None).await.unwrap_err();
+        assert!(matches!(err, McpError::Timeout { .. }));
+        assert!(client.pending.lock().await.is_empty());
+    }
+}
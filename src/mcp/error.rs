@@ -0,0 +1,46 @@
+//! MCP error types
+
+use thiserror::Error;
+
+/// MCP error types
+#[derive(Debug, Error)]
+pub enum McpError {
+    /// The server could not be started or reached
+    #[error("Failed to connect to MCP server '{server}': {message}")]
+    Connection { server: String, message: String },
+
+    /// Transport-level failure while exchanging messages
+    #[error("Transport error: {0}")]
+    Transport(String),
+
+    /// The connection was closed by the server
+    #[error("MCP connection closed")]
+    Closed,
+
+    /// The server no longer recognizes the session; the client must initialize again
+    #[error("MCP session expired")]
+    SessionExpired,
+
+    /// The server returned a JSON-RPC error
+    #[error("MCP server error {code}: {message}")]
+    Server { code: i64, message: String },
+
+    /// The server sent something that does not follow the protocol
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+
+    /// A request did not complete in time
+    #[error("MCP request '{method}' timed out after {secs}s")]
+    Timeout { method: String, secs: u64 },
+
+    /// I/O error
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// JSON error
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Result type for MCP operations
+pub type Result<T> = std::result::Result<T, McpError>;
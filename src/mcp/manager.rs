@@ -0,0 +1,325 @@
+//! MCP Server Manager
+//!
+//! Starts the servers declared in the configuration, registers their tools in
+//! a [`ToolRegistry`], and keeps track of the resources and prompt templates
+//! they offer to the chat UI.
+
+use super::client::McpClient;
+use super::error::{McpError, Result};
+use super::protocol::{McpPrompt, McpResource};
+use super::tool::McpTool;
+use super::transport::{
+    HttpOptions, SseTransport, StdioTransport, StreamableHttpTransport, Transport,
+};
+use crate::config::{McpConfig, McpServerConfig, McpTransportKind};
+use crate::llm::tools::ToolRegistry;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A resource and the server offering it
+#[derive(Debug, Clone)]
+pub struct ServerResource {
+    pub server: String,
+    pub resource: McpResource,
+}
+
+impl ServerResource {
+    /// Reference to the resource in chat input: `@<server>:<uri>`
+    pub fn reference(&self) -> String {
+        format!("@{}:{}", self.server, self.resource.uri)
+    }
+}
+
+/// A prompt template and the server offering it
+#[derive(Debug, Clone)]
+pub struct ServerPrompt {
+    pub server: String,
+    pub prompt: McpPrompt,
+}
+
+impl ServerPrompt {
+    /// Slash command running the prompt: `/<server>:<prompt>`
+    pub fn command(&self) -> String {
+        format!("/{}:{}", self.server, self.prompt.name)
+    }
+}
+
+/// Connected MCP servers and what they expose
+#[derive(Default)]
+pub struct McpManager {
+    clients: Vec<Arc<McpClient>>,
+    tools: Vec<Arc<McpTool>>,
+    resources: Vec<ServerResource>,
+    prompts: Vec<ServerPrompt>,
+}
+
+impl McpManager {
+    /// Connect to every enabled server in the configuration
+    ///
+    /// Servers that fail to start are logged and skipped so one broken server
+    /// does not prevent the others from being used.
+    pub async fn start(config: &McpConfig) -> Self {
+        let mut manager = Self::default();
+
+        for (name, server) in config.servers.iter().filter(|(_, s)| s.enabled) {
+            match connect_server(name, server).await {
+                Ok(client) => {
+                    let client = Arc::new(client);
+                    match client.list_tools().await {
+                        Ok(definitions) => {
+                            tracing::info!(
+                                "MCP server '{}' provides {} tool(s)",
+                                name,
+                                definitions.len()
+                            );
+                            manager.tools.extend(
+                                definitions
+                                    .into_iter()
+                                    .map(|d| Arc::new(McpTool::new(client.clone(), d))),
+                            );
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to list tools of MCP server '{}': {}", name, e)
+                        }
+                    }
+                    manager.load_resources_and_prompts(&client).await;
+                    manager.clients.push(client);
+                }
+                Err(e) => tracing::warn!("Skipping MCP server '{}': {}", name, e),
+            }
+        }
+
+        manager
+    }
+
+    async fn load_resources_and_prompts(&mut self, client: &McpClient) {
+        let name = client.name();
+        if client.supports("resources") {
+            match client.list_resources().await {
+                Ok(resources) => {
+                    self.resources
+                        .extend(resources.into_iter().map(|resource| ServerResource {
+                            server: name.to_string(),
+                            resource,
+                        }))
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to list resources of MCP server '{}': {}", name, e)
+                }
+            }
+        }
+        if client.supports("prompts") {
+            match client.list_prompts().await {
+                Ok(prompts) => {
+                    self.prompts
+                        .extend(prompts.into_iter().map(|prompt| ServerPrompt {
+                            server: name.to_string(),
+                            prompt,
+                        }))
+                }
+                Err(e) => tracing::warn!("Failed to list prompts of MCP server '{}': {}", name, e),
+            }
+        }
+    }
+
+    /// Connected servers
+    pub fn clients(&self) -> &[Arc<McpClient>] {
+        &self.clients
+    }
+
+    /// Connected server with the given name
+    pub fn client(&self, name: &str) -> Option<&Arc<McpClient>> {
+        self.clients.iter().find(|c| c.name() == name)
+    }
+
+    /// Resources offered by the connected servers
+    pub fn resources(&self) -> &[ServerResource] {
+        &self.resources
+    }
+
+    /// Prompt templates offered by the connected servers
+    pub fn prompts(&self) -> &[ServerPrompt] {
+        &self.prompts
+    }
+
+    /// Find the prompt run by a slash command such as `/server:prompt`
+    pub fn find_prompt(&self, command: &str) -> Option<&ServerPrompt> {
+        self.prompts.iter().find(|p| p.command() == command)
+    }
+
+    /// Read a resource as text
+    pub async fn read_resource(&self, server: &str, uri: &str) -> Result<String> {
+        let client = self.client(server).ok_or_else(|| unknown_server(server))?;
+        Ok(client.read_resource(uri).await?.to_text())
+    }
+
+    /// Render a prompt template into the text of a chat message
+    pub async fn get_prompt(
+        &self,
+        prompt: &ServerPrompt,
+        arguments: &BTreeMap<String, String>,
+    ) -> Result<String> {
+        let client = self
+            .client(&prompt.server)
+            .ok_or_else(|| unknown_server(&prompt.server))?;
+        Ok(client
+            .get_prompt(&prompt.prompt.name, arguments)
+            .await?
+            .to_text())
+    }
+
+    /// Append the contents of every `@server:uri` reference in `text`
+    ///
+    /// Resources that cannot be read are replaced by an error note so the
+    /// model knows the reference was not resolved.
+    pub async fn inline_resources(&self, text: &str) -> String {
+        let references = resource_references(text, |server| self.client(server).is_some());
+        let mut output = text.to_string();
+
+        for (server, uri) in references {
+            let content = match self.read_resource(&server, &uri).await {
+                Ok(content) => content,
+                Err(e) => format!("[Failed to read resource: {}]", e),
+            };
+            output.push_str(&format!(
+                "\n\n<resource server=\"{}\" uri=\"{}\">\n{}\n</resource>",
+                server, uri, content
+            ));
+        }
+
+        output
+    }
+
+    /// Tools exposed by the connected servers
+    pub fn tools(&self) -> &[Arc<McpTool>] {
+        &self.tools
+    }
+
+    /// Register all server tools, returning how many were added
+    ///
+    /// Tools whose names collide with an already registered tool are skipped.
+    pub fn register_tools(&self, registry: &mut ToolRegistry) -> usize {
+        use crate::llm::tools::Tool;
+
+        let mut registered = 0;
+        for tool in &self.tools {
+            if registry.has_tool(tool.name()) {
+                tracing::warn!(
+                    "Skipping MCP tool '{}': name already registered",
+                    tool.name()
+                );
+                continue;
+            }
+            registry.register(tool.clone());
+            registered += 1;
+        }
+        registered
+    }
+
+    /// Close all server connections
+    pub async fn shutdown(&self) {
+        for client in &self.clients {
+            if let Err(e) = client.close().await {
+                tracing::debug!("Error closing MCP server '{}': {}", client.name(), e);
+            }
+        }
+    }
+}
+
+fn unknown_server(server: &str) -> McpError {
+    McpError::Connection {
+        server: server.to_string(),
+        message: "server is not connected".to_string(),
+    }
+}
+
+/// Find the `@server:uri` references in chat input
+///
+/// Only servers accepted by `is_server` count, so e-mail addresses and other
+/// `@` words are left alone. Each reference is returned once.
+pub fn resource_references(text: &str, is_server: impl Fn(&str) -> bool) -> Vec<(String, String)> {
+    let mut references: Vec<(String, String)> = Vec::new();
+
+    for word in text.split_whitespace() {
+        let Some(reference) = word.strip_prefix('@') else {
+            continue;
+        };
+        let reference = reference.trim_end_matches([',', ';', '.', '!', '?', ')']);
+        let Some((server, uri)) = reference.split_once(':') else {
+            continue;
+        };
+        if uri.is_empty() || !is_server(server) {
+            continue;
+        }
+        let reference = (server.to_string(), uri.to_string());
+        if !references.contains(&reference) {
+            references.push(reference);
+        }
+    }
+
+    references
+}
+
+/// Connect to one server and perform the handshake
+async fn connect_server(name: &str, config: &McpServerConfig) -> Result<McpClient> {
+    let timeout = Duration::from_secs(config.timeout_secs);
+    let missing = |field: &str| McpError::Connection {
+        server: name.to_string(),
+        message: format!("no {} configured", field),
+    };
+
+    let (transport, incoming): (Arc<dyn Transport>, _) = match config.transport_kind() {
+        McpTransportKind::Stdio => {
+            let command = config
+                .command
+                .as_deref()
+                .ok_or_else(|| missing("command"))?;
+            let (transport, incoming) = StdioTransport::spawn(
+                name,
+                command,
+                &config.args,
+                &config.env,
+                config.cwd.as_ref(),
+            )?;
+            (Arc::new(transport), incoming)
+        }
+        kind => {
+            let url = config.url.as_deref().ok_or_else(|| missing("url"))?;
+            let options = HttpOptions {
+                headers: config.headers.clone(),
+                bearer_token: config.resolve_bearer_token(name),
+                timeout,
+            };
+            if kind == McpTransportKind::Sse {
+                let (transport, incoming) = SseTransport::connect(name, url, &options).await?;
+                (Arc::new(transport), incoming)
+            } else {
+                let (transport, incoming) = StreamableHttpTransport::new(name, url, &options)?;
+                (Arc::new(transport), incoming)
+            }
+        }
+    };
+
+    McpClient::connect(name, transport, incoming, timeout).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_references() {
+        let text =
+            "Compare @docs:file:///README.md, with @docs:db://users and mail me@example.com \
+                    or @docs:file:///README.md again (@other:x)";
+        let refs = resource_references(text, |server| server == "docs");
+        assert_eq!(
+            refs,
+            vec![
+                ("docs".to_string(), "file:///README.md".to_string()),
+                ("docs".to_string(), "db://users".to_string()),
+            ]
+        );
+    }
+}
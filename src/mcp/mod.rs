@@ -1,17 +1,37 @@
-//! Model Context Protocol Support (Placeholder)
+//! Model Context Protocol Support
 //!
-//! This module is a placeholder for MCP integration.
+//! Client for [Model Context Protocol](https://modelcontextprotocol.io) servers.
+//! Servers declared in the configuration are started, their tools are listed
+//! and registered in the [`ToolRegistry`](crate::llm::tools::ToolRegistry)
+//! under namespaced names (`mcp__<server>__<tool>`), and calls go through the
+//! normal tool approval flow.
 //!
-//! ## Planned Features
-//! - MCP server connections
-//! - Resource providers
-//! - Prompt templates
-//! - Tool extensions
+//! ```toml
+//! [mcp.servers.files]
+//! command = "npx"
+//! args = ["-y", "@modelcontextprotocol/server-filesystem", "."]
 //!
-//! ## Status
-//! 🔴 Not implemented - See FUTURE_FEATURES.md for roadmap
+//! [mcp.servers.remote]
+//! url = "https://mcp.example.com/mcp"
+//! ```
 //!
-//! ## Priority
-//! Medium - Useful for enterprise integrations
+//! Local servers are spoken to over stdio; remote servers over streamable
+//! HTTP or the legacy HTTP+SSE transport.
+//!
+//! Crustly can also act as a server: `crustly mcp serve` exposes its own
+//! tools over stdio (see [`McpServer`]).
 
+pub mod client;
+pub mod error;
+pub mod manager;
+pub mod protocol;
+pub mod server;
+pub mod tool;
 pub mod transport;
+
+// Re-exports
+pub use client::McpClient;
+pub use error::{McpError, Result};
+pub use manager::{McpManager, ServerPrompt, ServerResource};
+pub use server::{McpServer, ServeOptions};
+pub use tool::McpTool;
@@ -0,0 +1,440 @@
+//! MCP Protocol Types
+//!
+//! JSON-RPC 2.0 envelopes and the subset of Model Context Protocol messages
+//! used by the client.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Protocol revision requested during the initialize handshake
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// JSON-RPC version string
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// JSON-RPC error code for unknown methods
+pub const METHOD_NOT_FOUND: i64 = -32601;
+
+/// JSON-RPC error code for malformed JSON
+pub const PARSE_ERROR: i64 = -32700;
+
+/// JSON-RPC error code for invalid parameters
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// A JSON-RPC request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcRequest {
+    pub jsonrpc: String,
+    pub id: Value,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+}
+
+impl JsonRpcRequest {
+    /// Create a request
+    pub fn new(id: impl Into<Value>, method: impl Into<String>, params: Option<Value>) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id: id.into(),
+            method: method.into(),
+            params,
+        }
+    }
+}
+
+/// A JSON-RPC notification (a request without an id)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+}
+
+impl JsonRpcNotification {
+    /// Create a notification
+    pub fn new(method: impl Into<String>, params: Option<Value>) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            method: method.into(),
+            params,
+        }
+    }
+}
+
+/// A JSON-RPC error object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+/// A JSON-RPC response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcResponse {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonRpcError>,
+}
+
+impl JsonRpcResponse {
+    /// Create a successful response
+    pub fn success(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    /// Create an error response
+    pub fn error(id: Value, code: i64, message: impl Into<String>) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result: None,
+            error: Some(JsonRpcError {
+                code,
+                message: message.into(),
+                data: None,
+            }),
+        }
+    }
+}
+
+/// Any incoming JSON-RPC message
+#[derive(Debug, Clone)]
+pub enum JsonRpcMessage {
+    Request(JsonRpcRequest),
+    Notification(JsonRpcNotification),
+    Response(JsonRpcResponse),
+}
+
+impl JsonRpcMessage {
+    /// Classify a raw JSON-RPC message
+    pub fn from_value(value: Value) -> serde_json::Result<Self> {
+        let has_method = value.get("method").is_some();
+        let has_id = value.get("id").is_some_and(|id| !id.is_null());
+        if has_method && has_id {
+            serde_json::from_value(value).map(Self::Request)
+        } else if has_method {
+            serde_json::from_value(value).map(Self::Notification)
+        } else {
+            serde_json::from_value(value).map(Self::Response)
+        }
+    }
+}
+
+/// Name and version of an MCP client or server
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Implementation {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+}
+
+/// Result of the `initialize` request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitializeResult {
+    pub protocol_version: String,
+    #[serde(default)]
+    pub capabilities: Value,
+    #[serde(default)]
+    pub server_info: Implementation,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+}
+
+/// Optional hints describing a tool's behavior
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolAnnotations {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_only_hint: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destructive_hint: Option<bool>,
+}
+
+/// A tool exposed by an MCP server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpToolDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "empty_object_schema")]
+    pub input_schema: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<ToolAnnotations>,
+}
+
+fn empty_object_schema() -> Value {
+    serde_json::json!({ "type": "object", "properties": {} })
+}
+
+/// Result of `tools/list`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListToolsResult {
+    pub tools: Vec<McpToolDefinition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// A piece of content in a tool result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ContentItem {
+    Text {
+        text: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Image {
+        data: String,
+        mime_type: String,
+    },
+    Resource {
+        resource: Value,
+    },
+}
+
+/// Result of `tools/call`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallToolResult {
+    #[serde(default)]
+    pub content: Vec<ContentItem>,
+    #[serde(default)]
+    pub is_error: bool,
+}
+
+impl CallToolResult {
+    /// Flatten the result content into text for the model
+    pub fn to_text(&self) -> String {
+        self.content
+            .iter()
+            .map(|item| match item {
+                ContentItem::Text { text } => text.clone(),
+                ContentItem::Image { mime_type, data } => {
+                    format!("[image: {}, {} bytes base64]", mime_type, data.len())
+                }
+                ContentItem::Resource { resource } => match resource.get("text") {
+                    Some(Value::String(text)) => text.clone(),
+                    _ => format!(
+                        "[resource: {}]",
+                        resource
+                            .get("uri")
+                            .and_then(Value::as_str)
+                            .unwrap_or("unknown")
+                    ),
+                },
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// A resource exposed by an MCP server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResource {
+    pub uri: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// Result of `resources/list`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListResourcesResult {
+    pub resources: Vec<McpResource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Contents of a resource, as text or base64 data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceContents {
+    pub uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
+}
+
+/// Result of `resources/read`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadResourceResult {
+    #[serde(default)]
+    pub contents: Vec<ResourceContents>,
+}
+
+impl ReadResourceResult {
+    /// Flatten the contents into text for the model
+    pub fn to_text(&self) -> String {
+        self.contents
+            .iter()
+            .map(|item| match (&item.text, &item.blob) {
+                (Some(text), _) => text.clone(),
+                (None, Some(blob)) => format!(
+                    "[binary: {}, {} bytes base64]",
+                    item.mime_type.as_deref().unwrap_or("unknown type"),
+                    blob.len()
+                ),
+                (None, None) => String::new(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// An argument accepted by a prompt template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptArgument {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// A prompt template exposed by an MCP server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpPrompt {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<PromptArgument>,
+}
+
+/// Result of `prompts/list`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListPromptsResult {
+    pub prompts: Vec<McpPrompt>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// A message produced by a prompt template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptMessage {
+    pub role: String,
+    pub content: ContentItem,
+}
+
+/// Result of `prompts/get`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetPromptResult {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub messages: Vec<PromptMessage>,
+}
+
+impl GetPromptResult {
+    /// Flatten the prompt messages into a single chat message
+    pub fn to_text(&self) -> String {
+        self.messages
+            .iter()
+            .map(|message| {
+                CallToolResult {
+                    content: vec![message.content.clone()],
+                    is_error: false,
+                }
+                .to_text()
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_classify_messages() {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" });
+        assert!(matches!(
+            JsonRpcMessage::from_value(request).unwrap(),
+            JsonRpcMessage::Request(_)
+        ));
+
+        let notification =
+            json!({ "jsonrpc": "2.0", "method": "notifications/tools/list_changed" });
+        assert!(matches!(
+            JsonRpcMessage::from_value(notification).unwrap(),
+            JsonRpcMessage::Notification(_)
+        ));
+
+        let response = json!({ "jsonrpc": "2.0", "id": 1, "result": {} });
+        assert!(matches!(
+            JsonRpcMessage::from_value(response).unwrap(),
+            JsonRpcMessage::Response(_)
+        ));
+    }
+
+    #[test]
+    fn test_call_tool_result_to_text() {
+        let result: CallToolResult = serde_json::from_value(json!({
+            "content": [
+                { "type": "text", "text": "hello" },
+                { "type": "image", "data": "AAAA", "mimeType": "image/png" },
+                { "type": "resource", "resource": { "uri": "file:///a", "text": "body" } }
+            ],
+            "isError": false
+        }))
+        .unwrap();
+
+        assert_eq!(
+            result.to_text(),
+            "hello\n[image: image/png, 4 bytes base64]\nbody"
+        );
+    }
+
+    #[test]
+    fn test_tool_definition_defaults() {
+        let tool: McpToolDefinition = serde_json::from_value(json!({ "name": "echo" })).unwrap();
+        assert_eq!(tool.input_schema["type"], "object");
+        assert!(tool.description.is_none());
+    }
+
+    #[test]
+    fn test_resource_and_prompt_results_to_text() {
+        let read: ReadResourceResult = serde_json::from_value(json!({
+            "contents": [
+                { "uri": "db://a", "text": "rows" },
+                { "uri": "db://b", "mimeType": "image/png", "blob": "AAAA" }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(read.to_text(), "rows\n[binary: image/png, 4 bytes base64]");
+
+        let prompt: GetPromptResult = serde_json::from_value(json!({
+            "messages": [
+                { "role": "user", "content": { "type": "text", "text": "Review this" } },
+                { "role": "user", "content": { "type": "text", "text": "carefully" } }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(prompt.to_text(), "Review this\n\ncarefully");
+    }
+}
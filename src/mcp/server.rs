@@ -0,0 +1,364 @@
+//! MCP Server
+//!
+//! Serves the tools of a [`ToolRegistry`] to MCP clients over stdio, so
+//! editors and other agents can call Crustly's tools. Calls run with the same
+//! checks as in the chat: tools enforce read-only mode themselves, and tools
+//! that would need approval only run when the serve options allow them.
+
+use super::error::Result;
+use super::protocol::{
+    JsonRpcMessage, JsonRpcRequest, JsonRpcResponse, ToolAnnotations, INVALID_PARAMS,
+    METHOD_NOT_FOUND, PARSE_ERROR, PROTOCOL_VERSION,
+};
+use crate::llm::tools::{Tool, ToolError, ToolExecutionContext, ToolRegistry};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+/// What the server exposes and allows
+#[derive(Debug, Clone, Default)]
+pub struct ServeOptions {
+    /// Run every tool in read-only mode
+    pub read_only: bool,
+
+    /// Run tools that would normally ask for approval
+    pub auto_approve: bool,
+
+    /// Tools allowed to run without approval
+    pub allow: Vec<String>,
+
+    /// Tools hidden from clients
+    pub deny: Vec<String>,
+}
+
+/// MCP server exposing registry tools
+pub struct McpServer {
+    registry: Arc<ToolRegistry>,
+    context: ToolExecutionContext,
+    options: ServeOptions,
+}
+
+impl McpServer {
+    /// Create a server for the tools in `registry`
+    pub fn new(
+        registry: Arc<ToolRegistry>,
+        context: ToolExecutionContext,
+        options: ServeOptions,
+    ) -> Self {
+        let context = context.with_read_only_mode(options.read_only);
+        Self {
+            registry,
+            context,
+            options,
+        }
+    }
+
+    /// Tools visible to clients, sorted by name
+    pub fn tools(&self) -> Vec<Arc<dyn Tool>> {
+        let mut names = self.registry.list_tools();
+        names.sort();
+        names
+            .iter()
+            .filter(|name| !self.options.deny.contains(name))
+            .filter_map(|name| self.registry.get(name))
+            .collect()
+    }
+
+    /// Whether a tool call may run without asking anyone
+    fn is_allowed(&self, tool: &dyn Tool, arguments: &Value) -> bool {
+        !tool.call_requires_approval(arguments)
+            || self.options.auto_approve
+            || self.options.allow.iter().any(|name| name == tool.name())
+    }
+
+    /// Handle one incoming message, returning the response to send (if any)
+    pub async fn handle_message(&self, message: Value) -> Option<Value> {
+        let request = match JsonRpcMessage::from_value(message) {
+            Ok(JsonRpcMessage::Request(request)) => request,
+            Ok(JsonRpcMessage::Notification(notification)) => {
+                tracing::debug!("MCP client notification: {}", notification.method);
+                return None;
+            }
+            Ok(JsonRpcMessage::Response(_)) => return None, // We never send requests
+            Err(e) => {
+                return to_value(JsonRpcResponse::error(
+                    Value::Null,
+                    PARSE_ERROR,
+                    format!("Invalid message: {}", e),
+                ))
+            }
+        };
+
+        let id = request.id.clone();
+        let response = match self.handle_request(request).await {
+            Ok(result) => JsonRpcResponse::success(id, result),
+            Err((code, message)) => JsonRpcResponse::error(id, code, message),
+        };
+        to_value(response)
+    }
+
+    async fn handle_request(
+        &self,
+        request: JsonRpcRequest,
+    ) -> std::result::Result<Value, (i64, String)> {
+        let params = request.params.unwrap_or(Value::Null);
+
+        match request.method.as_str() {
+            "initialize" => Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "crustly", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(
+                json!({ "tools": self.tools().iter().map(|t| describe(t.as_ref())).collect::<Vec<_>>() }),
+            ),
+            "tools/call" => {
+                let name = params["name"]
+                    .as_str()
+                    .ok_or((INVALID_PARAMS, "Missing tool name".to_string()))?;
+                let arguments = match &params["arguments"] {
+                    Value::Null => json!({}),
+                    arguments => arguments.clone(),
+                };
+                Ok(self.call_tool(name, arguments).await)
+            }
+            method => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        }
+    }
+
+    /// Run a tool, reporting failures in the result as MCP expects
+    async fn call_tool(&self, name: &str, arguments: Value) -> Value {
+        let tool = match self.registry.get(name) {
+            Some(tool) if !self.options.deny.iter().any(|denied| denied == name) => tool,
+            _ => return tool_error(format!("Unknown tool: {}", name)),
+        };
+        if !self.is_allowed(tool.as_ref(), &arguments) {
+            return tool_error(format!(
+                "Tool '{}' requires approval. Allow it with `crustly mcp serve --allow {}` \
+                 or use --auto-approve.",
+                name, name
+            ));
+        }
+
+        // Approval was decided by the serve options above
+        let context = self.context.clone().with_auto_approve(true);
+        match self.registry.execute(name, arguments, &context).await {
+            Ok(result) if result.success => json!({
+                "content": [{ "type": "text", "text": result.output }],
+                "isError": false,
+            }),
+            Ok(result) => tool_error(result.error.unwrap_or(result.output)),
+            Err(ToolError::InvalidInput(message)) => {
+                tool_error(format!("Invalid input: {}", message))
+            }
+            Err(e) => tool_error(e.to_string()),
+        }
+    }
+
+    /// Serve newline-delimited JSON-RPC until `reader` is closed
+    ///
+    /// Requests are handled concurrently so a slow tool does not block pings
+    /// or other calls.
+    pub async fn serve<R, W>(self: Arc<Self>, reader: R, mut writer: W) -> Result<()>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Value>();
+        let writer_task = tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                let line = format!("{}\n", message);
+                if writer.write_all(line.as_bytes()).await.is_err() || writer.flush().await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let mut requests = JoinSet::new();
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let message = match serde_json::from_str::<Value>(&line) {
+                Ok(message) => message,
+                Err(e) => {
+                    if let Some(response) = to_value(JsonRpcResponse::error(
+                        Value::Null,
+                        PARSE_ERROR,
+                        format!("Parse error: {}", e),
+                    )) {
+                        let _ = tx.send(response);
+                    }
+                    continue;
+                }
+            };
+
+            let server = self.clone();
+            let tx = tx.clone();
+            requests.spawn(async move {
+                if let Some(response) = server.handle_message(message).await {
+                    let _ = tx.send(response);
+                }
+            });
+        }
+
+        // Finish in-flight requests before closing the output
+        while requests.join_next().await.is_some() {}
+        drop(tx);
+        let _ = writer_task.await;
+        Ok(())
+    }
+}
+
+/// Tool entry for `tools/list`
+fn describe(tool: &dyn Tool) -> Value {
+    let read_only = !tool.requires_approval();
+    let annotations = ToolAnnotations {
+        read_only_hint: Some(read_only),
+        destructive_hint: Some(!read_only),
+    };
+    json!({
+        "name": tool.name(),
+        "description": tool.description(),
+        "inputSchema": tool.input_schema(),
+        "annotations": annotations,
+    })
+}
+
+fn tool_error(message: String) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": message }],
+        "isError": true,
+    })
+}
+
+fn to_value(response: JsonRpcResponse) -> Option<Value> {
+    serde_json::to_value(response).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::tools::{read::ReadTool, write::WriteTool};
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    fn server(dir: &TempDir, options: ServeOptions) -> McpServer {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(ReadTool));
+        registry.register(Arc::new(WriteTool));
+        let context = ToolExecutionContext::new(Uuid::new_v4())
+            .with_working_directory(dir.path().to_path_buf());
+        McpServer::new(Arc::new(registry), context, options)
+    }
+
+    fn call(name: &str, arguments: Value) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": name, "arguments": arguments }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_list_tools_with_annotations_and_deny() {
+        let dir = TempDir::new().unwrap();
+        let server = server(
+            &dir,
+            ServeOptions {
+                deny: vec!["write_file".to_string()],
+                ..Default::default()
+            },
+        );
+
+        let response = server
+            .handle_message(json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }))
+            .await
+            .unwrap();
+        let tools = response["result"]["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0]["name"], "read_file");
+        assert_eq!(tools[0]["annotations"]["readOnlyHint"], true);
+
+        let response = server
+            .handle_message(call("write_file", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(response["result"]["isError"], true);
+    }
+
+    #[tokio::test]
+    async fn test_tools_needing_approval_must_be_allowed() {
+        let dir = TempDir::new().unwrap();
+        let arguments = json!({ "path": "out.txt", "content": "hi" });
+
+        let response = server(&dir, ServeOptions::default())
+            .handle_message(call("write_file", arguments.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response["result"]["isError"], true);
+        assert!(response["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("--allow write_file"));
+        assert!(!dir.path().join("out.txt").exists());
+
+        let allowed = ServeOptions {
+            allow: vec!["write_file".to_string()],
+            ..Default::default()
+        };
+        let response = server(&dir, allowed)
+            .handle_message(call("write_file", arguments))
+            .await
+            .unwrap();
+        assert_eq!(response["result"]["isError"], false);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("out.txt")).unwrap(),
+            "hi"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_only_mode_is_enforced() {
+        let dir = TempDir::new().unwrap();
+        let options = ServeOptions {
+            read_only: true,
+            auto_approve: true,
+            ..Default::default()
+        };
+
+        let response = server(&dir, options)
+            .handle_message(call(
+                "write_file",
+                json!({ "path": "out.txt", "content": "hi" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response["result"]["isError"], true);
+        assert!(!dir.path().join("out.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_method_and_notifications() {
+        let dir = TempDir::new().unwrap();
+        let server = server(&dir, ServeOptions::default());
+
+        let response = server
+            .handle_message(json!({ "jsonrpc": "2.0", "id": 7, "method": "resources/list" }))
+            .await
+            .unwrap();
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+
+        assert!(server
+            .handle_message(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .await
+            .is_none());
+    }
+}
@@ -0,0 +1,153 @@
+//! MCP Tool Adapter
+//!
+//! Exposes a tool from an MCP server as a regular [`Tool`] so it can be
+//! registered in the [`ToolRegistry`](crate::llm::tools::ToolRegistry) and go
+//! through the same approval flow as built-in tools.
+
+use super::client::McpClient;
+use super::protocol::McpToolDefinition;
+use crate::llm::tools::{
+    Result, Tool, ToolCapability, ToolError, ToolExecutionContext, ToolResult,
+};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Maximum tool name length accepted by LLM providers
+const MAX_TOOL_NAME_LEN: usize = 64;
+
+/// A tool provided by an MCP server
+pub struct McpTool {
+    client: Arc<McpClient>,
+    definition: McpToolDefinition,
+    qualified_name: String,
+    description: String,
+}
+
+impl McpTool {
+    /// Wrap a tool definition from a connected server
+    pub fn new(client: Arc<McpClient>, definition: McpToolDefinition) -> Self {
+        let qualified_name = qualified_tool_name(client.name(), &definition.name);
+        let description = format!(
+            "[MCP: {}] {}",
+            client.name(),
+            definition
+                .description
+                .as_deref()
+                .unwrap_or("Tool provided by an MCP server")
+        );
+
+        Self {
+            client,
+            definition,
+            qualified_name,
+            description,
+        }
+    }
+
+    /// Name of the tool on the server
+    pub fn remote_name(&self) -> &str {
+        &self.definition.name
+    }
+
+    /// Whether the server declares that the tool does not modify anything
+    fn is_read_only(&self) -> bool {
+        self.definition
+            .annotations
+            .as_ref()
+            .and_then(|a| a.read_only_hint)
+            .unwrap_or(false)
+    }
+}
+
+/// Build the registry name for a server tool: `mcp__<server>__<tool>`
+///
+/// Characters providers reject in tool names are replaced with `_`.
+pub fn qualified_tool_name(server: &str, tool: &str) -> String {
+    let sanitize = |s: &str| {
+        s.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>()
+    };
+
+    let mut name = format!("mcp__{}__{}", sanitize(server), sanitize(tool));
+    name.truncate(MAX_TOOL_NAME_LEN);
+    name
+}
+
+#[async_trait]
+impl Tool for McpTool {
+    fn name(&self) -> &str {
+        &self.qualified_name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn input_schema(&self) -> Value {
+        self.definition.input_schema.clone()
+    }
+
+    fn capabilities(&self) -> Vec<ToolCapability> {
+        if self.is_read_only() {
+            vec![ToolCapability::Network]
+        } else {
+            vec![ToolCapability::Network, ToolCapability::SystemModification]
+        }
+    }
+
+    fn requires_approval(&self) -> bool {
+        true // External tools always go through approval
+    }
+
+    async fn execute(&self, input: Value, context: &ToolExecutionContext) -> Result<ToolResult> {
+        if context.read_only_mode && !self.is_read_only() {
+            return Ok(ToolResult::error(format!(
+                "MCP tool '{}' may modify state and is not allowed in Plan mode.",
+                self.qualified_name
+            )));
+        }
+
+        let arguments = if input.is_null() {
+            serde_json::json!({})
+        } else {
+            input
+        };
+
+        let result = self
+            .client
+            .call_tool(&self.definition.name, arguments)
+            .await
+            .map_err(|e| ToolError::Execution(e.to_string()))?;
+
+        let text = result.to_text();
+        Ok(if result.is_error {
+            ToolResult::error(text)
+        } else {
+            ToolResult::success(text)
+        }
+        .with_metadata("mcp_server".to_string(), self.client.name().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_qualified_tool_name() {
+        assert_eq!(qualified_tool_name("git", "status"), "mcp__git__status");
+        assert_eq!(
+            qualified_tool_name("my server", "read.file"),
+            "mcp__my_server__read_file"
+        );
+        assert_eq!(qualified_tool_name("s", &"x".repeat(100)).len(), 64);
+    }
+}
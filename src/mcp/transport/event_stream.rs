@@ -0,0 +1,181 @@
+//! Server-Sent Events Parsing
+//!
+//! Incremental parser for `text/event-stream` bodies and the forwarding and
+//! reconnection helpers shared by the HTTP transports.
+
+use crate::mcp::error::{McpError, Result};
+use futures::StreamExt;
+use serde_json::Value;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Consecutive failed attempts after which a dropped stream is abandoned
+pub const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+
+const RECONNECT_INITIAL_DELAY_MS: u64 = 250;
+const RECONNECT_MAX_DELAY_MS: u64 = 8_000;
+
+/// A single server-sent event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// Event type (`message` when not specified)
+    pub event: String,
+
+    /// Event data, with multi-line data joined by `\n`
+    pub data: String,
+
+    /// Event ID, used to resume a stream
+    pub id: Option<String>,
+}
+
+/// Incremental `text/event-stream` parser
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: String,
+    event: Option<String>,
+    data: Vec<String>,
+    id: Option<String>,
+}
+
+impl SseParser {
+    /// Create a parser
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk of the body, returning the events it completes
+    pub fn feed(&mut self, chunk: &str) -> Vec<SseEvent> {
+        self.buffer.push_str(chunk);
+
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.find('\n') {
+            let line: String = self.buffer.drain(..=pos).collect();
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                // Blank line dispatches the event
+                if !self.data.is_empty() {
+                    events.push(SseEvent {
+                        event: self.event.take().unwrap_or_else(|| "message".to_string()),
+                        data: self.data.join("\n"),
+                        id: self.id.clone(),
+                    });
+                }
+                self.data.clear();
+                self.event = None;
+                continue;
+            }
+            if line.starts_with(':') {
+                continue; // Comment / keep-alive
+            }
+
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line, ""),
+            };
+            match field {
+                "event" => self.event = Some(value.to_string()),
+                "data" => self.data.push(value.to_string()),
+                "id" => self.id = Some(value.to_string()),
+                _ => {} // "retry" and unknown fields are ignored
+            }
+        }
+
+        events
+    }
+}
+
+/// Read an event stream response, calling `on_event` for each event
+///
+/// Returns when the server ends the stream.
+pub async fn read_events(
+    response: reqwest::Response,
+    mut on_event: impl FnMut(SseEvent),
+) -> Result<()> {
+    let mut parser = SseParser::new();
+    let mut stream = response.bytes_stream();
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| McpError::Transport(format!("event stream error: {}", e)))?;
+        for event in parser.feed(&String::from_utf8_lossy(&chunk)) {
+            on_event(event);
+        }
+    }
+
+    Ok(())
+}
+
+/// Forward the JSON-RPC message(s) carried by a `message` event
+///
+/// Returns `false` once the receiving side has gone away.
+pub fn forward_message(server: &str, event: &SseEvent, tx: &mpsc::UnboundedSender<Value>) -> bool {
+    if event.event != "message" {
+        tracing::debug!(
+            "Ignoring '{}' event from MCP server '{}'",
+            event.event,
+            server
+        );
+        return true;
+    }
+    forward_json(server, &event.data, tx)
+}
+
+/// Forward a JSON-RPC message or batch received as text
+pub fn forward_json(server: &str, body: &str, tx: &mpsc::UnboundedSender<Value>) -> bool {
+    match serde_json::from_str::<Value>(body) {
+        Ok(Value::Array(batch)) => batch.into_iter().all(|message| tx.send(message).is_ok()),
+        Ok(message) => tx.send(message).is_ok(),
+        Err(e) => {
+            tracing::warn!("MCP server '{}' sent invalid JSON: {}", server, e);
+            true
+        }
+    }
+}
+
+/// Delay before reconnection attempt `attempt` (starting at 1)
+pub fn reconnect_delay(attempt: u32) -> Duration {
+    let delay = RECONNECT_INITIAL_DELAY_MS.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+    Duration::from_millis(delay.min(RECONNECT_MAX_DELAY_MS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_events_across_chunks() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed("event: endpoint\ndata: /mess").is_empty());
+
+        let events = parser.feed("ages?id=1\n\n: keep-alive\n\ndata: {\"a\":1}\r\nid: 7\r\n\r\n");
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    event: "endpoint".to_string(),
+                    data: "/messages?id=1".to_string(),
+                    id: None,
+                },
+                SseEvent {
+                    event: "message".to_string(),
+                    data: "{\"a\":1}".to_string(),
+                    id: Some("7".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reconnect_delay_backs_off() {
+        assert_eq!(reconnect_delay(1), Duration::from_millis(250));
+        assert_eq!(reconnect_delay(3), Duration::from_millis(1000));
+        assert_eq!(reconnect_delay(30), Duration::from_millis(8000));
+    }
+
+    #[test]
+    fn test_multi_line_data() {
+        let mut parser = SseParser::new();
+        let events = parser.feed("data: first\ndata: second\n\n");
+        assert_eq!(events[0].data, "first\nsecond");
+    }
+}
@@ -0,0 +1,354 @@
+//! Streamable HTTP Transport
+//!
+//! Talks to a remote MCP server over the streamable HTTP transport: every
+//! message is POSTed to a single endpoint and the server answers with either a
+//! JSON body or an event stream. The session ID assigned by the server is sent
+//! back on every request, and a long-lived GET event stream carries
+//! server-initiated messages, reconnecting when it drops.
+
+use super::event_stream::{
+    forward_json, forward_message, read_events, reconnect_delay, MAX_RECONNECT_ATTEMPTS,
+};
+use super::{IncomingMessages, Transport};
+use crate::config::secrets::SecretString;
+use crate::mcp::error::{McpError, Result};
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::{StatusCode, Url};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Header carrying the session ID assigned by the server
+pub const SESSION_ID_HEADER: &str = "mcp-session-id";
+
+/// Header used to resume an event stream
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+
+/// Connection options shared by the HTTP-based transports
+#[derive(Debug, Clone)]
+pub struct HttpOptions {
+    /// Extra headers sent with every request
+    pub headers: BTreeMap<String, String>,
+
+    /// Token sent as `Authorization: Bearer <token>`
+    pub bearer_token: Option<SecretString>,
+
+    /// Timeout for connecting and for each POST
+    pub timeout: Duration,
+}
+
+impl HttpOptions {
+    /// Options with no extra headers or authentication
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            headers: BTreeMap::new(),
+            bearer_token: None,
+            timeout,
+        }
+    }
+
+    /// Build an HTTP client sending the configured headers
+    pub(crate) fn build_client(&self, server: &str) -> Result<reqwest::Client> {
+        let connection_error = |message: String| McpError::Connection {
+            server: server.to_string(),
+            message,
+        };
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| connection_error(format!("invalid header name '{}': {}", name, e)))?;
+            let value = HeaderValue::from_str(value).map_err(|e| {
+                connection_error(format!("invalid value for header '{}': {}", name, e))
+            })?;
+            headers.insert(name, value);
+        }
+        if let Some(token) = &self.bearer_token {
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", token.expose_secret()))
+                .map_err(|_| connection_error("invalid bearer token".to_string()))?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+
+        reqwest::Client::builder()
+            .default_headers(headers)
+            .connect_timeout(self.timeout)
+            .build()
+            .map_err(|e| connection_error(format!("failed to create HTTP client: {}", e)))
+    }
+}
+
+/// Parse a server URL, reporting failures as connection errors
+pub(crate) fn parse_url(server: &str, url: &str) -> Result<Url> {
+    Url::parse(url).map_err(|e| McpError::Connection {
+        server: server.to_string(),
+        message: format!("invalid URL '{}': {}", url, e),
+    })
+}
+
+/// Describe a failed HTTP response
+pub(crate) async fn status_error(response: reqwest::Response) -> McpError {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    let body = body.trim();
+    if body.is_empty() {
+        McpError::Transport(format!("server returned HTTP {}", status))
+    } else {
+        let snippet: String = body.chars().take(200).collect();
+        McpError::Transport(format!("server returned HTTP {}: {}", status, snippet))
+    }
+}
+
+/// Map a request failure to a transport error
+pub(crate) fn request_error(error: reqwest::Error) -> McpError {
+    if error.is_timeout() {
+        McpError::Transport("HTTP request timed out".to_string())
+    } else {
+        McpError::Transport(format!("HTTP request failed: {}", error))
+    }
+}
+
+/// Transport to a remote MCP server using streamable HTTP
+pub struct StreamableHttpTransport {
+    server: String,
+    url: Url,
+    http: reqwest::Client,
+    timeout: Duration,
+    session_id: Arc<Mutex<Option<String>>>,
+    tx: Mutex<Option<mpsc::UnboundedSender<Value>>>,
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+    closed: AtomicBool,
+}
+
+impl StreamableHttpTransport {
+    /// Create a transport for the server at `url`
+    ///
+    /// No request is made until the first message is sent.
+    pub fn new(server: &str, url: &str, options: &HttpOptions) -> Result<(Self, IncomingMessages)> {
+        let url = parse_url(server, url)?;
+        let http = options.build_client(server)?;
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let transport = Self {
+            server: server.to_string(),
+            url,
+            http,
+            timeout: options.timeout,
+            session_id: Arc::new(Mutex::new(None)),
+            tx: Mutex::new(Some(tx)),
+            tasks: Mutex::new(Vec::new()),
+            closed: AtomicBool::new(false),
+        };
+        Ok((transport, rx))
+    }
+
+    /// Session ID assigned by the server, if any
+    pub fn session_id(&self) -> Option<String> {
+        self.session_id.lock().unwrap().clone()
+    }
+
+    fn sender(&self) -> Result<mpsc::UnboundedSender<Value>> {
+        self.tx.lock().unwrap().clone().ok_or(McpError::Closed)
+    }
+
+    fn spawn_task(&self, task: impl std::future::Future<Output = ()> + Send + 'static) {
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.retain(|handle| !handle.is_finished());
+        tasks.push(tokio::spawn(task));
+    }
+
+    /// Open the GET event stream for server-initiated messages
+    fn start_listener(&self, tx: mpsc::UnboundedSender<Value>) {
+        self.spawn_task(listen(
+            self.server.clone(),
+            self.http.clone(),
+            self.url.clone(),
+            self.session_id.clone(),
+            tx,
+        ));
+    }
+}
+
+#[async_trait]
+impl Transport for StreamableHttpTransport {
+    async fn send(&self, message: Value) -> Result<()> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(McpError::Closed);
+        }
+        let tx = self.sender()?;
+
+        let session_id = self.session_id();
+        let mut request = self
+            .http
+            .post(self.url.clone())
+            .timeout(self.timeout)
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(&message);
+        if let Some(id) = &session_id {
+            request = request.header(SESSION_ID_HEADER, id);
+        }
+
+        let response = request.send().await.map_err(request_error)?;
+
+        if response.status() == StatusCode::NOT_FOUND && session_id.is_some() {
+            // The server dropped our session; it must be initialized again
+            *self.session_id.lock().unwrap() = None;
+            return Err(McpError::SessionExpired);
+        }
+        if !response.status().is_success() {
+            return Err(status_error(response).await);
+        }
+
+        if let Some(id) = response
+            .headers()
+            .get(SESSION_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            *self.session_id.lock().unwrap() = Some(id.to_string());
+        }
+
+        if message.get("method").and_then(Value::as_str) == Some("notifications/initialized") {
+            self.start_listener(tx.clone());
+        }
+
+        if response.status() == StatusCode::ACCEPTED {
+            return Ok(()); // Notification or response accepted, nothing to read
+        }
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        if content_type.starts_with("text/event-stream") {
+            // The reply (and any related server requests) arrive as events
+            let server = self.server.clone();
+            self.spawn_task(async move {
+                let result = read_events(response, |event| {
+                    forward_message(&server, &event, &tx);
+                })
+                .await;
+                if let Err(e) = result {
+                    tracing::warn!("MCP server '{}' response stream failed: {}", server, e);
+                }
+            });
+        } else {
+            let body = response.text().await.map_err(request_error)?;
+            if !body.trim().is_empty() {
+                forward_json(&self.server, &body, &tx);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<()> {
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        for handle in self.tasks.lock().unwrap().drain(..) {
+            handle.abort();
+        }
+        self.tx.lock().unwrap().take();
+
+        // Tell the server the session is over; failures are harmless
+        let session_id = self.session_id.lock().unwrap().take();
+        if let Some(id) = session_id {
+            let _ = self
+                .http
+                .delete(self.url.clone())
+                .timeout(self.timeout)
+                .header(SESSION_ID_HEADER, id)
+                .send()
+                .await;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for StreamableHttpTransport {
+    fn drop(&mut self) {
+        for handle in self.tasks.lock().unwrap().drain(..) {
+            handle.abort();
+        }
+    }
+}
+
+/// Keep the GET event stream open, resuming it when it drops
+async fn listen(
+    server: String,
+    http: reqwest::Client,
+    url: Url,
+    session_id: Arc<Mutex<Option<String>>>,
+    tx: mpsc::UnboundedSender<Value>,
+) {
+    let mut last_event_id: Option<String> = None;
+    let mut failures = 0;
+
+    loop {
+        let session = session_id.lock().unwrap().clone();
+        let mut request = http.get(url.clone()).header(ACCEPT, "text/event-stream");
+        if let Some(id) = &session {
+            request = request.header(SESSION_ID_HEADER, id);
+        }
+        if let Some(id) = &last_event_id {
+            request = request.header(LAST_EVENT_ID_HEADER, id);
+        }
+
+        match request.send().await {
+            Ok(response) if response.status() == StatusCode::METHOD_NOT_ALLOWED => {
+                tracing::debug!("MCP server '{}' does not offer an event stream", server);
+                return;
+            }
+            Ok(response) if response.status() == StatusCode::NOT_FOUND && session.is_some() => {
+                // Session expired; the next request re-initializes and reopens the stream
+                return;
+            }
+            Ok(response) if response.status().is_success() => {
+                failures = 0;
+                let result = read_events(response, |event| {
+                    if event.id.is_some() {
+                        last_event_id = event.id.clone();
+                    }
+                    forward_message(&server, &event, &tx);
+                })
+                .await;
+                if let Err(e) = result {
+                    tracing::debug!("MCP server '{}' event stream dropped: {}", server, e);
+                }
+                if tx.is_closed() {
+                    return;
+                }
+            }
+            Ok(response) => {
+                failures += 1;
+                tracing::debug!(
+                    "MCP server '{}' refused the event stream: HTTP {}",
+                    server,
+                    response.status()
+                );
+            }
+            Err(e) => {
+                failures += 1;
+                tracing::debug!("Failed to open MCP server '{}' event stream: {}", server, e);
+            }
+        }
+
+        if failures >= MAX_RECONNECT_ATTEMPTS {
+            tracing::warn!(
+                "Giving up on MCP server '{}' event stream after {} attempts",
+                server,
+                failures
+            );
+            return;
+        }
+        tokio::time::sleep(reconnect_delay(failures.max(1))).await;
+    }
+}
@@ -1,11 +1,34 @@
-//! MCP Transport Layer (Placeholder)
+//! MCP Transport Layer
 //!
-//! Transport implementations for Model Context Protocol.
-//!
-//! ## Planned Transports
-//! - stdio (standard input/output)
-//! - HTTP (REST API)
-//! - WebSocket (real-time)
-//!
-//! ## Status
-//! 🔴 Not implemented
+//! Transports move JSON-RPC messages between the client and an MCP server.
+//! Outgoing messages go through [`Transport::send`]; incoming messages are
+//! delivered on the channel returned when the transport is created.
+
+pub mod event_stream;
+pub mod http;
+pub mod sse;
+pub mod stdio;
+
+pub use http::{HttpOptions, StreamableHttpTransport};
+pub use sse::SseTransport;
+pub use stdio::StdioTransport;
+
+use super::error::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// Channel on which a transport delivers messages received from the server
+///
+/// The channel closes when the connection ends.
+pub type IncomingMessages = mpsc::UnboundedReceiver<Value>;
+
+/// A connection to an MCP server
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// Send a JSON-RPC message to the server
+    async fn send(&self, message: Value) -> Result<()>;
+
+    /// Shut the connection down
+    async fn close(&self) -> Result<()>;
+}
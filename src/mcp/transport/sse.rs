@@ -0,0 +1,252 @@
+//! Legacy SSE Transport
+//!
+//! Talks to a remote MCP server using the older HTTP+SSE transport: the
+//! client keeps a GET event stream open, the server announces the URL to POST
+//! messages to in an `endpoint` event, and replies arrive on the stream. When
+//! the stream drops it is reopened and the new endpoint is awaited before
+//! further messages are sent.
+
+use super::event_stream::{forward_message, read_events, reconnect_delay, MAX_RECONNECT_ATTEMPTS};
+use super::http::{parse_url, request_error, status_error, HttpOptions};
+use super::{IncomingMessages, Transport};
+use crate::mcp::error::{McpError, Result};
+use async_trait::async_trait;
+use reqwest::header::ACCEPT;
+use reqwest::Url;
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+
+/// Transport to a remote MCP server using HTTP+SSE
+pub struct SseTransport {
+    server: String,
+    http: reqwest::Client,
+    timeout: Duration,
+    endpoint: watch::Receiver<Option<Url>>,
+    task: JoinHandle<()>,
+    closed: AtomicBool,
+}
+
+impl SseTransport {
+    /// Open the event stream and wait for the server's message endpoint
+    pub async fn connect(
+        server: &str,
+        url: &str,
+        options: &HttpOptions,
+    ) -> Result<(Self, IncomingMessages)> {
+        let url = parse_url(server, url)?;
+        let http = options.build_client(server)?;
+        let connection_error = |message: String| McpError::Connection {
+            server: server.to_string(),
+            message,
+        };
+
+        // The first connection is made here so failures reach the caller
+        let response = open_stream(&http, &url)
+            .await
+            .map_err(|e| connection_error(e.to_string()))?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (endpoint_tx, mut endpoint) = watch::channel(None);
+        let task = tokio::spawn(run_stream(
+            server.to_string(),
+            http.clone(),
+            url,
+            response,
+            endpoint_tx,
+            tx,
+        ));
+
+        let transport = Self {
+            server: server.to_string(),
+            http,
+            timeout: options.timeout,
+            endpoint: endpoint.clone(),
+            task,
+            closed: AtomicBool::new(false),
+        };
+
+        let announced = tokio::time::timeout(options.timeout, endpoint.wait_for(Option::is_some))
+            .await
+            .map(|ready| ready.is_ok());
+        match announced {
+            Ok(true) => Ok((transport, rx)),
+            Ok(false) => Err(connection_error(
+                "event stream closed before an endpoint was announced".to_string(),
+            )),
+            Err(_) => Err(connection_error(
+                "server did not announce a message endpoint".to_string(),
+            )),
+        }
+    }
+
+    /// URL messages are currently POSTed to
+    pub fn endpoint(&self) -> Option<Url> {
+        self.endpoint.borrow().clone()
+    }
+
+    /// Wait for an endpoint, which is briefly unset while reconnecting
+    async fn current_endpoint(&self) -> Result<Url> {
+        let mut endpoint = self.endpoint.clone();
+        let current = tokio::time::timeout(self.timeout, endpoint.wait_for(Option::is_some))
+            .await
+            .map(|ready| ready.ok().and_then(|url| url.clone()));
+        match current {
+            Ok(Some(url)) => Ok(url),
+            Ok(None) => Err(McpError::Closed),
+            Err(_) => Err(McpError::Transport(format!(
+                "MCP server '{}' event stream is not connected",
+                self.server
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for SseTransport {
+    async fn send(&self, message: Value) -> Result<()> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(McpError::Closed);
+        }
+        let endpoint = self.current_endpoint().await?;
+
+        let response = self
+            .http
+            .post(endpoint)
+            .timeout(self.timeout)
+            .json(&message)
+            .send()
+            .await
+            .map_err(request_error)?;
+
+        if !response.status().is_success() {
+            return Err(status_error(response).await);
+        }
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.closed.store(true, Ordering::SeqCst);
+        self.task.abort();
+        Ok(())
+    }
+}
+
+impl Drop for SseTransport {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn open_stream(http: &reqwest::Client, url: &Url) -> Result<reqwest::Response> {
+    let response = http
+        .get(url.clone())
+        .header(ACCEPT, "text/event-stream")
+        .send()
+        .await
+        .map_err(request_error)?;
+    if !response.status().is_success() {
+        return Err(status_error(response).await);
+    }
+    Ok(response)
+}
+
+/// Resolve the endpoint the server announces against the SSE URL
+///
+/// Messages are posted with the configured headers, so an endpoint on
+/// another origin is refused rather than handed the credentials.
+fn resolve_endpoint(url: &Url, data: &str) -> std::result::Result<Url, String> {
+    let resolved = url.join(data).map_err(|e| e.to_string())?;
+    if resolved.origin() != url.origin() {
+        return Err(format!(
+            "{} is not on the same origin as {}",
+            resolved,
+            url.origin().ascii_serialization()
+        ));
+    }
+    Ok(resolved)
+}
+
+/// Read the event stream, reconnecting until it cannot be reopened
+///
+/// Dropping `tx` when giving up closes the incoming channel, which fails any
+/// requests still waiting for a reply.
+async fn run_stream(
+    server: String,
+    http: reqwest::Client,
+    url: Url,
+    mut response: reqwest::Response,
+    endpoint: watch::Sender<Option<Url>>,
+    tx: mpsc::UnboundedSender<Value>,
+) {
+    loop {
+        let result = read_events(response, |event| {
+            if event.event == "endpoint" {
+                match resolve_endpoint(&url, event.data.trim()) {
+                    Ok(resolved) => {
+                        tracing::debug!("MCP server '{}' endpoint: {}", server, resolved);
+                        endpoint.send_replace(Some(resolved));
+                    }
+                    Err(e) => {
+                        tracing::warn!("MCP server '{}' sent an invalid endpoint: {}", server, e)
+                    }
+                }
+            } else {
+                forward_message(&server, &event, &tx);
+            }
+        })
+        .await;
+        if let Err(e) = result {
+            tracing::debug!("MCP server '{}' event stream dropped: {}", server, e);
+        }
+        if tx.is_closed() {
+            return;
+        }
+
+        // The endpoint belongs to the old connection
+        endpoint.send_replace(None);
+        tracing::info!("Reconnecting to MCP server '{}'", server);
+
+        let mut attempt = 0;
+        response = loop {
+            attempt += 1;
+            tokio::time::sleep(reconnect_delay(attempt)).await;
+            match open_stream(&http, &url).await {
+                Ok(response) => break response,
+                Err(e) if attempt >= MAX_RECONNECT_ATTEMPTS => {
+                    tracing::warn!(
+                        "Giving up on MCP server '{}' after {} attempts: {}",
+                        server,
+                        attempt,
+                        e
+                    );
+                    return;
+                }
+                Err(e) => tracing::debug!("Reconnecting to MCP server '{}' failed: {}", server, e),
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_endpoint() {
+        let url = Url::parse("https://mcp.example.com/sse").unwrap();
+
+        assert_eq!(
+            resolve_endpoint(&url, "/messages?session=1")
+                .unwrap()
+                .as_str(),
+            "https://mcp.example.com/messages?session=1"
+        );
+        assert!(resolve_endpoint(&url, "https://mcp.example.com:443/messages").is_ok());
+        assert!(resolve_endpoint(&url, "https://evil.example.net/messages").is_err());
+        assert!(resolve_endpoint(&url, "//evil.example.net/messages").is_err());
+        assert!(resolve_endpoint(&url, "http://mcp.example.com/messages").is_err());
+    }
+}
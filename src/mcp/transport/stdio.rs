@@ -0,0 +1,134 @@
+//! stdio Transport
+//!
+//! Runs an MCP server as a child process and exchanges newline-delimited
+//! JSON-RPC messages over its stdin and stdout.
+
+use super::{IncomingMessages, Transport};
+use crate::mcp::error::{McpError, Result};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{mpsc, Mutex};
+
+/// Transport to an MCP server running as a child process
+pub struct StdioTransport {
+    server: String,
+    stdin: Mutex<Option<ChildStdin>>,
+    child: Mutex<Child>,
+}
+
+impl StdioTransport {
+    /// Spawn the server process and start reading its output
+    pub fn spawn(
+        server: &str,
+        command: &str,
+        args: &[String],
+        env: &BTreeMap<String, String>,
+        cwd: Option<&PathBuf>,
+    ) -> Result<(Self, IncomingMessages)> {
+        let mut cmd = Command::new(command);
+        cmd.args(args)
+            .envs(env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(cwd) = cwd {
+            cmd.current_dir(cwd);
+        }
+
+        let mut child = cmd.spawn().map_err(|e| McpError::Connection {
+            server: server.to_string(),
+            message: format!("failed to start '{}': {}", command, e),
+        })?;
+
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take().ok_or_else(|| McpError::Connection {
+            server: server.to_string(),
+            message: "server stdout is not available".to_string(),
+        })?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        // Each line on stdout is one JSON-RPC message
+        let name = server.to_string();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            loop {
+                match lines.next_line().await {
+                    Ok(Some(line)) if line.trim().is_empty() => continue,
+                    Ok(Some(line)) => match serde_json::from_str::<Value>(&line) {
+                        Ok(message) => {
+                            if tx.send(message).is_err() {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            tracing::warn!("MCP server '{}' sent invalid JSON: {}", name, e);
+                        }
+                    },
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::warn!("Failed to read from MCP server '{}': {}", name, e);
+                        break;
+                    }
+                }
+            }
+            tracing::debug!("MCP server '{}' closed its output", name);
+        });
+
+        // Servers log to stderr
+        if let Some(stderr) = child.stderr.take() {
+            let name = server.to_string();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    tracing::debug!("[mcp:{}] {}", name, line);
+                }
+            });
+        }
+
+        Ok((
+            Self {
+                server: server.to_string(),
+                stdin: Mutex::new(stdin),
+                child: Mutex::new(child),
+            },
+            rx,
+        ))
+    }
+}
+
+#[async_trait]
+impl Transport for StdioTransport {
+    async fn send(&self, message: Value) -> Result<()> {
+        let mut line = serde_json::to_string(&message)?;
+        line.push('\n');
+
+        let mut stdin = self.stdin.lock().await;
+        let stdin = stdin.as_mut().ok_or(McpError::Closed)?;
+        stdin.write_all(line.as_bytes()).await.map_err(|e| {
+            McpError::Transport(format!("failed to write to '{}': {}", self.server, e))
+        })?;
+        stdin.flush().await?;
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<()> {
+        // Closing stdin asks the server to exit; kill it if it doesn't
+        self.stdin.lock().await.take();
+
+        let mut child = self.child.lock().await;
+        match tokio::time::timeout(std::time::Duration::from_secs(2), child.wait()).await {
+            Ok(_) => Ok(()),
+            Err(_) => {
+                child.kill().await?;
+                Ok(())
+            }
+        }
+    }
+}
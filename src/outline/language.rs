@@ -0,0 +1,255 @@
+//! Supported Languages
+//!
+//! Maps file extensions to tree-sitter grammars and the node kinds that
+//! define symbols in each language.
+
+use std::path::Path;
+use tree_sitter::Node;
+
+/// A language with an outline grammar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Python,
+    /// TypeScript, also used for JavaScript
+    TypeScript,
+    /// TypeScript or JavaScript with JSX
+    Tsx,
+    Go,
+}
+
+/// Names of the supported languages, for messages
+pub const SUPPORTED: &str = "Rust, Python, TypeScript/JavaScript and Go";
+
+/// A symbol-defining node found while walking a tree
+pub(super) struct Definition<'tree> {
+    pub kind: &'static str,
+    pub name: String,
+    /// Node spanning the whole definition, including export keywords or
+    /// decorators
+    pub outer: Node<'tree>,
+    /// Where the signature starts
+    pub signature_start: usize,
+    /// Where the body starts; the signature ends here
+    pub body_start: Option<usize>,
+    /// Node whose children are the members of the symbol
+    pub members: Option<Node<'tree>>,
+}
+
+impl Language {
+    /// Language of a file, by extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Self::Rust),
+            "py" | "pyi" => Some(Self::Python),
+            "ts" | "mts" | "cts" | "js" | "mjs" | "cjs" => Some(Self::TypeScript),
+            "tsx" | "jsx" => Some(Self::Tsx),
+            "go" => Some(Self::Go),
+            _ => None,
+        }
+    }
+
+    pub(super) fn grammar(self) -> tree_sitter::Language {
+        match self {
+            Self::Rust => tree_sitter_rust::language(),
+            Self::Python => tree_sitter_python::language(),
+            Self::TypeScript => tree_sitter_typescript::language_typescript(),
+            Self::Tsx => tree_sitter_typescript::language_tsx(),
+            Self::Go => tree_sitter_go::language(),
+        }
+    }
+
+    /// Node kinds attached to the definition that follows them
+    pub(super) fn is_leading(self, node: Node) -> bool {
+        match self {
+            Self::Rust => matches!(
+                node.kind(),
+                "attribute_item" | "line_comment" | "block_comment"
+            ),
+            Self::Python => false, // Docstrings are inside the body
+            Self::TypeScript | Self::Tsx | Self::Go => node.kind() == "comment",
+        }
+    }
+
+    /// Nodes whose children are searched for definitions as if they were
+    /// at the node's level
+    pub(super) fn is_transparent(self, node: Node) -> bool {
+        match self {
+            Self::Go => node.kind() == "type_declaration",
+            Self::TypeScript | Self::Tsx => node.kind() == "ambient_declaration",
+            Self::Rust | Self::Python => false,
+        }
+    }
+
+    /// The definition `node` makes, if any
+    pub(super) fn definition<'tree>(
+        self,
+        node: Node<'tree>,
+        source: &str,
+    ) -> Option<Definition<'tree>> {
+        match self {
+            Self::Rust => rust_definition(node, source),
+            Self::Python => python_definition(node, source),
+            Self::TypeScript | Self::Tsx => typescript_definition(node, source),
+            Self::Go => go_definition(node, source),
+        }
+    }
+}
+
+fn text<'a>(node: Node, source: &'a str) -> &'a str {
+    node.utf8_text(source.as_bytes()).unwrap_or_default()
+}
+
+/// Definition with its name in the `name` field and its body in `body`
+fn named<'tree>(
+    node: Node<'tree>,
+    source: &str,
+    kind: &'static str,
+    has_members: bool,
+) -> Option<Definition<'tree>> {
+    let name = text(node.child_by_field_name("name")?, source).to_string();
+    let body = node.child_by_field_name("body");
+    Some(Definition {
+        kind,
+        name,
+        outer: node,
+        signature_start: node.start_byte(),
+        body_start: body.map(|body| body.start_byte()),
+        members: body.filter(|_| has_members),
+    })
+}
+
+fn rust_definition<'tree>(node: Node<'tree>, source: &str) -> Option<Definition<'tree>> {
+    match node.kind() {
+        "function_item" | "function_signature_item" => named(node, source, "fn", false),
+        "struct_item" => named(node, source, "struct", false),
+        "enum_item" => named(node, source, "enum", false),
+        "union_item" => named(node, source, "union", false),
+        "type_item" => named(node, source, "type", false),
+        "const_item" => named(node, source, "const", false),
+        "static_item" => named(node, source, "static", false),
+        "macro_definition" => named(node, source, "macro", false),
+        "trait_item" => named(node, source, "trait", true),
+        "mod_item" => named(node, source, "mod", true),
+        "impl_item" => {
+            let body = node.child_by_field_name("body");
+            Some(Definition {
+                kind: "impl",
+                name: text(node.child_by_field_name("type")?, source).to_string(),
+                outer: node,
+                signature_start: node.start_byte(),
+                body_start: body.map(|body| body.start_byte()),
+                members: body,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn python_definition<'tree>(node: Node<'tree>, source: &str) -> Option<Definition<'tree>> {
+    match node.kind() {
+        "function_definition" => named(node, source, "def", false),
+        "class_definition" => named(node, source, "class", true),
+        "decorated_definition" => {
+            let inner = python_definition(node.child_by_field_name("definition")?, source)?;
+            Some(Definition {
+                outer: node,
+                ..inner
+            })
+        }
+        _ => None,
+    }
+}
+
+fn typescript_definition<'tree>(node: Node<'tree>, source: &str) -> Option<Definition<'tree>> {
+    match node.kind() {
+        "function_declaration" | "generator_function_declaration" | "function_signature" => {
+            named(node, source, "function", false)
+        }
+        "class_declaration" | "abstract_class_declaration" => named(node, source, "class", true),
+        "interface_declaration" => named(node, source, "interface", false),
+        "type_alias_declaration" => named(node, source, "type", false),
+        "enum_declaration" => named(node, source, "enum", false),
+        "internal_module" | "module" => named(node, source, "namespace", true),
+        "method_definition" | "method_signature" | "abstract_method_signature" => {
+            named(node, source, "method", false)
+        }
+        "export_statement" => {
+            let inner = typescript_definition(node.child_by_field_name("declaration")?, source)?;
+            Some(Definition {
+                outer: node,
+                ..inner
+            })
+        }
+        // `const handler = (event) => { ... }`
+        "lexical_declaration" | "variable_declaration" => {
+            let mut cursor = node.walk();
+            let declarator = node
+                .named_children(&mut cursor)
+                .find(|child| child.kind() == "variable_declarator")?;
+            let value = declarator.child_by_field_name("value")?;
+            if !matches!(
+                value.kind(),
+                "arrow_function" | "function" | "function_expression"
+            ) {
+                return None;
+            }
+            Some(Definition {
+                kind: "function",
+                name: text(declarator.child_by_field_name("name")?, source).to_string(),
+                outer: node,
+                signature_start: node.start_byte(),
+                body_start: value.child_by_field_name("body").map(|b| b.start_byte()),
+                members: None,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn go_definition<'tree>(node: Node<'tree>, source: &str) -> Option<Definition<'tree>> {
+    match node.kind() {
+        "function_declaration" => named(node, source, "func", false),
+        "method_declaration" => {
+            let mut definition = named(node, source, "method", false)?;
+            if let Some(receiver) = node
+                .child_by_field_name("receiver")
+                .and_then(|receiver| go_receiver_type(receiver, source))
+            {
+                definition.name = format!("{}.{}", receiver, definition.name);
+            }
+            Some(definition)
+        }
+        "type_spec" | "type_alias" => {
+            let kind = match node.child_by_field_name("type").map(|t| t.kind()) {
+                Some("struct_type") => "struct",
+                Some("interface_type") => "interface",
+                _ => "type",
+            };
+            // A lone spec spans its `type` keyword and doc comment
+            let outer = node
+                .parent()
+                .filter(|parent| parent.named_child_count() == 1)
+                .unwrap_or(node);
+            Some(Definition {
+                kind,
+                name: text(node.child_by_field_name("name")?, source).to_string(),
+                outer,
+                signature_start: outer.start_byte(),
+                body_start: None,
+                members: None,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Type name of a method receiver, `Server` for `(s *Server)`
+fn go_receiver_type(receiver: Node, source: &str) -> Option<String> {
+    let mut cursor = receiver.walk();
+    let parameter = receiver.named_children(&mut cursor).next()?;
+    let receiver_type = text(parameter.child_by_field_name("type")?, source);
+    let name = receiver_type.trim_start_matches('*');
+    let name = name.split('[').next().unwrap_or(name);
+    Some(name.to_string())
+}
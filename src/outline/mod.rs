@@ -0,0 +1,373 @@
+//! Source Code Outlines
+//!
+//! Parses source files with tree-sitter and extracts the symbols they
+//! define (functions, types, impl blocks, classes, methods, ...) with their
+//! signatures and line ranges. The `code_outline` tool uses this to show the
+//! shape of a file without reading all of it.
+
+mod language;
+
+pub use language::{Language, SUPPORTED};
+
+use tree_sitter::{Node, Parser};
+
+/// Longest signature kept, in characters
+const MAX_SIGNATURE_CHARS: usize = 200;
+
+/// A symbol defined in a source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// Kind in the language's own terms: `fn`, `impl`, `class`, `func`, ...
+    pub kind: &'static str,
+
+    /// Name; `Type.Method` for Go methods
+    pub name: String,
+
+    /// Declaration up to its body, on one line
+    pub signature: String,
+
+    /// First line, starting at 1, including doc comments and attributes
+    pub start_line: usize,
+
+    /// Last line, inclusive
+    pub end_line: usize,
+
+    /// Members: methods of an impl block or class, items of a module
+    pub children: Vec<Symbol>,
+}
+
+/// Symbols defined in `source`, in order of appearance
+pub fn outline(source: &str, language: Language) -> Vec<Symbol> {
+    let mut parser = Parser::new();
+    if let Err(e) = parser.set_language(language.grammar()) {
+        tracing::warn!("Failed to load the {:?} grammar: {}", language, e);
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(source, None) else {
+        return Vec::new();
+    };
+
+    let mut symbols = Vec::new();
+    collect(tree.root_node(), source, language, &mut symbols);
+    symbols
+}
+
+fn collect(node: Node, source: &str, language: Language, symbols: &mut Vec<Symbol>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if language.is_transparent(child) {
+            collect(child, source, language, symbols);
+            continue;
+        }
+        let Some(definition) = language.definition(child, source) else {
+            continue;
+        };
+
+        let mut start = definition.outer;
+        while let Some(previous) = start.prev_named_sibling() {
+            let adjacent = previous.end_position().row + 1 >= start.start_position().row;
+            if !adjacent || !language.is_leading(previous) {
+                break;
+            }
+            start = previous;
+        }
+
+        let signature_end = definition.body_start.unwrap_or_else(|| {
+            let from = definition.signature_start;
+            source[from..]
+                .find('\n')
+                .map_or(definition.outer.end_byte(), |newline| from + newline)
+        });
+
+        let mut symbol = Symbol {
+            kind: definition.kind,
+            name: definition.name,
+            signature: signature(&source[definition.signature_start..signature_end]),
+            start_line: start.start_position().row + 1,
+            end_line: definition.outer.end_position().row + 1,
+            children: Vec::new(),
+        };
+        if let Some(members) = definition.members {
+            collect(members, source, language, &mut symbol.children);
+        }
+        symbols.push(symbol);
+    }
+}
+
+/// Collapse a declaration to one line, without the opening of its body
+fn signature(text: &str) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let trimmed = collapsed.trim_end_matches(['{', ':', ';', ' ']);
+    if trimmed.chars().count() > MAX_SIGNATURE_CHARS {
+        let cut: String = trimmed.chars().take(MAX_SIGNATURE_CHARS).collect();
+        format!("{}...", cut)
+    } else {
+        trimmed.to_string()
+    }
+}
+
+impl Symbol {
+    /// Symbols matching `path`, searching members too
+    ///
+    /// `path` is a name, optionally qualified with the names of the
+    /// enclosing symbols: `load`, `Config::load` or `Config.load`.
+    pub fn find<'a>(symbols: &'a [Symbol], path: &str) -> Vec<&'a Symbol> {
+        let wanted: Vec<&str> = split_path(path).collect();
+        let mut found = Vec::new();
+        if !wanted.is_empty() {
+            find_in(symbols, &mut Vec::new(), &wanted, &mut found);
+        }
+        found
+    }
+}
+
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split("::")
+        .flat_map(|part| part.split('.'))
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+}
+
+fn find_in<'a>(
+    symbols: &'a [Symbol],
+    scope: &mut Vec<&'a str>,
+    wanted: &[&str],
+    found: &mut Vec<&'a Symbol>,
+) {
+    for symbol in symbols {
+        let depth = scope.len();
+        scope.extend(split_path(&symbol.name));
+        if scope.ends_with(wanted) {
+            found.push(symbol);
+        }
+        find_in(&symbol.children, scope, wanted, found);
+        scope.truncate(depth);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// `(depth, kind, name, start, end)` of every symbol
+    fn flatten(symbols: &[Symbol]) -> Vec<(usize, &str, &str, usize, usize)> {
+        fn walk<'a>(
+            symbols: &'a [Symbol],
+            depth: usize,
+            out: &mut Vec<(usize, &'a str, &'a str, usize, usize)>,
+        ) {
+            for s in symbols {
+                out.push((depth, s.kind, &s.name, s.start_line, s.end_line));
+                walk(&s.children, depth + 1, out);
+            }
+        }
+        let mut out = Vec::new();
+        walk(symbols, 0, &mut out);
+        out
+    }
+
+    #[test]
+    fn test_language_from_path() {
+        assert_eq!(
+            Language::from_path(Path::new("src/main.rs")),
+            Some(Language::Rust)
+        );
+        assert_eq!(
+            Language::from_path(Path::new("app.js")),
+            Some(Language::TypeScript)
+        );
+        assert_eq!(
+            Language::from_path(Path::new("App.tsx")),
+            Some(Language::Tsx)
+        );
+        assert_eq!(Language::from_path(Path::new("README.md")), None);
+    }
+
+    #[test]
+    fn test_rust_outline() {
+        let source = r#"use std::fmt;
+
+/// Settings
+#[derive(Debug)]
+pub struct Config {
+    name: String,
+}
+
+impl Config {
+    pub fn new(
+        name: &str,
+    ) -> Self {
+        Self { name: name.to_string() }
+    }
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+pub trait Load {
+    fn load() -> Self;
+}
+
+mod tests {
+    fn helper() {}
+}
+"#;
+        let symbols = outline(source, Language::Rust);
+        assert_eq!(
+            flatten(&symbols),
+            vec![
+                (0, "struct", "Config", 3, 7),
+                (0, "impl", "Config", 9, 15),
+                (1, "fn", "new", 10, 14),
+                (0, "impl", "Config", 17, 21),
+                (1, "fn", "fmt", 18, 20),
+                (0, "trait", "Load", 23, 25),
+                (1, "fn", "load", 24, 24),
+                (0, "mod", "tests", 27, 29),
+                (1, "fn", "helper", 28, 28),
+            ]
+        );
+        assert_eq!(symbols[0].signature, "pub struct Config");
+        assert_eq!(
+            symbols[1].children[0].signature,
+            "pub fn new( name: &str, ) -> Self"
+        );
+        assert_eq!(symbols[2].signature, "impl fmt::Display for Config");
+        assert_eq!(symbols[3].children[0].signature, "fn load() -> Self");
+    }
+
+    #[test]
+    fn test_python_outline() {
+        let source = r#"import os
+
+class Store(Base):
+    """Key-value store"""
+
+    @property
+    def size(self) -> int:
+        return 0
+
+    def get(self, key):
+        return None
+
+def main():
+    pass
+"#;
+        let symbols = outline(source, Language::Python);
+        assert_eq!(
+            flatten(&symbols),
+            vec![
+                (0, "class", "Store", 3, 11),
+                (1, "def", "size", 6, 8),
+                (1, "def", "get", 10, 11),
+                (0, "def", "main", 13, 14),
+            ]
+        );
+        assert_eq!(symbols[0].signature, "class Store(Base)");
+        assert_eq!(symbols[0].children[0].signature, "def size(self) -> int");
+    }
+
+    #[test]
+    fn test_typescript_outline() {
+        let source = r#"// Greets people
+export function greet(name: string): string {
+  return `hi ${name}`;
+}
+
+export interface Options {
+  loud: boolean;
+}
+
+export class Greeter {
+  constructor(private prefix: string) {}
+
+  greet(name: string) {
+    return this.prefix + name;
+  }
+}
+
+const handler = async (event: Event) => {
+  return event;
+};
+
+const limit = 10;
+"#;
+        let symbols = outline(source, Language::TypeScript);
+        assert_eq!(
+            flatten(&symbols),
+            vec![
+                (0, "function", "greet", 1, 4),
+                (0, "interface", "Options", 6, 8),
+                (0, "class", "Greeter", 10, 16),
+                (1, "method", "constructor", 11, 11),
+                (1, "method", "greet", 13, 15),
+                (0, "function", "handler", 18, 20),
+            ]
+        );
+        assert_eq!(symbols[0].signature, "function greet(name: string): string");
+        assert_eq!(
+            symbols[3].signature,
+            "const handler = async (event: Event) =>"
+        );
+    }
+
+    #[test]
+    fn test_go_outline() {
+        let source = r#"package main
+
+// Server serves requests
+type Server struct {
+	addr string
+}
+
+type (
+	ID    string
+	Store interface{ Get(ID) string }
+)
+
+func (s *Server) Start() error {
+	return nil
+}
+
+func main() {
+}
+"#;
+        let symbols = outline(source, Language::Go);
+        assert_eq!(
+            flatten(&symbols),
+            vec![
+                (0, "struct", "Server", 3, 6),
+                (0, "type", "ID", 9, 9),
+                (0, "interface", "Store", 10, 10),
+                (0, "method", "Server.Start", 13, 15),
+                (0, "func", "main", 17, 18),
+            ]
+        );
+        assert_eq!(symbols[0].signature, "type Server struct");
+        assert_eq!(symbols[3].signature, "func (s *Server) Start() error");
+    }
+
+    #[test]
+    fn test_find() {
+        let source = "impl Config {\n    fn load() {}\n}\nmod io {\n    fn load() {}\n}\n";
+        let symbols = outline(source, Language::Rust);
+
+        assert_eq!(Symbol::find(&symbols, "load").len(), 2);
+        let found = Symbol::find(&symbols, "Config::load");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].start_line, 2);
+        assert_eq!(Symbol::find(&symbols, "io.load")[0].start_line, 5);
+        assert!(Symbol::find(&symbols, "Other::load").is_empty());
+        assert!(Symbol::find(&symbols, "").is_empty());
+
+        // Go methods match with or without their receiver
+        let go = "package main\nfunc (s *Server) Start() {}\n";
+        let symbols = outline(go, Language::Go);
+        assert_eq!(Symbol::find(&symbols, "Start").len(), 1);
+        assert_eq!(Symbol::find(&symbols, "Server.Start").len(), 1);
+    }
+}
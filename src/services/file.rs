@@ -2,13 +2,32 @@
 //!
 //! Provides business logic for file tracking operations.
 
-use crate::db::{models::File, repository::FileRepository};
+use crate::db::{
+    models::{Checkpoint, File, FileSnapshot},
+    repository::FileRepository,
+};
 use crate::services::ServiceContext;
 use anyhow::{Context, Result};
 use chrono::Utc;
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
+/// What restoring a checkpoint did to a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreAction {
+    /// File content was put back
+    Restored,
+    /// File did not exist before the checkpoint and was removed
+    Deleted,
+}
+
+/// A file touched while restoring a checkpoint
+#[derive(Debug, Clone)]
+pub struct RestoredFile {
+    pub path: PathBuf,
+    pub action: RestoreAction,
+}
+
 /// Service for managing file tracking
 #[derive(Clone)]
 pub struct FileService {
@@ -166,6 +185,149 @@ impl FileService {
         let files = self.list_files_for_session(session_id).await?;
         Ok(files.into_iter().filter(|f| f.content.is_none()).collect())
     }
+
+    /// Snapshot a file before it is modified during a checkpoint
+    ///
+    /// Only the first snapshot of a path per checkpoint is kept, since that is
+    /// the state the checkpoint restores. Returns whether a snapshot was taken.
+    pub async fn snapshot_file(
+        &self,
+        session_id: Uuid,
+        checkpoint_id: Uuid,
+        path: &Path,
+    ) -> Result<bool> {
+        let repo = FileRepository::new(self.context.pool());
+        if repo.has_snapshot(checkpoint_id, path).await? {
+            return Ok(false);
+        }
+
+        let content = if path.exists() {
+            Some(
+                tokio::fs::read(path)
+                    .await
+                    .with_context(|| format!("Failed to read {:?} for snapshot", path))?,
+            )
+        } else {
+            None
+        };
+
+        let snapshot = FileSnapshot {
+            id: Uuid::new_v4(),
+            session_id,
+            checkpoint_id,
+            path: path.to_path_buf(),
+            content,
+            created_at: Utc::now(),
+            commit_hash: None,
+        };
+        repo.create_snapshot(&snapshot)
+            .await
+            .context("Failed to snapshot file")?;
+
+        Ok(true)
+    }
+
+    /// Paths of the files snapshotted during a checkpoint
+    pub async fn checkpoint_paths(&self, checkpoint_id: Uuid) -> Result<Vec<PathBuf>> {
+        let repo = FileRepository::new(self.context.pool());
+        let snapshots = repo
+            .find_snapshots(checkpoint_id)
+            .await
+            .context("Failed to find checkpoint files")?;
+        Ok(snapshots
+            .into_iter()
+            .map(|snapshot| snapshot.path)
+            .collect())
+    }
+
+    /// Record the commit that auto-commit mode made for a checkpoint
+    pub async fn link_commit(&self, checkpoint_id: Uuid, commit_hash: &str) -> Result<()> {
+        let repo = FileRepository::new(self.context.pool());
+        repo.set_checkpoint_commit(checkpoint_id, commit_hash)
+            .await
+            .context("Failed to link checkpoint to commit")
+    }
+
+    /// List checkpoints for a session, newest first
+    pub async fn list_checkpoints(&self, session_id: Uuid) -> Result<Vec<Checkpoint>> {
+        let repo = FileRepository::new(self.context.pool());
+        repo.list_checkpoints(session_id)
+            .await
+            .context("Failed to list checkpoints")
+    }
+
+    /// Restore files to their state before a checkpoint
+    ///
+    /// Undoes the checkpoint and every later one. Their snapshots are removed
+    /// afterwards, so the restored state becomes the new baseline.
+    pub async fn restore_checkpoint(
+        &self,
+        session_id: Uuid,
+        checkpoint_id: Uuid,
+    ) -> Result<Vec<RestoredFile>> {
+        let repo = FileRepository::new(self.context.pool());
+        let snapshots = repo.find_snapshots_since(session_id, checkpoint_id).await?;
+        if snapshots.is_empty() {
+            anyhow::bail!("Checkpoint not found: {}", checkpoint_id);
+        }
+
+        // The oldest snapshot of each path is its state before the checkpoint
+        let mut seen = std::collections::HashSet::new();
+        let mut restored = Vec::new();
+        for snapshot in snapshots {
+            if !seen.insert(snapshot.path.clone()) {
+                continue;
+            }
+
+            let action = match &snapshot.content {
+                Some(content) => {
+                    if let Some(parent) = snapshot.path.parent() {
+                        tokio::fs::create_dir_all(parent).await?;
+                    }
+                    tokio::fs::write(&snapshot.path, content)
+                        .await
+                        .with_context(|| format!("Failed to restore {:?}", snapshot.path))?;
+                    RestoreAction::Restored
+                }
+                None => {
+                    if snapshot.path.exists() {
+                        tokio::fs::remove_file(&snapshot.path)
+                            .await
+                            .with_context(|| format!("Failed to remove {:?}", snapshot.path))?;
+                    }
+                    RestoreAction::Deleted
+                }
+            };
+            restored.push(RestoredFile {
+                path: snapshot.path,
+                action,
+            });
+        }
+
+        repo.delete_snapshots_since(session_id, checkpoint_id)
+            .await
+            .context("Failed to clear restored snapshots")?;
+
+        tracing::info!(
+            "Restored {} file(s) to before checkpoint {}",
+            restored.len(),
+            checkpoint_id
+        );
+        Ok(restored)
+    }
+
+    /// Undo the most recent checkpoint in a session
+    pub async fn undo_last_checkpoint(
+        &self,
+        session_id: Uuid,
+    ) -> Result<Option<(Checkpoint, Vec<RestoredFile>)>> {
+        let Some(checkpoint) = self.list_checkpoints(session_id).await?.into_iter().next() else {
+            return Ok(None);
+        };
+
+        let restored = self.restore_checkpoint(session_id, checkpoint.id).await?;
+        Ok(Some((checkpoint, restored)))
+    }
 }
 
 #[cfg(test)]
@@ -452,4 +614,116 @@ mod tests {
         assert_eq!(with_content.len(), 1);
         assert_eq!(without_content.len(), 1);
     }
+
+    #[tokio::test]
+    async fn test_checkpoint_snapshot_and_restore() {
+        let (file_service, session_service) = create_test_service().await;
+        let session = session_service
+            .create_session(Some("Test".to_string()))
+            .await
+            .unwrap();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let existing = temp_dir.path().join("a.txt");
+        let created = temp_dir.path().join("new.txt");
+        std::fs::write(&existing, "v1").unwrap();
+
+        // Turn 1: modify a.txt and create new.txt
+        let turn1 = Uuid::new_v4();
+        assert!(file_service
+            .snapshot_file(session.id, turn1, &existing)
+            .await
+            .unwrap());
+        std::fs::write(&existing, "v2").unwrap();
+        // Second snapshot of the same path in a turn is skipped
+        assert!(!file_service
+            .snapshot_file(session.id, turn1, &existing)
+            .await
+            .unwrap());
+        file_service
+            .snapshot_file(session.id, turn1, &created)
+            .await
+            .unwrap();
+        std::fs::write(&created, "new").unwrap();
+
+        // Turn 2: modify a.txt again
+        let turn2 = Uuid::new_v4();
+        file_service
+            .snapshot_file(session.id, turn2, &existing)
+            .await
+            .unwrap();
+        std::fs::write(&existing, "v3").unwrap();
+
+        let checkpoints = file_service.list_checkpoints(session.id).await.unwrap();
+        assert_eq!(checkpoints.len(), 2);
+        assert_eq!(checkpoints[0].id, turn2);
+        assert_eq!(checkpoints[1].file_count, 2);
+
+        // Snapshots are not reported as tracked files
+        assert_eq!(
+            file_service
+                .count_files_in_session(session.id)
+                .await
+                .unwrap(),
+            0
+        );
+
+        // Undo turn 2
+        let (checkpoint, restored) = file_service
+            .undo_last_checkpoint(session.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(checkpoint.id, turn2);
+        assert_eq!(restored.len(), 1);
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "v2");
+
+        // Restore to before turn 1
+        let restored = file_service
+            .restore_checkpoint(session.id, turn1)
+            .await
+            .unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "v1");
+        assert!(!created.exists());
+        assert!(restored
+            .iter()
+            .any(|r| r.path == created && r.action == RestoreAction::Deleted));
+
+        assert!(file_service
+            .list_checkpoints(session.id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(file_service
+            .undo_last_checkpoint(session.id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_restores_binary_file() {
+        let (file_service, session_service) = create_test_service().await;
+        let session = session_service
+            .create_session(Some("Test".to_string()))
+            .await
+            .unwrap();
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let image = temp_dir.path().join("logo.png");
+        let original = vec![0x89, b'P', b'N', b'G', 0x00, 0xff, 0xfe];
+        std::fs::write(&image, &original).unwrap();
+
+        let turn = Uuid::new_v4();
+        assert!(file_service
+            .snapshot_file(session.id, turn, &image)
+            .await
+            .unwrap());
+        std::fs::write(&image, "overwritten").unwrap();
+
+        file_service
+            .restore_checkpoint(session.id, turn)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&image).unwrap(), original);
+    }
 }
@@ -41,6 +41,7 @@ impl MessageService {
             created_at: Utc::now(),
             token_count: None,
             cost: None,
+            commit_hash: None,
         };
 
         repo.create(&message)
@@ -108,6 +109,20 @@ impl MessageService {
         Ok(())
     }
 
+    /// Record the commit that auto-commit mode made for a message's turn
+    pub async fn set_commit_hash(&self, id: Uuid, commit_hash: String) -> Result<()> {
+        let mut message = self.get_message_required(id).await?;
+        message.commit_hash = Some(commit_hash);
+
+        let repo = MessageRepository::new(self.context.pool());
+        repo.update(&message)
+            .await
+            .context("Failed to update message commit")?;
+
+        tracing::debug!("Linked message {} to commit {:?}", id, message.commit_hash);
+        Ok(())
+    }
+
     /// Delete a message
     pub async fn delete_message(&self, id: Uuid) -> Result<()> {
         let repo = MessageRepository::new(self.context.pool());
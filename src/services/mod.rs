@@ -8,10 +8,10 @@ pub mod message;
 pub mod plan;
 pub mod session;
 
-pub use file::FileService;
+pub use file::{FileService, RestoreAction, RestoredFile};
 pub use message::MessageService;
 pub use plan::PlanService;
-pub use session::SessionService;
+pub use session::{SessionExport, SessionFilter, SessionService};
 
 use crate::db::Pool;
 use std::sync::Arc;
@@ -3,14 +3,62 @@
 //! Provides business logic for session management operations.
 
 use crate::db::{
-    models::Session,
-    repository::{SessionListOptions, SessionRepository},
+    models::{Message, Session},
+    repository::{MessageRepository, SessionListOptions, SessionRepository},
 };
 use crate::services::ServiceContext;
 use anyhow::{Context, Result};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// Version of the session export format
+pub const SESSION_EXPORT_VERSION: u32 = 1;
+
+/// A session and its messages, as exported to JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionExport {
+    /// Export format version
+    pub version: u32,
+    /// The session
+    pub session: Session,
+    /// Its messages, in order
+    pub messages: Vec<Message>,
+}
+
+/// Criteria for picking sessions out of a list
+#[derive(Debug, Clone, Default)]
+pub struct SessionFilter {
+    /// Last active at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Last active at or before this time
+    pub until: Option<DateTime<Utc>>,
+    /// Model name contains this (case-insensitive)
+    pub model: Option<String>,
+    /// Cost at least this much
+    pub min_cost: Option<f64>,
+    /// Cost at most this much
+    pub max_cost: Option<f64>,
+}
+
+impl SessionFilter {
+    /// Whether a session meets every criterion
+    pub fn matches(&self, session: &Session) -> bool {
+        let model_matches = match &self.model {
+            Some(model) => session
+                .model
+                .as_ref()
+                .is_some_and(|m| m.to_lowercase().contains(&model.to_lowercase())),
+            None => true,
+        };
+        model_matches
+            && !self.since.is_some_and(|since| session.updated_at < since)
+            && !self.until.is_some_and(|until| session.updated_at > until)
+            && !self.min_cost.is_some_and(|min| session.total_cost < min)
+            && !self.max_cost.is_some_and(|max| session.total_cost > max)
+    }
+}
+
 /// Service for managing sessions
 #[derive(Clone)]
 pub struct SessionService {
@@ -96,6 +144,18 @@ impl SessionService {
         Ok(())
     }
 
+    /// Set the model of a session
+    ///
+    /// Unlike [`Self::update_session`], this doesn't overwrite the usage
+    /// recorded while the session's messages were sent.
+    pub async fn update_session_model(&self, id: Uuid, model: &str) -> Result<()> {
+        let repo = SessionRepository::new(self.context.pool());
+        repo.set_model(id, model).await?;
+
+        tracing::debug!("Updated session model: {} ({})", id, model);
+        Ok(())
+    }
+
     /// Update session usage statistics
     pub async fn update_session_usage(&self, id: Uuid, token_count: i32, cost: f64) -> Result<()> {
         let mut session = self.get_session_required(id).await?;
@@ -143,11 +203,100 @@ impl SessionService {
     pub async fn delete_session(&self, id: Uuid) -> Result<()> {
         let repo = SessionRepository::new(self.context.pool());
         repo.delete(id).await.context("Failed to delete session")?;
+        if let Err(e) = crate::llm::tools::output::remove_artifacts(id).await {
+            tracing::warn!(
+                "Failed to remove stored tool output of session {}: {}",
+                id,
+                e
+            );
+        }
 
         tracing::info!("Deleted session: {}", id);
         Ok(())
     }
 
+    /// Find a session by its full ID or a unique prefix of it
+    ///
+    /// Archived sessions are included, so they can be resumed too.
+    pub async fn resolve_session(&self, id_or_prefix: &str) -> Result<Session> {
+        let id_or_prefix = id_or_prefix.trim().to_lowercase();
+        if let Ok(id) = Uuid::parse_str(&id_or_prefix) {
+            return self
+                .get_session(id)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Session not found: {}", id));
+        }
+        if id_or_prefix.is_empty() {
+            anyhow::bail!("Session ID must not be empty");
+        }
+
+        let repo = SessionRepository::new(self.context.pool());
+        let mut sessions = repo.find_by_prefix(&id_or_prefix).await?;
+        match sessions.len() {
+            0 => anyhow::bail!("No session matches '{}'", id_or_prefix),
+            1 => Ok(sessions.remove(0)),
+            n => anyhow::bail!(
+                "'{}' matches {} sessions; use a longer prefix",
+                id_or_prefix,
+                n
+            ),
+        }
+    }
+
+    /// A session with all its messages
+    pub async fn export_session(&self, id: Uuid) -> Result<SessionExport> {
+        let session = self.get_session_required(id).await?;
+        let messages = MessageRepository::new(self.context.pool())
+            .find_by_session(id)
+            .await?;
+
+        Ok(SessionExport {
+            version: SESSION_EXPORT_VERSION,
+            session,
+            messages,
+        })
+    }
+
+    /// Import an exported session as a new session
+    ///
+    /// The session and its messages get fresh IDs, so the same export can be
+    /// imported more than once; everything else is kept.
+    pub async fn import_session(&self, export: SessionExport) -> Result<Session> {
+        if export.version > SESSION_EXPORT_VERSION {
+            anyhow::bail!(
+                "Unsupported session export version {} (expected at most {})",
+                export.version,
+                SESSION_EXPORT_VERSION
+            );
+        }
+
+        let session = Session {
+            id: Uuid::new_v4(),
+            ..export.session
+        };
+        SessionRepository::new(self.context.pool())
+            .create(&session)
+            .await
+            .context("Failed to create session")?;
+
+        let message_repo = MessageRepository::new(self.context.pool());
+        for message in export.messages {
+            let message = Message {
+                id: Uuid::new_v4(),
+                session_id: session.id,
+                ..message
+            };
+            message_repo.create(&message).await?;
+            if message.commit_hash.is_some() {
+                // Not part of a message's creation
+                message_repo.update(&message).await?;
+            }
+        }
+
+        tracing::info!("Imported session: {}", session.id);
+        Ok(session)
+    }
+
     /// Get the most recent active session
     pub async fn get_most_recent_session(&self) -> Result<Option<Session>> {
         let repo = SessionRepository::new(self.context.pool());
@@ -354,6 +503,131 @@ mod tests {
         assert_eq!(recent.unwrap().id, session2.id);
     }
 
+    #[tokio::test]
+    async fn test_resolve_session() {
+        let service = create_test_service().await;
+        let session = service
+            .create_session(Some("Session 1".to_string()))
+            .await
+            .unwrap();
+        let id = session.id.to_string();
+
+        assert_eq!(service.resolve_session(&id).await.unwrap().id, session.id);
+        assert_eq!(
+            service
+                .resolve_session(&id[..8].to_uppercase())
+                .await
+                .unwrap()
+                .id,
+            session.id
+        );
+        assert!(service.resolve_session("").await.is_err());
+        assert!(service
+            .resolve_session(&Uuid::new_v4().to_string())
+            .await
+            .is_err());
+
+        // A second session sharing the prefix makes it ambiguous
+        let mut twin = session.clone();
+        twin.id =
+            Uuid::parse_str(&format!("{}{}", &id[..8], &Uuid::new_v4().to_string()[8..])).unwrap();
+        SessionRepository::new(service.context.pool())
+            .create(&twin)
+            .await
+            .unwrap();
+        let err = service.resolve_session(&id[..8]).await.unwrap_err();
+        assert!(err.to_string().contains("matches 2 sessions"));
+        assert_eq!(service.resolve_session(&id).await.unwrap().id, session.id);
+    }
+
+    #[tokio::test]
+    async fn test_export_import_roundtrip() {
+        use crate::services::MessageService;
+
+        let service = create_test_service().await;
+        let session = service
+            .create_session(Some("Exported".to_string()))
+            .await
+            .unwrap();
+        let message_service = MessageService::new(service.context.clone());
+        message_service
+            .create_message(session.id, "user".to_string(), "Hello".to_string())
+            .await
+            .unwrap();
+        let reply = message_service
+            .create_message(session.id, "assistant".to_string(), "Hi".to_string())
+            .await
+            .unwrap();
+        message_service
+            .set_commit_hash(reply.id, "abc123".to_string())
+            .await
+            .unwrap();
+
+        let export = service.export_session(session.id).await.unwrap();
+        assert_eq!(export.version, SESSION_EXPORT_VERSION);
+        assert_eq!(export.messages.len(), 2);
+
+        let json = serde_json::to_string(&export).unwrap();
+        let imported = service
+            .import_session(serde_json::from_str(&json).unwrap())
+            .await
+            .unwrap();
+        assert_ne!(imported.id, session.id);
+        assert_eq!(imported.title.as_deref(), Some("Exported"));
+
+        let messages = message_service
+            .list_messages_for_session(imported.id)
+            .await
+            .unwrap();
+        let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["Hello", "Hi"]);
+        assert_eq!(messages[1].commit_hash.as_deref(), Some("abc123"));
+
+        let mut future = export;
+        future.version = SESSION_EXPORT_VERSION + 1;
+        assert!(service.import_session(future).await.is_err());
+    }
+
+    #[test]
+    fn test_session_filter() {
+        let session = Session {
+            id: Uuid::new_v4(),
+            title: None,
+            model: Some("claude-3-5-sonnet".to_string()),
+            created_at: Utc::now(),
+            updated_at: "2025-06-15T12:00:00Z".parse().unwrap(),
+            archived_at: None,
+            token_count: 100,
+            total_cost: 0.5,
+        };
+
+        assert!(SessionFilter::default().matches(&session));
+        let filter = SessionFilter {
+            since: Some("2025-06-01T00:00:00Z".parse().unwrap()),
+            until: Some("2025-06-30T00:00:00Z".parse().unwrap()),
+            model: Some("Sonnet".to_string()),
+            min_cost: Some(0.1),
+            max_cost: Some(1.0),
+        };
+        assert!(filter.matches(&session));
+
+        let later = SessionFilter {
+            since: Some("2025-07-01T00:00:00Z".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(!later.matches(&session));
+        let other_model = SessionFilter {
+            model: Some("gpt".to_string()),
+            ..Default::default()
+        };
+        assert!(!other_model.matches(&session));
+        let cheap = SessionFilter {
+            max_cost: Some(0.1),
+            ..Default::default()
+        };
+        assert!(!cheap.matches(&session));
+    }
+
     #[tokio::test]
     async fn test_count_sessions() {
         let service = create_test_service().await;
@@ -2,12 +2,20 @@
 //!
 //! Core state management for the terminal user interface.
 
+use super::approval::ApprovalEditor;
+use super::custom_commands::{self, CustomCommand};
 use super::events::{AppMode, EventHandler, ToolApprovalRequest, ToolApprovalResponse, TuiEvent};
 use super::plan::PlanDocument;
 use super::prompt_analyzer::PromptAnalyzer;
-use crate::db::models::{Message, Session};
+use super::prompt_form::PromptForm;
+use crate::db::models::{Checkpoint, Message, Session};
 use crate::llm::agent::AgentService;
-use crate::services::{MessageService, PlanService, ServiceContext, SessionService};
+use crate::llm::tools::{ToolProgress, ToolProgressEvent};
+use crate::mcp::{McpManager, ServerResource};
+use crate::services::{
+    FileService, MessageService, PlanService, RestoreAction, RestoredFile, ServiceContext,
+    SessionService,
+};
 use anyhow::Result;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -36,12 +44,75 @@ impl From<Message> for DisplayMessage {
     }
 }
 
+/// Built-in slash commands with their descriptions
+pub const BUILTIN_COMMANDS: &[(&str, &str)] = &[
+    ("/undo", "Revert file changes from the last turn"),
+    ("/checkpoints", "Browse checkpoints and restore files"),
+    ("/mcp", "List MCP servers, prompts and resources"),
+    ("/memory", "Show the project instruction files in use"),
+];
+
+/// Maximum number of output lines kept for the tool activity pane
+const TOOL_ACTIVITY_MAX_LINES: usize = 200;
+
+/// Live state of the most recent tool execution
+#[derive(Debug, Clone)]
+pub struct ToolActivity {
+    pub tool_name: String,
+    pub lines: std::collections::VecDeque<ToolOutputLine>,
+    pub percent: Option<u8>,
+    pub status: Option<String>,
+    pub finished: Option<bool>,
+    pub started_at: std::time::Instant,
+}
+
+/// A single line of tool output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolOutputLine {
+    pub text: String,
+    pub is_stderr: bool,
+}
+
+impl ToolActivity {
+    /// Start tracking a tool execution
+    pub fn new(tool_name: String) -> Self {
+        Self {
+            tool_name,
+            lines: std::collections::VecDeque::new(),
+            percent: None,
+            status: None,
+            finished: None,
+            started_at: std::time::Instant::now(),
+        }
+    }
+
+    /// Apply a progress update
+    pub fn apply(&mut self, progress: ToolProgress) {
+        match progress {
+            ToolProgress::Started => {}
+            ToolProgress::Stdout(text) => self.push_line(text, false),
+            ToolProgress::Stderr(text) => self.push_line(text, true),
+            ToolProgress::Percent(percent) => self.percent = Some(percent.min(100)),
+            ToolProgress::Status(status) => self.status = Some(status),
+            ToolProgress::Finished { success } => self.finished = Some(success),
+        }
+    }
+
+    fn push_line(&mut self, text: String, is_stderr: bool) {
+        if self.lines.len() >= TOOL_ACTIVITY_MAX_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(ToolOutputLine { text, is_stderr });
+    }
+}
+
 /// Main application state
 pub struct App {
     // Core state
     pub current_session: Option<Session>,
     pub messages: Vec<DisplayMessage>,
     pub sessions: Vec<Session>,
+    initial_session: Option<Uuid>,
 
     // UI state
     pub mode: AppMode,
@@ -64,6 +135,12 @@ pub struct App {
     // Approval state
     pub pending_approval: Option<ToolApprovalRequest>,
     pub show_approval_details: bool,
+    pub approval_scroll: u16,
+    pub approval_editor: Option<ApprovalEditor>,
+    pub approval_input_edited: bool,
+
+    // Tool activity state (live output of the running tool)
+    pub tool_activity: Option<ToolActivity>,
 
     // Plan mode state
     pub current_plan: Option<PlanDocument>,
@@ -76,6 +153,18 @@ pub struct App {
     pub file_picker_selected: usize,
     pub file_picker_scroll_offset: usize,
     pub file_picker_current_dir: std::path::PathBuf,
+    pub file_picker_show_resources: bool,
+
+    // MCP state (resources for the `@` picker, prompts as slash commands)
+    mcp_manager: Option<Arc<McpManager>>,
+    pub prompt_form: Option<PromptForm>,
+
+    // Custom slash commands from markdown files
+    pub custom_commands: Vec<CustomCommand>,
+
+    // Checkpoint browser state
+    pub checkpoints: Vec<Checkpoint>,
+    pub selected_checkpoint_index: usize,
 
     // Working directory
     pub working_directory: std::path::PathBuf,
@@ -85,6 +174,7 @@ pub struct App {
     session_service: SessionService,
     message_service: MessageService,
     plan_service: PlanService,
+    file_service: FileService,
 
     // Events
     event_handler: EventHandler,
@@ -100,6 +190,7 @@ impl App {
             current_session: None,
             messages: Vec::new(),
             sessions: Vec::new(),
+            initial_session: None,
             mode: AppMode::Splash,
             input_buffer: String::new(),
             scroll_offset: 0,
@@ -112,6 +203,10 @@ impl App {
             splash_shown_at: Some(std::time::Instant::now()),
             pending_approval: None,
             show_approval_details: false,
+            approval_scroll: 0,
+            approval_editor: None,
+            approval_input_edited: false,
+            tool_activity: None,
             current_plan: None,
             plan_scroll_offset: 0,
             selected_task_index: None,
@@ -120,10 +215,17 @@ impl App {
             file_picker_selected: 0,
             file_picker_scroll_offset: 0,
             file_picker_current_dir: std::env::current_dir().unwrap_or_default(),
+            file_picker_show_resources: false,
+            mcp_manager: None,
+            prompt_form: None,
+            custom_commands: Vec::new(),
+            checkpoints: Vec::new(),
+            selected_checkpoint_index: 0,
             working_directory: std::env::current_dir().unwrap_or_default(),
             session_service: SessionService::new(context.clone()),
             message_service: MessageService::new(context.clone()),
-            plan_service: PlanService::new(context),
+            plan_service: PlanService::new(context.clone()),
+            file_service: FileService::new(context),
             agent_service,
             event_handler: EventHandler::new(),
             prompt_analyzer: PromptAnalyzer::new(),
@@ -142,8 +244,10 @@ impl App {
 
     /// Initialize the app by loading or creating a session
     pub async fn initialize(&mut self) -> Result<()> {
-        // Try to load most recent session
-        if let Some(session) = self.session_service.get_most_recent_session().await? {
+        // Load the requested session, else try the most recent one
+        if let Some(session_id) = self.initial_session {
+            self.load_session(session_id).await?;
+        } else if let Some(session) = self.session_service.get_most_recent_session().await? {
             self.load_session(session.id).await?;
         } else {
             // Create a new session if none exists
@@ -153,9 +257,84 @@ impl App {
         // Load sessions list
         self.load_sessions().await?;
 
+        self.reload_custom_commands();
+
         Ok(())
     }
 
+    /// Read the custom slash command files again
+    pub fn reload_custom_commands(&mut self) {
+        self.custom_commands = custom_commands::load_commands(
+            custom_commands::default_user_dir().as_deref(),
+            &self.working_directory,
+        );
+    }
+
+    /// Slash commands matching the command being typed, with descriptions
+    ///
+    /// Empty unless the input is a single word starting with `/`.
+    pub fn slash_command_matches(&self) -> Vec<(String, String)> {
+        let input = self.input_buffer.as_str();
+        if !input.starts_with('/') || input.contains(char::is_whitespace) {
+            return Vec::new();
+        }
+
+        let builtin = BUILTIN_COMMANDS
+            .iter()
+            .map(|(command, description)| (command.to_string(), description.to_string()));
+        let custom = self
+            .custom_commands
+            .iter()
+            .filter(|command| {
+                !BUILTIN_COMMANDS
+                    .iter()
+                    .any(|(name, _)| *name == command.command())
+            })
+            .map(|command| (command.command(), command.summary()));
+        let mcp = self.mcp_manager.iter().flat_map(|manager| {
+            manager.prompts().iter().map(|prompt| {
+                (
+                    prompt.command(),
+                    prompt.prompt.description.clone().unwrap_or_default(),
+                )
+            })
+        });
+
+        builtin
+            .chain(custom)
+            .chain(mcp)
+            .filter(|(command, _)| command.starts_with(input))
+            .collect()
+    }
+
+    /// Complete the slash command being typed, as far as it is unambiguous
+    fn complete_slash_command(&mut self) {
+        if self.input_buffer.starts_with('/') {
+            self.reload_custom_commands();
+        }
+        let matches = self.slash_command_matches();
+        let Some((first, _)) = matches.first() else {
+            return;
+        };
+        if matches.len() == 1 {
+            self.input_buffer = format!("{} ", first);
+            return;
+        }
+        let mut prefix = first.clone();
+        for (command, _) in &matches[1..] {
+            let common = prefix
+                .chars()
+                .zip(command.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(c, _)| c.len_utf8())
+                .sum();
+            prefix.truncate(common);
+        }
+        if prefix.len() > self.input_buffer.len() {
+            self.input_buffer = prefix;
+        }
+    }
+
     /// Get event handler
     pub fn event_handler(&self) -> &EventHandler {
         &self.event_handler
@@ -176,6 +355,24 @@ impl App {
         self.agent_service = agent_service;
     }
 
+    /// Open this session on startup instead of the most recent one
+    pub fn set_initial_session(&mut self, session_id: Uuid) {
+        self.initial_session = Some(session_id);
+    }
+
+    /// Set the MCP servers whose resources and prompts are offered in chat
+    pub fn set_mcp_manager(&mut self, mcp_manager: Arc<McpManager>) {
+        self.mcp_manager = Some(mcp_manager);
+    }
+
+    /// Resources offered by the connected MCP servers
+    pub fn mcp_resources(&self) -> &[ServerResource] {
+        self.mcp_manager
+            .as_ref()
+            .map(|m| m.resources())
+            .unwrap_or_default()
+    }
+
     /// Receive next event
     pub async fn next_event(&mut self) -> Option<TuiEvent> {
         self.event_handler.next().await
@@ -234,6 +431,7 @@ impl App {
                             request_id: approval_request.request_id,
                             approved: false,
                             reason: Some("Approval request timed out after 5 minutes".to_string()),
+                            edited_input: None,
                         };
 
                         // Send response
@@ -256,10 +454,14 @@ impl App {
                 // Response is sent via channel, just update UI state
                 self.pending_approval = None;
                 self.show_approval_details = false;
+                self.approval_editor = None;
                 self.mode = AppMode::Chat;
                 // Auto-scroll to show tool execution result
                 self.scroll_offset = 0;
             }
+            TuiEvent::ToolProgress(event) => {
+                self.handle_tool_progress(event);
+            }
             TuiEvent::Resize(_, _) | TuiEvent::AgentProcessing => {
                 // These are handled by the render loop
             }
@@ -343,6 +545,7 @@ impl App {
             AppMode::Chat => self.handle_chat_key(event).await?,
             AppMode::Plan => self.handle_plan_key(event).await?,
             AppMode::Sessions => self.handle_sessions_key(event).await?,
+            AppMode::Checkpoints => self.handle_checkpoints_key(event).await?,
             AppMode::ToolApproval => self.handle_approval_key(event).await?,
             AppMode::FilePicker => self.handle_file_picker_key(event).await?,
             AppMode::Help | AppMode::Settings => {
@@ -360,10 +563,28 @@ impl App {
         use super::events::keys;
         use crossterm::event::KeyCode;
 
+        // Answers to an MCP prompt's arguments may be empty (to skip one)
+        if self.prompt_form.is_some() {
+            if keys::is_submit(&event) {
+                let answer = std::mem::take(&mut self.input_buffer);
+                self.answer_prompt_form(&answer).await?;
+                return Ok(());
+            }
+            if keys::is_cancel(&event) {
+                if let Some(form) = self.prompt_form.take() {
+                    self.push_system_message(format!("Cancelled {}.", form.prompt.command()));
+                }
+                self.input_buffer.clear();
+                return Ok(());
+            }
+        }
+
         if keys::is_submit(&event) && !self.input_buffer.trim().is_empty() {
             let content = self.input_buffer.clone();
             self.input_buffer.clear();
-            self.send_message(content).await?;
+            if !self.handle_slash_command(&content).await? {
+                self.send_message(content).await?;
+            }
         } else if keys::is_cancel(&event) {
             self.input_buffer.clear();
             self.error_message = None;
@@ -379,6 +600,7 @@ impl App {
             match event.code {
                 KeyCode::Char('@') => {
                     // Trigger file picker mode
+                    self.file_picker_show_resources = false;
                     self.open_file_picker().await?;
                 }
                 KeyCode::Char(c) => {
@@ -390,6 +612,7 @@ impl App {
                 KeyCode::Enter => {
                     self.input_buffer.push('\n');
                 }
+                KeyCode::Tab => self.complete_slash_command(),
                 _ => {}
             }
         }
@@ -418,6 +641,248 @@ impl App {
         Ok(())
     }
 
+    /// Handle keys in the checkpoint browser
+    async fn handle_checkpoints_key(&mut self, event: crossterm::event::KeyEvent) -> Result<()> {
+        use super::events::keys;
+
+        if keys::is_cancel(&event) {
+            self.switch_mode(AppMode::Chat).await?;
+        } else if keys::is_up(&event) {
+            self.selected_checkpoint_index = self.selected_checkpoint_index.saturating_sub(1);
+        } else if keys::is_down(&event) {
+            self.selected_checkpoint_index =
+                (self.selected_checkpoint_index + 1).min(self.checkpoints.len().saturating_sub(1));
+        } else if keys::is_enter(&event) {
+            if let (Some(session), Some(checkpoint)) = (
+                &self.current_session,
+                self.checkpoints.get(self.selected_checkpoint_index),
+            ) {
+                let restored = self
+                    .file_service
+                    .restore_checkpoint(session.id, checkpoint.id)
+                    .await?;
+                let when = checkpoint.created_at.format("%H:%M:%S").to_string();
+                self.push_system_message(format_restored(
+                    &format!("Restored files to before the turn at {}", when),
+                    &restored,
+                ));
+                self.switch_mode(AppMode::Chat).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle slash commands typed in chat: built-in, custom and MCP prompts
+    ///
+    /// Returns `true` if the input was a command and must not be sent to the agent.
+    async fn handle_slash_command(&mut self, input: &str) -> Result<bool> {
+        let command = input.trim();
+        if !command.starts_with('/') || self.is_processing {
+            return Ok(false);
+        }
+
+        match command {
+            "/undo" => {
+                let Some(session) = &self.current_session else {
+                    return Ok(true);
+                };
+                match self.file_service.undo_last_checkpoint(session.id).await? {
+                    Some((_, restored)) => {
+                        self.push_system_message(format_restored(
+                            "Undid file changes from the last turn",
+                            &restored,
+                        ));
+                    }
+                    None => {
+                        self.push_system_message("Nothing to undo.".to_string());
+                    }
+                }
+                Ok(true)
+            }
+            "/checkpoints" => {
+                self.switch_mode(AppMode::Checkpoints).await?;
+                Ok(true)
+            }
+            "/mcp" => {
+                self.push_system_message(self.describe_mcp());
+                Ok(true)
+            }
+            "/memory" => {
+                self.push_system_message(self.describe_memory());
+                Ok(true)
+            }
+            _ => {
+                let (name, arguments) = command
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((command, ""));
+
+                // Custom commands are read again so edits apply right away
+                self.reload_custom_commands();
+                if let Some(custom) = self
+                    .custom_commands
+                    .iter()
+                    .find(|custom| custom.command() == name)
+                    .cloned()
+                {
+                    let prompt = custom.render(arguments);
+                    if prompt.is_empty() {
+                        self.show_error(format!("{} has an empty prompt", name));
+                    } else {
+                        self.send_message_with(prompt, custom.model, custom.allowed_tools)
+                            .await?;
+                    }
+                    return Ok(true);
+                }
+
+                // MCP prompt templates run as `/<server>:<prompt> [name=value ...]`
+                let Some(prompt) = self
+                    .mcp_manager
+                    .as_ref()
+                    .and_then(|m| m.find_prompt(name))
+                    .cloned()
+                else {
+                    return Ok(false);
+                };
+                self.prompt_form = Some(PromptForm::new(prompt, arguments));
+                self.continue_prompt_form().await?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Record an answer to the MCP prompt being filled in
+    async fn answer_prompt_form(&mut self, answer: &str) -> Result<()> {
+        let Some(form) = &mut self.prompt_form else {
+            return Ok(());
+        };
+        if let Err(e) = form.answer(answer) {
+            self.error_message = Some(e);
+            return Ok(());
+        }
+        self.error_message = None;
+        self.continue_prompt_form().await
+    }
+
+    /// Ask for the next prompt argument, or run the prompt once all are known
+    async fn continue_prompt_form(&mut self) -> Result<()> {
+        let Some(form) = &self.prompt_form else {
+            return Ok(());
+        };
+        if let Some(question) = form.question() {
+            self.push_system_message(question);
+            return Ok(());
+        }
+
+        let Some(form) = self.prompt_form.take() else {
+            return Ok(());
+        };
+        let Some(manager) = self.mcp_manager.clone() else {
+            return Ok(());
+        };
+        match manager.get_prompt(&form.prompt, &form.values).await {
+            Ok(text) if !text.trim().is_empty() => self.send_message(text).await?,
+            Ok(_) => self.show_error(format!(
+                "{} produced an empty prompt",
+                form.prompt.command()
+            )),
+            Err(e) => self.show_error(format!("{} failed: {}", form.prompt.command(), e)),
+        }
+        Ok(())
+    }
+
+    /// List the project instruction files that go into the system prompt
+    fn describe_memory(&self) -> String {
+        use crate::llm::prompt::context_files::{FILE_NAMES, GLOBAL_FILE_NAME};
+
+        let Some(project_context) = self.agent_service.project_context() else {
+            return "Project instruction files are disabled ([context_files] in the config)."
+                .to_string();
+        };
+        let files = project_context.load();
+        if files.is_empty() {
+            return format!(
+                "No project instruction files found. Crustly reads {} from the working \
+                 directory up to the repository root, and {} from the config directory.",
+                FILE_NAMES.join(", "),
+                GLOBAL_FILE_NAME
+            );
+        }
+
+        let mut text = String::from("Project instructions in the system prompt:");
+        for file in &files {
+            text.push_str(&format!(
+                "\n- **{}** ({} chars",
+                project_context.display_path(&file.path),
+                file.chars
+            ));
+            if file.truncated {
+                text.push_str(&format!(
+                    ", cut to {} by the size limits",
+                    file.content.chars().count()
+                ));
+            }
+            text.push(')');
+            for include in &file.includes {
+                text.push_str(&format!(
+                    "\n  - includes {}",
+                    project_context.display_path(include)
+                ));
+            }
+        }
+        text
+    }
+
+    /// List the connected MCP servers with their prompts and resources
+    fn describe_mcp(&self) -> String {
+        let Some(manager) = self
+            .mcp_manager
+            .as_ref()
+            .filter(|m| !m.clients().is_empty())
+        else {
+            return "No MCP servers connected.".to_string();
+        };
+
+        let mut text = String::from("MCP servers:");
+        for client in manager.clients() {
+            text.push_str(&format!(
+                "\n- **{}** ({} {})",
+                client.name(),
+                client.server_info().name,
+                client.server_info().version
+            ));
+        }
+        if !manager.prompts().is_empty() {
+            text.push_str("\n\nPrompts:");
+            for prompt in manager.prompts() {
+                text.push_str(&format!("\n- `{}`", prompt.command()));
+                if let Some(description) = &prompt.prompt.description {
+                    text.push_str(&format!(" — {}", description));
+                }
+            }
+        }
+        if !manager.resources().is_empty() {
+            text.push_str("\n\nResources (type @ and press Tab to pick one):");
+            for resource in manager.resources() {
+                text.push_str(&format!("\n- `{}`", resource.reference()));
+            }
+        }
+        text
+    }
+
+    /// Show a local notice in the chat (not sent to the agent)
+    fn push_system_message(&mut self, content: String) {
+        self.messages.push(DisplayMessage {
+            id: Uuid::new_v4(),
+            role: "system".to_string(),
+            content,
+            timestamp: chrono::Utc::now(),
+            token_count: None,
+            cost: None,
+        });
+        self.scroll_offset = 0;
+    }
+
     /// Handle keys in plan mode
     async fn handle_plan_key(&mut self, event: crossterm::event::KeyEvent) -> Result<()> {
         use super::events::keys;
@@ -593,6 +1058,16 @@ impl App {
 
     /// Send a message to the agent
     async fn send_message(&mut self, content: String) -> Result<()> {
+        self.send_message_with(content, None, None).await
+    }
+
+    /// Send a message, optionally to another model or with fewer tools
+    async fn send_message_with(
+        &mut self,
+        content: String,
+        model: Option<String>,
+        allowed_tools: Option<Vec<String>>,
+    ) -> Result<()> {
         if let Some(session) = &self.current_session {
             self.is_processing = true;
             self.error_message = None;
@@ -624,14 +1099,22 @@ impl App {
             let session_id = session.id;
             let event_sender = self.event_sender();
             let read_only_mode = self.mode == AppMode::Plan;
+            let mcp_manager = self.mcp_manager.clone();
 
             tokio::spawn(async move {
+                // Inline the contents of `@server:uri` references
+                let transformed_content = match mcp_manager {
+                    Some(manager) => manager.inline_resources(&transformed_content).await,
+                    None => transformed_content,
+                };
+
                 match agent_service
-                    .send_message_with_tools_and_mode(
+                    .send_message_with_tool_filter(
                         session_id,
                         transformed_content,
-                        None,
+                        model,
                         read_only_mode,
+                        allowed_tools.as_deref(),
                     )
                     .await
                 {
@@ -648,6 +1131,25 @@ impl App {
         Ok(())
     }
 
+    /// Update the tool activity pane from a progress event
+    fn handle_tool_progress(&mut self, event: ToolProgressEvent) {
+        // A new tool starting replaces whatever was shown before
+        let is_new_tool = matches!(event.progress, ToolProgress::Started)
+            || self
+                .tool_activity
+                .as_ref()
+                .map(|a| a.tool_name != event.tool_name)
+                .unwrap_or(true);
+
+        if is_new_tool {
+            self.tool_activity = Some(ToolActivity::new(event.tool_name));
+        }
+
+        if let Some(activity) = &mut self.tool_activity {
+            activity.apply(event.progress);
+        }
+    }
+
     /// Append a streaming chunk
     fn append_streaming_chunk(&mut self, chunk: String) {
         if let Some(ref mut response) = self.streaming_response {
@@ -666,6 +1168,7 @@ impl App {
     ) -> Result<()> {
         self.is_processing = false;
         self.streaming_response = None;
+        self.tool_activity = None;
 
         // Check task completion FIRST (before moving response.content)
         let task_failed = if self.executing_plan {
@@ -687,6 +1190,17 @@ impl App {
         };
         self.messages.push(assistant_msg);
 
+        if let Some(hash) = &response.commit_hash {
+            self.messages.push(DisplayMessage {
+                id: Uuid::new_v4(),
+                role: "system".to_string(),
+                content: format!("📝 Changes committed as {}", &hash[..hash.len().min(7)]),
+                timestamp: chrono::Utc::now(),
+                token_count: None,
+                cost: None,
+            });
+        }
+
         // Update session model if not already set
         if let Some(session) = &mut self.current_session {
             if session.model.is_none() {
@@ -1216,6 +1730,7 @@ impl App {
     fn show_error(&mut self, error: String) {
         self.is_processing = false;
         self.streaming_response = None;
+        self.tool_activity = None;
         self.error_message = Some(error);
         // Auto-scroll to show the error
         self.scroll_offset = 0;
@@ -1230,6 +1745,14 @@ impl App {
             self.load_sessions().await?;
         }
 
+        if mode == AppMode::Checkpoints {
+            self.checkpoints = match &self.current_session {
+                Some(session) => self.file_service.list_checkpoints(session.id).await?,
+                None => Vec::new(),
+            };
+            self.selected_checkpoint_index = 0;
+        }
+
         Ok(())
     }
 
@@ -1247,6 +1770,9 @@ impl App {
     fn handle_approval_requested(&mut self, request: ToolApprovalRequest) {
         self.pending_approval = Some(request);
         self.show_approval_details = false;
+        self.approval_scroll = 0;
+        self.approval_editor = None;
+        self.approval_input_edited = false;
         self.mode = AppMode::ToolApproval;
     }
 
@@ -1254,6 +1780,10 @@ impl App {
     async fn handle_approval_key(&mut self, event: crossterm::event::KeyEvent) -> Result<()> {
         use super::events::keys;
 
+        if self.approval_editor.is_some() {
+            return self.handle_approval_edit_key(event).await;
+        }
+
         if let Some(ref approval_request) = self.pending_approval {
             if keys::is_approve(&event) {
                 // User approved
@@ -1261,6 +1791,9 @@ impl App {
                     request_id: approval_request.request_id,
                     approved: true,
                     reason: None,
+                    edited_input: self
+                        .approval_input_edited
+                        .then(|| approval_request.tool_input.clone()),
                 };
 
                 // Send response back through the channel
@@ -1276,6 +1809,7 @@ impl App {
                     request_id: approval_request.request_id,
                     approved: false,
                     reason: Some("User denied permission".to_string()),
+                    edited_input: None,
                 };
 
                 // Send response back through the channel
@@ -1288,12 +1822,87 @@ impl App {
             } else if keys::is_view_details(&event) {
                 // Toggle details view
                 self.show_approval_details = !self.show_approval_details;
+                self.approval_scroll = 0;
+            } else if keys::is_edit(&event) {
+                self.approval_editor = Some(ApprovalEditor::open(&approval_request.tool_input));
+            } else if keys::is_up(&event) {
+                self.approval_scroll = self.approval_scroll.saturating_sub(1);
+            } else if keys::is_down(&event) {
+                self.approval_scroll = (self.approval_scroll + 1).min(self.approval_body_len());
+            } else if keys::is_page_up(&event) {
+                self.approval_scroll = self.approval_scroll.saturating_sub(10);
+            } else if keys::is_page_down(&event) {
+                self.approval_scroll = (self.approval_scroll + 10).min(self.approval_body_len());
             }
         }
 
         Ok(())
     }
 
+    /// Handle keys while editing a pending tool call
+    async fn handle_approval_edit_key(&mut self, event: crossterm::event::KeyEvent) -> Result<()> {
+        use super::events::keys;
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        let (Some(editor), Some(request)) = (&mut self.approval_editor, &mut self.pending_approval)
+        else {
+            return Ok(());
+        };
+
+        if keys::is_cancel(&event) {
+            // Discard the edit
+            self.approval_editor = None;
+        } else if keys::is_submit(&event) {
+            match editor.apply(&request.tool_input) {
+                Ok(edited) => {
+                    request.preview = self
+                        .agent_service
+                        .preview_tool_call(&request.tool_name, &edited)
+                        .await;
+                    request.tool_input = edited;
+                    self.approval_input_edited = true;
+                    self.approval_editor = None;
+                    self.approval_scroll = 0;
+                }
+                Err(e) => editor.error = Some(e),
+            }
+        } else {
+            match event.code {
+                KeyCode::Char(c)
+                    if !event
+                        .modifiers
+                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+                {
+                    editor.buffer.push(c);
+                }
+                KeyCode::Enter => editor.buffer.push('\n'),
+                KeyCode::Tab => editor.buffer.push_str("    "),
+                KeyCode::Backspace => {
+                    editor.buffer.pop();
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Number of lines in the scrollable body of the approval dialog
+    fn approval_body_len(&self) -> u16 {
+        use crate::llm::agent::ToolPreview;
+
+        let Some(request) = &self.pending_approval else {
+            return 0;
+        };
+        let lines = match &request.preview {
+            Some(ToolPreview::Diff(diff)) if !self.show_approval_details => diff.lines().count(),
+            _ => serde_json::to_string_pretty(&request.tool_input)
+                .map(|json| json.lines().count())
+                .unwrap_or(0),
+        };
+        lines.min(u16::MAX as usize) as u16
+    }
+
     /// Open file picker and populate file list
     async fn open_file_picker(&mut self) -> Result<()> {
         // Get list of files in current directory
@@ -1346,9 +1955,19 @@ impl App {
             if self.file_picker_selected < self.file_picker_scroll_offset {
                 self.file_picker_scroll_offset = self.file_picker_selected;
             }
+        } else if event.code == KeyCode::Tab && !self.mcp_resources().is_empty() {
+            // Switch between files and MCP resources
+            self.file_picker_show_resources = !self.file_picker_show_resources;
+            self.file_picker_selected = 0;
+            self.file_picker_scroll_offset = 0;
         } else if keys::is_down(&event) {
             // Move selection down
-            if self.file_picker_selected + 1 < self.file_picker_files.len() {
+            let len = if self.file_picker_show_resources {
+                self.mcp_resources().len()
+            } else {
+                self.file_picker_files.len()
+            };
+            if self.file_picker_selected + 1 < len {
                 self.file_picker_selected += 1;
 
                 // Adjust scroll offset if needed (assuming 20 visible items)
@@ -1357,6 +1976,15 @@ impl App {
                     self.file_picker_scroll_offset = self.file_picker_selected - visible_items + 1;
                 }
             }
+        } else if self.file_picker_show_resources
+            && (keys::is_enter(&event) || event.code == KeyCode::Char(' '))
+        {
+            // Insert a reference whose contents are inlined when sent
+            if let Some(resource) = self.mcp_resources().get(self.file_picker_selected) {
+                let reference = resource.reference();
+                self.input_buffer.push_str(&reference);
+                self.switch_mode(AppMode::Chat).await?;
+            }
         } else if keys::is_enter(&event) || event.code == KeyCode::Char(' ') {
             // Select file or navigate into directory
             if let Some(selected_path) = self.file_picker_files.get(self.file_picker_selected) {
@@ -1385,6 +2013,19 @@ impl App {
     }
 }
 
+/// Describe restored files for a chat notice
+fn format_restored(title: &str, restored: &[RestoredFile]) -> String {
+    let mut text = format!("{} ({} file(s)):", title, restored.len());
+    for file in restored {
+        let action = match file.action {
+            RestoreAction::Restored => "restored",
+            RestoreAction::Deleted => "deleted",
+        };
+        text.push_str(&format!("\n- `{}` ({})", file.path.display(), action));
+    }
+    text
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1400,10 +2041,30 @@ mod tests {
             created_at: chrono::Utc::now(),
             token_count: Some(10),
             cost: Some(0.001),
+            commit_hash: None,
         };
 
         let display_msg: DisplayMessage = msg.into();
         assert_eq!(display_msg.role, "user");
         assert_eq!(display_msg.content, "Hello");
     }
+
+    #[test]
+    fn test_tool_activity_apply() {
+        let mut activity = ToolActivity::new("bash".to_string());
+        activity.apply(ToolProgress::Stdout("compiling".to_string()));
+        activity.apply(ToolProgress::Stderr("warning".to_string()));
+        activity.apply(ToolProgress::Percent(150));
+        activity.apply(ToolProgress::Finished { success: true });
+
+        assert_eq!(activity.lines.len(), 2);
+        assert!(activity.lines[1].is_stderr);
+        assert_eq!(activity.percent, Some(100));
+        assert_eq!(activity.finished, Some(true));
+
+        for i in 0..(TOOL_ACTIVITY_MAX_LINES + 10) {
+            activity.apply(ToolProgress::Stdout(i.to_string()));
+        }
+        assert_eq!(activity.lines.len(), TOOL_ACTIVITY_MAX_LINES);
+    }
 }
@@ -0,0 +1,133 @@
+//! Tool Approval Editing
+//!
+//! Lets the user edit a tool call's proposal (the text a write or edit would
+//! put into a file) before approving it.
+
+use serde_json::Value;
+
+/// Input fields holding the text a file-modifying tool would write, in priority order
+const EDITABLE_FIELDS: &[&str] = &["content", "new_text", "text", "replacement", "source"];
+
+/// In-progress edit of a pending tool call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalEditor {
+    /// Input field being edited (None when editing the whole input as JSON)
+    pub field: Option<String>,
+
+    /// Text being edited
+    pub buffer: String,
+
+    /// Error from the last attempt to apply the edit
+    pub error: Option<String>,
+}
+
+impl ApprovalEditor {
+    /// Start editing a tool input
+    ///
+    /// Edits the proposed text directly when the input has one, otherwise
+    /// falls back to the pretty-printed JSON input.
+    pub fn open(tool_input: &Value) -> Self {
+        for field in EDITABLE_FIELDS {
+            let buffer = match tool_input.get(*field) {
+                Some(Value::String(text)) => text.clone(),
+                // Notebook sources are arrays of lines that keep their newlines
+                Some(Value::Array(lines)) if lines.iter().all(Value::is_string) => {
+                    lines.iter().filter_map(Value::as_str).collect::<String>()
+                }
+                _ => continue,
+            };
+            return Self {
+                field: Some(field.to_string()),
+                buffer,
+                error: None,
+            };
+        }
+
+        Self {
+            field: None,
+            buffer: serde_json::to_string_pretty(tool_input).unwrap_or_else(|_| "{}".to_string()),
+            error: None,
+        }
+    }
+
+    /// Short description of what is being edited
+    pub fn label(&self) -> String {
+        match &self.field {
+            Some(field) => format!("Editing `{}`", field),
+            None => "Editing tool input (JSON)".to_string(),
+        }
+    }
+
+    /// Build the edited tool input
+    pub fn apply(&self, tool_input: &Value) -> Result<Value, String> {
+        let Some(field) = &self.field else {
+            let edited: Value =
+                serde_json::from_str(&self.buffer).map_err(|e| format!("Invalid JSON: {}", e))?;
+            if !edited.is_object() {
+                return Err("Tool input must be a JSON object".to_string());
+            }
+            return Ok(edited);
+        };
+
+        let mut edited = tool_input.clone();
+        let value = if tool_input.get(field).is_some_and(Value::is_array) {
+            Value::Array(
+                self.buffer
+                    .split_inclusive('\n')
+                    .map(|line| Value::String(line.to_string()))
+                    .collect(),
+            )
+        } else {
+            Value::String(self.buffer.clone())
+        };
+        if let Some(obj) = edited.as_object_mut() {
+            obj.insert(field.clone(), value);
+        }
+        Ok(edited)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_edit_text_field() {
+        let input =
+            json!({ "path": "a.rs", "operation": "replace", "old_text": "a", "new_text": "b" });
+        let mut editor = ApprovalEditor::open(&input);
+        assert_eq!(editor.field.as_deref(), Some("new_text"));
+        assert_eq!(editor.buffer, "b");
+
+        editor.buffer.push_str("\nc");
+        let edited = editor.apply(&input).unwrap();
+        assert_eq!(edited["new_text"], "b\nc");
+        assert_eq!(edited["old_text"], "a");
+    }
+
+    #[test]
+    fn test_edit_notebook_source() {
+        let input = json!({ "path": "n.ipynb", "operation": "edit_cell", "index": 0, "source": ["x = 1\n", "y = 2"] });
+        let mut editor = ApprovalEditor::open(&input);
+        assert_eq!(editor.buffer, "x = 1\ny = 2");
+
+        editor.buffer = "x = 1\ny = 3\n".to_string();
+        let edited = editor.apply(&input).unwrap();
+        assert_eq!(edited["source"], json!(["x = 1\n", "y = 3\n"]));
+    }
+
+    #[test]
+    fn test_edit_json_fallback() {
+        let input = json!({ "path": "n.ipynb", "operation": "delete_cell", "index": 2 });
+        let mut editor = ApprovalEditor::open(&input);
+        assert!(editor.field.is_none());
+
+        editor.buffer =
+            "{ \"path\": \"n.ipynb\", \"operation\": \"delete_cell\", \"index\": 1 }".to_string();
+        assert_eq!(editor.apply(&input).unwrap()["index"], 1);
+
+        editor.buffer = "[1, 2".to_string();
+        assert!(editor.apply(&input).unwrap_err().contains("Invalid JSON"));
+    }
+}
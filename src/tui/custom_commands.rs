@@ -0,0 +1,244 @@
+//! Custom Slash Commands
+//!
+//! Markdown files in `commands/` under the Crustly config directory (for the
+//! user) and in `.crustly/commands/` under the working directory (for the
+//! project) become slash commands named after the file: `review.md` runs as
+//! `/review`. A project command replaces a user command of the same name.
+//!
+//! The file body is the prompt sent, with `$ARGUMENTS` replaced by whatever
+//! follows the command. An optional frontmatter block sets a description,
+//! the tools the model may use, and the model to ask:
+//!
+//! ```markdown
+//! ---
+//! description: Review the staged changes for security problems
+//! allowed-tools: read_file, grep, git
+//! model: claude-3-5-sonnet-20240620
+//! ---
+//! Review `git diff --cached` for security problems. Focus on $ARGUMENTS.
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Placeholder replaced by the text following the command
+pub const ARGUMENTS_PLACEHOLDER: &str = "$ARGUMENTS";
+
+/// Directory of project commands, relative to the working directory
+pub const PROJECT_COMMANDS_DIR: &str = ".crustly/commands";
+
+/// Where a command was defined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandScope {
+    User,
+    Project,
+}
+
+/// A slash command read from a markdown file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomCommand {
+    /// Name typed after the slash
+    pub name: String,
+    pub description: Option<String>,
+    /// Tools the model may use; all tools when `None`
+    pub allowed_tools: Option<Vec<String>>,
+    /// Model to ask instead of the provider default
+    pub model: Option<String>,
+    /// Prompt template
+    pub body: String,
+    pub path: PathBuf,
+    pub scope: CommandScope,
+}
+
+impl CustomCommand {
+    /// Parse a command file's contents
+    pub fn parse(name: String, text: &str, path: PathBuf, scope: CommandScope) -> Self {
+        let mut command = Self {
+            name,
+            description: None,
+            allowed_tools: None,
+            model: None,
+            body: text.trim().to_string(),
+            path,
+            scope,
+        };
+
+        let text = text.trim_start();
+        let Some((frontmatter, body)) = text
+            .strip_prefix("---")
+            .and_then(|rest| rest.split_once("\n---"))
+        else {
+            return command;
+        };
+        command.body = body
+            .split_once('\n')
+            .map_or("", |(_, body)| body)
+            .trim()
+            .to_string();
+
+        for line in frontmatter.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+            if value.is_empty() {
+                continue;
+            }
+            match key.trim() {
+                "description" => command.description = Some(value.to_string()),
+                "model" => command.model = Some(value.to_string()),
+                "allowed-tools" | "allowed_tools" => {
+                    let list = value.trim_start_matches('[').trim_end_matches(']');
+                    command.allowed_tools = Some(
+                        list.split(|c: char| c == ',' || c.is_whitespace())
+                            .map(|tool| tool.trim_matches(|c| c == '"' || c == '\''))
+                            .filter(|tool| !tool.is_empty())
+                            .map(str::to_string)
+                            .collect(),
+                    );
+                }
+                other => {
+                    tracing::debug!("Ignoring frontmatter key '{}' in {:?}", other, command.path)
+                }
+            }
+        }
+        command
+    }
+
+    /// The command as typed, e.g. `/review`
+    pub fn command(&self) -> String {
+        format!("/{}", self.name)
+    }
+
+    /// Description, or else the first line of the prompt
+    pub fn summary(&self) -> String {
+        self.description.clone().unwrap_or_else(|| {
+            self.body
+                .lines()
+                .find(|line| !line.trim().is_empty())
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        })
+    }
+
+    /// The prompt to send for the text following the command
+    ///
+    /// Without a `$ARGUMENTS` placeholder, arguments are appended.
+    pub fn render(&self, arguments: &str) -> String {
+        let arguments = arguments.trim();
+        if self.body.contains(ARGUMENTS_PLACEHOLDER) {
+            self.body.replace(ARGUMENTS_PLACEHOLDER, arguments)
+        } else if arguments.is_empty() {
+            self.body.clone()
+        } else {
+            format!("{}\n\n{}", self.body, arguments)
+        }
+    }
+}
+
+/// User commands directory in the Crustly config directory
+pub fn default_user_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("crustly").join("commands"))
+}
+
+/// Read the user commands in `user_dir` and the project commands under
+/// `working_directory`, sorted by name
+pub fn load_commands(user_dir: Option<&Path>, working_directory: &Path) -> Vec<CustomCommand> {
+    let mut commands = BTreeMap::new();
+    let dirs = user_dir
+        .map(|dir| (dir.to_path_buf(), CommandScope::User))
+        .into_iter()
+        .chain([(
+            working_directory.join(PROJECT_COMMANDS_DIR),
+            CommandScope::Project,
+        )]);
+
+    for (dir, scope) in dirs {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "md"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                tracing::debug!("Skipping command file with unusable name {:?}", path);
+                continue;
+            }
+            match std::fs::read_to_string(&path) {
+                Ok(text) => {
+                    let command = CustomCommand::parse(name.to_string(), &text, path, scope);
+                    commands.insert(command.name.clone(), command);
+                }
+                Err(e) => tracing::debug!("Cannot read command file {:?}: {}", path, e),
+            }
+        }
+    }
+    commands.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_frontmatter_and_render() {
+        let command = CustomCommand::parse(
+            "review".to_string(),
+            "---\ndescription: Security review\nallowed-tools: [read_file, \"grep\"]\n\
+             model: small-model\nargument-hint: <area>\n---\n\nReview the diff. Focus on $ARGUMENTS.\n",
+            PathBuf::from("review.md"),
+            CommandScope::Project,
+        );
+        assert_eq!(command.description.as_deref(), Some("Security review"));
+        assert_eq!(
+            command.allowed_tools,
+            Some(vec!["read_file".to_string(), "grep".to_string()])
+        );
+        assert_eq!(command.model.as_deref(), Some("small-model"));
+        assert_eq!(
+            command.render("  input validation "),
+            "Review the diff. Focus on input validation."
+        );
+
+        let plain = CustomCommand::parse(
+            "tests".to_string(),
+            "Write unit tests.\n",
+            PathBuf::from("tests.md"),
+            CommandScope::User,
+        );
+        assert_eq!(plain.allowed_tools, None);
+        assert_eq!(plain.summary(), "Write unit tests.");
+        assert_eq!(plain.render(""), "Write unit tests.");
+        assert_eq!(
+            plain.render("for the parser"),
+            "Write unit tests.\n\nfor the parser"
+        );
+    }
+
+    #[test]
+    fn test_project_commands_replace_user_commands() {
+        let user = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        let project_dir = project.path().join(PROJECT_COMMANDS_DIR);
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(user.path().join("review.md"), "User review").unwrap();
+        std::fs::write(user.path().join("explain.md"), "Explain $ARGUMENTS").unwrap();
+        std::fs::write(user.path().join("notes.txt"), "Not a command").unwrap();
+        std::fs::write(project_dir.join("review.md"), "Project review").unwrap();
+
+        let commands = load_commands(Some(user.path()), project.path());
+        let names: Vec<_> = commands.iter().map(CustomCommand::command).collect();
+        assert_eq!(names, vec!["/explain", "/review"]);
+        assert_eq!(commands[1].body, "Project review");
+        assert_eq!(commands[1].scope, CommandScope::Project);
+    }
+}
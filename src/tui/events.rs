@@ -2,7 +2,8 @@
 //!
 //! Handles user input and application events for the terminal interface.
 
-use crate::llm::agent::AgentResponse;
+use crate::llm::agent::{AgentResponse, ToolPreview};
+use crate::llm::tools::ToolProgressEvent;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use serde_json::Value;
 use std::time::Duration;
@@ -56,6 +57,9 @@ pub enum TuiEvent {
 
     /// Tool approval response
     ToolApprovalResponse(ToolApprovalResponse),
+
+    /// Live progress from a running tool
+    ToolProgress(ToolProgressEvent),
 }
 
 /// Tool approval request details
@@ -76,6 +80,9 @@ pub struct ToolApprovalRequest {
     /// Tool capabilities
     pub capabilities: Vec<String>,
 
+    /// Preview of the file changes the tool would make
+    pub preview: Option<ToolPreview>,
+
     /// Channel to send response back
     pub response_tx: mpsc::UnboundedSender<ToolApprovalResponse>,
 
@@ -108,6 +115,9 @@ pub struct ToolApprovalResponse {
 
     /// Optional reason for denial
     pub reason: Option<String>,
+
+    /// Tool input edited by the user before approving
+    pub edited_input: Option<Value>,
 }
 
 /// Application mode
@@ -121,6 +131,8 @@ pub enum AppMode {
     Plan,
     /// Session list/management
     Sessions,
+    /// Checkpoint browser (undo file changes)
+    Checkpoints,
     /// Help screen
     Help,
     /// Settings
@@ -301,6 +313,11 @@ pub mod keys {
     pub fn is_view_details(event: &KeyEvent) -> bool {
         matches!(event.code, KeyCode::Char('v') | KeyCode::Char('V')) && event.modifiers.is_empty()
     }
+
+    /// 'E' - Edit
+    pub fn is_edit(event: &KeyEvent) -> bool {
+        matches!(event.code, KeyCode::Char('e') | KeyCode::Char('E')) && event.modifiers.is_empty()
+    }
 }
 
 #[cfg(test)]
@@ -132,6 +132,124 @@ pub fn highlight_code(code: &str, language: &str) -> Vec<Line<'static>> {
     lines
 }
 
+/// Background tint for added lines in diffs
+const DIFF_ADDED_BG: Color = Color::Rgb(20, 50, 20);
+
+/// Background tint for removed lines in diffs
+const DIFF_REMOVED_BG: Color = Color::Rgb(60, 20, 20);
+
+/// Highlight a unified diff
+///
+/// File headers and hunk headers are styled, and the code on context, added
+/// and removed lines is syntax highlighted based on each file's extension.
+pub fn highlight_diff(diff: &str) -> Vec<Line<'static>> {
+    let theme = get_theme();
+    let mut lines = Vec::new();
+    // Separate highlighters for the old and new file, so multi-line
+    // constructs (strings, comments) are tracked on each side
+    let mut highlighters: Option<(HighlightLines, HighlightLines)> = None;
+
+    for line in LinesWithEndings::from(diff) {
+        let text = line.trim_end_matches(['\n', '\r']);
+
+        if let Some(path) = text.strip_prefix("+++ ") {
+            let extension = path.rsplit('.').next().unwrap_or_default();
+            highlighters = find_syntax(extension).map(|syntax| {
+                (
+                    HighlightLines::new(syntax, theme),
+                    HighlightLines::new(syntax, theme),
+                )
+            });
+            lines.push(Line::from(Span::styled(
+                text.to_string(),
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(ratatui::style::Modifier::BOLD),
+            )));
+            continue;
+        }
+        if text.starts_with("--- ") {
+            lines.push(Line::from(Span::styled(
+                text.to_string(),
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(ratatui::style::Modifier::BOLD),
+            )));
+            continue;
+        }
+        if text.starts_with("@@") {
+            lines.push(Line::from(Span::styled(
+                text.to_string(),
+                Style::default().fg(Color::Cyan),
+            )));
+            continue;
+        }
+
+        let (marker, code) = match text.chars().next() {
+            Some(c @ ('+' | '-' | ' ')) => (c, &line[1..]),
+            _ => {
+                // "\ No newline at end of file" and anything unexpected
+                lines.push(Line::from(Span::styled(
+                    text.to_string(),
+                    Style::default().fg(Color::DarkGray),
+                )));
+                continue;
+            }
+        };
+
+        let (marker_style, background) = match marker {
+            '+' => (Style::default().fg(Color::Green), Some(DIFF_ADDED_BG)),
+            '-' => (Style::default().fg(Color::Red), Some(DIFF_REMOVED_BG)),
+            _ => (Style::default().fg(Color::DarkGray), None),
+        };
+        let with_background = |style: Style| match background {
+            Some(bg) => style.bg(bg),
+            None => style,
+        };
+
+        let mut spans = vec![Span::styled(
+            format!("{} ", marker),
+            with_background(marker_style.add_modifier(ratatui::style::Modifier::BOLD)),
+        )];
+
+        let ranges = highlighters.as_mut().and_then(|(old, new)| match marker {
+            '-' => old.highlight_line(code, &SYNTAX_SET).ok(),
+            '+' => new.highlight_line(code, &SYNTAX_SET).ok(),
+            _ => {
+                // Context lines exist on both sides
+                let _ = old.highlight_line(code, &SYNTAX_SET);
+                new.highlight_line(code, &SYNTAX_SET).ok()
+            }
+        });
+
+        match ranges {
+            Some(ranges) => {
+                for (style, text) in ranges {
+                    spans.push(Span::styled(
+                        text.trim_end_matches(['\n', '\r']).to_string(),
+                        with_background(syntect_style_to_ratatui(style)),
+                    ));
+                }
+            }
+            None => {
+                let fg = match marker {
+                    '+' => Color::Green,
+                    '-' => Color::Red,
+                    _ => Color::White,
+                };
+                spans.push(Span::styled(
+                    code.trim_end_matches(['\n', '\r']).to_string(),
+                    with_background(Style::default().fg(fg)),
+                ));
+            }
+        }
+
+        lines.push(Line::from(spans));
+    }
+
+    lines
+}
+
 /// Get a list of all supported languages
 pub fn supported_languages() -> Vec<String> {
     SYNTAX_SET
@@ -205,6 +323,21 @@ mod tests {
         assert!(lines.is_empty() || lines.len() == 1);
     }
 
+    #[test]
+    fn test_highlight_diff() {
+        let diff = "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,2 +1,2 @@\n fn main() {\n-    let a = 1;\n+    let a = 10;\n";
+        let lines = highlight_diff(diff);
+        assert_eq!(lines.len(), 6);
+
+        let removed = &lines[4];
+        assert_eq!(removed.spans[0].content, "- ");
+        assert_eq!(removed.spans[0].style.bg, Some(DIFF_REMOVED_BG));
+        assert!(removed.spans.len() > 2, "code should be syntax highlighted");
+
+        let added: String = lines[5].spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(added, "+     let a = 10;");
+    }
+
     #[test]
     fn test_code_with_special_characters() {
         let code = "let x = \"Hello, 世界!\";";
@@ -3,10 +3,13 @@
 //! Provides an interactive terminal interface for the AI assistant using Ratatui.
 
 pub mod app;
+pub mod approval;
+pub mod custom_commands;
 pub mod error;
 pub mod events;
 pub mod plan;
 pub mod prompt_analyzer;
+pub mod prompt_form;
 pub mod render;
 pub mod runner;
 
@@ -22,7 +25,7 @@ pub mod styles;
 pub mod utils;
 
 // Re-exports
-pub use app::{App, DisplayMessage};
+pub use app::{App, DisplayMessage, ToolActivity};
 pub use events::{AppMode, EventHandler, TuiEvent};
 pub use plan::{PlanDocument, PlanStatus, PlanTask, TaskStatus, TaskType};
 pub use prompt_analyzer::PromptAnalyzer;
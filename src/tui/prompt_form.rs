@@ -0,0 +1,145 @@
+//! MCP Prompt Arguments
+//!
+//! Collects the arguments of an MCP prompt template run as a slash command.
+//! Arguments can be given inline as `name=value` after the command; any that
+//! are missing are asked for one at a time in the chat.
+
+use crate::mcp::protocol::PromptArgument;
+use crate::mcp::ServerPrompt;
+use std::collections::BTreeMap;
+
+/// Arguments being collected for an MCP prompt
+#[derive(Debug, Clone)]
+pub struct PromptForm {
+    /// Prompt being run
+    pub prompt: ServerPrompt,
+
+    /// Values collected so far
+    pub values: BTreeMap<String, String>,
+
+    /// Index of the argument currently asked for
+    next: usize,
+}
+
+impl PromptForm {
+    /// Start collecting arguments, taking `name=value` pairs from `inline`
+    pub fn new(prompt: ServerPrompt, inline: &str) -> Self {
+        let mut values = BTreeMap::new();
+        for pair in inline.split_whitespace() {
+            if let Some((name, value)) = pair.split_once('=') {
+                if prompt.prompt.arguments.iter().any(|a| a.name == name) {
+                    values.insert(name.to_string(), value.to_string());
+                }
+            }
+        }
+
+        let mut form = Self {
+            prompt,
+            values,
+            next: 0,
+        };
+        form.skip_answered();
+        form
+    }
+
+    /// Argument asked for next, or `None` once all have been collected
+    pub fn current(&self) -> Option<&PromptArgument> {
+        self.prompt.prompt.arguments.get(self.next)
+    }
+
+    /// Question shown for the current argument
+    pub fn question(&self) -> Option<String> {
+        let argument = self.current()?;
+        let mut question = format!("{} › `{}`", self.prompt.command(), argument.name);
+        if let Some(description) = &argument.description {
+            question.push_str(&format!(" — {}", description));
+        }
+        question.push_str(if argument.required {
+            " (required, Esc to cancel)"
+        } else {
+            " (optional, Enter to skip)"
+        });
+        Some(question)
+    }
+
+    /// Record the answer to the current argument
+    ///
+    /// An empty answer skips an optional argument and is rejected for a
+    /// required one.
+    pub fn answer(&mut self, value: &str) -> Result<(), String> {
+        let Some(argument) = self.current() else {
+            return Ok(());
+        };
+
+        let value = value.trim();
+        if value.is_empty() {
+            if argument.required {
+                return Err(format!("`{}` is required", argument.name));
+            }
+        } else {
+            self.values.insert(argument.name.clone(), value.to_string());
+        }
+
+        self.next += 1;
+        self.skip_answered();
+        Ok(())
+    }
+
+    fn skip_answered(&mut self) {
+        while let Some(argument) = self.current() {
+            if !self.values.contains_key(&argument.name) {
+                break;
+            }
+            self.next += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::protocol::McpPrompt;
+
+    fn review_prompt() -> ServerPrompt {
+        let argument = |name: &str, required| PromptArgument {
+            name: name.to_string(),
+            description: None,
+            required,
+        };
+        ServerPrompt {
+            server: "git".to_string(),
+            prompt: McpPrompt {
+                name: "review".to_string(),
+                description: None,
+                arguments: vec![
+                    argument("branch", true),
+                    argument("focus", false),
+                    argument("style", false),
+                ],
+            },
+        }
+    }
+
+    #[test]
+    fn test_inline_arguments_are_skipped() {
+        let form = PromptForm::new(review_prompt(), "branch=main unknown=1");
+        assert_eq!(form.values.len(), 1);
+        assert_eq!(form.current().unwrap().name, "focus");
+        assert!(form.question().unwrap().contains("/git:review › `focus`"));
+    }
+
+    #[test]
+    fn test_answers() {
+        let mut form = PromptForm::new(review_prompt(), "");
+        assert!(form.answer("  ").is_err());
+        assert_eq!(form.current().unwrap().name, "branch");
+
+        form.answer("main").unwrap();
+        form.answer("").unwrap(); // Skip optional `focus`
+        form.answer("terse").unwrap();
+        assert!(form.current().is_none());
+        assert_eq!(form.values["branch"], "main");
+        assert_eq!(form.values["style"], "terse");
+        assert!(!form.values.contains_key("focus"));
+    }
+}
@@ -4,13 +4,18 @@
 
 use super::app::App;
 use super::events::AppMode;
+use super::highlight::highlight_diff;
 use super::markdown::parse_markdown;
 use super::splash;
+use crate::llm::agent::ToolPreview;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Clear, Paragraph, Wrap,
+    },
     Frame,
 };
 
@@ -40,7 +45,16 @@ pub fn render(f: &mut Frame, app: &App) {
             // Already handled above
         }
         AppMode::Chat => {
-            render_chat(f, app, chunks[1]);
+            if app.tool_activity.is_some() {
+                let main_chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(5), Constraint::Length(12)])
+                    .split(chunks[1]);
+                render_chat(f, app, main_chunks[0]);
+                render_tool_activity(f, app, main_chunks[1]);
+            } else {
+                render_chat(f, app, chunks[1]);
+            }
             render_input(f, app, chunks[2]);
         }
         AppMode::Plan => {
@@ -51,6 +65,9 @@ pub fn render(f: &mut Frame, app: &App) {
         AppMode::Sessions => {
             render_sessions(f, app, chunks[1]);
         }
+        AppMode::Checkpoints => {
+            render_checkpoints(f, app, chunks[1]);
+        }
         AppMode::Help => {
             render_help(f, app, chunks[1]);
         }
@@ -198,6 +215,14 @@ fn render_chat(f: &mut Frame, app: &App, area: Rect) {
                     .add_modifier(Modifier::BOLD),
                 "  ",
             )
+        } else if msg.role == "system" {
+            (
+                "⚙ Crustly".to_string(),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+                "",
+            )
         } else {
             (
                 format!("🤖 {}", model_name),
@@ -293,6 +318,94 @@ fn render_chat(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(chat, area);
 }
 
+/// Render live output of the running tool
+fn render_tool_activity(f: &mut Frame, app: &App, area: Rect) {
+    let Some(ref activity) = app.tool_activity else {
+        return;
+    };
+
+    let (state_text, state_color) = match activity.finished {
+        None => {
+            let spinner_frames = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+            (
+                spinner_frames[app.animation_frame % spinner_frames.len()].to_string(),
+                Color::Yellow,
+            )
+        }
+        Some(true) => ("✓".to_string(), Color::Green),
+        Some(false) => ("✗".to_string(), Color::Red),
+    };
+
+    let mut title = vec![
+        Span::styled(
+            format!(" {} ", state_text),
+            Style::default()
+                .fg(state_color)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!("{} ", activity.tool_name),
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!("({}s) ", activity.started_at.elapsed().as_secs()),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ];
+    if let Some(percent) = activity.percent {
+        title.push(Span::styled(
+            format!("{}% ", percent),
+            Style::default().fg(Color::Magenta),
+        ));
+    }
+
+    let mut lines: Vec<Line> = Vec::new();
+    if let Some(ref status) = activity.status {
+        lines.push(Line::from(Span::styled(
+            status.clone(),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    if let Some(percent) = activity.percent {
+        let width = area.width.saturating_sub(4) as usize;
+        let filled = width * percent as usize / 100;
+        lines.push(Line::from(vec![
+            Span::styled("█".repeat(filled), Style::default().fg(Color::Magenta)),
+            Span::styled(
+                "░".repeat(width.saturating_sub(filled)),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]));
+    }
+
+    // Only the tail of the output fits in the pane
+    let visible_height = area.height.saturating_sub(2) as usize;
+    let output_height = visible_height.saturating_sub(lines.len());
+    let skip = activity.lines.len().saturating_sub(output_height);
+    for line in activity.lines.iter().skip(skip) {
+        let color = if line.is_stderr {
+            Color::Red
+        } else {
+            Color::White
+        };
+        lines.push(Line::from(Span::styled(
+            line.text.clone(),
+            Style::default().fg(color),
+        )));
+    }
+
+    let pane = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(Line::from(title))
+            .border_style(Style::default().fg(Color::DarkGray)),
+    );
+
+    f.render_widget(pane, area);
+}
+
 /// Render the input box
 fn render_input(f: &mut Frame, app: &App, area: Rect) {
     let mut input_text = app.input_buffer.clone();
@@ -327,14 +440,40 @@ fn render_input(f: &mut Frame, app: &App, area: Rect) {
         Style::default().fg(Color::Cyan)
     };
 
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(border_style);
+
+    // Slash commands matching what is typed, completed with Tab
+    let matches = if app.is_processing {
+        Vec::new()
+    } else {
+        app.slash_command_matches()
+    };
+    let hint = match matches.as_slice() {
+        [] => None,
+        [(command, description)] if description.is_empty() => Some(format!(" Tab: {} ", command)),
+        [(command, description)] => Some(format!(" Tab: {} — {} ", command, description)),
+        _ => Some(format!(
+            " Tab: {} ",
+            matches
+                .iter()
+                .map(|(command, _)| command.as_str())
+                .collect::<Vec<_>>()
+                .join("  ")
+        )),
+    };
+    if let Some(hint) = hint {
+        block = block.title(
+            Title::from(Span::styled(hint, Style::default().fg(Color::Yellow)))
+                .position(Position::Bottom),
+        );
+    }
+
     let input = Paragraph::new(input_lines)
         .style(Style::default().fg(Color::White))
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(title)
-                .border_style(border_style),
-        )
+        .block(block)
         .wrap(Wrap { trim: false });
 
     f.render_widget(input, area);
@@ -389,6 +528,70 @@ fn render_sessions(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(sessions, area);
 }
 
+/// Render the checkpoint browser
+fn render_checkpoints(f: &mut Frame, app: &App, area: Rect) {
+    let mut lines: Vec<Line> = Vec::new();
+
+    lines.push(Line::from(Span::styled(
+        "Checkpoints (↑/↓ to navigate, Enter to restore files to before the turn, Esc to cancel)",
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    )));
+    lines.push(Line::from(""));
+
+    if app.checkpoints.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  No file changes in this session yet.",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    for (idx, checkpoint) in app.checkpoints.iter().enumerate() {
+        let is_selected = idx == app.selected_checkpoint_index;
+        let prefix = if is_selected { "> " } else { "  " };
+
+        let prompt = checkpoint
+            .prompt
+            .as_deref()
+            .and_then(|p| p.lines().find(|l| !l.trim().is_empty()))
+            .unwrap_or("(unknown prompt)");
+        let prompt: String = if prompt.chars().count() > 60 {
+            format!("{}...", prompt.chars().take(57).collect::<String>())
+        } else {
+            prompt.to_string()
+        };
+        let created = checkpoint.created_at.format("%Y-%m-%d %H:%M");
+
+        let style = if is_selected {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+
+        lines.push(Line::from(vec![
+            Span::styled(format!("{}{} ", prefix, created), style),
+            Span::styled(
+                format!("[{} file(s)] ", checkpoint.file_count),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::styled(prompt, style),
+        ]));
+    }
+
+    let checkpoints = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Checkpoints "),
+        )
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(checkpoints, area);
+}
+
 /// Render the help screen
 fn render_help(f: &mut Frame, _app: &App, area: Rect) {
     let help_text = vec![
@@ -535,6 +738,71 @@ fn render_help(f: &mut Frame, _app: &App, area: Rect) {
                 Style::default().fg(Color::White),
             ),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "  /undo        ",
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("→ ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                "Revert file changes from the last turn",
+                Style::default().fg(Color::White),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  /checkpoints ",
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("→ ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                "Browse checkpoints and restore files",
+                Style::default().fg(Color::White),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  /mcp         ",
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("→ ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                "List MCP servers, prompts (/server:prompt) and resources",
+                Style::default().fg(Color::White),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  /memory      ",
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("→ ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                "Show the project instruction files in use",
+                Style::default().fg(Color::White),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "  /<name>      ",
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("→ ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                "Run a custom command (.crustly/commands/<name>.md), Tab completes",
+                Style::default().fg(Color::White),
+            ),
+        ]),
         Line::from(""),
         Line::from(Span::styled(
             "╭─ SESSION LIST ────────────────────────────────────────────╮",
@@ -1035,21 +1303,38 @@ fn render_approval(f: &mut Frame, app: &App, area: Rect) {
             .as_ref()
             .and_then(|s| s.model.as_deref())
             .unwrap_or("AI");
+
+        // Diffs and edits get a larger dialog
+        let expanded =
+            app.approval_editor.is_some() || matches!(request.preview, Some(ToolPreview::Diff(_)));
+
         // Center the dialog
         let dialog_chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Min(0),
-                Constraint::Length(if app.show_approval_details { 30 } else { 20 }),
-                Constraint::Min(0),
-            ])
+            .constraints(if expanded {
+                [
+                    Constraint::Percentage(5),
+                    Constraint::Percentage(90),
+                    Constraint::Percentage(5),
+                ]
+            } else {
+                [
+                    Constraint::Min(0),
+                    Constraint::Length(if app.show_approval_details { 32 } else { 22 }),
+                    Constraint::Min(0),
+                ]
+            })
             .split(area);
 
         let center_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
                 Constraint::Min(0),
-                Constraint::Length(80),
+                Constraint::Length(if expanded {
+                    area.width.saturating_sub(4).min(120)
+                } else {
+                    80
+                }),
                 Constraint::Min(0),
             ])
             .split(dialog_chunks[1]);
@@ -1067,6 +1352,25 @@ fn render_approval(f: &mut Frame, app: &App, area: Rect) {
             Color::Green
         };
 
+        let mut tool_line = vec![
+            Span::styled(
+                format!("{} wants to use the tool: ", model_name),
+                Style::default().fg(Color::White),
+            ),
+            Span::styled(
+                &request.tool_name,
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ];
+        if app.approval_input_edited {
+            tool_line.push(Span::styled(
+                "  (edited)",
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+
         let mut lines = vec![
             Line::from(""),
             Line::from(vec![
@@ -1087,18 +1391,7 @@ fn render_approval(f: &mut Frame, app: &App, area: Rect) {
                 ),
             ]),
             Line::from(""),
-            Line::from(vec![
-                Span::styled(
-                    format!("{} wants to use the tool: ", model_name),
-                    Style::default().fg(Color::White),
-                ),
-                Span::styled(
-                    &request.tool_name,
-                    Style::default()
-                        .fg(Color::Cyan)
-                        .add_modifier(Modifier::BOLD),
-                ),
-            ]),
+            Line::from(tool_line),
             Line::from(""),
             Line::from(vec![
                 Span::styled("Description: ", Style::default().fg(Color::DarkGray)),
@@ -1109,40 +1402,86 @@ fn render_approval(f: &mut Frame, app: &App, area: Rect) {
 
         // Show capabilities
         if !request.capabilities.is_empty() {
-            lines.push(Line::from(vec![Span::styled(
-                "⚠️  Capabilities: ",
-                Style::default().fg(Color::Yellow),
-            )]));
-            for cap in &request.capabilities {
+            if expanded {
                 lines.push(Line::from(vec![
-                    Span::styled("   • ", Style::default().fg(Color::DarkGray)),
-                    Span::styled(cap, Style::default().fg(Color::Red)),
+                    Span::styled("⚠️  Capabilities: ", Style::default().fg(Color::Yellow)),
+                    Span::styled(
+                        request.capabilities.join(", "),
+                        Style::default().fg(Color::Red),
+                    ),
                 ]));
+            } else {
+                lines.push(Line::from(vec![Span::styled(
+                    "⚠️  Capabilities: ",
+                    Style::default().fg(Color::Yellow),
+                )]));
+                for cap in &request.capabilities {
+                    lines.push(Line::from(vec![
+                        Span::styled("   • ", Style::default().fg(Color::DarkGray)),
+                        Span::styled(cap, Style::default().fg(Color::Red)),
+                    ]));
+                }
             }
             lines.push(Line::from(""));
         }
 
-        // Show input parameters (basic or detailed)
-        if app.show_approval_details {
-            lines.push(Line::from(vec![Span::styled(
+        if let Some(ToolPreview::Unavailable(reason)) = &request.preview {
+            lines.push(Line::from(vec![
+                Span::styled("Preview unavailable: ", Style::default().fg(Color::Yellow)),
+                Span::styled(reason.as_str(), Style::default().fg(Color::Red)),
+            ]));
+            lines.push(Line::from(""));
+        }
+
+        let header_height = lines.len() as u16;
+
+        // Scrollable body: the edit buffer, the diff, or the tool input
+        let mut scrollable = true;
+        let mut body: Vec<Line> = Vec::new();
+        if let Some(ref editor) = app.approval_editor {
+            body.push(Line::from(Span::styled(
+                format!("{} — Ctrl+Enter to save, Esc to discard", editor.label()),
+                Style::default().fg(Color::Cyan),
+            )));
+            if let Some(ref error) = editor.error {
+                body.push(Line::from(Span::styled(
+                    error.as_str(),
+                    Style::default().fg(Color::Red),
+                )));
+            }
+            body.push(Line::from(""));
+            let text = format!("{}█", editor.buffer);
+            for line in text.split('\n') {
+                body.push(Line::from(Span::styled(
+                    line.to_string(),
+                    Style::default().fg(Color::White),
+                )));
+            }
+            scrollable = false;
+        } else if let (Some(ToolPreview::Diff(diff)), false) =
+            (&request.preview, app.show_approval_details)
+        {
+            body = highlight_diff(diff);
+        } else if app.show_approval_details {
+            body.push(Line::from(vec![Span::styled(
                 "Tool Input (JSON):",
                 Style::default().fg(Color::DarkGray),
             )]));
-            lines.push(Line::from(""));
+            body.push(Line::from(""));
             let json_str = serde_json::to_string_pretty(&request.tool_input)
                 .unwrap_or_else(|_| "{}".to_string());
             for line in json_str.lines() {
-                lines.push(Line::from(vec![Span::styled(
+                body.push(Line::from(vec![Span::styled(
                     line.to_string(),
                     Style::default().fg(Color::Green),
                 )]));
             }
-            lines.push(Line::from(""));
         } else {
+            scrollable = false;
             // Show simplified input
             if let Some(obj) = request.tool_input.as_object() {
                 if !obj.is_empty() {
-                    lines.push(Line::from(vec![Span::styled(
+                    body.push(Line::from(vec![Span::styled(
                         "Parameters: ",
                         Style::default().fg(Color::DarkGray),
                     )]));
@@ -1157,13 +1496,13 @@ fn render_approval(f: &mut Frame, app: &App, area: Rect) {
                             }
                             _ => value.to_string(),
                         };
-                        lines.push(Line::from(vec![
+                        body.push(Line::from(vec![
                             Span::styled(format!("   {}: ", key), Style::default().fg(Color::Cyan)),
                             Span::styled(value_str, Style::default().fg(Color::White)),
                         ]));
                     }
                     if obj.len() > 3 {
-                        lines.push(Line::from(vec![
+                        body.push(Line::from(vec![
                             Span::styled("   ... ", Style::default().fg(Color::DarkGray)),
                             Span::styled(
                                 format!("({} more)", obj.len() - 3),
@@ -1171,55 +1510,94 @@ fn render_approval(f: &mut Frame, app: &App, area: Rect) {
                             ),
                         ]));
                     }
-                    lines.push(Line::from(""));
                 }
             }
         }
 
         // Show action buttons
-        lines.push(Line::from(""));
-        lines.push(Line::from(vec![
-            Span::styled(
-                "[A]",
-                Style::default()
-                    .fg(Color::Green)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::styled("pprove  ", Style::default().fg(Color::White)),
-            Span::styled(
-                "[D]",
-                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-            ),
-            Span::styled("eny  ", Style::default().fg(Color::White)),
-            Span::styled(
-                "[V]",
-                Style::default()
-                    .fg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::styled("iew Details  ", Style::default().fg(Color::White)),
-            Span::styled(
-                "[Esc]",
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::styled(" Cancel", Style::default().fg(Color::White)),
-        ]));
+        let key_style = |color: Color| Style::default().fg(color).add_modifier(Modifier::BOLD);
+        let label_style = Style::default().fg(Color::White);
+        let footer = if app.approval_editor.is_some() {
+            Line::from(vec![
+                Span::styled("[Ctrl+Enter]", key_style(Color::Green)),
+                Span::styled(" Save edit  ", label_style),
+                Span::styled("[Esc]", key_style(Color::Yellow)),
+                Span::styled(" Discard edit", label_style),
+            ])
+        } else {
+            let mut spans = vec![
+                Span::styled("[A]", key_style(Color::Green)),
+                Span::styled("pprove  ", label_style),
+                Span::styled("[D]", key_style(Color::Red)),
+                Span::styled("eny  ", label_style),
+                Span::styled("[E]", key_style(Color::Magenta)),
+                Span::styled("dit  ", label_style),
+                Span::styled("[V]", key_style(Color::Cyan)),
+                Span::styled(
+                    if matches!(request.preview, Some(ToolPreview::Diff(_))) {
+                        if app.show_approval_details {
+                            "iew Diff  "
+                        } else {
+                            "iew JSON  "
+                        }
+                    } else {
+                        "iew Details  "
+                    },
+                    label_style,
+                ),
+            ];
+            if scrollable {
+                spans.push(Span::styled("[↑↓/PgUp/PgDn]", key_style(Color::Cyan)));
+                spans.push(Span::styled(" Scroll  ", label_style));
+            }
+            spans.push(Span::styled("[Esc]", key_style(Color::Yellow)));
+            spans.push(Span::styled(" Cancel", label_style));
+            Line::from(spans)
+        };
 
-        let dialog = Paragraph::new(lines)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Red))
-                    .title(Span::styled(
-                        " ⚠️  PERMISSION REQUIRED ",
-                        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-                    )),
-            )
-            .alignment(Alignment::Left);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Red))
+            .title(Span::styled(
+                " ⚠️  PERMISSION REQUIRED ",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ));
+        let inner = block.inner(dialog_area);
+        f.render_widget(Clear, dialog_area);
+        f.render_widget(block, dialog_area);
 
-        f.render_widget(dialog, dialog_area);
+        let sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(header_height),
+                Constraint::Min(1),
+                Constraint::Length(2),
+            ])
+            .split(inner);
+
+        f.render_widget(
+            Paragraph::new(lines).alignment(Alignment::Left),
+            sections[0],
+        );
+
+        let body_height = sections[1].height as usize;
+        let scroll = if app.approval_editor.is_some() {
+            // Keep the end of the edit buffer (where the cursor is) visible
+            body.len().saturating_sub(body_height)
+        } else if scrollable {
+            (app.approval_scroll as usize).min(body.len().saturating_sub(body_height))
+        } else {
+            0
+        };
+        f.render_widget(
+            Paragraph::new(body).scroll((scroll.min(u16::MAX as usize) as u16, 0)),
+            sections[1],
+        );
+
+        f.render_widget(
+            Paragraph::new(vec![Line::from(""), footer]).alignment(Alignment::Left),
+            sections[2],
+        );
     }
 }
 
@@ -1227,76 +1605,120 @@ fn render_approval(f: &mut Frame, app: &App, area: Rect) {
 fn render_file_picker(f: &mut Frame, app: &App, area: Rect) {
     let mut lines: Vec<Line> = Vec::new();
 
+    let resources = app.mcp_resources();
+    let show_resources = app.file_picker_show_resources && !resources.is_empty();
+
     // Header
+    let (heading, location) = if show_resources {
+        ("🔌 MCP Resources", format!("{} available", resources.len()))
+    } else {
+        (
+            "📁 File Picker",
+            app.file_picker_current_dir.to_string_lossy().to_string(),
+        )
+    };
     lines.push(Line::from(vec![
         Span::styled(
-            "📁 File Picker",
+            heading,
             Style::default()
                 .fg(Color::Cyan)
                 .add_modifier(Modifier::BOLD),
         ),
         Span::styled("  │  ", Style::default().fg(Color::DarkGray)),
-        Span::styled(
-            app.file_picker_current_dir.to_string_lossy().to_string(),
-            Style::default().fg(Color::Yellow),
-        ),
+        Span::styled(location, Style::default().fg(Color::Yellow)),
     ]));
     lines.push(Line::from(""));
 
     // Calculate visible range
     let visible_items = (area.height as usize).saturating_sub(6); // Leave space for header and help
+    let total = if show_resources {
+        resources.len()
+    } else {
+        app.file_picker_files.len()
+    };
     let start = app.file_picker_scroll_offset;
-    let end = (start + visible_items).min(app.file_picker_files.len());
-
-    // Render file list
-    for (idx, path) in app
-        .file_picker_files
-        .iter()
-        .enumerate()
-        .skip(start)
-        .take(end - start)
-    {
-        let is_selected = idx == app.file_picker_selected;
-        let is_dir = path.is_dir();
-
-        let icon = if path.ends_with("..") {
-            "📂 .."
-        } else if is_dir {
-            "📂"
-        } else {
-            "📄"
-        };
+    let end = (start + visible_items).min(total);
 
-        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("?");
+    // Render resource list
+    if show_resources {
+        for (idx, resource) in resources.iter().enumerate().skip(start).take(end - start) {
+            let is_selected = idx == app.file_picker_selected;
+            let style = if is_selected {
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let prefix = if is_selected { "▶ " } else { "  " };
+
+            let mut spans = vec![
+                Span::styled(prefix, style),
+                Span::styled(format!("🔗 {}", resource.reference()), style),
+            ];
+            if !resource.resource.name.is_empty() {
+                spans.push(Span::styled(
+                    format!("  {}", resource.resource.name),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            lines.push(Line::from(spans));
+        }
+    }
 
-        let style = if is_selected {
-            Style::default()
-                .fg(Color::Black)
-                .bg(Color::Cyan)
-                .add_modifier(Modifier::BOLD)
-        } else if is_dir {
-            Style::default().fg(Color::Cyan)
-        } else {
-            Style::default().fg(Color::White)
-        };
+    // Render file list
+    if !show_resources {
+        for (idx, path) in app
+            .file_picker_files
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(end - start)
+        {
+            let is_selected = idx == app.file_picker_selected;
+            let is_dir = path.is_dir();
+
+            let icon = if path.ends_with("..") {
+                "📂 .."
+            } else if is_dir {
+                "📂"
+            } else {
+                "📄"
+            };
+
+            let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("?");
+
+            let style = if is_selected {
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+            } else if is_dir {
+                Style::default().fg(Color::Cyan)
+            } else {
+                Style::default().fg(Color::White)
+            };
 
-        let prefix = if is_selected { "▶ " } else { "  " };
+            let prefix = if is_selected { "▶ " } else { "  " };
 
-        lines.push(Line::from(vec![
-            Span::styled(prefix, style),
-            Span::styled(format!("{} {}", icon, filename), style),
-        ]));
+            lines.push(Line::from(vec![
+                Span::styled(prefix, style),
+                Span::styled(format!("{} {}", icon, filename), style),
+            ]));
+        }
     }
 
     // Add scroll indicator if needed
-    if app.file_picker_files.len() > visible_items {
+    if total > visible_items {
         lines.push(Line::from(""));
         lines.push(Line::from(vec![Span::styled(
             format!(
-                "Showing {}-{} of {} files",
+                "Showing {}-{} of {} {}",
                 start + 1,
                 end,
-                app.file_picker_files.len()
+                total,
+                if show_resources { "resources" } else { "files" }
             ),
             Style::default().fg(Color::DarkGray),
         )]));
@@ -1325,6 +1747,24 @@ fn render_file_picker(f: &mut Frame, app: &App, area: Rect) {
         ),
         Span::styled(" Cancel", Style::default().fg(Color::White)),
     ]));
+    if !resources.is_empty() {
+        lines.push(Line::from(vec![
+            Span::styled(
+                "[Tab]",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                if show_resources {
+                    " Show files"
+                } else {
+                    " Show MCP resources"
+                },
+                Style::default().fg(Color::White),
+            ),
+        ]));
+    }
 
     let widget = Paragraph::new(lines)
         .block(
@@ -1332,7 +1772,11 @@ fn render_file_picker(f: &mut Frame, app: &App, area: Rect) {
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(Color::Cyan))
                 .title(Span::styled(
-                    " Select a file ",
+                    if show_resources {
+                        " Select a resource "
+                    } else {
+                        " Select a file "
+                    },
                     Style::default()
                         .fg(Color::Cyan)
                         .add_modifier(Modifier::BOLD),
@@ -1350,6 +1794,7 @@ fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
         AppMode::Chat => "CHAT",
         AppMode::Plan => "PLAN",
         AppMode::Sessions => "SESSIONS",
+        AppMode::Checkpoints => "CHECKPOINTS",
         AppMode::Help => "HELP",
         AppMode::Settings => "SETTINGS",
         AppMode::ToolApproval => "PERMISSION",
@@ -3,7 +3,13 @@
 //! Tests for command-line argument parsing using Clap.
 
 use clap::Parser;
-use crustly::cli::{Cli, Commands, DbCommands, OutputFormat};
+use crustly::cli::{
+    attach_context, ApprovalPolicy, Cli, Commands, DbCommands, ListFormat, McpCommands,
+    OutputFormat, SessionChoice, SessionCommands,
+};
+use crustly::llm::tools::{bash::BashTool, git::GitTool, http::HttpClientTool, write::WriteTool};
+use serde_json::json;
+use std::path::PathBuf;
 
 #[test]
 fn test_cli_parse_no_command() {
@@ -18,7 +24,7 @@ fn test_cli_parse_no_command() {
 fn test_cli_parse_chat_command() {
     let cli = Cli::try_parse_from(["crustly", "chat"]).unwrap();
     match cli.command {
-        Some(Commands::Chat { session }) => {
+        Some(Commands::Chat { session, .. }) => {
             assert!(session.is_none());
         }
         _ => panic!("Expected Chat command"),
@@ -29,13 +35,68 @@ fn test_cli_parse_chat_command() {
 fn test_cli_parse_chat_with_session() {
     let cli = Cli::try_parse_from(["crustly", "chat", "--session", "test-session-id"]).unwrap();
     match cli.command {
-        Some(Commands::Chat { session }) => {
+        Some(Commands::Chat { session, .. }) => {
             assert_eq!(session, Some("test-session-id".to_string()));
         }
         _ => panic!("Expected Chat command with session"),
     }
 }
 
+#[test]
+fn test_cli_parse_chat_continue() {
+    let cli = Cli::try_parse_from(["crustly", "chat", "--continue"]).unwrap();
+    match cli.command {
+        Some(Commands::Chat {
+            session,
+            continue_session,
+        }) => {
+            assert!(session.is_none());
+            assert!(continue_session);
+            assert_eq!(
+                SessionChoice::from_args(session, continue_session),
+                SessionChoice::Continue
+            );
+        }
+        _ => panic!("Expected Chat command with continue"),
+    }
+
+    // Resuming a given session and the most recent one don't mix
+    assert!(Cli::try_parse_from(["crustly", "chat", "--continue", "-s", "abc"]).is_err());
+}
+
+#[test]
+fn test_cli_parse_run_with_session() {
+    let cli = Cli::try_parse_from(["crustly", "run", "--session", "1f3a", "Next step"]).unwrap();
+    match cli.command {
+        Some(Commands::Run {
+            session,
+            continue_session,
+            ..
+        }) => {
+            assert_eq!(
+                SessionChoice::from_args(session, continue_session),
+                SessionChoice::Id("1f3a".to_string())
+            );
+        }
+        _ => panic!("Expected Run command with session"),
+    }
+
+    let cli = Cli::try_parse_from(["crustly", "run", "Fresh start"]).unwrap();
+    match cli.command {
+        Some(Commands::Run {
+            session,
+            continue_session,
+            ..
+        }) => {
+            assert_eq!(
+                SessionChoice::from_args(session, continue_session),
+                SessionChoice::Default
+            );
+        }
+        _ => panic!("Expected Run command"),
+    }
+}
+
 #[test]
 fn test_cli_parse_run_command() {
     let cli = Cli::try_parse_from(["crustly", "run", "Hello, how are you?"]).unwrap();
@@ -44,6 +105,7 @@ fn test_cli_parse_run_command() {
             prompt,
             auto_approve,
             format,
+            ..
         }) => {
             assert_eq!(prompt, "Hello, how are you?");
             assert!(!auto_approve);
@@ -53,6 +115,50 @@ fn test_cli_parse_run_command() {
     }
 }
 
+#[test]
+fn test_cli_parse_run_with_attachments() {
+    let cli = Cli::try_parse_from([
+        "crustly",
+        "run",
+        "--file",
+        "a.rs",
+        "--file",
+        "b.rs",
+        "--no-stdin",
+        "-o",
+        "review.md",
+        "Review these",
+    ])
+    .unwrap();
+    match cli.command {
+        Some(Commands::Run {
+            prompt,
+            file,
+            no_stdin,
+            output,
+            ..
+        }) => {
+            assert_eq!(prompt, "Review these");
+            assert_eq!(file, vec![PathBuf::from("a.rs"), PathBuf::from("b.rs")]);
+            assert!(no_stdin);
+            assert_eq!(output, Some(PathBuf::from("review.md")));
+        }
+        _ => panic!("Expected Run command"),
+    }
+}
+
+#[test]
+fn test_attach_context() {
+    assert_eq!(attach_context("Hi", None, &[]), "Hi");
+
+    let files = vec![(PathBuf::from("src/a.rs"), "fn a() {}\n".to_string())];
+    assert_eq!(
+        attach_context("Review this", Some("+ added line\n"), &files),
+        "Review this\n\n<stdin>\n+ added line\n</stdin>\n\n\
+         <file path=\"src/a.rs\">\nfn a() {}\n</file>"
+    );
+}
+
 #[test]
 fn test_cli_parse_run_with_json_format() {
     let cli = Cli::try_parse_from(["crustly", "run", "--format", "json", "Test prompt"]).unwrap();
@@ -61,6 +167,7 @@ fn test_cli_parse_run_with_json_format() {
             prompt,
             auto_approve,
             format,
+            ..
         }) => {
             assert_eq!(prompt, "Test prompt");
             assert!(!auto_approve);
@@ -79,6 +186,7 @@ fn test_cli_parse_run_with_markdown_format() {
             prompt,
             auto_approve,
             format,
+            ..
         }) => {
             assert_eq!(prompt, "Test prompt");
             assert!(!auto_approve);
@@ -88,6 +196,18 @@ fn test_cli_parse_run_with_markdown_format() {
     }
 }
 
+#[test]
+fn test_cli_parse_run_with_stream_json_format() {
+    let cli =
+        Cli::try_parse_from(["crustly", "run", "--format", "stream-json", "Test prompt"]).unwrap();
+    match cli.command {
+        Some(Commands::Run { format, .. }) => {
+            assert!(matches!(format, OutputFormat::StreamJson));
+        }
+        _ => panic!("Expected Run command with stream-json format"),
+    }
+}
+
 #[test]
 fn test_cli_parse_run_with_auto_approve() {
     let cli = Cli::try_parse_from(["crustly", "run", "--auto-approve", "Test prompt"]).unwrap();
@@ -95,7 +215,7 @@ fn test_cli_parse_run_with_auto_approve() {
         Some(Commands::Run {
             prompt,
             auto_approve,
-            format: _,
+            ..
         }) => {
             assert_eq!(prompt, "Test prompt");
             assert!(auto_approve);
@@ -111,7 +231,7 @@ fn test_cli_parse_run_with_yolo_alias() {
         Some(Commands::Run {
             prompt,
             auto_approve,
-            format: _,
+            ..
         }) => {
             assert_eq!(prompt, "Test prompt");
             assert!(auto_approve);
@@ -120,6 +240,161 @@ fn test_cli_parse_run_with_yolo_alias() {
     }
 }
 
+#[test]
+fn test_cli_parse_run_with_approval_policy() {
+    let cli = Cli::try_parse_from([
+        "crustly",
+        "run",
+        "--read-only",
+        "--allow",
+        "git",
+        "--allow",
+        "write_file",
+        "-",
+    ])
+    .unwrap();
+    match cli.command {
+        Some(Commands::Run {
+            prompt,
+            auto_approve,
+            read_only,
+            allow,
+            ..
+        }) => {
+            assert_eq!(prompt, "-");
+            assert!(!auto_approve);
+            assert!(read_only);
+            assert_eq!(allow, vec!["git", "write_file"]);
+        }
+        _ => panic!("Expected Run command with approval policy"),
+    }
+}
+
+#[test]
+fn test_approval_policy() {
+    let command = json!({ "command": "ls && rm -rf ~" });
+    let commit = json!({ "operation": "commit", "message": "wip" });
+
+    let deny = ApprovalPolicy::default();
+    assert!(!deny.approves(&BashTool, &command));
+
+    let allow = ApprovalPolicy {
+        allow: vec!["git".to_string()],
+        ..Default::default()
+    };
+    assert!(allow.approves(&GitTool, &commit));
+    assert!(!allow.approves(&BashTool, &command));
+
+    // Plan mode approves only calls that just read
+    let read_only = ApprovalPolicy {
+        read_only: true,
+        ..Default::default()
+    };
+    assert!(read_only.approves(&GitTool, &json!({ "operation": "status" })));
+    assert!(!read_only.approves(&GitTool, &commit));
+    assert!(!read_only.approves(&BashTool, &command));
+    assert!(!read_only.approves(
+        &HttpClientTool,
+        &json!({ "method": "DELETE", "url": "https://example.com/item/1" })
+    ));
+    assert!(!read_only.approves(&WriteTool, &json!({ "path": "a.txt", "content": "x" })));
+
+    let read_only_allow = ApprovalPolicy {
+        read_only: true,
+        allow: vec!["bash".to_string()],
+        ..Default::default()
+    };
+    assert!(read_only_allow.approves(&BashTool, &command));
+
+    let yolo = ApprovalPolicy {
+        auto_approve: true,
+        ..Default::default()
+    };
+    assert!(yolo.approves(&BashTool, &command));
+}
+
+#[test]
+fn test_cli_parse_sessions_list() {
+    let cli = Cli::try_parse_from([
+        "crustly",
+        "sessions",
+        "list",
+        "--archived",
+        "--since",
+        "2025-06-01",
+        "--until",
+        "2025-06-30",
+        "--model",
+        "sonnet",
+        "--min-cost",
+        "0.5",
+        "--format",
+        "json",
+    ])
+    .unwrap();
+    match cli.command {
+        Some(Commands::Sessions {
+            operation:
+                SessionCommands::List {
+                    archived,
+                    since,
+                    until,
+                    model,
+                    min_cost,
+                    max_cost,
+                    limit,
+                    format,
+                },
+        }) => {
+            assert!(archived);
+            assert_eq!(since.unwrap().to_rfc3339(), "2025-06-01T00:00:00+00:00");
+            assert_eq!(until.unwrap().to_rfc3339(), "2025-06-30T23:59:59+00:00");
+            assert_eq!(model.as_deref(), Some("sonnet"));
+            assert_eq!(min_cost, Some(0.5));
+            assert!(max_cost.is_none());
+            assert!(limit.is_none());
+            assert_eq!(format, ListFormat::Json);
+        }
+        _ => panic!("Expected Sessions List command"),
+    }
+
+    assert!(Cli::try_parse_from(["crustly", "sessions", "list", "--since", "June"]).is_err());
+}
+
+#[test]
+fn test_cli_parse_sessions_commands() {
+    let cli = Cli::try_parse_from(["crustly", "sessions", "rename", "1f3a", "New title"]).unwrap();
+    match cli.command {
+        Some(Commands::Sessions {
+            operation: SessionCommands::Rename { session, title },
+        }) => {
+            assert_eq!(session, "1f3a");
+            assert_eq!(title, "New title");
+        }
+        _ => panic!("Expected Sessions Rename command"),
+    }
+
+    let cli =
+        Cli::try_parse_from(["crustly", "sessions", "export", "1f3a", "-o", "s.json"]).unwrap();
+    match cli.command {
+        Some(Commands::Sessions {
+            operation: SessionCommands::Export { session, output },
+        }) => {
+            assert_eq!(session, "1f3a");
+            assert_eq!(output, Some("s.json".into()));
+        }
+        _ => panic!("Expected Sessions Export command"),
+    }
+
+    let cli = Cli::try_parse_from(["crustly", "sessions", "delete", "1f3a", "--force"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Some(Commands::Sessions {
+            operation: SessionCommands::Delete { force: true, .. }
+        })
+    ));
+}
+
 #[test]
 fn test_cli_parse_init_command() {
     let cli = Cli::try_parse_from(["crustly", "init"]).unwrap();
@@ -233,6 +508,7 @@ fn test_cli_parse_combined_flags() {
             prompt,
             auto_approve,
             format,
+            ..
         }) => {
             assert_eq!(prompt, "Test prompt");
             assert!(auto_approve);
@@ -271,3 +547,138 @@ fn test_cli_db_invalid_operation() {
     let result = Cli::try_parse_from(["crustly", "db", "invalid"]);
     assert!(result.is_err());
 }
+
+#[test]
+fn test_cli_parse_mcp_serve() {
+    let cli = Cli::try_parse_from([
+        "crustly",
+        "mcp",
+        "serve",
+        "--read-only",
+        "--allow",
+        "bash",
+        "--allow",
+        "edit_file",
+        "--deny",
+        "web_search",
+    ])
+    .unwrap();
+    match cli.command {
+        Some(Commands::Mcp {
+            operation:
+                McpCommands::Serve {
+                    read_only,
+                    auto_approve,
+                    allow,
+                    deny,
+                },
+        }) => {
+            assert!(read_only);
+            assert!(!auto_approve);
+            assert_eq!(allow, vec!["bash", "edit_file"]);
+            assert_eq!(deny, vec!["web_search"]);
+        }
+        _ => panic!("Expected MCP serve command"),
+    }
+}
+
+#[test]
+fn test_cli_parse_completions() {
+    let cli = Cli::try_parse_from(["crustly", "completions", "zsh"]).unwrap();
+    match cli.command {
+        Some(Commands::Completions { shell }) => {
+            assert_eq!(shell, clap_complete::Shell::Zsh);
+        }
+        _ => panic!("Expected Completions command"),
+    }
+
+    assert!(Cli::try_parse_from(["crustly", "completions", "tcsh"]).is_err());
+}
+
+#[test]
+fn test_cli_parse_man() {
+    let cli = Cli::try_parse_from(["crustly", "man"]).unwrap();
+    assert!(matches!(cli.command, Some(Commands::Man { output: None })));
+
+    let cli = Cli::try_parse_from(["crustly", "man", "-o", "target/man"]).unwrap();
+    match cli.command {
+        Some(Commands::Man { output }) => {
+            assert_eq!(output, Some(PathBuf::from("target/man")));
+        }
+        _ => panic!("Expected Man command"),
+    }
+}
+
+#[test]
+fn test_cli_parse_doctor() {
+    let cli = Cli::try_parse_from(["crustly", "doctor"]).unwrap();
+    match cli.command {
+        Some(Commands::Doctor { offline, migrate }) => {
+            assert!(!offline);
+            assert!(!migrate);
+        }
+        _ => panic!("Expected Doctor command"),
+    }
+
+    let cli = Cli::try_parse_from([
+        "crustly",
+        "--config",
+        "ci.toml",
+        "doctor",
+        "--offline",
+        "--migrate",
+    ])
+    .unwrap();
+    assert_eq!(cli.config.as_deref(), Some("ci.toml"));
+    match cli.command {
+        Some(Commands::Doctor { offline, migrate }) => {
+            assert!(offline);
+            assert!(migrate);
+        }
+        _ => panic!("Expected Doctor command"),
+    }
+}
+
+#[test]
+fn test_cli_parse_batch() {
+    let cli = Cli::try_parse_from(["crustly", "batch", "prompts.jsonl"]).unwrap();
+    match cli.command {
+        Some(Commands::Batch {
+            input,
+            output,
+            jobs,
+            timeout,
+            retry_failed,
+        }) => {
+            assert_eq!(input, PathBuf::from("prompts.jsonl"));
+            assert_eq!(output, None);
+            assert_eq!(jobs, 4);
+            assert_eq!(timeout, 600);
+            assert!(!retry_failed);
+        }
+        _ => panic!("Expected Batch command"),
+    }
+
+    let cli = Cli::try_parse_from([
+        "crustly",
+        "batch",
+        "prompts.jsonl",
+        "-j",
+        "8",
+        "--timeout",
+        "120",
+        "-o",
+        "out.jsonl",
+        "--retry-failed",
+    ])
+    .unwrap();
+    assert!(matches!(
+        cli.command,
+        Some(Commands::Batch {
+            jobs: 8,
+            timeout: 120,
+            retry_failed: true,
+            ..
+        })
+    ));
+}
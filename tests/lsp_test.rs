@@ -0,0 +1,286 @@
+//! LSP Integration Tests
+//!
+//! Runs the stub language server binary over stdio and exercises the
+//! manager, document synchronization, and the `lsp` and `rename_symbol`
+//! tools.
+
+use crustly::{
+    config::{LspConfig, LspServerConfig},
+    llm::tools::{
+        lsp::{LspTool, RenameSymbolTool},
+        Tool, ToolExecutionContext,
+    },
+    lsp::LspManager,
+};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use uuid::Uuid;
+
+const LIB: &str = "pub fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n";
+const MAIN: &str =
+    "fn main() {\n    let total = add(1, 2);\n    println!(\"{}\", add(total, 3));\n}\n";
+
+/// A small project served by the stub server
+fn workspace() -> (TempDir, PathBuf, Arc<LspManager>) {
+    workspace_with(LspConfig::default())
+}
+
+/// A small project served by the stub server, with the given base config
+fn workspace_with(mut config: LspConfig) -> (TempDir, PathBuf, Arc<LspManager>) {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+    std::fs::write(root.join("src/lib.rs"), LIB).unwrap();
+    std::fs::write(root.join("src/main.rs"), MAIN).unwrap();
+
+    config.servers.insert(
+        "rust".to_string(),
+        LspServerConfig {
+            root_markers: vec!["Cargo.toml".to_string()],
+            ..LspServerConfig::new(env!("CARGO_BIN_EXE_lsp-stub-server"), &["rs"])
+        },
+    );
+    let manager = Arc::new(LspManager::new(&config, root.clone()));
+    (dir, root, manager)
+}
+
+fn context(root: &Path) -> ToolExecutionContext {
+    ToolExecutionContext::new(Uuid::new_v4()).with_working_directory(root.to_path_buf())
+}
+
+#[tokio::test]
+async fn test_navigation_operations() {
+    let (_dir, root, manager) = workspace();
+    let tool = LspTool::new(manager.clone());
+    let context = context(&root);
+
+    let result = tool
+        .execute(
+            json!({"operation": "definition", "path": "src/main.rs", "line": 2, "symbol": "add"}),
+            &context,
+        )
+        .await
+        .unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(
+        result.output,
+        "src/lib.rs:1:8  pub fn add(a: i32, b: i32) -> i32 {"
+    );
+
+    let result = tool
+        .execute(
+            json!({"operation": "references", "path": "src/main.rs", "symbol": "add"}),
+            &context,
+        )
+        .await
+        .unwrap();
+    assert!(result.output.starts_with("3 reference(s):"));
+    assert!(result.output.contains("src/main.rs:3:20"));
+
+    let result = tool
+        .execute(
+            json!({"operation": "hover", "path": "src/main.rs", "line": 2, "column": 17}),
+            &context,
+        )
+        .await
+        .unwrap();
+    assert!(result.output.contains("fn add()"));
+
+    // Without a path, servers are found through the project's root markers
+    let result = tool
+        .execute(json!({"operation": "symbols", "query": "ma"}), &context)
+        .await
+        .unwrap();
+    assert_eq!(
+        result.output,
+        "1 symbol(s):\nFunction main  src/main.rs:1:4  fn main() {"
+    );
+
+    let result = tool
+        .execute(
+            json!({"operation": "definition", "path": "src/main.rs", "symbol": "missing"}),
+            &context,
+        )
+        .await
+        .unwrap();
+    assert!(!result.success);
+
+    // One server for the whole workspace
+    assert_eq!(manager.running().await.len(), 1);
+    assert_eq!(manager.running().await[0].root(), root);
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_rename_symbol_preview_and_execute() {
+    let (_dir, root, manager) = workspace();
+    let rename = RenameSymbolTool::new(manager.clone());
+    let context = context(&root);
+    let input = json!({"path": "src/lib.rs", "line": 1, "symbol": "add", "new_name": "sum"});
+
+    assert!(rename.requires_approval());
+    let changes = rename.preview(&input, &context).await.unwrap();
+    assert_eq!(changes.len(), 2);
+    let main = changes.iter().find(|c| c.path == "src/main.rs").unwrap();
+    assert_eq!(main.before.as_deref(), Some(MAIN));
+    assert_eq!(main.after, MAIN.replace("add", "sum"));
+    // Nothing is written by the preview
+    assert_eq!(
+        std::fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+        LIB
+    );
+
+    let result = rename.execute(input.clone(), &context).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert!(result.output.contains("3 edit(s) in 2 file(s)"));
+    assert_eq!(
+        std::fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+        LIB.replace("add", "sum")
+    );
+    assert_eq!(
+        std::fs::read_to_string(root.join("src/main.rs")).unwrap(),
+        MAIN.replace("add", "sum")
+    );
+
+    // The open document was synced, so the server sees the new name
+    let result = LspTool::new(manager.clone())
+        .execute(
+            json!({"operation": "definition", "path": "src/main.rs", "symbol": "sum"}),
+            &context,
+        )
+        .await
+        .unwrap();
+    assert!(result.output.starts_with("src/lib.rs:1:8"));
+
+    // Refused in plan mode
+    let result = rename
+        .execute(input, &context.clone().with_read_only_mode(true))
+        .await
+        .unwrap();
+    assert!(!result.success);
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_rename_symbol_plans_once() {
+    // Without a diagnostics wait, syncing the edit starts no server
+    let (_dir, root, manager) = workspace_with(LspConfig {
+        diagnostics_wait_ms: 0,
+        ..LspConfig::default()
+    });
+    let rename = RenameSymbolTool::new(manager.clone());
+    let context = context(&root);
+    let input = json!({"path": "src/lib.rs", "line": 1, "symbol": "add", "new_name": "sum"});
+
+    let mut paths = rename.modified_paths(&input, &context).await;
+    paths.sort();
+    assert_eq!(paths.len(), 2);
+
+    // The plan made for the snapshot is the one executed, without asking the
+    // server again
+    manager.shutdown().await;
+    let result = rename.execute(input, &context).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert!(result.output.contains("3 edit(s) in 2 file(s)"));
+    assert_eq!(
+        std::fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+        LIB.replace("add", "sum")
+    );
+    assert!(manager.running().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_file_changes_are_synced() {
+    let (_dir, root, manager) = workspace();
+    let path = root.join("src/lib.rs");
+
+    // Not opened yet: no server is started for a change
+    manager.file_changed(&path).await;
+    assert!(manager.running().await.is_empty());
+
+    let client = manager.client_for(&path).await.unwrap();
+    let uri = client.sync_file(&path).await.unwrap();
+    assert!(client.is_open(&path).await);
+
+    std::fs::write(&path, format!("{}ERROR\n", LIB)).unwrap();
+    manager.file_changed(&path).await;
+
+    let mut diagnostics = Vec::new();
+    for _ in 0..50 {
+        diagnostics = client.diagnostics(&uri);
+        if !diagnostics.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].range.start.line, 3);
+
+    // Deleting the file closes the document
+    std::fs::remove_file(&path).unwrap();
+    manager.file_changed(&path).await;
+    assert!(!client.is_open(&path).await);
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_files_edited_reports_diagnostics() {
+    let (_dir, root, manager) = workspace();
+    let lib = root.join("src/lib.rs");
+    let main = root.join("src/main.rs");
+
+    // The server is started by the first edit, which doesn't wait for it
+    std::fs::write(&lib, format!("{}// pending\n", LIB)).unwrap();
+    assert!(manager
+        .files_edited(std::slice::from_ref(&lib))
+        .await
+        .is_none());
+    let client = manager.client_for(&lib).await.unwrap();
+    for _ in 0..50 {
+        if client.diagnostics_sequence() > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    // Once it has published, edits wait for its diagnostics
+    std::fs::write(&lib, format!("{}ERROR\n", LIB)).unwrap();
+    let report = manager
+        .files_edited(std::slice::from_ref(&lib))
+        .await
+        .unwrap();
+    assert_eq!(
+        report,
+        "Diagnostics after the edit:\nsrc/lib.rs:4:1: error: stub error"
+    );
+
+    std::fs::write(&lib, LIB).unwrap();
+    std::fs::write(&main, MAIN.replace("add", "sum")).unwrap();
+    let report = manager
+        .files_edited(&[lib.clone(), main.clone(), root.join("README.md")])
+        .await
+        .unwrap();
+    assert!(report.contains("src/lib.rs: no errors or warnings"));
+    assert!(report.contains("src/main.rs: no errors or warnings"));
+    assert!(!report.contains("README.md"));
+
+    // Unchanged content is not checked again
+    assert!(manager
+        .files_edited(std::slice::from_ref(&lib))
+        .await
+        .is_none());
+    manager.shutdown().await;
+
+    // Without a wait, only open documents are synced
+    let config = LspConfig {
+        diagnostics_wait_ms: 0,
+        ..LspConfig::default()
+    };
+    let manager = LspManager::new(&config, root.clone());
+    assert!(manager.files_edited(&[lib]).await.is_none());
+    assert!(manager.running().await.is_empty());
+}
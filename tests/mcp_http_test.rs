@@ -0,0 +1,342 @@
+//! MCP HTTP Transport Tests
+//!
+//! Runs a small in-process hyper server that speaks both the streamable HTTP
+//! and the legacy SSE transport, and exercises sessions, authentication,
+//! reconnection and timeouts through the MCP manager.
+
+use crustly::{
+    config::{McpConfig, McpServerConfig, McpTransportKind, SecretString},
+    mcp::{McpError, McpManager},
+};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+const TOKEN: &str = "test-token";
+
+type EventSender = mpsc::UnboundedSender<Result<String, Infallible>>;
+
+/// Shared state of the stand-in server
+#[derive(Default)]
+struct ServerState {
+    sessions_created: AtomicUsize,
+    expired: Mutex<HashSet<String>>,
+    deleted: Mutex<Vec<String>>,
+    listen_streams: Mutex<Vec<EventSender>>,
+    pongs: AtomicUsize,
+    sse_connections: AtomicUsize,
+    sse_stream: Mutex<Option<EventSender>>,
+}
+
+impl ServerState {
+    /// Forget every session issued so far
+    fn expire_sessions(&self) {
+        let count = self.sessions_created.load(Ordering::SeqCst);
+        let mut expired = self.expired.lock().unwrap();
+        for n in 1..=count {
+            expired.insert(format!("session-{}", n));
+        }
+    }
+
+    /// Close the current legacy SSE stream
+    fn drop_sse_stream(&self) {
+        self.sse_stream.lock().unwrap().take();
+    }
+}
+
+fn sse_event(event: &str, data: &str) -> String {
+    format!("event: {}\ndata: {}\n\n", event, data)
+}
+
+fn event_stream(rx: mpsc::UnboundedReceiver<Result<String, Infallible>>) -> Response<Body> {
+    Response::builder()
+        .header("content-type", "text/event-stream")
+        .body(Body::wrap_stream(UnboundedReceiverStream::new(rx)))
+        .unwrap()
+}
+
+fn status(code: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(code)
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// Answer a JSON-RPC request, or `None` for notifications and responses
+async fn handle(message: &Value) -> Option<Value> {
+    let id = message.get("id")?.clone();
+    let method = message.get("method")?.as_str()?;
+    let params = &message["params"];
+
+    let result = match method {
+        "initialize" => json!({
+            "protocolVersion": params["protocolVersion"],
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "http-stub", "version": "0.1.0" }
+        }),
+        "tools/list" => json!({
+            "tools": [
+                { "name": "echo", "inputSchema": { "type": "object" } },
+                { "name": "slow", "inputSchema": { "type": "object" } }
+            ]
+        }),
+        "tools/call" => {
+            if params["name"] == "slow" {
+                tokio::time::sleep(Duration::from_secs(3)).await;
+            }
+            json!({ "content": [{ "type": "text", "text": params["arguments"]["text"] }] })
+        }
+        _ => {
+            return Some(json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32601, "message": "not found" }
+            }))
+        }
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+async fn route(state: Arc<ServerState>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let authorized = req
+        .headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        == Some(&format!("Bearer {}", TOKEN));
+    if !authorized {
+        return Ok(status(StatusCode::UNAUTHORIZED));
+    }
+
+    let session = req
+        .headers()
+        .get("mcp-session-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+
+    let response = match (method, path.as_str()) {
+        (Method::POST, "/mcp") => {
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            let message: Value = serde_json::from_slice(&body).unwrap();
+
+            if message["method"] == "initialize" {
+                let n = state.sessions_created.fetch_add(1, Ordering::SeqCst) + 1;
+                let reply = handle(&message).await.unwrap();
+                return Ok(Response::builder()
+                    .header("content-type", "application/json")
+                    .header("mcp-session-id", format!("session-{}", n))
+                    .body(Body::from(reply.to_string()))
+                    .unwrap());
+            }
+
+            match &session {
+                None => return Ok(status(StatusCode::BAD_REQUEST)),
+                Some(id) if state.expired.lock().unwrap().contains(id) => {
+                    return Ok(status(StatusCode::NOT_FOUND))
+                }
+                Some(_) => {}
+            }
+
+            if message.get("method").is_none() {
+                state.pongs.fetch_add(1, Ordering::SeqCst);
+            }
+            match handle(&message).await {
+                None => status(StatusCode::ACCEPTED),
+                // Listings are streamed, everything else is plain JSON
+                Some(reply) if message["method"] == "tools/list" => {
+                    let (tx, rx) = mpsc::unbounded_channel();
+                    let _ = tx.send(Ok(sse_event("message", &reply.to_string())));
+                    event_stream(rx)
+                }
+                Some(reply) => Response::builder()
+                    .header("content-type", "application/json")
+                    .body(Body::from(reply.to_string()))
+                    .unwrap(),
+            }
+        }
+        (Method::GET, "/mcp") => {
+            // Server-initiated ping over the listening stream
+            let (tx, rx) = mpsc::unbounded_channel();
+            let ping = json!({ "jsonrpc": "2.0", "id": "srv-1", "method": "ping" });
+            let _ = tx.send(Ok(sse_event("message", &ping.to_string())));
+            state.listen_streams.lock().unwrap().push(tx);
+            event_stream(rx)
+        }
+        (Method::DELETE, "/mcp") => {
+            state.deleted.lock().unwrap().extend(session);
+            status(StatusCode::OK)
+        }
+        (Method::GET, "/sse") => {
+            let n = state.sse_connections.fetch_add(1, Ordering::SeqCst) + 1;
+            let (tx, rx) = mpsc::unbounded_channel();
+            let _ = tx.send(Ok(sse_event(
+                "endpoint",
+                &format!("/messages?stream={}", n),
+            )));
+            *state.sse_stream.lock().unwrap() = Some(tx);
+            event_stream(rx)
+        }
+        (Method::POST, "/messages") => {
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            let message: Value = serde_json::from_slice(&body).unwrap();
+            if let Some(reply) = handle(&message).await {
+                if let Some(tx) = state.sse_stream.lock().unwrap().as_ref() {
+                    let _ = tx.send(Ok(sse_event("message", &reply.to_string())));
+                }
+            }
+            status(StatusCode::ACCEPTED)
+        }
+        _ => status(StatusCode::NOT_FOUND),
+    };
+    Ok(response)
+}
+
+async fn start_server() -> (String, Arc<ServerState>) {
+    let state = Arc::new(ServerState::default());
+    let shared = state.clone();
+    let make_service = make_service_fn(move |_| {
+        let state = shared.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| route(state.clone(), req))) }
+    });
+
+    let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+    let base_url = format!("http://{}", server.local_addr());
+    tokio::spawn(server);
+    (base_url, state)
+}
+
+fn remote_config(url: String, transport: McpTransportKind, token: &str) -> McpConfig {
+    let mut server = McpServerConfig::remote(url, transport);
+    server.bearer_token = Some(SecretString::from_str(token));
+    server.timeout_secs = 1;
+
+    let mut servers = BTreeMap::new();
+    servers.insert("remote".to_string(), server);
+    McpConfig { servers }
+}
+
+async fn wait_until(condition: impl Fn() -> bool) -> bool {
+    for _ in 0..100 {
+        if condition() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn test_streamable_http_session_and_tools() {
+    let (base_url, state) = start_server().await;
+    let config = remote_config(format!("{}/mcp", base_url), McpTransportKind::Http, TOKEN);
+
+    let manager = McpManager::start(&config).await;
+    assert_eq!(manager.clients().len(), 1);
+    assert_eq!(manager.clients()[0].server_info().name, "http-stub");
+    // tools/list was answered over an event stream
+    assert_eq!(manager.tools().len(), 2);
+
+    let result = manager.clients()[0]
+        .call_tool("echo", json!({ "text": "over http" }))
+        .await
+        .unwrap();
+    assert_eq!(result.to_text(), "over http");
+
+    // The server's ping on the listening stream is answered
+    assert!(wait_until(|| state.pongs.load(Ordering::SeqCst) > 0).await);
+
+    manager.shutdown().await;
+    assert_eq!(
+        *state.deleted.lock().unwrap(),
+        vec!["session-1".to_string()]
+    );
+}
+
+#[tokio::test]
+async fn test_streamable_http_reinitializes_expired_session() {
+    let (base_url, state) = start_server().await;
+    let config = remote_config(format!("{}/mcp", base_url), McpTransportKind::Http, TOKEN);
+    let manager = McpManager::start(&config).await;
+
+    state.expire_sessions();
+    let result = manager.clients()[0]
+        .call_tool("echo", json!({ "text": "again" }))
+        .await
+        .unwrap();
+    assert_eq!(result.to_text(), "again");
+    assert_eq!(state.sessions_created.load(Ordering::SeqCst), 2);
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_streamable_http_rejects_bad_token() {
+    let (base_url, state) = start_server().await;
+    let config = remote_config(format!("{}/mcp", base_url), McpTransportKind::Http, "wrong");
+
+    let manager = McpManager::start(&config).await;
+    assert!(manager.clients().is_empty());
+    assert_eq!(state.sessions_created.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn test_streamable_http_request_timeout() {
+    let (base_url, _state) = start_server().await;
+    let config = remote_config(format!("{}/mcp", base_url), McpTransportKind::Http, TOKEN);
+    let manager = McpManager::start(&config).await;
+
+    let err = manager.clients()[0]
+        .call_tool("slow", json!({}))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        McpError::Timeout { .. } | McpError::Transport(_)
+    ));
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_sse_transport_reconnects() {
+    let (base_url, state) = start_server().await;
+    let config = remote_config(format!("{}/sse", base_url), McpTransportKind::Sse, TOKEN);
+
+    let manager = McpManager::start(&config).await;
+    assert_eq!(manager.clients().len(), 1);
+    assert_eq!(manager.tools().len(), 2);
+
+    // Drop the event stream; the client opens a new one
+    state.drop_sse_stream();
+    assert!(wait_until(|| state.sse_connections.load(Ordering::SeqCst) == 2).await);
+
+    let result = manager.clients()[0]
+        .call_tool("echo", json!({ "text": "reconnected" }))
+        .await
+        .unwrap();
+    assert_eq!(result.to_text(), "reconnected");
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_sse_transport_requires_endpoint() {
+    let (base_url, _state) = start_server().await;
+    // Wrong path: the server answers 404 instead of opening a stream
+    let config = remote_config(
+        format!("{}/missing", base_url),
+        McpTransportKind::Sse,
+        TOKEN,
+    );
+
+    let manager = McpManager::start(&config).await;
+    assert!(manager.clients().is_empty());
+}
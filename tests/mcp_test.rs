@@ -0,0 +1,213 @@
+//! MCP Integration Tests
+//!
+//! Runs the stub MCP server binary over stdio and exercises the client,
+//! the tool adapter, registration in the tool registry, and resources and
+//! prompt templates.
+
+use crustly::{
+    config::{McpConfig, McpServerConfig},
+    llm::tools::{Tool, ToolCapability, ToolExecutionContext, ToolRegistry},
+    mcp::McpManager,
+};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+fn stub_config() -> McpConfig {
+    let mut servers = BTreeMap::new();
+    servers.insert(
+        "stub".to_string(),
+        McpServerConfig {
+            timeout_secs: 10,
+            ..McpServerConfig::stdio(env!("CARGO_BIN_EXE_mcp-stub-server"))
+        },
+    );
+    McpConfig { servers }
+}
+
+#[tokio::test]
+async fn test_mcp_tools_are_registered_with_namespaced_names() {
+    let manager = McpManager::start(&stub_config()).await;
+    assert_eq!(manager.clients().len(), 1);
+    assert_eq!(manager.clients()[0].server_info().name, "stub");
+
+    let mut registry = ToolRegistry::new();
+    assert_eq!(manager.register_tools(&mut registry), 3);
+    assert!(registry.has_tool("mcp__stub__echo"));
+    assert!(registry.has_tool("mcp__stub__add"));
+
+    let echo = registry.get("mcp__stub__echo").unwrap();
+    assert!(echo.requires_approval());
+    assert!(echo.description().contains("Echo the given text"));
+    assert_eq!(echo.input_schema()["required"][0], "text");
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_mcp_tool_calls() {
+    let manager = McpManager::start(&stub_config()).await;
+    let mut registry = ToolRegistry::new();
+    manager.register_tools(&mut registry);
+    let context = ToolExecutionContext::new(Uuid::new_v4()).with_auto_approve(true);
+
+    let result = registry
+        .execute(
+            "mcp__stub__echo",
+            serde_json::json!({ "text": "hello" }),
+            &context,
+        )
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(result.output, "hello");
+
+    let result = registry
+        .execute("mcp__stub__fail", serde_json::json!({}), &context)
+        .await
+        .unwrap();
+    assert!(!result.success);
+    assert_eq!(result.error.as_deref(), Some("something went wrong"));
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_mcp_read_only_mode() {
+    let manager = McpManager::start(&stub_config()).await;
+    let tools = manager.tools();
+    let add = tools.iter().find(|t| t.remote_name() == "add").unwrap();
+    let echo = tools.iter().find(|t| t.remote_name() == "echo").unwrap();
+    assert!(!add
+        .capabilities()
+        .contains(&ToolCapability::SystemModification));
+
+    let context = ToolExecutionContext::new(Uuid::new_v4()).with_read_only_mode(true);
+
+    // Tools annotated read-only still run in Plan mode
+    let result = add
+        .execute(serde_json::json!({ "a": 2, "b": 3 }), &context)
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(result.output, "5");
+
+    let result = echo
+        .execute(serde_json::json!({ "text": "x" }), &context)
+        .await
+        .unwrap();
+    assert!(!result.success);
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_mcp_resources_are_inlined() {
+    let manager = McpManager::start(&stub_config()).await;
+    let resources = manager.resources();
+    assert_eq!(resources.len(), 1);
+    assert_eq!(resources[0].reference(), "@stub:stub://greeting");
+
+    let text = manager
+        .inline_resources("Summarize @stub:stub://greeting and @stub:stub://missing")
+        .await;
+    assert!(text.starts_with("Summarize @stub:stub://greeting"));
+    assert!(text.contains(
+        "<resource server=\"stub\" uri=\"stub://greeting\">\nHello from the stub\n</resource>"
+    ));
+    assert!(text.contains("[Failed to read resource: MCP server error -32002"));
+
+    // Unknown servers are left alone
+    let text = manager.inline_resources("ping @nobody:x").await;
+    assert_eq!(text, "ping @nobody:x");
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_mcp_prompts() {
+    let manager = McpManager::start(&stub_config()).await;
+    let prompt = manager.find_prompt("/stub:review").unwrap().clone();
+    assert_eq!(prompt.prompt.arguments.len(), 2);
+    assert!(prompt.prompt.arguments[0].required);
+
+    let mut arguments = BTreeMap::new();
+    arguments.insert("branch".to_string(), "main".to_string());
+    arguments.insert("focus".to_string(), "tests".to_string());
+    let text = manager.get_prompt(&prompt, &arguments).await.unwrap();
+    assert_eq!(text, "Review the changes on main, focusing on tests");
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_failing_server_is_skipped() {
+    let mut config = stub_config();
+    config.servers.insert(
+        "broken".to_string(),
+        McpServerConfig {
+            timeout_secs: 5,
+            ..McpServerConfig::stdio("/nonexistent/mcp-server")
+        },
+    );
+
+    let manager = McpManager::start(&config).await;
+    assert_eq!(manager.clients().len(), 1);
+    assert_eq!(manager.tools().len(), 3);
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_crustly_serves_its_tools() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "served over MCP\n").unwrap();
+
+    let mut servers = BTreeMap::new();
+    servers.insert(
+        "crustly".to_string(),
+        McpServerConfig {
+            args: vec![
+                "mcp".to_string(),
+                "serve".to_string(),
+                "--deny".to_string(),
+                "web_search".to_string(),
+            ],
+            cwd: Some(dir.path().to_path_buf()),
+            timeout_secs: 30,
+            ..McpServerConfig::stdio(env!("CARGO_BIN_EXE_crustly"))
+        },
+    );
+    let manager = McpManager::start(&McpConfig { servers }).await;
+    assert_eq!(manager.clients().len(), 1);
+    assert_eq!(manager.clients()[0].server_info().name, "crustly");
+
+    let client = &manager.clients()[0];
+    let tools = client.list_tools().await.unwrap();
+    let read = tools.iter().find(|t| t.name == "read_file").unwrap();
+    assert_eq!(
+        read.annotations.as_ref().unwrap().read_only_hint,
+        Some(true)
+    );
+    assert!(tools.iter().any(|t| t.name == "edit_file"));
+    assert!(!tools.iter().any(|t| t.name == "web_search"));
+
+    let result = client
+        .call_tool("read_file", serde_json::json!({ "path": "notes.txt" }))
+        .await
+        .unwrap();
+    assert!(!result.is_error);
+    assert!(result.to_text().contains("served over MCP"));
+
+    // Tools that need approval are refused unless allowed
+    let result = client
+        .call_tool(
+            "write_file",
+            serde_json::json!({ "path": "out.txt", "content": "x" }),
+        )
+        .await
+        .unwrap();
+    assert!(result.is_error);
+    assert!(!dir.path().join("out.txt").exists());
+
+    manager.shutdown().await;
+}
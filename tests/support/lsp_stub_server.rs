@@ -0,0 +1,311 @@
+//! Stub Language Server
+//!
+//! Minimal stdio language server used by the LSP integration tests. It
+//! understands `fn <name>` definitions in the `.rs` files under its root and
+//! answers definition, references, hover, workspace symbol and rename
+//! requests from the text alone. Lines containing `ERROR` are published as
+//! diagnostics.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+struct Server {
+    root: PathBuf,
+    open: HashMap<String, String>,
+}
+
+fn main() {
+    let stdin = std::io::stdin();
+    let mut input = stdin.lock();
+    let mut stdout = std::io::stdout();
+    let mut server = Server {
+        root: PathBuf::from("."),
+        open: HashMap::new(),
+    };
+
+    while let Some(message) = read_message(&mut input) {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+
+        match (method, message.get("id")) {
+            ("exit", _) => return,
+            ("initialized", _) => {
+                // Ask for settings like real servers do; the answer is ignored
+                write_message(
+                    &mut stdout,
+                    &json!({
+                        "jsonrpc": "2.0",
+                        "id": "config-1",
+                        "method": "workspace/configuration",
+                        "params": { "items": [{ "section": "stub" }] }
+                    }),
+                );
+            }
+            ("textDocument/didOpen", _) => {
+                let document = &params["textDocument"];
+                let uri = document["uri"].as_str().unwrap_or_default().to_string();
+                let text = document["text"].as_str().unwrap_or_default().to_string();
+                server.open.insert(uri.clone(), text);
+                publish_diagnostics(&mut stdout, &server, &uri);
+            }
+            ("textDocument/didChange", _) => {
+                let uri = params["textDocument"]["uri"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string();
+                if let Some(change) = params["contentChanges"].as_array().and_then(|c| c.last()) {
+                    let text = change["text"].as_str().unwrap_or_default().to_string();
+                    server.open.insert(uri.clone(), text);
+                }
+                publish_diagnostics(&mut stdout, &server, &uri);
+            }
+            ("textDocument/didClose", _) => {
+                if let Some(uri) = params["textDocument"]["uri"].as_str() {
+                    server.open.remove(uri);
+                }
+            }
+            (_, Some(id)) if !method.is_empty() => {
+                let result = server.handle(method, params);
+                write_message(
+                    &mut stdout,
+                    &json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                );
+            }
+            _ => {} // Responses and other notifications
+        }
+    }
+}
+
+fn read_message(input: &mut impl BufRead) -> Option<Value> {
+    let mut length = 0;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse().ok()?;
+        }
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+fn write_message(output: &mut impl Write, message: &Value) {
+    let body = message.to_string();
+    let _ = write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+    let _ = output.flush();
+}
+
+fn publish_diagnostics(output: &mut impl Write, server: &Server, uri: &str) {
+    let diagnostics: Vec<Value> = server.open[uri]
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.contains("ERROR"))
+        .map(|(index, line)| {
+            json!({
+                "range": range(index, 0, line.len()),
+                "severity": 1,
+                "message": "stub error"
+            })
+        })
+        .collect();
+    write_message(
+        output,
+        &json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics }
+        }),
+    );
+}
+
+fn range(line: usize, start: usize, end: usize) -> Value {
+    json!({
+        "start": { "line": line, "character": start },
+        "end": { "line": line, "character": end }
+    })
+}
+
+fn uri_for(path: &Path) -> String {
+    format!("file://{}", path.display())
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Columns of whole-word occurrences of `word` in `line`
+fn occurrences(line: &str, word: &str) -> Vec<usize> {
+    let chars: Vec<char> = line.chars().collect();
+    let target: Vec<char> = word.chars().collect();
+    (0..chars.len())
+        .filter(|&i| {
+            chars[i..].starts_with(&target)
+                && (i == 0 || !is_word(chars[i - 1]))
+                && !chars.get(i + target.len()).is_some_and(|c| is_word(*c))
+        })
+        .collect()
+}
+
+impl Server {
+    /// Text of every `.rs` file under the root, preferring open documents
+    fn files(&self) -> Vec<(String, String)> {
+        let mut paths = Vec::new();
+        collect_files(&self.root, &mut paths);
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| {
+                let uri = uri_for(&path);
+                let text = self
+                    .open
+                    .get(&uri)
+                    .cloned()
+                    .unwrap_or_else(|| std::fs::read_to_string(&path).unwrap_or_default());
+                (uri, text)
+            })
+            .collect()
+    }
+
+    fn word_at(&self, params: &Value) -> Option<String> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let line = params["position"]["line"].as_u64()? as usize;
+        let column = params["position"]["character"].as_u64()? as usize;
+        let text = self.open.get(uri)?;
+        let chars: Vec<char> = text.lines().nth(line)?.chars().collect();
+        let mut start = column.min(chars.len());
+        while start > 0 && is_word(chars[start - 1]) {
+            start -= 1;
+        }
+        let mut end = column.min(chars.len());
+        while end < chars.len() && is_word(chars[end]) {
+            end += 1;
+        }
+        (start < end).then(|| chars[start..end].iter().collect())
+    }
+
+    /// Locations of `word`, only definitions if `definitions` is set
+    fn find(&self, word: &str, definitions: bool) -> Vec<(String, usize, usize)> {
+        let mut found = Vec::new();
+        for (uri, text) in self.files() {
+            for (index, line) in text.lines().enumerate() {
+                for column in occurrences(line, word) {
+                    let is_definition = line[..column].trim_end().ends_with("fn");
+                    if !definitions || is_definition {
+                        found.push((uri.clone(), index, column));
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    fn handle(&mut self, method: &str, params: &Value) -> Value {
+        match method {
+            "initialize" => {
+                if let Some(root) = params["rootUri"]
+                    .as_str()
+                    .and_then(|uri| uri.strip_prefix("file://"))
+                {
+                    self.root = PathBuf::from(root);
+                }
+                json!({
+                    "capabilities": {
+                        "textDocumentSync": 1,
+                        "definitionProvider": true,
+                        "referencesProvider": true,
+                        "hoverProvider": true,
+                        "workspaceSymbolProvider": true,
+                        "renameProvider": true
+                    },
+                    "serverInfo": { "name": "lsp-stub", "version": "0.1.0" }
+                })
+            }
+            "textDocument/definition" => match self.word_at(params) {
+                Some(word) => self
+                    .find(&word, true)
+                    .into_iter()
+                    .map(|(uri, line, column)| {
+                        json!({ "uri": uri, "range": range(line, column, column + word.len()) })
+                    })
+                    .collect(),
+                None => Value::Null,
+            },
+            "textDocument/references" => match self.word_at(params) {
+                Some(word) => self
+                    .find(&word, false)
+                    .into_iter()
+                    .map(|(uri, line, column)| {
+                        json!({ "uri": uri, "range": range(line, column, column + word.len()) })
+                    })
+                    .collect(),
+                None => Value::Null,
+            },
+            "textDocument/hover" => match self.word_at(params) {
+                Some(word) => json!({
+                    "contents": { "kind": "markdown", "value": format!("```rust\nfn {}()\n```", word) }
+                }),
+                None => Value::Null,
+            },
+            "workspace/symbol" => {
+                let query = params["query"].as_str().unwrap_or_default();
+                let mut symbols = Vec::new();
+                for (uri, text) in self.files() {
+                    for (index, line) in text.lines().enumerate() {
+                        let Some(rest) = line.trim_start().strip_prefix("fn ") else {
+                            continue;
+                        };
+                        let name: String = rest.chars().take_while(|c| is_word(*c)).collect();
+                        if name.contains(query) {
+                            let column = line.find(&name).unwrap_or_default();
+                            symbols.push(json!({
+                                "name": name,
+                                "kind": 12,
+                                "location": { "uri": uri, "range": range(index, column, column + name.len()) }
+                            }));
+                        }
+                    }
+                }
+                Value::Array(symbols)
+            }
+            "textDocument/rename" => {
+                let Some(word) = self.word_at(params) else {
+                    return Value::Null;
+                };
+                let new_name = params["newName"].as_str().unwrap_or_default();
+                let mut changes: HashMap<String, Vec<Value>> = HashMap::new();
+                for (uri, line, column) in self.find(&word, false) {
+                    changes.entry(uri).or_default().push(json!({
+                        "range": range(line, column, column + word.len()),
+                        "newText": new_name
+                    }));
+                }
+                json!({ "changes": changes })
+            }
+            "shutdown" => Value::Null,
+            _ => Value::Null,
+        }
+    }
+}
+
+fn collect_files(dir: &Path, paths: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, paths);
+        } else if path.extension().is_some_and(|e| e == "rs") {
+            paths.push(path);
+        }
+    }
+}
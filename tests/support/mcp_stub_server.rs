@@ -0,0 +1,129 @@
+//! Stub MCP Server
+//!
+//! Minimal stdio MCP server used by the MCP integration tests. It exposes
+//! three tools: `echo`, `add` (annotated read-only) and `fail`, one resource
+//! (`stub://greeting`) and one prompt template (`review`).
+
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+fn main() {
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else { break };
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+
+        // Notifications need no response
+        let Some(id) = message.get("id").cloned() else {
+            continue;
+        };
+
+        let response = match handle(&message) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, error)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": error }
+            }),
+        };
+
+        let _ = writeln!(stdout, "{}", response);
+        let _ = stdout.flush();
+    }
+}
+
+fn handle(message: &Value) -> Result<Value, (i64, String)> {
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    match message["method"].as_str().unwrap_or_default() {
+        "initialize" => Ok(json!({
+            "protocolVersion": params["protocolVersion"],
+            "capabilities": { "tools": {}, "resources": {}, "prompts": {} },
+            "serverInfo": { "name": "stub", "version": "0.1.0" }
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({
+            "tools": [
+                {
+                    "name": "echo",
+                    "description": "Echo the given text",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": { "text": { "type": "string" } },
+                        "required": ["text"]
+                    }
+                },
+                {
+                    "name": "add",
+                    "description": "Add two numbers",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": { "a": { "type": "number" }, "b": { "type": "number" } }
+                    },
+                    "annotations": { "readOnlyHint": true }
+                },
+                {
+                    "name": "fail",
+                    "description": "Always fails",
+                    "inputSchema": { "type": "object" }
+                }
+            ]
+        })),
+        "tools/call" => {
+            let args = &params["arguments"];
+            match params["name"].as_str().unwrap_or_default() {
+                "echo" => Ok(json!({
+                    "content": [{ "type": "text", "text": args["text"].as_str().unwrap_or_default() }]
+                })),
+                "add" => {
+                    let sum = args["a"].as_f64().unwrap_or(0.0) + args["b"].as_f64().unwrap_or(0.0);
+                    Ok(json!({ "content": [{ "type": "text", "text": sum.to_string() }] }))
+                }
+                "fail" => Ok(json!({
+                    "content": [{ "type": "text", "text": "something went wrong" }],
+                    "isError": true
+                })),
+                other => Err((-32602, format!("Unknown tool: {}", other))),
+            }
+        }
+        "resources/list" => Ok(json!({
+            "resources": [
+                { "uri": "stub://greeting", "name": "Greeting", "mimeType": "text/plain" }
+            ]
+        })),
+        "resources/read" => match params["uri"].as_str().unwrap_or_default() {
+            "stub://greeting" => Ok(json!({
+                "contents": [{ "uri": "stub://greeting", "text": "Hello from the stub" }]
+            })),
+            other => Err((-32002, format!("Resource not found: {}", other))),
+        },
+        "prompts/list" => Ok(json!({
+            "prompts": [{
+                "name": "review",
+                "description": "Review a branch",
+                "arguments": [
+                    { "name": "branch", "required": true },
+                    { "name": "focus" }
+                ]
+            }]
+        })),
+        "prompts/get" => {
+            let args = &params["arguments"];
+            let mut text = format!(
+                "Review the changes on {}",
+                args["branch"].as_str().unwrap_or("HEAD")
+            );
+            if let Some(focus) = args["focus"].as_str() {
+                text.push_str(&format!(", focusing on {}", focus));
+            }
+            Ok(json!({
+                "messages": [{ "role": "user", "content": { "type": "text", "text": text } }]
+            }))
+        }
+        other => Err((-32601, format!("Method not found: {}", other))),
+    }
+}